@@ -5,8 +5,10 @@ use std::path::{Path, PathBuf};
 use std::str::FromStr;
 
 use bitflags::bitflags;
+use knuffel::errors::DecodeError;
 use miette::{miette, Context, IntoDiagnostic, NarratableReportHandler};
-use niri_ipc::{LayoutSwitchTarget, SizeChange};
+use niri_ipc::{LayoutSwitchTarget, SizeChange, WorkspaceReferenceArg};
+use regex::Regex;
 use smithay::input::keyboard::keysyms::KEY_NoSymbol;
 use smithay::input::keyboard::xkb::{keysym_from_name, KEYSYM_CASE_INSENSITIVE};
 use smithay::input::keyboard::{Keysym, XkbConfig};
@@ -38,13 +40,28 @@ pub struct Config {
     pub hotkey_overlay: HotkeyOverlay,
     #[knuffel(child, default)]
     pub animations: Animations,
+    #[knuffel(children(name = "window-rule"))]
+    pub window_rules: Vec<WindowRule>,
+    #[knuffel(children(name = "workspace"))]
+    pub workspaces: Vec<Workspace>,
     #[knuffel(child, default)]
     pub binds: Binds,
+    #[knuffel(children(name = "mode"))]
+    pub modes: Vec<BindMode>,
+    #[knuffel(child, default)]
+    pub gestures: Gestures,
     #[knuffel(child, default)]
     pub debug: DebugConfig,
+    #[knuffel(children(name = "include"))]
+    includes: Vec<Include>,
+}
+
+#[derive(knuffel::Decode, Debug, Clone, PartialEq, Eq)]
+struct Include {
+    #[knuffel(argument)]
+    path: PathBuf,
 }
 
-// FIXME: Add other devices.
 #[derive(knuffel::Decode, Debug, Default, PartialEq)]
 pub struct Input {
     #[knuffel(child, default)]
@@ -54,7 +71,15 @@ pub struct Input {
     #[knuffel(child, default)]
     pub mouse: Mouse,
     #[knuffel(child, default)]
+    pub trackpoint: Trackpoint,
+    #[knuffel(child, default)]
+    pub trackball: Trackball,
+    #[knuffel(child, default)]
     pub tablet: Tablet,
+    #[knuffel(child, default)]
+    pub touch: Touch,
+    #[knuffel(children(name = "device"))]
+    pub devices: Vec<InputDevice>,
     #[knuffel(child)]
     pub disable_power_key_handling: bool,
 }
@@ -119,9 +144,10 @@ pub enum TrackLayout {
     Window,
 }
 
-// FIXME: Add the rest of the settings.
-#[derive(knuffel::Decode, Debug, Default, PartialEq)]
+#[derive(knuffel::Decode, Debug, PartialEq)]
 pub struct Touchpad {
+    #[knuffel(child)]
+    pub off: bool,
     #[knuffel(child)]
     pub tap: bool,
     #[knuffel(child)]
@@ -135,17 +161,90 @@ pub struct Touchpad {
     #[knuffel(child, unwrap(argument, str))]
     pub accel_profile: Option<AccelProfile>,
     #[knuffel(child, unwrap(argument, str))]
+    pub scroll_method: Option<ScrollMethod>,
+    #[knuffel(child, unwrap(argument, str))]
+    pub click_method: Option<ClickMethod>,
+    #[knuffel(child, unwrap(argument, str))]
     pub tap_button_map: Option<TapButtonMap>,
+    #[knuffel(child)]
+    pub left_handed: bool,
+    #[knuffel(child)]
+    pub middle_emulation: bool,
+    #[knuffel(child, unwrap(argument), default = 1.)]
+    pub scroll_factor: f64,
+}
+
+/// Settings shared by the mouse, trackpoint and trackball sections.
+#[derive(knuffel::Decode, Debug, PartialEq)]
+pub struct PointerDevice {
+    #[knuffel(child)]
+    pub off: bool,
+    #[knuffel(child)]
+    pub natural_scroll: bool,
+    #[knuffel(child, unwrap(argument), default)]
+    pub accel_speed: f64,
+    #[knuffel(child, unwrap(argument, str))]
+    pub accel_profile: Option<AccelProfile>,
+    #[knuffel(child, unwrap(argument, str))]
+    pub scroll_method: Option<ScrollMethod>,
+    #[knuffel(child, unwrap(argument))]
+    pub scroll_button: Option<u32>,
+    #[knuffel(child)]
+    pub left_handed: bool,
+    #[knuffel(child)]
+    pub middle_emulation: bool,
+    #[knuffel(child, unwrap(argument), default = 1.)]
+    pub scroll_factor: f64,
 }
 
+pub type Mouse = PointerDevice;
+pub type Trackpoint = PointerDevice;
+pub type Trackball = PointerDevice;
+
 #[derive(knuffel::Decode, Debug, Default, PartialEq)]
-pub struct Mouse {
+pub struct Touch {
+    #[knuffel(child)]
+    pub off: bool,
+    #[knuffel(child, unwrap(argument))]
+    pub map_to_output: Option<String>,
+}
+
+/// Settings for one input device, matched by its exact libinput name.
+///
+/// These replace the settings of the section that the device would otherwise use. Settings that
+/// the device doesn't support are ignored.
+#[derive(knuffel::Decode, Debug, PartialEq)]
+pub struct InputDevice {
+    #[knuffel(argument)]
+    pub name: String,
+    #[knuffel(child)]
+    pub off: bool,
+    #[knuffel(child)]
+    pub tap: bool,
+    #[knuffel(child)]
+    pub dwt: bool,
+    #[knuffel(child)]
+    pub dwtp: bool,
     #[knuffel(child)]
     pub natural_scroll: bool,
     #[knuffel(child, unwrap(argument), default)]
     pub accel_speed: f64,
     #[knuffel(child, unwrap(argument, str))]
     pub accel_profile: Option<AccelProfile>,
+    #[knuffel(child, unwrap(argument, str))]
+    pub scroll_method: Option<ScrollMethod>,
+    #[knuffel(child, unwrap(argument))]
+    pub scroll_button: Option<u32>,
+    #[knuffel(child, unwrap(argument, str))]
+    pub click_method: Option<ClickMethod>,
+    #[knuffel(child, unwrap(argument, str))]
+    pub tap_button_map: Option<TapButtonMap>,
+    #[knuffel(child)]
+    pub left_handed: bool,
+    #[knuffel(child)]
+    pub middle_emulation: bool,
+    #[knuffel(child, unwrap(argument), default = 1.)]
+    pub scroll_factor: f64,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -178,6 +277,76 @@ impl From<TapButtonMap> for input::TapButtonMap {
     }
 }
 
+impl Default for Touchpad {
+    fn default() -> Self {
+        Self {
+            off: false,
+            tap: false,
+            dwt: false,
+            dwtp: false,
+            natural_scroll: false,
+            accel_speed: 0.,
+            accel_profile: None,
+            scroll_method: None,
+            click_method: None,
+            tap_button_map: None,
+            left_handed: false,
+            middle_emulation: false,
+            scroll_factor: 1.,
+        }
+    }
+}
+
+impl Default for PointerDevice {
+    fn default() -> Self {
+        Self {
+            off: false,
+            natural_scroll: false,
+            accel_speed: 0.,
+            accel_profile: None,
+            scroll_method: None,
+            scroll_button: None,
+            left_handed: false,
+            middle_emulation: false,
+            scroll_factor: 1.,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ScrollMethod {
+    NoScroll,
+    TwoFinger,
+    Edge,
+    OnButtonDown,
+}
+
+impl From<ScrollMethod> for input::ScrollMethod {
+    fn from(value: ScrollMethod) -> Self {
+        match value {
+            ScrollMethod::NoScroll => Self::NoScroll,
+            ScrollMethod::TwoFinger => Self::TwoFinger,
+            ScrollMethod::Edge => Self::Edge,
+            ScrollMethod::OnButtonDown => Self::OnButtonDown,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ClickMethod {
+    ButtonAreas,
+    Clickfinger,
+}
+
+impl From<ClickMethod> for input::ClickMethod {
+    fn from(value: ClickMethod) -> Self {
+        match value {
+            ClickMethod::ButtonAreas => Self::ButtonAreas,
+            ClickMethod::Clickfinger => Self::Clickfinger,
+        }
+    }
+}
+
 #[derive(knuffel::Decode, Debug, Default, PartialEq)]
 pub struct Tablet {
     #[knuffel(child, unwrap(argument))]
@@ -213,6 +382,38 @@ impl Default for Output {
     }
 }
 
+/// Output configuration changes made at runtime, e.g. through IPC.
+///
+/// These are kept in memory and applied on top of the output configuration from the config file.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct OutputOverride {
+    pub off: Option<bool>,
+    pub scale: Option<f64>,
+    pub transform: Option<Transform>,
+    pub position: Option<Option<Position>>,
+    pub mode: Option<Option<Mode>>,
+}
+
+impl OutputOverride {
+    pub fn apply_to(&self, output: &mut Output) {
+        if let Some(off) = self.off {
+            output.off = off;
+        }
+        if let Some(scale) = self.scale {
+            output.scale = scale;
+        }
+        if let Some(transform) = self.transform {
+            output.transform = transform;
+        }
+        if let Some(position) = self.position {
+            output.position = position;
+        }
+        if let Some(mode) = self.mode {
+            output.mode = mode;
+        }
+    }
+}
+
 /// Output transform, which goes counter-clockwise.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Transform {
@@ -226,6 +427,21 @@ pub enum Transform {
     Flipped270,
 }
 
+impl From<niri_ipc::Transform> for Transform {
+    fn from(value: niri_ipc::Transform) -> Self {
+        match value {
+            niri_ipc::Transform::Normal => Self::Normal,
+            niri_ipc::Transform::_90 => Self::_90,
+            niri_ipc::Transform::_180 => Self::_180,
+            niri_ipc::Transform::_270 => Self::_270,
+            niri_ipc::Transform::Flipped => Self::Flipped,
+            niri_ipc::Transform::Flipped90 => Self::Flipped90,
+            niri_ipc::Transform::Flipped180 => Self::Flipped180,
+            niri_ipc::Transform::Flipped270 => Self::Flipped270,
+        }
+    }
+}
+
 impl FromStr for Transform {
     type Err = miette::Error;
 
@@ -277,12 +493,35 @@ pub struct Mode {
     pub refresh: Option<f64>,
 }
 
+impl From<niri_ipc::ConfiguredMode> for Mode {
+    fn from(value: niri_ipc::ConfiguredMode) -> Self {
+        Self {
+            width: value.width,
+            height: value.height,
+            refresh: value.refresh,
+        }
+    }
+}
+
+impl From<niri_ipc::ConfiguredPosition> for Position {
+    fn from(value: niri_ipc::ConfiguredPosition) -> Self {
+        Self {
+            x: value.x,
+            y: value.y,
+        }
+    }
+}
+
 #[derive(knuffel::Decode, Debug, Default, Clone, PartialEq)]
 pub struct Layout {
     #[knuffel(child, default)]
     pub focus_ring: FocusRing,
     #[knuffel(child, default = default_border())]
     pub border: FocusRing,
+    #[knuffel(child, default)]
+    pub tab_indicator: TabIndicator,
+    #[knuffel(child, default)]
+    pub insert_hint: InsertHint,
     #[knuffel(child, unwrap(children), default)]
     pub preset_column_widths: Vec<PresetWidth>,
     #[knuffel(child)]
@@ -333,6 +572,49 @@ pub const fn default_border() -> FocusRing {
     }
 }
 
+#[derive(knuffel::Decode, Debug, Clone, Copy, PartialEq)]
+pub struct TabIndicator {
+    #[knuffel(child)]
+    pub off: bool,
+    #[knuffel(child, unwrap(argument), default = 4)]
+    pub width: u16,
+    #[knuffel(child, unwrap(argument), default = 4)]
+    pub gap: u16,
+    #[knuffel(child, default = Color::new(127, 200, 255, 255))]
+    pub active_color: Color,
+    #[knuffel(child, default = Color::new(80, 80, 80, 255))]
+    pub inactive_color: Color,
+}
+
+impl Default for TabIndicator {
+    fn default() -> Self {
+        Self {
+            off: false,
+            width: 4,
+            gap: 4,
+            active_color: Color::new(127, 200, 255, 255),
+            inactive_color: Color::new(80, 80, 80, 255),
+        }
+    }
+}
+
+#[derive(knuffel::Decode, Debug, Clone, Copy, PartialEq)]
+pub struct InsertHint {
+    #[knuffel(child)]
+    pub off: bool,
+    #[knuffel(child, default = Color::new(127, 200, 255, 128))]
+    pub color: Color,
+}
+
+impl Default for InsertHint {
+    fn default() -> Self {
+        Self {
+            off: false,
+            color: Color::new(127, 200, 255, 128),
+        }
+    }
+}
+
 #[derive(knuffel::Decode, Debug, Default, Clone, Copy, PartialEq, Eq)]
 pub struct Color {
     #[knuffel(argument)]
@@ -396,6 +678,148 @@ pub struct Struts {
     pub bottom: u16,
 }
 
+#[derive(knuffel::Decode, Debug, Default, Clone, PartialEq)]
+pub struct WindowRule {
+    #[knuffel(children(name = "match"))]
+    pub matches: Vec<Match>,
+    #[knuffel(children(name = "exclude"))]
+    pub excludes: Vec<Match>,
+
+    // Rules applied when the window opens.
+    #[knuffel(child)]
+    pub default_column_width: Option<DefaultColumnWidth>,
+    #[knuffel(child, unwrap(argument))]
+    pub open_on_output: Option<String>,
+    #[knuffel(child, unwrap(argument))]
+    pub open_on_workspace: Option<String>,
+    #[knuffel(child, unwrap(argument))]
+    pub open_maximized: Option<bool>,
+    #[knuffel(child, unwrap(argument))]
+    pub open_fullscreen: Option<bool>,
+
+    // Rules applied for the whole lifetime of the window.
+    #[knuffel(child, unwrap(argument))]
+    pub min_width: Option<u16>,
+    #[knuffel(child, unwrap(argument))]
+    pub min_height: Option<u16>,
+    #[knuffel(child, unwrap(argument))]
+    pub max_width: Option<u16>,
+    #[knuffel(child, unwrap(argument))]
+    pub max_height: Option<u16>,
+    #[knuffel(child, default)]
+    pub focus_ring: BorderRule,
+    #[knuffel(child, default)]
+    pub border: BorderRule,
+}
+
+#[derive(knuffel::Decode, Debug, Default, Clone, PartialEq)]
+pub struct Match {
+    #[knuffel(property, str)]
+    pub app_id: Option<RegexEq>,
+    #[knuffel(property, str)]
+    pub title: Option<RegexEq>,
+    #[knuffel(property)]
+    pub at_startup: Option<bool>,
+}
+
+/// Overrides of the focus ring or border options for a window.
+#[derive(knuffel::Decode, Debug, Default, Clone, Copy, PartialEq)]
+pub struct BorderRule {
+    #[knuffel(child)]
+    pub off: bool,
+    #[knuffel(child)]
+    pub on: bool,
+    #[knuffel(child, unwrap(argument))]
+    pub width: Option<u16>,
+    #[knuffel(child)]
+    pub active_color: Option<Color>,
+    #[knuffel(child)]
+    pub inactive_color: Option<Color>,
+}
+
+impl BorderRule {
+    /// Applies the overrides from `other` on top of these ones.
+    pub fn merge_with(&mut self, other: &Self) {
+        if other.off {
+            self.off = true;
+            self.on = false;
+        }
+
+        if other.on {
+            self.off = false;
+            self.on = true;
+        }
+
+        if let Some(x) = other.width {
+            self.width = Some(x);
+        }
+        if let Some(x) = other.active_color {
+            self.active_color = Some(x);
+        }
+        if let Some(x) = other.inactive_color {
+            self.inactive_color = Some(x);
+        }
+    }
+
+    /// Returns the options with these overrides applied.
+    pub fn resolve_against(&self, mut config: FocusRing) -> FocusRing {
+        if self.off {
+            config.off = true;
+        }
+        if self.on {
+            config.off = false;
+        }
+
+        if let Some(x) = self.width {
+            config.width = x;
+        }
+        if let Some(x) = self.active_color {
+            config.active_color = x;
+        }
+        if let Some(x) = self.inactive_color {
+            config.inactive_color = x;
+        }
+
+        config
+    }
+}
+
+/// `Regex` that implements `PartialEq` by its string form.
+#[derive(Debug, Clone)]
+pub struct RegexEq(pub Regex);
+
+impl PartialEq for RegexEq {
+    fn eq(&self, other: &Self) -> bool {
+        self.0.as_str() == other.0.as_str()
+    }
+}
+
+impl Eq for RegexEq {}
+
+impl FromStr for RegexEq {
+    type Err = <Regex as FromStr>::Err;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Regex::from_str(s).map(Self)
+    }
+}
+
+/// Named workspace that always exists.
+#[derive(knuffel::Decode, Debug, Clone, PartialEq, Eq)]
+pub struct Workspace {
+    #[knuffel(argument)]
+    pub name: String,
+    #[knuffel(child, unwrap(argument))]
+    pub open_on_output: Option<String>,
+}
+
+/// Workspace referenced by an action, either by index or by name.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum WorkspaceReference {
+    Index(u8),
+    Name(String),
+}
+
 #[derive(knuffel::Decode, Debug, Default, Clone, Copy, PartialEq, Eq)]
 pub struct HotkeyOverlay {
     #[knuffel(child)]
@@ -414,8 +838,12 @@ pub struct Animations {
     pub horizontal_view_movement: Animation,
     #[knuffel(child, default = Animation::default_window_open())]
     pub window_open: Animation,
+    #[knuffel(child, default = Animation::default_window_movement())]
+    pub window_movement: Animation,
     #[knuffel(child, default = Animation::default_config_notification_open_close())]
     pub config_notification_open_close: Animation,
+    #[knuffel(child, default = Animation::default_overview_open_close())]
+    pub overview_open_close: Animation,
 }
 
 impl Default for Animations {
@@ -426,7 +854,9 @@ impl Default for Animations {
             workspace_switch: Animation::default_workspace_switch(),
             horizontal_view_movement: Animation::default_horizontal_view_movement(),
             window_open: Animation::default_window_open(),
+            window_movement: Animation::default_window_movement(),
             config_notification_open_close: Animation::default_config_notification_open_close(),
+            overview_open_close: Animation::default_overview_open_close(),
         }
     }
 }
@@ -470,6 +900,14 @@ impl Animation {
         Self::default()
     }
 
+    pub const fn default_overview_open_close() -> Self {
+        Self::default()
+    }
+
+    pub const fn default_window_movement() -> Self {
+        Self::default()
+    }
+
     pub const fn default_window_open() -> Self {
         Self {
             duration_ms: Some(150),
@@ -496,12 +934,101 @@ pub struct Bind {
     pub actions: Vec<Action>,
 }
 
+/// Named set of binds that replaces the regular binds while it is active.
+#[derive(knuffel::Decode, Debug, PartialEq)]
+pub struct BindMode {
+    #[knuffel(argument)]
+    pub name: String,
+    /// Leave the mode after this long without any of its binds being triggered.
+    #[knuffel(property)]
+    pub timeout_ms: Option<u32>,
+    /// Leave the mode after the first bind, and on any unbound key.
+    ///
+    /// This makes the mode work as the second half of a key sequence.
+    #[knuffel(property, default)]
+    pub oneshot: bool,
+    /// Show the hotkey overlay with the binds of the mode while it is active.
+    #[knuffel(property, default)]
+    pub show_hotkey_overlay: bool,
+    #[knuffel(children)]
+    pub binds: Vec<Bind>,
+}
+
 #[derive(Debug, PartialEq, Eq)]
 pub struct Key {
-    pub keysym: Keysym,
+    pub trigger: Trigger,
     pub modifiers: Modifiers,
 }
 
+/// What triggers a bind: a key, a mouse button or scrolling the wheel.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Trigger {
+    Keysym(Keysym),
+    MouseLeft,
+    MouseRight,
+    MouseMiddle,
+    MouseBack,
+    MouseForward,
+    WheelScrollDown,
+    WheelScrollUp,
+    WheelScrollLeft,
+    WheelScrollRight,
+}
+
+#[derive(knuffel::Decode, Debug, PartialEq)]
+pub struct Gestures(#[knuffel(children)] pub Vec<GestureBind>);
+
+#[derive(Debug, PartialEq)]
+pub struct GestureBind {
+    pub kind: GestureKind,
+    pub fingers: u8,
+    pub direction: Option<GestureDirection>,
+    pub action: GestureAction,
+}
+
+/// Gesture bind as written in the config, before checking that it has exactly one action.
+#[derive(knuffel::Decode)]
+struct GestureBindNode {
+    #[knuffel(node_name)]
+    kind: GestureKind,
+    #[knuffel(property)]
+    fingers: u8,
+    #[knuffel(property, str)]
+    direction: Option<GestureDirection>,
+    #[knuffel(children)]
+    actions: Vec<GestureAction>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GestureKind {
+    Swipe,
+    Pinch,
+    Hold,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GestureDirection {
+    Horizontal,
+    Vertical,
+    Left,
+    Right,
+    Up,
+    Down,
+    In,
+    Out,
+}
+
+/// What a gesture does.
+///
+/// The continuous gestures follow the fingers and only make sense for swipes. Everything else
+/// runs a regular action once the gesture is over.
+#[derive(Debug, Clone, PartialEq)]
+pub enum GestureAction {
+    WorkspaceSwitch,
+    ViewScroll,
+    Action(Action),
+}
+
 bitflags! {
     #[derive(Debug, Clone, Copy, PartialEq, Eq)]
     pub struct Modifiers : u8 {
@@ -531,7 +1058,13 @@ pub enum Action {
     ScreenshotScreen,
     ScreenshotWindow,
     CloseWindow,
+    #[knuffel(skip)]
+    CloseWindowById(u64),
     FullscreenWindow,
+    #[knuffel(skip)]
+    FullscreenWindowById(u64),
+    #[knuffel(skip)]
+    FocusWindow(u64),
     FocusColumnLeft,
     FocusColumnRight,
     FocusColumnFirst,
@@ -555,13 +1088,18 @@ pub enum Action {
     CenterColumn,
     FocusWorkspaceDown,
     FocusWorkspaceUp,
-    FocusWorkspace(#[knuffel(argument)] u8),
+    FocusWorkspace(#[knuffel(argument)] WorkspaceReference),
     MoveWindowToWorkspaceDown,
     MoveWindowToWorkspaceUp,
-    MoveWindowToWorkspace(#[knuffel(argument)] u8),
+    MoveWindowToWorkspace(#[knuffel(argument)] WorkspaceReference),
+    #[knuffel(skip)]
+    MoveWindowToWorkspaceById {
+        window_id: u64,
+        reference: WorkspaceReference,
+    },
     MoveColumnToWorkspaceDown,
     MoveColumnToWorkspaceUp,
-    MoveColumnToWorkspace(#[knuffel(argument)] u8),
+    MoveColumnToWorkspace(#[knuffel(argument)] WorkspaceReference),
     MoveWorkspaceDown,
     MoveWorkspaceUp,
     FocusMonitorLeft,
@@ -577,15 +1115,65 @@ pub enum Action {
     MoveColumnToMonitorDown,
     MoveColumnToMonitorUp,
     SetWindowHeight(#[knuffel(argument, str)] SizeChange),
+    #[knuffel(skip)]
+    SetWindowHeightById {
+        id: u64,
+        change: SizeChange,
+    },
     SwitchPresetColumnWidth,
+    #[knuffel(skip)]
+    SwitchPresetColumnWidthById(u64),
     MaximizeColumn,
+    #[knuffel(skip)]
+    MaximizeColumnById(u64),
     SetColumnWidth(#[knuffel(argument, str)] SizeChange),
+    #[knuffel(skip)]
+    SetColumnWidthById {
+        id: u64,
+        change: SizeChange,
+    },
     SwitchLayout(#[knuffel(argument, str)] LayoutSwitchTarget),
     ShowHotkeyOverlay,
+    EnterMode(#[knuffel(argument)] String),
+    ExitMode,
     MoveWorkspaceToMonitorLeft,
     MoveWorkspaceToMonitorRight,
     MoveWorkspaceToMonitorDown,
     MoveWorkspaceToMonitorUp,
+    ToggleWindowFloating,
+    SwitchFocusBetweenFloatingAndTiling,
+    ToggleColumnTabbedDisplay,
+    ToggleOverview,
+}
+
+impl Default for Gestures {
+    fn default() -> Self {
+        Self(vec![
+            GestureBind {
+                kind: GestureKind::Swipe,
+                fingers: 3,
+                direction: Some(GestureDirection::Vertical),
+                action: GestureAction::WorkspaceSwitch,
+            },
+            GestureBind {
+                kind: GestureKind::Swipe,
+                fingers: 3,
+                direction: Some(GestureDirection::Horizontal),
+                action: GestureAction::ViewScroll,
+            },
+        ])
+    }
+}
+
+impl GestureDirection {
+    /// Returns whether a gesture bound to this direction triggers on a gesture going `actual`.
+    pub fn matches(self, actual: GestureDirection) -> bool {
+        match self {
+            Self::Horizontal => matches!(actual, Self::Left | Self::Right | Self::Horizontal),
+            Self::Vertical => matches!(actual, Self::Up | Self::Down | Self::Vertical),
+            _ => self == actual,
+        }
+    }
 }
 
 impl From<niri_ipc::Action> for Action {
@@ -597,8 +1185,11 @@ impl From<niri_ipc::Action> for Action {
             niri_ipc::Action::Screenshot => Self::Screenshot,
             niri_ipc::Action::ScreenshotScreen => Self::ScreenshotScreen,
             niri_ipc::Action::ScreenshotWindow => Self::ScreenshotWindow,
-            niri_ipc::Action::CloseWindow => Self::CloseWindow,
-            niri_ipc::Action::FullscreenWindow => Self::FullscreenWindow,
+            niri_ipc::Action::FocusWindow { id } => Self::FocusWindow(id),
+            niri_ipc::Action::CloseWindow { id: None } => Self::CloseWindow,
+            niri_ipc::Action::CloseWindow { id: Some(id) } => Self::CloseWindowById(id),
+            niri_ipc::Action::FullscreenWindow { id: None } => Self::FullscreenWindow,
+            niri_ipc::Action::FullscreenWindow { id: Some(id) } => Self::FullscreenWindowById(id),
             niri_ipc::Action::FocusColumnLeft => Self::FocusColumnLeft,
             niri_ipc::Action::FocusColumnRight => Self::FocusColumnRight,
             niri_ipc::Action::FocusColumnFirst => Self::FocusColumnFirst,
@@ -624,13 +1215,27 @@ impl From<niri_ipc::Action> for Action {
             niri_ipc::Action::CenterColumn => Self::CenterColumn,
             niri_ipc::Action::FocusWorkspaceDown => Self::FocusWorkspaceDown,
             niri_ipc::Action::FocusWorkspaceUp => Self::FocusWorkspaceUp,
-            niri_ipc::Action::FocusWorkspace { index } => Self::FocusWorkspace(index),
+            niri_ipc::Action::FocusWorkspace { reference } => {
+                Self::FocusWorkspace(WorkspaceReference::from(reference))
+            }
             niri_ipc::Action::MoveWindowToWorkspaceDown => Self::MoveWindowToWorkspaceDown,
             niri_ipc::Action::MoveWindowToWorkspaceUp => Self::MoveWindowToWorkspaceUp,
-            niri_ipc::Action::MoveWindowToWorkspace { index } => Self::MoveWindowToWorkspace(index),
+            niri_ipc::Action::MoveWindowToWorkspace {
+                window_id: None,
+                reference,
+            } => Self::MoveWindowToWorkspace(WorkspaceReference::from(reference)),
+            niri_ipc::Action::MoveWindowToWorkspace {
+                window_id: Some(window_id),
+                reference,
+            } => Self::MoveWindowToWorkspaceById {
+                window_id,
+                reference: WorkspaceReference::from(reference),
+            },
             niri_ipc::Action::MoveColumnToWorkspaceDown => Self::MoveColumnToWorkspaceDown,
             niri_ipc::Action::MoveColumnToWorkspaceUp => Self::MoveColumnToWorkspaceUp,
-            niri_ipc::Action::MoveColumnToWorkspace { index } => Self::MoveColumnToWorkspace(index),
+            niri_ipc::Action::MoveColumnToWorkspace { reference } => {
+                Self::MoveColumnToWorkspace(WorkspaceReference::from(reference))
+            }
             niri_ipc::Action::MoveWorkspaceDown => Self::MoveWorkspaceDown,
             niri_ipc::Action::MoveWorkspaceUp => Self::MoveWorkspaceUp,
             niri_ipc::Action::FocusMonitorLeft => Self::FocusMonitorLeft,
@@ -645,17 +1250,37 @@ impl From<niri_ipc::Action> for Action {
             niri_ipc::Action::MoveColumnToMonitorRight => Self::MoveColumnToMonitorRight,
             niri_ipc::Action::MoveColumnToMonitorDown => Self::MoveColumnToMonitorDown,
             niri_ipc::Action::MoveColumnToMonitorUp => Self::MoveColumnToMonitorUp,
-            niri_ipc::Action::SetWindowHeight { change } => Self::SetWindowHeight(change),
-            niri_ipc::Action::SwitchPresetColumnWidth => Self::SwitchPresetColumnWidth,
-            niri_ipc::Action::MaximizeColumn => Self::MaximizeColumn,
-            niri_ipc::Action::SetColumnWidth { change } => Self::SetColumnWidth(change),
+            niri_ipc::Action::SetWindowHeight { id: None, change } => Self::SetWindowHeight(change),
+            niri_ipc::Action::SetWindowHeight {
+                id: Some(id),
+                change,
+            } => Self::SetWindowHeightById { id, change },
+            niri_ipc::Action::SwitchPresetColumnWidth { id: None } => Self::SwitchPresetColumnWidth,
+            niri_ipc::Action::SwitchPresetColumnWidth { id: Some(id) } => {
+                Self::SwitchPresetColumnWidthById(id)
+            }
+            niri_ipc::Action::MaximizeColumn { id: None } => Self::MaximizeColumn,
+            niri_ipc::Action::MaximizeColumn { id: Some(id) } => Self::MaximizeColumnById(id),
+            niri_ipc::Action::SetColumnWidth { id: None, change } => Self::SetColumnWidth(change),
+            niri_ipc::Action::SetColumnWidth {
+                id: Some(id),
+                change,
+            } => Self::SetColumnWidthById { id, change },
             niri_ipc::Action::SwitchLayout { layout } => Self::SwitchLayout(layout),
             niri_ipc::Action::ShowHotkeyOverlay => Self::ShowHotkeyOverlay,
+            niri_ipc::Action::EnterMode { name } => Self::EnterMode(name),
+            niri_ipc::Action::ExitMode => Self::ExitMode,
             niri_ipc::Action::MoveWorkspaceToMonitorLeft => Self::MoveWorkspaceToMonitorLeft,
             niri_ipc::Action::MoveWorkspaceToMonitorRight => Self::MoveWorkspaceToMonitorRight,
             niri_ipc::Action::MoveWorkspaceToMonitorDown => Self::MoveWorkspaceToMonitorDown,
             niri_ipc::Action::MoveWorkspaceToMonitorUp => Self::MoveWorkspaceToMonitorUp,
             niri_ipc::Action::ToggleDebugTint => Self::ToggleDebugTint,
+            niri_ipc::Action::ToggleWindowFloating => Self::ToggleWindowFloating,
+            niri_ipc::Action::SwitchFocusBetweenFloatingAndTiling => {
+                Self::SwitchFocusBetweenFloatingAndTiling
+            }
+            niri_ipc::Action::ToggleColumnTabbedDisplay => Self::ToggleColumnTabbedDisplay,
+            niri_ipc::Action::ToggleOverview => Self::ToggleOverview,
         }
     }
 }
@@ -678,24 +1303,164 @@ pub struct DebugConfig {
 
 impl Config {
     pub fn load(path: &Path) -> miette::Result<Self> {
+        Self::load_with_files(path, &mut Vec::new())
+    }
+
+    /// Loads the config along with all files it includes.
+    ///
+    /// Every file that was read is pushed to `files`, even when loading fails, so that the caller
+    /// can watch them for changes.
+    pub fn load_with_files(path: &Path, files: &mut Vec<PathBuf>) -> miette::Result<Self> {
         let _span = tracy_client::span!("Config::load");
-        Self::load_internal(path).context("error loading config")
+        Self::load_internal(path, files).context("error loading config")
+    }
+
+    fn load_internal(path: &Path, files: &mut Vec<PathBuf>) -> miette::Result<Self> {
+        let mut config = Self::parse("", "").unwrap();
+        config.load_file(path, "config.kdl", files, &mut Vec::new())?;
+        debug!("loaded config from {path:?}");
+        Ok(config)
     }
 
-    fn load_internal(path: &Path) -> miette::Result<Self> {
+    /// Reads one config file and merges it on top of `self`.
+    ///
+    /// Top-level nodes are applied in document order, with `include` directives loaded in place.
+    /// Outputs, workspaces, binds and modes replace earlier ones with the same name or key, other
+    /// lists are appended to, and sections like `input` or `layout` replace earlier ones as a
+    /// whole.
+    fn load_file(
+        &mut self,
+        path: &Path,
+        filename: &str,
+        files: &mut Vec<PathBuf>,
+        stack: &mut Vec<PathBuf>,
+    ) -> miette::Result<()> {
+        files.push(path.to_owned());
+
         let contents = std::fs::read_to_string(path)
             .into_diagnostic()
             .with_context(|| format!("error reading {path:?}"))?;
 
-        let config = Self::parse("config.kdl", &contents).context("error parsing")?;
-        debug!("loaded config from {path:?}");
-        Ok(config)
+        // Each file is parsed on its own, so that errors point into the right file.
+        let document: knuffel::ast::Document<knuffel::span::Span> =
+            knuffel::parse_ast(filename, &contents).context("error parsing")?;
+        let Config {
+            input,
+            outputs,
+            spawn_at_startup,
+            layout,
+            prefer_no_csd,
+            cursor,
+            screenshot_path,
+            hotkey_overlay,
+            animations,
+            window_rules,
+            workspaces,
+            binds,
+            modes,
+            gestures,
+            debug,
+            includes,
+        } = Self::parse(filename, &contents).context("error parsing")?;
+
+        let mut input = Some(input);
+        let mut layout = Some(layout);
+        let mut cursor = Some(cursor);
+        let mut screenshot_path = Some(screenshot_path);
+        let mut hotkey_overlay = Some(hotkey_overlay);
+        let mut animations = Some(animations);
+        let mut binds = Some(binds);
+        let mut gestures = Some(gestures);
+        let mut debug = Some(debug);
+        let mut outputs = outputs.into_iter();
+        let mut spawn_at_startup = spawn_at_startup.into_iter();
+        let mut window_rules = window_rules.into_iter();
+        let mut workspaces = workspaces.into_iter();
+        let mut modes = modes.into_iter();
+        let mut includes = includes.into_iter();
+
+        let canonical = path.canonicalize().unwrap_or_else(|_| path.to_owned());
+        stack.push(canonical);
+
+        for node in &document.nodes {
+            match &**node.node_name {
+                "input" => self.input = input.take().unwrap(),
+                "output" => {
+                    let output = outputs.next().unwrap();
+                    match self.outputs.iter_mut().find(|o| o.name == output.name) {
+                        Some(existing) => *existing = output,
+                        None => self.outputs.push(output),
+                    }
+                }
+                "spawn-at-startup" => self.spawn_at_startup.extend(spawn_at_startup.next()),
+                "layout" => self.layout = layout.take().unwrap(),
+                "prefer-no-csd" => self.prefer_no_csd = prefer_no_csd,
+                "cursor" => self.cursor = cursor.take().unwrap(),
+                "screenshot-path" => self.screenshot_path = screenshot_path.take().unwrap(),
+                "hotkey-overlay" => self.hotkey_overlay = hotkey_overlay.take().unwrap(),
+                "animations" => self.animations = animations.take().unwrap(),
+                "window-rule" => self.window_rules.extend(window_rules.next()),
+                "workspace" => {
+                    let ws = workspaces.next().unwrap();
+                    match self.workspaces.iter_mut().find(|w| w.name == ws.name) {
+                        Some(existing) => *existing = ws,
+                        None => self.workspaces.push(ws),
+                    }
+                }
+                "binds" => {
+                    for bind in binds.take().unwrap().0 {
+                        match self.binds.0.iter_mut().find(|b| b.key == bind.key) {
+                            Some(existing) => *existing = bind,
+                            None => self.binds.0.push(bind),
+                        }
+                    }
+                }
+                "mode" => {
+                    let mode = modes.next().unwrap();
+                    match self.modes.iter_mut().find(|m| m.name == mode.name) {
+                        Some(existing) => *existing = mode,
+                        None => self.modes.push(mode),
+                    }
+                }
+                "gestures" => self.gestures = gestures.take().unwrap(),
+                "debug" => self.debug = debug.take().unwrap(),
+                "include" => {
+                    let include = includes.next().unwrap();
+
+                    // Relative paths are resolved against the directory of the including file.
+                    let include_path = match path.parent() {
+                        Some(parent) => parent.join(&include.path),
+                        None => include.path.clone(),
+                    };
+
+                    let canonical = include_path
+                        .canonicalize()
+                        .unwrap_or_else(|_| include_path.clone());
+                    if stack.contains(&canonical) {
+                        return Err(miette!("{include_path:?} includes itself"))
+                            .with_context(|| format!("error including {:?}", include.path));
+                    }
+
+                    let include_filename = include.path.to_string_lossy();
+                    self.load_file(&include_path, &include_filename, files, stack)
+                        .with_context(|| format!("error including {:?}", include.path))?;
+                }
+                _ => (),
+            }
+        }
+
+        stack.pop();
+        Ok(())
     }
 
     pub fn parse(filename: &str, text: &str) -> Result<Self, knuffel::Error> {
         let _span = tracy_client::span!("Config::parse");
         knuffel::parse(filename, text)
     }
+
+    pub fn find_mode(&self, name: &str) -> Option<&BindMode> {
+        self.modes.iter().find(|mode| mode.name == name)
+    }
 }
 
 impl Default for Config {
@@ -708,6 +1473,53 @@ impl Default for Config {
     }
 }
 
+impl From<WorkspaceReferenceArg> for WorkspaceReference {
+    fn from(reference: WorkspaceReferenceArg) -> Self {
+        match reference {
+            WorkspaceReferenceArg::Index(index) => Self::Index(index),
+            WorkspaceReferenceArg::Name(name) => Self::Name(name),
+        }
+    }
+}
+
+impl<S: knuffel::traits::ErrorSpan> knuffel::DecodeScalar<S> for WorkspaceReference {
+    fn type_check(
+        type_name: &Option<knuffel::span::Spanned<knuffel::ast::TypeName, S>>,
+        ctx: &mut knuffel::decode::Context<S>,
+    ) {
+        if let Some(type_name) = &type_name {
+            ctx.emit_error(DecodeError::unexpected(
+                type_name,
+                "type name",
+                "no type name expected for this node",
+            ));
+        }
+    }
+
+    fn raw_decode(
+        value: &knuffel::span::Spanned<knuffel::ast::Literal, S>,
+        ctx: &mut knuffel::decode::Context<S>,
+    ) -> Result<Self, DecodeError<S>> {
+        match &**value {
+            knuffel::ast::Literal::Int(index) => match index.try_into() {
+                Ok(index) => Ok(Self::Index(index)),
+                Err(err) => {
+                    ctx.emit_error(DecodeError::conversion(value, err));
+                    Ok(Self::Index(0))
+                }
+            },
+            knuffel::ast::Literal::String(name) => Ok(Self::Name(name.to_string())),
+            _ => {
+                ctx.emit_error(DecodeError::unsupported(
+                    value,
+                    "expected a workspace index or name",
+                ));
+                Ok(Self::Index(0))
+            }
+        }
+    }
+}
+
 impl FromStr for Mode {
     type Err = miette::Error;
 
@@ -769,12 +1581,140 @@ impl FromStr for Key {
             }
         }
 
-        let keysym = keysym_from_name(key, KEYSYM_CASE_INSENSITIVE);
-        if keysym.raw() == KEY_NoSymbol {
-            return Err(miette!("invalid key: {key}"));
+        let trigger = if key.eq_ignore_ascii_case("MouseLeft") {
+            Trigger::MouseLeft
+        } else if key.eq_ignore_ascii_case("MouseRight") {
+            Trigger::MouseRight
+        } else if key.eq_ignore_ascii_case("MouseMiddle") {
+            Trigger::MouseMiddle
+        } else if key.eq_ignore_ascii_case("MouseBack") {
+            Trigger::MouseBack
+        } else if key.eq_ignore_ascii_case("MouseForward") {
+            Trigger::MouseForward
+        } else if key.eq_ignore_ascii_case("WheelScrollDown") {
+            Trigger::WheelScrollDown
+        } else if key.eq_ignore_ascii_case("WheelScrollUp") {
+            Trigger::WheelScrollUp
+        } else if key.eq_ignore_ascii_case("WheelScrollLeft") {
+            Trigger::WheelScrollLeft
+        } else if key.eq_ignore_ascii_case("WheelScrollRight") {
+            Trigger::WheelScrollRight
+        } else {
+            let keysym = keysym_from_name(key, KEYSYM_CASE_INSENSITIVE);
+            if keysym.raw() == KEY_NoSymbol {
+                return Err(miette!("invalid key: {key}"));
+            }
+            Trigger::Keysym(keysym)
+        };
+
+        Ok(Key { trigger, modifiers })
+    }
+}
+
+impl FromStr for GestureKind {
+    type Err = miette::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "swipe" => Ok(Self::Swipe),
+            "pinch" => Ok(Self::Pinch),
+            "hold" => Ok(Self::Hold),
+            _ => Err(miette!(
+                r#"invalid gesture, can be "swipe", "pinch" or "hold""#
+            )),
         }
+    }
+}
+
+impl FromStr for GestureDirection {
+    type Err = miette::Error;
 
-        Ok(Key { keysym, modifiers })
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "horizontal" => Ok(Self::Horizontal),
+            "vertical" => Ok(Self::Vertical),
+            "left" => Ok(Self::Left),
+            "right" => Ok(Self::Right),
+            "up" => Ok(Self::Up),
+            "down" => Ok(Self::Down),
+            "in" => Ok(Self::In),
+            "out" => Ok(Self::Out),
+            _ => Err(miette!(
+                r#"invalid gesture direction, can be "horizontal", "vertical", "left", "right", "up", "down", "in" or "out""#
+            )),
+        }
+    }
+}
+
+impl<S: knuffel::traits::ErrorSpan> knuffel::Decode<S> for GestureAction {
+    fn decode_node(
+        node: &knuffel::ast::SpannedNode<S>,
+        ctx: &mut knuffel::decode::Context<S>,
+    ) -> Result<Self, DecodeError<S>> {
+        let action = match &**node.node_name {
+            "workspace-switch" => Self::WorkspaceSwitch,
+            "view-scroll" => Self::ViewScroll,
+            _ => return Action::decode_node(node, ctx).map(Self::Action),
+        };
+
+        if let Some(type_name) = &node.type_name {
+            ctx.emit_error(DecodeError::unexpected(
+                type_name,
+                "type name",
+                "no type name expected for this node",
+            ));
+        }
+        for arg in &node.arguments {
+            ctx.emit_error(DecodeError::unexpected(
+                &arg.literal,
+                "argument",
+                "no arguments expected for this node",
+            ));
+        }
+        for name in node.properties.keys() {
+            ctx.emit_error(DecodeError::unexpected(
+                name,
+                "property",
+                "no properties expected for this node",
+            ));
+        }
+        for child in node.children() {
+            ctx.emit_error(DecodeError::unexpected(
+                child,
+                "node",
+                "no children expected for this node",
+            ));
+        }
+
+        Ok(action)
+    }
+}
+
+impl<S: knuffel::traits::ErrorSpan> knuffel::Decode<S> for GestureBind {
+    fn decode_node(
+        node: &knuffel::ast::SpannedNode<S>,
+        ctx: &mut knuffel::decode::Context<S>,
+    ) -> Result<Self, DecodeError<S>> {
+        let bind = GestureBindNode::decode_node(node, ctx)?;
+
+        // A continuous action can't be combined with anything else, so allow only one action.
+        for child in node.children().skip(1) {
+            ctx.emit_error(DecodeError::unexpected(
+                child,
+                "node",
+                "only one action is allowed per gesture",
+            ));
+        }
+        let Some(action) = bind.actions.into_iter().next() else {
+            return Err(DecodeError::missing(node, "gesture needs an action"));
+        };
+
+        Ok(Self {
+            kind: bind.kind,
+            fingers: bind.fingers,
+            direction: bind.direction,
+            action,
+        })
     }
 }
 
@@ -792,6 +1732,36 @@ impl FromStr for AccelProfile {
     }
 }
 
+impl FromStr for ScrollMethod {
+    type Err = miette::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "no-scroll" => Ok(Self::NoScroll),
+            "two-finger" => Ok(Self::TwoFinger),
+            "edge" => Ok(Self::Edge),
+            "on-button-down" => Ok(Self::OnButtonDown),
+            _ => Err(miette!(
+                r#"invalid scroll method, can be "no-scroll", "two-finger", "edge" or "on-button-down""#
+            )),
+        }
+    }
+}
+
+impl FromStr for ClickMethod {
+    type Err = miette::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "button-areas" => Ok(Self::ButtonAreas),
+            "clickfinger" => Ok(Self::Clickfinger),
+            _ => Err(miette!(
+                r#"invalid click method, can be "button-areas" or "clickfinger""#
+            )),
+        }
+    }
+}
+
 impl FromStr for TapButtonMap {
     type Err = miette::Error;
 
@@ -848,6 +1818,8 @@ mod tests {
                     accel-speed 0.2
                     accel-profile "flat"
                     tap-button-map "left-middle-right"
+                    click-method "clickfinger"
+                    scroll-factor 0.5
                 }
 
                 mouse {
@@ -856,10 +1828,30 @@ mod tests {
                     accel-profile "flat"
                 }
 
+                trackpoint {
+                    scroll-method "on-button-down"
+                    scroll-button 274
+                    middle-emulation
+                }
+
+                trackball {
+                    left-handed
+                }
+
                 tablet {
                     map-to-output "eDP-1"
                 }
 
+                touch {
+                    off
+                    map-to-output "eDP-1"
+                }
+
+                device "Logitech G203" {
+                    accel-profile "flat"
+                    scroll-factor 2.0
+                }
+
                 disable-power-key-handling
             }
 
@@ -883,6 +1875,11 @@ mod tests {
                     inactive-color 255 200 100 0
                 }
 
+                tab-indicator {
+                    width 6
+                    gap 2
+                }
+
                 preset-column-widths {
                     proportion 0.25
                     proportion 0.5
@@ -929,6 +1926,27 @@ mod tests {
                 }
             }
 
+            window-rule {
+                match app-id=".*alacritty"
+                exclude title="~nvim"
+
+                default-column-width { proportion 0.75; }
+                open-on-output "eDP-1"
+                open-maximized true
+                min-width 400
+
+                border {
+                    on
+                    width 8
+                }
+            }
+
+            workspace "browser"
+
+            workspace "chat" {
+                open-on-output "DP-1"
+            }
+
             binds {
                 Mod+T { spawn "alacritty"; }
                 Mod+Q { close-window; }
@@ -936,6 +1954,28 @@ mod tests {
                 Mod+Ctrl+Shift+L { move-window-to-monitor-right; }
                 Mod+Comma { consume-window-into-column; }
                 Mod+1 { focus-workspace 1;}
+                Mod+Shift+1 { focus-workspace "browser";}
+                Mod+WheelScrollDown { focus-workspace-down; }
+                Mod+MouseMiddle { close-window; }
+                Mod+Space { switch-layout "1"; }
+                Mod+Shift+Space { switch-layout "English (US)"; }
+                Mod+R { enter-mode "resize"; }
+            }
+
+            mode "resize" timeout-ms=2000 {
+                H { set-column-width "-10%"; }
+                Escape { exit-mode; }
+            }
+
+            mode "go" oneshot=true show-hotkey-overlay=true {
+                "1" { focus-workspace 1; }
+            }
+
+            gestures {
+                swipe fingers=3 direction="horizontal" { view-scroll; }
+                swipe fingers=4 direction="up" { toggle-overview; }
+                pinch fingers=4 direction="in" { close-window; }
+                hold fingers=3 { center-column; }
             }
 
             debug {
@@ -962,15 +2002,50 @@ mod tests {
                         accel_speed: 0.2,
                         accel_profile: Some(AccelProfile::Flat),
                         tap_button_map: Some(TapButtonMap::LeftMiddleRight),
+                        click_method: Some(ClickMethod::Clickfinger),
+                        scroll_factor: 0.5,
+                        ..Default::default()
                     },
                     mouse: Mouse {
                         natural_scroll: true,
                         accel_speed: 0.4,
                         accel_profile: Some(AccelProfile::Flat),
+                        ..Default::default()
+                    },
+                    trackpoint: Trackpoint {
+                        scroll_method: Some(ScrollMethod::OnButtonDown),
+                        scroll_button: Some(274),
+                        middle_emulation: true,
+                        ..Default::default()
+                    },
+                    trackball: Trackball {
+                        left_handed: true,
+                        ..Default::default()
                     },
                     tablet: Tablet {
                         map_to_output: Some("eDP-1".to_owned()),
                     },
+                    touch: Touch {
+                        off: true,
+                        map_to_output: Some("eDP-1".to_owned()),
+                    },
+                    devices: vec![InputDevice {
+                        name: "Logitech G203".to_owned(),
+                        off: false,
+                        tap: false,
+                        dwt: false,
+                        dwtp: false,
+                        natural_scroll: false,
+                        accel_speed: 0.,
+                        accel_profile: Some(AccelProfile::Flat),
+                        scroll_method: None,
+                        scroll_button: None,
+                        click_method: None,
+                        tap_button_map: None,
+                        left_handed: false,
+                        middle_emulation: false,
+                        scroll_factor: 2.,
+                    }],
                     disable_power_key_handling: true,
                 },
                 outputs: vec![Output {
@@ -1018,6 +2093,12 @@ mod tests {
                             a: 0,
                         },
                     },
+                    tab_indicator: TabIndicator {
+                        width: 6,
+                        gap: 2,
+                        ..Default::default()
+                    },
+                    insert_hint: InsertHint::default(),
                     preset_column_widths: vec![
                         PresetWidth::Proportion(0.25),
                         PresetWidth::Proportion(0.5),
@@ -1061,58 +2142,306 @@ mod tests {
                     },
                     ..Default::default()
                 },
+                window_rules: vec![WindowRule {
+                    matches: vec![Match {
+                        app_id: Some(RegexEq::from_str(".*alacritty").unwrap()),
+                        title: None,
+                        at_startup: None,
+                    }],
+                    excludes: vec![Match {
+                        app_id: None,
+                        title: Some(RegexEq::from_str("~nvim").unwrap()),
+                        at_startup: None,
+                    }],
+                    default_column_width: Some(DefaultColumnWidth(vec![PresetWidth::Proportion(
+                        0.75,
+                    )])),
+                    open_on_output: Some("eDP-1".to_owned()),
+                    open_maximized: Some(true),
+                    min_width: Some(400),
+                    border: BorderRule {
+                        on: true,
+                        width: Some(8),
+                        ..Default::default()
+                    },
+                    ..Default::default()
+                }],
+                workspaces: vec![
+                    Workspace {
+                        name: "browser".to_owned(),
+                        open_on_output: None,
+                    },
+                    Workspace {
+                        name: "chat".to_owned(),
+                        open_on_output: Some("DP-1".to_owned()),
+                    },
+                ],
                 binds: Binds(vec![
                     Bind {
                         key: Key {
-                            keysym: Keysym::t,
+                            trigger: Trigger::Keysym(Keysym::t),
                             modifiers: Modifiers::COMPOSITOR,
                         },
                         actions: vec![Action::Spawn(vec!["alacritty".to_owned()])],
                     },
                     Bind {
                         key: Key {
-                            keysym: Keysym::q,
+                            trigger: Trigger::Keysym(Keysym::q),
                             modifiers: Modifiers::COMPOSITOR,
                         },
                         actions: vec![Action::CloseWindow],
                     },
                     Bind {
                         key: Key {
-                            keysym: Keysym::h,
+                            trigger: Trigger::Keysym(Keysym::h),
                             modifiers: Modifiers::COMPOSITOR | Modifiers::SHIFT,
                         },
                         actions: vec![Action::FocusMonitorLeft],
                     },
                     Bind {
                         key: Key {
-                            keysym: Keysym::l,
+                            trigger: Trigger::Keysym(Keysym::l),
                             modifiers: Modifiers::COMPOSITOR | Modifiers::SHIFT | Modifiers::CTRL,
                         },
                         actions: vec![Action::MoveWindowToMonitorRight],
                     },
                     Bind {
                         key: Key {
-                            keysym: Keysym::comma,
+                            trigger: Trigger::Keysym(Keysym::comma),
                             modifiers: Modifiers::COMPOSITOR,
                         },
                         actions: vec![Action::ConsumeWindowIntoColumn],
                     },
                     Bind {
                         key: Key {
-                            keysym: Keysym::_1,
+                            trigger: Trigger::Keysym(Keysym::_1),
+                            modifiers: Modifiers::COMPOSITOR,
+                        },
+                        actions: vec![Action::FocusWorkspace(WorkspaceReference::Index(1))],
+                    },
+                    Bind {
+                        key: Key {
+                            trigger: Trigger::Keysym(Keysym::_1),
+                            modifiers: Modifiers::COMPOSITOR | Modifiers::SHIFT,
+                        },
+                        actions: vec![Action::FocusWorkspace(WorkspaceReference::Name(
+                            "browser".to_owned(),
+                        ))],
+                    },
+                    Bind {
+                        key: Key {
+                            trigger: Trigger::WheelScrollDown,
+                            modifiers: Modifiers::COMPOSITOR,
+                        },
+                        actions: vec![Action::FocusWorkspaceDown],
+                    },
+                    Bind {
+                        key: Key {
+                            trigger: Trigger::MouseMiddle,
+                            modifiers: Modifiers::COMPOSITOR,
+                        },
+                        actions: vec![Action::CloseWindow],
+                    },
+                    Bind {
+                        key: Key {
+                            trigger: Trigger::Keysym(Keysym::space),
+                            modifiers: Modifiers::COMPOSITOR,
+                        },
+                        actions: vec![Action::SwitchLayout(LayoutSwitchTarget::Index(1))],
+                    },
+                    Bind {
+                        key: Key {
+                            trigger: Trigger::Keysym(Keysym::space),
+                            modifiers: Modifiers::COMPOSITOR | Modifiers::SHIFT,
+                        },
+                        actions: vec![Action::SwitchLayout(LayoutSwitchTarget::Name(
+                            "English (US)".to_owned(),
+                        ))],
+                    },
+                    Bind {
+                        key: Key {
+                            trigger: Trigger::Keysym(Keysym::r),
                             modifiers: Modifiers::COMPOSITOR,
                         },
-                        actions: vec![Action::FocusWorkspace(1)],
+                        actions: vec![Action::EnterMode("resize".to_owned())],
+                    },
+                ]),
+                modes: vec![
+                    BindMode {
+                        name: "resize".to_owned(),
+                        timeout_ms: Some(2000),
+                        oneshot: false,
+                        show_hotkey_overlay: false,
+                        binds: vec![
+                            Bind {
+                                key: Key {
+                                    trigger: Trigger::Keysym(Keysym::h),
+                                    modifiers: Modifiers::empty(),
+                                },
+                                actions: vec![Action::SetColumnWidth(
+                                    SizeChange::AdjustProportion(-10.),
+                                )],
+                            },
+                            Bind {
+                                key: Key {
+                                    trigger: Trigger::Keysym(Keysym::Escape),
+                                    modifiers: Modifiers::empty(),
+                                },
+                                actions: vec![Action::ExitMode],
+                            },
+                        ],
+                    },
+                    BindMode {
+                        name: "go".to_owned(),
+                        timeout_ms: None,
+                        oneshot: true,
+                        show_hotkey_overlay: true,
+                        binds: vec![Bind {
+                            key: Key {
+                                trigger: Trigger::Keysym(Keysym::_1),
+                                modifiers: Modifiers::empty(),
+                            },
+                            actions: vec![Action::FocusWorkspace(WorkspaceReference::Index(1))],
+                        }],
+                    },
+                ],
+                gestures: Gestures(vec![
+                    GestureBind {
+                        kind: GestureKind::Swipe,
+                        fingers: 3,
+                        direction: Some(GestureDirection::Horizontal),
+                        action: GestureAction::ViewScroll,
+                    },
+                    GestureBind {
+                        kind: GestureKind::Swipe,
+                        fingers: 4,
+                        direction: Some(GestureDirection::Up),
+                        action: GestureAction::Action(Action::ToggleOverview),
+                    },
+                    GestureBind {
+                        kind: GestureKind::Pinch,
+                        fingers: 4,
+                        direction: Some(GestureDirection::In),
+                        action: GestureAction::Action(Action::CloseWindow),
+                    },
+                    GestureBind {
+                        kind: GestureKind::Hold,
+                        fingers: 3,
+                        direction: None,
+                        action: GestureAction::Action(Action::CenterColumn),
                     },
                 ]),
                 debug: DebugConfig {
                     render_drm_device: Some(PathBuf::from("/dev/dri/renderD129")),
                     ..Default::default()
                 },
+                includes: vec![],
             },
         );
     }
 
+    #[test]
+    fn load_includes() {
+        let tmp = tempfile::tempdir().unwrap();
+        let dir = tmp.path();
+        std::fs::create_dir_all(dir.join("hosts")).unwrap();
+
+        std::fs::write(
+            dir.join("config.kdl"),
+            r#"
+            output "eDP-1" { scale 2.0; }
+            spawn-at-startup "waybar"
+            prefer-no-csd
+
+            binds {
+                Mod+T { spawn "alacritty"; }
+                Mod+Q { close-window; }
+            }
+
+            include "hosts/laptop.kdl"
+
+            cursor { xcursor-size 32; }
+            "#,
+        )
+        .unwrap();
+        std::fs::write(
+            dir.join("hosts/laptop.kdl"),
+            r#"
+            output "eDP-1" { scale 1.5; }
+            output "HDMI-A-1" { off; }
+            spawn-at-startup "nm-applet"
+            cursor { xcursor-size 16; }
+
+            binds {
+                Mod+T { spawn "foot"; }
+            }
+            "#,
+        )
+        .unwrap();
+
+        let mut files = Vec::new();
+        let config = Config::load_with_files(&dir.join("config.kdl"), &mut files).unwrap();
+        assert_eq!(
+            files,
+            [dir.join("config.kdl"), dir.join("hosts/laptop.kdl")]
+        );
+
+        assert_eq!(
+            config
+                .outputs
+                .iter()
+                .map(|o| (&*o.name, o.scale, o.off))
+                .collect::<Vec<_>>(),
+            [("eDP-1", 1.5, false), ("HDMI-A-1", 1., true)],
+        );
+        assert_eq!(
+            config.spawn_at_startup,
+            [
+                SpawnAtStartup {
+                    command: vec!["waybar".to_owned()],
+                },
+                SpawnAtStartup {
+                    command: vec!["nm-applet".to_owned()],
+                },
+            ],
+        );
+        assert!(config.prefer_no_csd);
+        assert_eq!(config.cursor.xcursor_size, 32);
+        assert_eq!(
+            config
+                .binds
+                .0
+                .iter()
+                .map(|b| &b.actions)
+                .collect::<Vec<_>>(),
+            [
+                &vec![Action::Spawn(vec!["foot".to_owned()])],
+                &vec![Action::CloseWindow],
+            ],
+        );
+
+        // Include cycles are an error.
+        std::fs::write(dir.join("hosts/laptop.kdl"), "include \"../config.kdl\"").unwrap();
+        let mut files = Vec::new();
+        assert!(Config::load_with_files(&dir.join("config.kdl"), &mut files).is_err());
+        assert_eq!(
+            files,
+            [dir.join("config.kdl"), dir.join("hosts/laptop.kdl")]
+        );
+    }
+
+    #[test]
+    fn gesture_needs_exactly_one_action() {
+        let parse =
+            |gesture: &str| Config::parse("config.kdl", &format!("gestures {{ {gesture} }}"));
+
+        assert!(parse("swipe fingers=3 direction=\"up\" { toggle-overview; }").is_ok());
+        assert!(parse("swipe fingers=3 direction=\"up\"").is_err());
+        assert!(
+            parse("swipe fingers=3 direction=\"up\" { view-scroll; toggle-overview; }").is_err()
+        );
+    }
+
     #[test]
     fn can_create_default_config() {
         let _ = Config::default();
@@ -9,22 +9,82 @@ use serde::{Deserialize, Serialize};
 /// Name of the environment variable containing the niri IPC socket path.
 pub const SOCKET_PATH_ENV: &str = "NIRI_SOCKET";
 
+/// Version of the IPC protocol.
+///
+/// This is increased whenever requests or responses change in an incompatible way.
+pub const PROTOCOL_VERSION: u32 = 2;
+
 /// Request from client to niri.
 #[derive(Debug, Serialize, Deserialize)]
 pub enum Request {
+    /// Request the version of niri and of its IPC protocol.
+    Version,
     /// Request information about connected outputs.
     Outputs,
+    /// Request information about workspaces.
+    Workspaces,
+    /// Request information about open windows.
+    Windows,
+    /// Request information about the focused window.
+    FocusedWindow,
+    /// Request information about the keyboard layouts.
+    KeyboardLayouts,
+    /// Request the name of the active bind mode.
+    BindMode,
+    /// Keep the connection open and receive a stream of [`Event`]s.
+    ///
+    /// After the [`Reply`], events are sent as newline-delimited JSON.
+    EventStream,
     /// Perform an action.
     Action(Action),
+    /// Change the configuration of an output at runtime.
+    ///
+    /// The change is kept on top of the config file until niri exits.
+    Output {
+        /// Name of the output.
+        output: String,
+        /// Configuration change to apply.
+        action: OutputAction,
+    },
 }
 
-/// Response from niri to client.
+/// Reply from niri to client.
+///
+/// Every request gets a reply. The error contains a human-readable message.
+pub type Reply = Result<Response, String>;
+
+/// Successful response from niri to client.
 #[derive(Debug, Serialize, Deserialize)]
 pub enum Response {
+    /// A request that does not need a response was handled successfully.
+    Handled,
+    /// Version information.
+    Version(Version),
     /// Information about connected outputs.
     ///
     /// Map from connector name to output info.
     Outputs(HashMap<String, Output>),
+    /// Information about workspaces.
+    Workspaces(Vec<Workspace>),
+    /// Information about open windows.
+    Windows(Vec<Window>),
+    /// Information about the focused window, if any.
+    FocusedWindow(Option<Window>),
+    /// Information about the keyboard layouts.
+    KeyboardLayouts(KeyboardLayouts),
+    /// Name of the active bind mode, if any.
+    BindMode(Option<String>),
+}
+
+/// Version information.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Version {
+    /// Version of the running niri compositor.
+    pub compositor: String,
+    /// Version of the IPC protocol spoken by the compositor.
+    ///
+    /// See [`PROTOCOL_VERSION`].
+    pub protocol: u32,
 }
 
 /// Actions that niri can perform.
@@ -51,10 +111,28 @@ pub enum Action {
     ScreenshotScreen,
     /// Screenshot the focused window.
     ScreenshotWindow,
-    /// Close the focused window.
-    CloseWindow,
-    /// Toggle fullscreen on the focused window.
-    FullscreenWindow,
+    /// Focus a window by id.
+    FocusWindow {
+        /// Id of the window to focus.
+        #[cfg_attr(feature = "clap", arg(long))]
+        id: u64,
+    },
+    /// Close a window.
+    CloseWindow {
+        /// Id of the window to close.
+        ///
+        /// If `None`, uses the focused window.
+        #[cfg_attr(feature = "clap", arg(long))]
+        id: Option<u64>,
+    },
+    /// Toggle fullscreen on a window.
+    FullscreenWindow {
+        /// Id of the window to toggle fullscreen of.
+        ///
+        /// If `None`, uses the focused window.
+        #[cfg_attr(feature = "clap", arg(long))]
+        id: Option<u64>,
+    },
     /// Focus the column to the left.
     FocusColumnLeft,
     /// Focus the column to the right.
@@ -101,31 +179,43 @@ pub enum Action {
     FocusWorkspaceDown,
     /// Focus the workspace above.
     FocusWorkspaceUp,
-    /// Focus a workspace by index.
+    /// Focus a workspace by reference (index or name).
     FocusWorkspace {
-        /// Index of the workspace to focus.
+        /// Reference (index or name) of the workspace to focus.
         #[cfg_attr(feature = "clap", arg())]
-        index: u8,
+        #[serde(alias = "index")]
+        reference: WorkspaceReferenceArg,
     },
     /// Move the focused window to the workspace below.
     MoveWindowToWorkspaceDown,
     /// Move the focused window to the workspace above.
     MoveWindowToWorkspaceUp,
-    /// Move the focused window to a workspace by index.
+    /// Move a window to a workspace by reference (index or name).
+    ///
+    /// Moving a window by id does not change the focus. An index refers to a workspace on the
+    /// window's own output.
     MoveWindowToWorkspace {
-        /// Index of the target workspace.
+        /// Id of the window to move.
+        ///
+        /// If `None`, uses the focused window.
+        #[cfg_attr(feature = "clap", arg(long))]
+        window_id: Option<u64>,
+
+        /// Reference (index or name) of the target workspace.
         #[cfg_attr(feature = "clap", arg())]
-        index: u8,
+        #[serde(alias = "index")]
+        reference: WorkspaceReferenceArg,
     },
     /// Move the focused column to the workspace below.
     MoveColumnToWorkspaceDown,
     /// Move the focused column to the workspace above.
     MoveColumnToWorkspaceUp,
-    /// Move the focused column to a workspace by index.
+    /// Move the focused column to a workspace by reference (index or name).
     MoveColumnToWorkspace {
-        /// Index of the target workspace.
+        /// Reference (index or name) of the target workspace.
         #[cfg_attr(feature = "clap", arg())]
-        index: u8,
+        #[serde(alias = "index")]
+        reference: WorkspaceReferenceArg,
     },
     /// Move the focused workspace down.
     MoveWorkspaceDown,
@@ -155,30 +245,64 @@ pub enum Action {
     MoveColumnToMonitorDown,
     /// Move the focused column to the monitor above.
     MoveColumnToMonitorUp,
-    /// Change the height of the focused window.
+    /// Change the height of a window.
     SetWindowHeight {
+        /// Id of the window whose height to change.
+        ///
+        /// If `None`, uses the focused window.
+        #[cfg_attr(feature = "clap", arg(long))]
+        id: Option<u64>,
+
         /// How to change the height.
         #[cfg_attr(feature = "clap", arg())]
         change: SizeChange,
     },
     /// Switch between preset column widths.
-    SwitchPresetColumnWidth,
-    /// Toggle the maximized state of the focused column.
-    MaximizeColumn,
-    /// Change the width of the focused column.
+    SwitchPresetColumnWidth {
+        /// Id of a window in the column to change the width of.
+        ///
+        /// If `None`, uses the focused column.
+        #[cfg_attr(feature = "clap", arg(long))]
+        id: Option<u64>,
+    },
+    /// Toggle the maximized state of a column.
+    MaximizeColumn {
+        /// Id of a window in the column to maximize.
+        ///
+        /// If `None`, uses the focused column.
+        #[cfg_attr(feature = "clap", arg(long))]
+        id: Option<u64>,
+    },
+    /// Change the width of a column.
     SetColumnWidth {
+        /// Id of a window in the column to change the width of.
+        ///
+        /// If `None`, uses the focused column.
+        #[cfg_attr(feature = "clap", arg(long))]
+        id: Option<u64>,
+
         /// How to change the width.
         #[cfg_attr(feature = "clap", arg())]
         change: SizeChange,
     },
     /// Switch between keyboard layouts.
     SwitchLayout {
-        /// Layout to switch to.
+        /// Layout to switch to: "next", "prev", an index starting from 0, or a layout name.
+        ///
+        /// Run `niri msg keyboard-layouts` to see the layouts.
         #[cfg_attr(feature = "clap", arg())]
         layout: LayoutSwitchTarget,
     },
     /// Show the hotkey overlay.
     ShowHotkeyOverlay,
+    /// Enter a bind mode from the config.
+    EnterMode {
+        /// Name of the mode.
+        #[cfg_attr(feature = "clap", arg())]
+        name: String,
+    },
+    /// Leave the active bind mode.
+    ExitMode,
     /// Move the focused workspace to the monitor to the left.
     MoveWorkspaceToMonitorLeft,
     /// Move the focused workspace to the monitor to the right.
@@ -189,6 +313,14 @@ pub enum Action {
     MoveWorkspaceToMonitorUp,
     /// Toggle a debug tint on windows.
     ToggleDebugTint,
+    /// Move the focused window between the floating and the tiling layout.
+    ToggleWindowFloating,
+    /// Switch the focus between the floating and the tiling layout.
+    SwitchFocusBetweenFloatingAndTiling,
+    /// Toggle the focused column between normal and tabbed display.
+    ToggleColumnTabbedDisplay,
+    /// Toggle the overview of all workspaces.
+    ToggleOverview,
 }
 
 /// Change in window or column size.
@@ -204,13 +336,159 @@ pub enum SizeChange {
     AdjustProportion(f64),
 }
 
+/// Workspace reference (index or name) to operate on.
+///
+/// For compatibility with clients written before workspaces could be referenced by name, a bare
+/// number is also accepted as an index, and the actions taking a reference accept it in an
+/// `index` field.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(from = "WorkspaceReferenceArgDe")]
+pub enum WorkspaceReferenceArg {
+    /// Index of the workspace.
+    Index(u8),
+    /// Name of the workspace.
+    Name(String),
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum WorkspaceReferenceArgDe {
+    Bare(u8),
+    Index {
+        #[serde(rename = "Index")]
+        index: u8,
+    },
+    Name {
+        #[serde(rename = "Name")]
+        name: String,
+    },
+}
+
+impl From<WorkspaceReferenceArgDe> for WorkspaceReferenceArg {
+    fn from(value: WorkspaceReferenceArgDe) -> Self {
+        match value {
+            WorkspaceReferenceArgDe::Bare(index) | WorkspaceReferenceArgDe::Index { index } => {
+                Self::Index(index)
+            }
+            WorkspaceReferenceArgDe::Name { name } => Self::Name(name),
+        }
+    }
+}
+
 /// Layout to switch to.
-#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 pub enum LayoutSwitchTarget {
     /// The next configured layout.
     Next,
     /// The previous configured layout.
     Prev,
+    /// The layout with this index in the keymap, starting from 0.
+    Index(u8),
+    /// The layout with this name, as reported by [`Request::KeyboardLayouts`].
+    Name(String),
+}
+
+/// Output configuration change.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "clap", derive(clap::Subcommand))]
+pub enum OutputAction {
+    /// Turn off the output.
+    Off,
+    /// Turn on the output.
+    On,
+    /// Set the output mode.
+    Mode {
+        /// Mode to set, or "auto" for automatic selection.
+        ///
+        /// Run `niri msg outputs` to see the available modes.
+        #[cfg_attr(feature = "clap", arg())]
+        mode: ModeToSet,
+    },
+    /// Set the output scale.
+    Scale {
+        /// Scale factor to set.
+        #[cfg_attr(feature = "clap", arg())]
+        scale: f64,
+    },
+    /// Set the output transform.
+    Transform {
+        /// Transform to set, counter-clockwise.
+        #[cfg_attr(feature = "clap", arg())]
+        transform: Transform,
+    },
+    /// Set the output position.
+    Position {
+        /// Position to set, or "auto" for automatic selection.
+        #[cfg_attr(feature = "clap", command(subcommand))]
+        position: PositionToSet,
+    },
+}
+
+/// Output mode to set.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub enum ModeToSet {
+    /// Niri will pick the mode automatically.
+    Automatic,
+    /// Specific mode.
+    Specific(ConfiguredMode),
+}
+
+/// Output mode as set in the config file.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub struct ConfiguredMode {
+    /// Width in physical pixels.
+    pub width: u16,
+    /// Height in physical pixels.
+    pub height: u16,
+    /// Refresh rate in hertz.
+    ///
+    /// `None` picks the highest refresh rate for this size.
+    pub refresh: Option<f64>,
+}
+
+/// Output position to set.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "clap", derive(clap::Subcommand))]
+pub enum PositionToSet {
+    /// Position the output automatically.
+    #[cfg_attr(feature = "clap", command(name = "auto"))]
+    Automatic,
+    /// Set a specific position.
+    #[cfg_attr(feature = "clap", command(name = "set"))]
+    Specific(ConfiguredPosition),
+}
+
+/// Output position as set in the config file.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "clap", derive(clap::Args))]
+pub struct ConfiguredPosition {
+    /// Logical X position.
+    #[cfg_attr(feature = "clap", arg(allow_negative_numbers = true))]
+    pub x: i32,
+    /// Logical Y position.
+    #[cfg_attr(feature = "clap", arg(allow_negative_numbers = true))]
+    pub y: i32,
+}
+
+/// Output transform, which goes counter-clockwise.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Transform {
+    /// Untransformed.
+    Normal,
+    /// Rotated by 90°.
+    _90,
+    /// Rotated by 180°.
+    _180,
+    /// Rotated by 270°.
+    _270,
+    /// Flipped horizontally.
+    Flipped,
+    /// Rotated by 90° and flipped horizontally.
+    Flipped90,
+    /// Flipped vertically.
+    Flipped180,
+    /// Rotated by 270° and flipped horizontally.
+    Flipped270,
 }
 
 /// Connected output.
@@ -233,7 +511,7 @@ pub struct Output {
 }
 
 /// Output mode.
-#[derive(Debug, Serialize, Deserialize, Clone, Copy)]
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
 pub struct Mode {
     /// Width in physical pixels.
     pub width: u16,
@@ -243,6 +521,129 @@ pub struct Mode {
     pub refresh_rate: u32,
 }
 
+/// Workspace.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Workspace {
+    /// Index of the workspace on its output, starting from 1.
+    ///
+    /// This is the same index that actions like `focus-workspace` accept.
+    pub idx: u8,
+    /// Name of the workspace, if it is a named workspace.
+    pub name: Option<String>,
+    /// Name of the output that the workspace is on.
+    ///
+    /// `None` if no outputs are connected.
+    pub output: Option<String>,
+    /// Whether the workspace is the active one on its output.
+    pub is_active: bool,
+    /// Whether the workspace is the active one on the focused output.
+    pub is_focused: bool,
+}
+
+/// Toplevel window.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Window {
+    /// Unique id of this window.
+    ///
+    /// The id stays the same for as long as the window is open, and is not reused afterwards.
+    pub id: u64,
+    /// Title, if set.
+    pub title: Option<String>,
+    /// Application ID, if set.
+    pub app_id: Option<String>,
+    /// Name of the output that the window is on.
+    ///
+    /// `None` if no outputs are connected.
+    pub output: Option<String>,
+    /// Index of the workspace that the window is on, same as [`Workspace::idx`].
+    pub workspace_idx: u8,
+    /// Index of the window's column on the workspace, starting from 1.
+    ///
+    /// `None` for floating windows and for windows that are being moved with the mouse.
+    pub column_idx: Option<usize>,
+    /// Index of the window in its column, starting from 1.
+    ///
+    /// `None` for floating windows and for windows that are being moved with the mouse.
+    pub tile_idx: Option<usize>,
+    /// Width and height of the window in logical pixels.
+    pub size: (i32, i32),
+    /// Whether the window is fullscreen.
+    pub is_fullscreen: bool,
+    /// Index of the keyboard layout in the window, same as in
+    /// [`Event::KeyboardLayoutSwitched`].
+    ///
+    /// `None` unless keyboard layouts are tracked per window.
+    pub keyboard_layout_idx: Option<u8>,
+}
+
+/// Keyboard layouts of the current keymap.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct KeyboardLayouts {
+    /// Names of the layouts, in keymap order.
+    pub names: Vec<String>,
+    /// Index of the active layout in `names`.
+    pub current_idx: u8,
+}
+
+/// Event sent over the event stream.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub enum Event {
+    /// A workspace became the active one on its output, or its output became focused.
+    WorkspaceActivated {
+        /// The activated workspace.
+        workspace: Workspace,
+    },
+    /// A window was opened.
+    WindowOpened {
+        /// The new window.
+        window: Window,
+    },
+    /// A window was closed.
+    WindowClosed {
+        /// The closed window, as it was last reported.
+        window: Window,
+    },
+    /// The focused window changed.
+    WindowFocusChanged {
+        /// The newly focused window, or `None` if no window is focused.
+        window: Option<Window>,
+    },
+    /// The title of a window changed.
+    WindowTitleChanged {
+        /// The window with the new title.
+        window: Window,
+    },
+    /// The active keyboard layout changed.
+    KeyboardLayoutSwitched {
+        /// Index of the layout in the keymap.
+        idx: u8,
+        /// Name of the layout.
+        name: String,
+    },
+    /// A bind mode was entered or left.
+    BindModeChanged {
+        /// Name of the active mode, or `None` if no mode is active.
+        name: Option<String>,
+    },
+    /// An output was connected.
+    OutputConnected {
+        /// Connector name of the output.
+        connector: String,
+        /// Information about the output.
+        output: Output,
+    },
+    /// An output was disconnected.
+    OutputDisconnected {
+        /// Connector name of the output.
+        connector: String,
+    },
+    /// The config was reloaded.
+    ConfigReloaded {
+        /// Whether the new config failed to load, in which case the old one remains in use.
+        failed: bool,
+    },
+}
+
 impl FromStr for SizeChange {
     type Err = &'static str;
 
@@ -283,14 +684,100 @@ impl FromStr for SizeChange {
     }
 }
 
+impl FromStr for WorkspaceReferenceArg {
+    type Err = &'static str;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s.is_empty() {
+            return Err("workspace reference is missing");
+        }
+
+        // Anything that parses as an index is an index, otherwise it's a name.
+        match s.parse() {
+            Ok(index) => Ok(Self::Index(index)),
+            Err(_) => Ok(Self::Name(s.to_owned())),
+        }
+    }
+}
+
 impl FromStr for LayoutSwitchTarget {
     type Err = &'static str;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s.is_empty() {
+            return Err(r#"layout is missing, can be "next", "prev", an index or a name"#);
+        }
+
         match s {
             "next" => Ok(Self::Next),
             "prev" => Ok(Self::Prev),
-            _ => Err(r#"invalid layout action, can be "next" or "prev""#),
+            // Anything that parses as an index is an index, otherwise it's a name.
+            _ => match s.parse() {
+                Ok(index) => Ok(Self::Index(index)),
+                Err(_) => Ok(Self::Name(s.to_owned())),
+            },
+        }
+    }
+}
+
+impl FromStr for ModeToSet {
+    type Err = &'static str;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s.eq_ignore_ascii_case("auto") {
+            return Ok(Self::Automatic);
+        }
+
+        let mode = s.parse()?;
+        Ok(Self::Specific(mode))
+    }
+}
+
+impl FromStr for ConfiguredMode {
+    type Err = &'static str;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let Some((width, rest)) = s.split_once('x') else {
+            return Err("no 'x' separator found");
+        };
+
+        let (height, refresh) = match rest.split_once('@') {
+            Some((height, refresh)) => (height, Some(refresh)),
+            None => (rest, None),
+        };
+
+        let width = width.parse().map_err(|_| "error parsing width")?;
+        let height = height.parse().map_err(|_| "error parsing height")?;
+        let refresh = refresh
+            .map(str::parse)
+            .transpose()
+            .map_err(|_| "error parsing refresh rate")?;
+
+        Ok(Self {
+            width,
+            height,
+            refresh,
+        })
+    }
+}
+
+impl FromStr for Transform {
+    type Err = &'static str;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "normal" => Ok(Self::Normal),
+            "90" => Ok(Self::_90),
+            "180" => Ok(Self::_180),
+            "270" => Ok(Self::_270),
+            "flipped" => Ok(Self::Flipped),
+            "flipped-90" => Ok(Self::Flipped90),
+            "flipped-180" => Ok(Self::Flipped180),
+            "flipped-270" => Ok(Self::Flipped270),
+            _ => Err(concat!(
+                r#"invalid transform, can be "90", "180", "270", "#,
+                r#""flipped", "flipped-90", "flipped-180" or "flipped-270""#
+            )),
         }
     }
 }
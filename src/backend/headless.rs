@@ -0,0 +1,237 @@
+//! Headless backend for tests and `niri --headless`.
+//!
+//! This can optionally create a GLES renderer (e.g. with Mesa's software rendering).
+
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::mem;
+use std::rc::Rc;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use anyhow::Context;
+use smithay::backend::allocator::dmabuf::Dmabuf;
+use smithay::backend::allocator::Fourcc;
+use smithay::backend::egl::{EGLContext, EGLDevice, EGLDisplay};
+use smithay::backend::renderer::element::RenderElementStates;
+use smithay::backend::renderer::gles::GlesRenderer;
+use smithay::backend::renderer::{DebugFlags, ImportDma, ImportEgl, Renderer};
+use smithay::output::{Mode, Output, PhysicalProperties, Subpixel};
+use smithay::reexports::calloop::timer::{TimeoutAction, Timer};
+use smithay::reexports::wayland_protocols::wp::presentation_time::server::wp_presentation_feedback;
+use smithay::utils::Scale;
+
+use super::RenderResult;
+use crate::niri::{Niri, RedrawState};
+use crate::render_helpers::render_to_texture;
+use crate::utils::get_monotonic_time;
+
+pub struct Headless {
+    renderer: Option<GlesRenderer>,
+    ipc_outputs: Rc<RefCell<HashMap<String, niri_ipc::Output>>>,
+    enabled_outputs: Arc<Mutex<HashMap<String, Output>>>,
+}
+
+impl Headless {
+    pub fn new() -> Self {
+        Self {
+            renderer: None,
+            ipc_outputs: Default::default(),
+            enabled_outputs: Default::default(),
+        }
+    }
+
+    pub fn init(&mut self, _niri: &mut Niri) {}
+
+    /// Creates a renderer on a software EGL device.
+    ///
+    /// Without a renderer, the headless backend skips rendering altogether.
+    pub fn add_renderer(&mut self, niri: &mut Niri) -> anyhow::Result<()> {
+        if self.renderer.is_some() {
+            warn!("headless renderer already exists");
+            return Ok(());
+        }
+
+        let device = EGLDevice::enumerate()
+            .context("error enumerating EGL devices")?
+            .find(|device| device.is_software())
+            .context("no software EGL device found")?;
+        let display = unsafe { EGLDisplay::new(device) }.context("error creating EGL display")?;
+        let context = EGLContext::new(&display).context("error creating EGL context")?;
+        let mut renderer =
+            unsafe { GlesRenderer::new(context) }.context("error creating GLES renderer")?;
+
+        if let Err(err) = renderer.bind_wl_display(&niri.display_handle) {
+            warn!("error binding renderer wl_display: {err}");
+        }
+
+        self.renderer = Some(renderer);
+        Ok(())
+    }
+
+    /// Adds a virtual output named `headless-{n}` with the given size in physical pixels.
+    pub fn add_output(&mut self, niri: &mut Niri, n: u8, size: (u16, u16)) {
+        let connector = format!("headless-{n}");
+
+        let output = Output::new(
+            connector.clone(),
+            PhysicalProperties {
+                size: (0, 0).into(),
+                subpixel: Subpixel::Unknown,
+                make: "niri".into(),
+                model: "Headless".into(),
+            },
+        );
+
+        let mode = Mode {
+            size: (i32::from(size.0), i32::from(size.1)).into(),
+            refresh: 60_000,
+        };
+        output.change_current_state(Some(mode), None, None, None);
+        output.set_preferred(mode);
+
+        let physical_properties = output.physical_properties();
+        self.ipc_outputs.borrow_mut().insert(
+            connector.clone(),
+            niri_ipc::Output {
+                name: output.name(),
+                make: physical_properties.make,
+                model: physical_properties.model,
+                physical_size: None,
+                modes: vec![niri_ipc::Mode {
+                    width: size.0,
+                    height: size.1,
+                    refresh_rate: 60_000,
+                }],
+                current_mode: Some(0),
+            },
+        );
+
+        self.enabled_outputs
+            .lock()
+            .unwrap()
+            .insert(connector, output.clone());
+
+        niri.add_output(output, None);
+    }
+
+    /// Removes a virtual output previously added with [`Headless::add_output`].
+    pub fn remove_output(&mut self, niri: &mut Niri, n: u8) {
+        let connector = format!("headless-{n}");
+
+        self.ipc_outputs.borrow_mut().remove(&connector);
+        let output = self.enabled_outputs.lock().unwrap().remove(&connector);
+
+        if let Some(output) = output {
+            niri.remove_output(&output);
+        } else {
+            warn!("headless output {connector} does not exist");
+        }
+    }
+
+    pub fn seat_name(&self) -> String {
+        "headless".to_owned()
+    }
+
+    pub fn with_primary_renderer<T>(
+        &mut self,
+        f: impl FnOnce(&mut GlesRenderer) -> T,
+    ) -> Option<T> {
+        self.renderer.as_mut().map(f)
+    }
+
+    pub fn render(&mut self, niri: &mut Niri, output: &Output) -> RenderResult {
+        let _span = tracy_client::span!("Headless::render");
+
+        if let Some(renderer) = &mut self.renderer {
+            let size = output.current_mode().unwrap().size;
+            let transform = output.current_transform();
+            let size = transform.transform_size(size);
+            let scale = Scale::from(output.current_scale().fractional_scale());
+
+            let elements = niri.render::<GlesRenderer>(renderer, output, true);
+            match render_to_texture(
+                renderer,
+                size,
+                scale,
+                Fourcc::Abgr8888,
+                elements.into_iter(),
+            ) {
+                Ok((_texture, sync_point)) => {
+                    let _ = sync_point.wait();
+                }
+                Err(err) => warn!("error rendering headless output: {err:?}"),
+            }
+        }
+
+        // There's no real scanout, so present all surfaces right away.
+        let states = RenderElementStates::default();
+        let mut presentation_feedbacks = niri.take_presentation_feedbacks(output, &states);
+        let mode = output.current_mode().unwrap();
+        let refresh = Duration::from_secs_f64(1_000f64 / mode.refresh as f64);
+        presentation_feedbacks.presented::<_, smithay::utils::Monotonic>(
+            get_monotonic_time(),
+            refresh,
+            0,
+            wp_presentation_feedback::Kind::empty(),
+        );
+
+        let output_state = niri.output_state.get_mut(output).unwrap();
+        match mem::replace(&mut output_state.redraw_state, RedrawState::Idle) {
+            RedrawState::Idle => unreachable!(),
+            RedrawState::Queued(_) => (),
+            RedrawState::WaitingForVBlank { .. } => unreachable!(),
+            RedrawState::WaitingForEstimatedVBlank(_) => unreachable!(),
+            RedrawState::WaitingForEstimatedVBlankAndQueued(_) => unreachable!(),
+        }
+
+        // Drive the animations with a timer in place of the vblank.
+        if output_state.unfinished_animations_remain {
+            let output = output.clone();
+            niri.event_loop
+                .insert_source(Timer::from_duration(refresh), move |_, _, state| {
+                    if state.niri.output_state.contains_key(&output) {
+                        state.niri.queue_redraw(output.clone());
+                    }
+                    TimeoutAction::Drop
+                })
+                .unwrap();
+        }
+
+        RenderResult::Submitted
+    }
+
+    pub fn toggle_debug_tint(&mut self) {
+        if let Some(renderer) = &mut self.renderer {
+            renderer.set_debug_flags(renderer.debug_flags() ^ DebugFlags::TINT);
+        }
+    }
+
+    pub fn import_dmabuf(&mut self, dmabuf: &Dmabuf) -> bool {
+        let Some(renderer) = &mut self.renderer else {
+            return false;
+        };
+
+        match renderer.import_dmabuf(dmabuf, None) {
+            Ok(_texture) => true,
+            Err(err) => {
+                debug!("error importing dmabuf: {err:?}");
+                false
+            }
+        }
+    }
+
+    pub fn ipc_outputs(&self) -> Rc<RefCell<HashMap<String, niri_ipc::Output>>> {
+        self.ipc_outputs.clone()
+    }
+
+    pub fn enabled_outputs(&self) -> Arc<Mutex<HashMap<String, Output>>> {
+        self.enabled_outputs.clone()
+    }
+}
+
+impl Default for Headless {
+    fn default() -> Self {
+        Self::new()
+    }
+}
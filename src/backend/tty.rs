@@ -50,7 +50,7 @@ use super::RenderResult;
 use crate::frame_clock::FrameClock;
 use crate::niri::{Niri, RedrawState, State};
 use crate::render_helpers::renderer::AsGlesRenderer;
-use crate::utils::get_monotonic_time;
+use crate::utils::{get_monotonic_time, refresh_rate_matches};
 
 const SUPPORTED_COLOR_FORMATS: &[Fourcc] = &[Fourcc::Argb8888, Fourcc::Abgr8888];
 
@@ -354,7 +354,9 @@ impl Tty {
                 }
 
                 if self.update_output_config_on_resume {
-                    self.on_output_config_changed(niri);
+                    if let Err(err) = self.on_output_config_changed(niri) {
+                        warn!("error applying output config: {err:?}");
+                    }
                 }
 
                 self.refresh_ipc_outputs();
@@ -1230,16 +1232,20 @@ impl Tty {
         }
     }
 
-    pub fn on_output_config_changed(&mut self, niri: &mut Niri) {
+    pub fn on_output_config_changed(&mut self, niri: &mut Niri) -> anyhow::Result<()> {
         let _span = tracy_client::span!("Tty::on_output_config_changed");
 
         // If we're inactive, we can't do anything, so just set a flag for later.
         if !self.session.is_active() {
             self.update_output_config_on_resume = true;
-            return;
+            return Ok(());
         }
         self.update_output_config_on_resume = false;
 
+        // Keep going on errors to apply as much of the config as possible, but report the first
+        // one.
+        let mut result = Ok(());
+
         let mut to_disconnect = vec![];
         let mut to_connect = vec![];
 
@@ -1316,6 +1322,9 @@ impl Tty {
                 debug!("output {:?}: picking mode: {mode:?}", surface.name);
                 if let Err(err) = surface.compositor.use_mode(mode) {
                     warn!("error changing mode: {err:?}");
+                    if result.is_ok() {
+                        result = Err(anyhow!(err).context("error changing mode"));
+                    }
                     continue;
                 }
 
@@ -1365,10 +1374,15 @@ impl Tty {
         for (node, connector, crtc) in to_connect {
             if let Err(err) = self.connector_connected(niri, node, connector, crtc) {
                 warn!("error connecting connector: {err:?}");
+                if result.is_ok() {
+                    result = Err(err.context("error connecting connector"));
+                }
             }
         }
 
         self.refresh_ipc_outputs();
+
+        result
     }
 }
 
@@ -1561,17 +1575,15 @@ fn pick_mode(
     let mut fallback = false;
 
     if let Some(target) = target {
-        let refresh = target.refresh.map(|r| (r * 1000.).round() as i32);
-
         for m in connector.modes() {
             if m.size() != (target.width, target.height) {
                 continue;
             }
 
-            if let Some(refresh) = refresh {
+            if let Some(refresh) = target.refresh {
                 // If refresh is set, only pick modes with matching refresh.
                 let wl_mode = Mode::from(*m);
-                if wl_mode.refresh == refresh {
+                if refresh_rate_matches(wl_mode.refresh as u32, refresh) {
                     mode = Some(m);
                 }
             } else if let Some(curr) = mode {
@@ -2,7 +2,7 @@ use std::ffi::OsString;
 use std::path::PathBuf;
 
 use clap::{Parser, Subcommand};
-use niri_ipc::Action;
+use niri_ipc::{Action, OutputAction};
 
 use crate::utils::version;
 
@@ -15,6 +15,9 @@ pub struct Cli {
     /// Path to config file (default: `$XDG_CONFIG_HOME/niri/config.kdl`).
     #[arg(short, long)]
     pub config: Option<PathBuf>,
+    /// Run without a display on a virtual 1920x1080 output, e.g. for testing.
+    #[arg(long)]
+    pub headless: bool,
     /// Command to run upon compositor startup.
     #[arg(last = true)]
     pub command: Vec<OsString>,
@@ -45,11 +48,56 @@ pub enum Sub {
 
 #[derive(Subcommand)]
 pub enum Msg {
+    /// Print the version of the running niri instance.
+    Version,
     /// List connected outputs.
     Outputs,
+    /// List workspaces.
+    Workspaces,
+    /// List open windows.
+    Windows,
+    /// Print information about the focused window.
+    FocusedWindow,
+    /// List keyboard layouts and show the active one.
+    KeyboardLayouts,
+    /// Print the name of the active bind mode.
+    BindMode,
+    /// Keep running and print events as they happen.
+    EventStream,
     /// Perform an action.
     Action {
         #[command(subcommand)]
         action: Action,
     },
+    /// Send several messages over a single connection.
+    ///
+    /// Every line is a `niri msg` command without the `niri msg` prefix, for example
+    /// `action focus-column-left`. Empty lines and lines starting with `#` are skipped. Stops at
+    /// the first error.
+    Batch {
+        /// File to read the messages from, or `-` for stdin (default: stdin).
+        #[arg()]
+        file: Option<PathBuf>,
+    },
+    /// Change output configuration temporarily.
+    ///
+    /// The changes are kept on top of the config file until niri exits.
+    Output {
+        /// Output name.
+        ///
+        /// Run `niri msg outputs` to see the output names.
+        #[arg()]
+        output: String,
+        /// Configuration to apply.
+        #[command(subcommand)]
+        action: OutputAction,
+    },
+}
+
+/// Line of `niri msg batch` input.
+#[derive(Parser)]
+#[command(no_binary_name = true)]
+pub struct BatchLine {
+    #[command(subcommand)]
+    pub msg: Msg,
 }
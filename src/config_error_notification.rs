@@ -138,6 +138,7 @@ impl ConfigErrorNotification {
             return None;
         }
 
+        // Text is rasterized at the next integer scale and downscaled for fractional scales.
         let scale = output.current_scale().integer_scale();
         let path = self.created_path.as_deref();
 
@@ -166,7 +167,9 @@ impl ConfigErrorNotification {
             .geometry(output.current_scale().fractional_scale().into())
             .size;
 
-        let y_range = buffer_size.h + PADDING * 2 * scale;
+        let output_scale = output.current_scale().fractional_scale();
+        let margin = (f64::from(PADDING * 2) * output_scale).round() as i32;
+        let y_range = buffer_size.h + margin;
 
         let x = (output_size.w / 2 - buffer_size.w / 2).max(0);
         let y = match &self.state {
@@ -174,7 +177,7 @@ impl ConfigErrorNotification {
             State::Showing(anim) | State::Hiding(anim) => {
                 (-buffer_size.h as f64 + anim.value() * y_range as f64).round() as i32
             }
-            State::Shown(_) => PADDING * 2 * scale,
+            State::Shown(_) => margin,
         };
         let elem = RelocateRenderElement::from_element(elem, (x, y), Relocate::Absolute);
 
@@ -67,6 +67,7 @@ impl ExitConfirmDialog {
             return None;
         }
 
+        // Text is rasterized at the next integer scale and downscaled for fractional scales.
         let scale = output.current_scale().integer_scale();
 
         let mut buffers = self.buffers.borrow_mut();
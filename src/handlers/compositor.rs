@@ -8,17 +8,18 @@ use smithay::reexports::wayland_server::protocol::wl_surface::WlSurface;
 use smithay::reexports::wayland_server::{Client, Resource};
 use smithay::wayland::buffer::BufferHandler;
 use smithay::wayland::compositor::{
-    add_blocker, add_pre_commit_hook, get_parent, is_sync_subsurface, send_surface_state,
-    with_states, BufferAssignment, CompositorClientState, CompositorHandler, CompositorState,
-    SurfaceAttributes,
+    add_blocker, add_pre_commit_hook, get_parent, is_sync_subsurface, with_states,
+    BufferAssignment, CompositorClientState, CompositorHandler, CompositorState, SurfaceAttributes,
 };
 use smithay::wayland::dmabuf::get_dmabuf;
 use smithay::wayland::shm::{ShmHandler, ShmState};
 use smithay::{delegate_compositor, delegate_shm};
 
 use super::xdg_shell;
+use crate::layout::workspace::ColumnWidth;
 use crate::niri::{ClientState, State};
-use crate::utils::clone2;
+use crate::utils::{clone2, send_scale_transform};
+use crate::window::ResolvedWindowRules;
 
 impl CompositorHandler for State {
     fn compositor_state(&mut self) -> &mut CompositorState {
@@ -36,10 +37,10 @@ impl CompositorHandler for State {
         }
 
         if let Some(output) = self.niri.output_for_root(&root) {
-            let scale = output.current_scale().integer_scale();
+            let scale = output.current_scale();
             let transform = output.current_transform();
             with_states(surface, |data| {
-                send_surface_state(surface, data, scale, transform);
+                send_scale_transform(surface, data, scale, transform);
             });
         }
     }
@@ -108,22 +109,66 @@ impl CompositorHandler for State {
                     let window = entry.remove();
                     window.on_commit();
 
+                    // The app id and title could have changed since the window was created.
+                    let rules = ResolvedWindowRules::recompute(
+                        &self.niri.config.borrow().window_rules,
+                        &window,
+                    );
+                    rules.clone().store(&window);
+
                     let parent = window
                         .toplevel()
                         .parent()
                         .and_then(|parent| self.niri.layout.find_window_and_output(&parent))
                         .map(|(win, _)| win.clone());
 
+                    let target_output = rules
+                        .open_on_output
+                        .as_deref()
+                        .and_then(|name| self.niri.output_by_name.get(name))
+                        .cloned();
+
+                    // A width rule without a value means the window picks its own width.
+                    let width = rules.default_width.map(|width| {
+                        width.unwrap_or_else(|| ColumnWidth::Fixed(window.geometry().size.w))
+                    });
+                    let is_full_width = rules.open_maximized == Some(true);
+
+                    let target_workspace = rules
+                        .open_on_workspace
+                        .as_deref()
+                        .filter(|name| self.niri.layout.find_workspace_by_name(name).is_some());
+
                     let win = window.clone();
 
-                    // Open dialogs immediately to the right of their parent window.
-                    let output = if let Some(p) = parent {
-                        self.niri.layout.add_window_right_of(&p, win, None, false)
+                    let output = if let Some(name) = target_workspace {
+                        self.niri
+                            .layout
+                            .add_window_to_named_workspace(name, win, width, is_full_width)
+                            .cloned()
+                    } else if let Some(output) = target_output {
+                        self.niri
+                            .layout
+                            .add_window_on_output(&output, win, width, is_full_width);
+                        Some(output)
+                    } else if let Some(p) = parent {
+                        // Open dialogs immediately to the right of their parent window.
+                        self.niri
+                            .layout
+                            .add_window_right_of(&p, win, width, is_full_width)
+                            .cloned()
                     } else {
-                        self.niri.layout.add_window(win, None, false)
+                        self.niri
+                            .layout
+                            .add_window(win, width, is_full_width)
+                            .cloned()
                     };
 
-                    if let Some(output) = output.cloned() {
+                    if rules.open_fullscreen == Some(true) {
+                        self.niri.layout.set_fullscreen(&window, true);
+                    }
+
+                    if let Some(output) = output {
                         self.niri.layout.start_open_animation_for_window(&window);
                         self.niri.queue_redraw(output);
                     }
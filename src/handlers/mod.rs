@@ -2,6 +2,7 @@ mod compositor;
 mod layer_shell;
 mod xdg_shell;
 
+use std::collections::HashMap;
 use std::fs::File;
 use std::io::Write;
 use std::os::fd::OwnedFd;
@@ -20,8 +21,9 @@ use smithay::reexports::wayland_server::protocol::wl_output::WlOutput;
 use smithay::reexports::wayland_server::protocol::wl_surface::WlSurface;
 use smithay::reexports::wayland_server::Resource;
 use smithay::utils::{Logical, Rectangle, Size};
-use smithay::wayland::compositor::{send_surface_state, with_states};
+use smithay::wayland::compositor::{get_parent, with_states};
 use smithay::wayland::dmabuf::{DmabufGlobal, DmabufHandler, DmabufState, ImportNotifier};
+use smithay::wayland::fractional_scale::FractionalScaleHandler;
 use smithay::wayland::idle_inhibit::IdleInhibitHandler;
 use smithay::wayland::idle_notify::{IdleNotifierHandler, IdleNotifierState};
 use smithay::wayland::input_method::{InputMethodHandler, PopupSurface};
@@ -44,23 +46,28 @@ use smithay::wayland::session_lock::{
 };
 use smithay::{
     delegate_cursor_shape, delegate_data_control, delegate_data_device, delegate_dmabuf,
-    delegate_idle_inhibit, delegate_idle_notify, delegate_input_method_manager, delegate_output,
-    delegate_pointer_constraints, delegate_pointer_gestures, delegate_presentation,
-    delegate_primary_selection, delegate_relative_pointer, delegate_seat,
-    delegate_security_context, delegate_session_lock, delegate_tablet_manager,
-    delegate_text_input_manager, delegate_virtual_keyboard_manager,
+    delegate_fractional_scale, delegate_idle_inhibit, delegate_idle_notify,
+    delegate_input_method_manager, delegate_output, delegate_pointer_constraints,
+    delegate_pointer_gestures, delegate_presentation, delegate_primary_selection,
+    delegate_relative_pointer, delegate_seat, delegate_security_context, delegate_session_lock,
+    delegate_tablet_manager, delegate_text_input_manager, delegate_viewporter,
+    delegate_virtual_keyboard_manager,
 };
 
-use crate::delegate_foreign_toplevel;
 use crate::niri::{ClientState, State};
 use crate::protocols::foreign_toplevel::{
     self, ForeignToplevelHandler, ForeignToplevelManagerState,
 };
-use crate::utils::output_size;
+use crate::protocols::output_management::{
+    HeadConfiguration, OutputManagementHandler, OutputManagementManagerState,
+};
+use crate::utils::{output_size, send_scale_transform};
+use crate::{delegate_foreign_toplevel, delegate_output_management};
 
 impl SeatHandler for State {
     type KeyboardFocus = WlSurface;
     type PointerFocus = WlSurface;
+    type TouchFocus = WlSurface;
 
     fn seat_state(&mut self) -> &mut SeatState<State> {
         &mut self.niri.seat_state
@@ -118,11 +125,11 @@ impl InputMethodHandler for State {
     fn new_popup(&mut self, surface: PopupSurface) {
         let popup = PopupKind::from(surface.clone());
         if let Some(output) = self.output_for_popup(&popup) {
-            let scale = output.current_scale().integer_scale();
+            let scale = output.current_scale();
             let transform = output.current_transform();
             let wl_surface = surface.wl_surface();
             with_states(wl_surface, |data| {
-                send_surface_state(wl_surface, data, scale, transform);
+                send_scale_transform(wl_surface, data, scale, transform);
             });
         }
         if let Err(err) = self.niri.popups.track_popup(popup) {
@@ -221,6 +228,38 @@ delegate_output!(State);
 
 delegate_presentation!(State);
 
+impl FractionalScaleHandler for State {
+    fn new_fractional_scale(&mut self, surface: WlSurface) {
+        let mut root = surface.clone();
+        while let Some(parent) = get_parent(&root) {
+            root = parent;
+        }
+
+        let output = if let Some(popup) = self.niri.popups.find_popup(&root) {
+            self.output_for_popup(&popup)
+        } else {
+            self.niri.output_for_root(&root).or_else(|| {
+                // Check lock surfaces.
+                self.niri.output_state.iter().find_map(|(output, state)| {
+                    let lock_surface = state.lock_surface.as_ref()?;
+                    (lock_surface.wl_surface() == &root).then_some(output)
+                })
+            })
+        };
+
+        if let Some(output) = output {
+            let scale = output.current_scale();
+            let transform = output.current_transform();
+            with_states(&surface, |data| {
+                send_scale_transform(&surface, data, scale, transform);
+            });
+        }
+    }
+}
+delegate_fractional_scale!(State);
+
+delegate_viewporter!(State);
+
 impl DmabufHandler for State {
     fn dmabuf_state(&mut self) -> &mut DmabufState {
         &mut self.niri.dmabuf_state
@@ -271,11 +310,11 @@ pub fn configure_lock_surface(surface: &LockSurface, output: &Output) {
         let size = output_size(output);
         states.size = Some(Size::from((size.w as u32, size.h as u32)));
     });
-    let scale = output.current_scale().integer_scale();
+    let scale = output.current_scale();
     let transform = output.current_transform();
     let wl_surface = surface.wl_surface();
     with_states(wl_surface, |data| {
-        send_surface_state(wl_surface, data, scale, transform);
+        send_scale_transform(wl_surface, data, scale, transform);
     });
     surface.send_configure();
 }
@@ -374,3 +413,49 @@ impl ForeignToplevelHandler for State {
     }
 }
 delegate_foreign_toplevel!(State);
+
+impl OutputManagementHandler for State {
+    fn output_management_state(&mut self) -> &mut OutputManagementManagerState {
+        &mut self.niri.output_management_state
+    }
+
+    fn apply_output_config(
+        &mut self,
+        new_config: HashMap<String, HeadConfiguration>,
+    ) -> anyhow::Result<()> {
+        let mut overrides = self.niri.output_overrides.clone();
+        for (name, head_config) in new_config {
+            let override_ = overrides.entry(name).or_default();
+
+            match head_config {
+                HeadConfiguration::Disabled => override_.off = Some(true),
+                HeadConfiguration::Enabled {
+                    mode,
+                    position,
+                    transform,
+                    scale,
+                } => {
+                    override_.off = Some(false);
+                    if let Some(mode) = mode {
+                        override_.mode = Some(Some(mode));
+                    }
+                    if let Some(position) = position {
+                        override_.position = Some(Some(niri_config::Position {
+                            x: position.x,
+                            y: position.y,
+                        }));
+                    }
+                    if let Some(transform) = transform {
+                        override_.transform = Some(transform);
+                    }
+                    if let Some(scale) = scale {
+                        override_.scale = Some(scale);
+                    }
+                }
+            }
+        }
+
+        self.set_output_overrides(overrides)
+    }
+}
+delegate_output_management!(State);
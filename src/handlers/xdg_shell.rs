@@ -3,16 +3,17 @@ use smithay::desktop::{
     PopupKeyboardGrab, PopupKind, PopupManager, PopupPointerGrab, PopupUngrabStrategy, Window,
     WindowSurfaceType,
 };
-use smithay::input::pointer::Focus;
+use smithay::input::pointer::{CursorIcon, CursorImageStatus, Focus};
 use smithay::output::Output;
 use smithay::reexports::wayland_protocols::xdg::decoration::zv1::server::zxdg_toplevel_decoration_v1;
 use smithay::reexports::wayland_protocols::xdg::shell::server::xdg_positioner::ConstraintAdjustment;
-use smithay::reexports::wayland_protocols::xdg::shell::server::xdg_toplevel::{self, ResizeEdge};
+use smithay::reexports::wayland_protocols::xdg::shell::server::xdg_toplevel;
 use smithay::reexports::wayland_server::protocol::wl_output;
 use smithay::reexports::wayland_server::protocol::wl_seat::WlSeat;
 use smithay::reexports::wayland_server::protocol::wl_surface::WlSurface;
+use smithay::reexports::wayland_server::Resource;
 use smithay::utils::{Logical, Rectangle, Serial};
-use smithay::wayland::compositor::{send_surface_state, with_states};
+use smithay::wayland::compositor::with_states;
 use smithay::wayland::input_method::InputMethodSeat;
 use smithay::wayland::shell::kde::decoration::{KdeDecorationHandler, KdeDecorationState};
 use smithay::wayland::shell::wlr_layer::Layer;
@@ -23,8 +24,13 @@ use smithay::wayland::shell::xdg::{
 };
 use smithay::{delegate_kde_decoration, delegate_xdg_decoration, delegate_xdg_shell};
 
+use crate::input::move_grab::MoveGrab;
+use crate::input::resize_grab::ResizeGrab;
+use crate::layout::workspace::{ColumnWidth, Workspace};
+use crate::layout::ResizeEdge;
 use crate::niri::{PopupGrabState, State};
-use crate::utils::clone2;
+use crate::utils::{clone2, send_scale_transform};
+use crate::window::{ResolvedWindowRules, WindowId};
 
 impl XdgShellHandler for State {
     fn xdg_shell_state(&mut self) -> &mut XdgShellState {
@@ -35,14 +41,32 @@ impl XdgShellHandler for State {
         let wl_surface = surface.wl_surface().clone();
         let window = Window::new(surface);
 
+        let config = self.niri.config.borrow();
+        let rules = ResolvedWindowRules::compute(
+            &config.window_rules,
+            window.toplevel(),
+            self.niri.is_at_startup(),
+        );
+
         // Tell the surface the preferred size and bounds for its likely output.
-        if let Some(ws) = self.niri.layout.active_workspace() {
-            ws.configure_new_window(&window);
+        if let Some(ws) = self.niri.workspace_for_new_window(&rules) {
+            ws.configure_new_window(&window, initial_width(ws, &rules), &rules);
+
+            if rules.open_fullscreen == Some(true) {
+                window.toplevel().with_pending_state(|state| {
+                    state.size = Some(ws.view_size());
+                    state.states.set(xdg_toplevel::State::Fullscreen);
+                });
+            }
         }
 
+        rules.store(&window);
+
+        // Assign the id right away so that ids follow the order in which windows are created.
+        WindowId::of(&window);
+
         // If the user prefers no CSD, it's a reasonable assumption that they would prefer to get
         // rid of the various client-side rounded corners also by using the tiled state.
-        let config = self.niri.config.borrow();
         if config.prefer_no_csd {
             window.toplevel().with_pending_state(|state| {
                 state.states.set(xdg_toplevel::State::TiledLeft);
@@ -65,18 +89,103 @@ impl XdgShellHandler for State {
         }
     }
 
-    fn move_request(&mut self, _surface: ToplevelSurface, _seat: WlSeat, _serial: Serial) {
-        // FIXME
+    fn move_request(&mut self, surface: ToplevelSurface, _seat: WlSeat, serial: Serial) {
+        let pointer = self.niri.seat.get_pointer().unwrap();
+        if !pointer.has_grab(serial) {
+            return;
+        }
+
+        let Some(start_data) = pointer.grab_start_data() else {
+            return;
+        };
+
+        let Some((focus, _)) = &start_data.focus else {
+            return;
+        };
+
+        let wl_surface = surface.wl_surface();
+        if !focus.id().same_client_as(&wl_surface.id()) {
+            return;
+        }
+
+        let Some((window, output)) = self
+            .niri
+            .layout
+            .find_window_and_output(wl_surface)
+            .map(clone2)
+        else {
+            return;
+        };
+
+        let output_geo = self.niri.global_space.output_geometry(&output).unwrap();
+        let pos_within_output = start_data.location - output_geo.loc.to_f64();
+
+        if !self
+            .niri
+            .layout
+            .interactive_move_begin(&window, &output, pos_within_output)
+        {
+            return;
+        }
+
+        let grab = MoveGrab::new(start_data, window);
+        pointer.set_grab(self, grab, serial, Focus::Clear);
+        self.niri
+            .cursor_manager
+            .set_cursor_image(CursorImageStatus::Named(CursorIcon::Grabbing));
+
+        // FIXME: granular.
+        self.niri.queue_redraw_all();
     }
 
     fn resize_request(
         &mut self,
-        _surface: ToplevelSurface,
+        surface: ToplevelSurface,
         _seat: WlSeat,
-        _serial: Serial,
-        _edges: ResizeEdge,
+        serial: Serial,
+        edges: xdg_toplevel::ResizeEdge,
     ) {
-        // FIXME
+        let pointer = self.niri.seat.get_pointer().unwrap();
+        if !pointer.has_grab(serial) {
+            return;
+        }
+
+        let Some(start_data) = pointer.grab_start_data() else {
+            return;
+        };
+
+        let Some((focus, _)) = &start_data.focus else {
+            return;
+        };
+
+        let wl_surface = surface.wl_surface();
+        if !focus.id().same_client_as(&wl_surface.id()) {
+            return;
+        }
+
+        let Some(window) = self
+            .niri
+            .layout
+            .find_window_and_output(wl_surface)
+            .map(|(w, _)| w.clone())
+        else {
+            return;
+        };
+
+        let edges = ResizeEdge::from(edges);
+        if !self
+            .niri
+            .layout
+            .interactive_resize_begin(window.clone(), edges)
+        {
+            return;
+        }
+
+        let grab = ResizeGrab::new(start_data, window);
+        pointer.set_grab(self, grab, serial, Focus::Clear);
+        self.niri
+            .cursor_manager
+            .set_cursor_image(CursorImageStatus::Named(edges.cursor_icon()));
     }
 
     fn reposition_request(
@@ -231,7 +340,8 @@ impl XdgShellHandler for State {
 
                 self.niri.layout.set_fullscreen(&window, true);
             } else if let Some(window) = self.niri.unmapped_windows.get(surface.wl_surface()) {
-                if let Some(ws) = self.niri.layout.active_workspace() {
+                let rules = ResolvedWindowRules::of(window);
+                if let Some(ws) = self.niri.workspace_for_new_window(&rules) {
                     window.toplevel().with_pending_state(|state| {
                         state.size = Some(ws.view_size());
                         state.states.set(xdg_toplevel::State::Fullscreen);
@@ -254,9 +364,11 @@ impl XdgShellHandler for State {
             let window = window.clone();
             self.niri.layout.set_fullscreen(&window, false);
         } else if let Some(window) = self.niri.unmapped_windows.get(surface.wl_surface()) {
-            if let Some(ws) = self.niri.layout.active_workspace() {
+            let rules = ResolvedWindowRules::of(window);
+            if let Some(ws) = self.niri.workspace_for_new_window(&rules) {
+                let width = initial_width(ws, &rules);
                 window.toplevel().with_pending_state(|state| {
-                    state.size = Some(ws.new_window_size());
+                    state.size = Some(ws.new_window_size(width, &rules));
                     state.states.unset(xdg_toplevel::State::Fullscreen);
                 });
             }
@@ -350,6 +462,15 @@ impl KdeDecorationHandler for State {
 
 delegate_kde_decoration!(State);
 
+/// Returns the width to configure a new window with according to its rules.
+pub fn initial_width(ws: &Workspace<Window>, rules: &ResolvedWindowRules) -> Option<ColumnWidth> {
+    if rules.open_maximized == Some(true) {
+        return Some(ColumnWidth::Proportion(1.));
+    }
+
+    ws.resolve_default_width(rules.default_width)
+}
+
 pub fn send_initial_configure_if_needed(toplevel: &ToplevelSurface) {
     if !initial_configure_sent(toplevel) {
         toplevel.send_configure();
@@ -388,10 +509,10 @@ impl State {
                     if !initial_configure_sent {
                         if let Some(output) = self.output_for_popup(&PopupKind::Xdg(popup.clone()))
                         {
-                            let scale = output.current_scale().integer_scale();
+                            let scale = output.current_scale();
                             let transform = output.current_transform();
                             with_states(surface, |data| {
-                                send_surface_state(surface, data, scale, transform);
+                                send_scale_transform(surface, data, scale, transform);
                             });
                         }
                         popup.send_configure().expect("initial configure failed");
@@ -4,7 +4,8 @@ use std::collections::HashMap;
 use std::iter::zip;
 use std::rc::Rc;
 
-use niri_config::{Action, Config, Key, Modifiers};
+use niri_config::{Action, BindMode, Config, Key, Modifiers, Trigger, WorkspaceReference};
+use niri_ipc::SizeChange;
 use pangocairo::cairo::{self, ImageSurface};
 use pangocairo::pango::{AttrColor, AttrInt, AttrList, AttrString, FontDescription, Weight};
 use smithay::backend::renderer::element::memory::{
@@ -31,6 +32,8 @@ pub struct HotkeyOverlay {
     is_open: bool,
     config: Rc<RefCell<Config>>,
     comp_mod: CompositorMod,
+    /// Bind mode whose binds are shown instead of the important hotkeys.
+    mode: Option<String>,
     buffers: RefCell<HashMap<WeakOutput, RenderedOverlay>>,
 }
 
@@ -48,6 +51,7 @@ impl HotkeyOverlay {
             is_open: false,
             config,
             comp_mod,
+            mode: None,
             buffers: RefCell::new(HashMap::new()),
         }
     }
@@ -78,6 +82,17 @@ impl HotkeyOverlay {
         self.buffers.borrow_mut().clear();
     }
 
+    /// Sets the bind mode to show, returning whether the overlay needs a redraw.
+    pub fn set_mode(&mut self, mode: Option<&str>) -> bool {
+        if self.mode.as_deref() == mode {
+            return false;
+        }
+
+        self.mode = mode.map(str::to_owned);
+        self.buffers.borrow_mut().clear();
+        self.is_open
+    }
+
     pub fn render<R: NiriRenderer>(
         &self,
         renderer: &mut R,
@@ -87,6 +102,7 @@ impl HotkeyOverlay {
             return None;
         }
 
+        // Text is rasterized at the next integer scale and downscaled for fractional scales.
         let scale = output.current_scale().integer_scale();
         let margin = MARGIN * scale;
 
@@ -106,7 +122,9 @@ impl HotkeyOverlay {
         }
 
         let rendered = buffers.entry(weak).or_insert_with(|| {
-            render(&self.config.borrow(), self.comp_mod, scale).unwrap_or_else(|_| {
+            let config = self.config.borrow();
+            let mode = self.mode.as_deref().and_then(|name| config.find_mode(name));
+            render(&config, mode, self.comp_mod, scale).unwrap_or_else(|_| {
                 // This can go negative but whatever, as long as there's no rerender loop.
                 let mut size = output_size;
                 size.w -= margin * 2;
@@ -131,15 +149,28 @@ impl HotkeyOverlay {
         )
         .ok()?;
 
-        let x = (output_size.w / 2 - rendered.size.w / 2).max(0);
-        let y = (output_size.h / 2 - rendered.size.h / 2).max(0);
+        // The overlay was rendered at the integer scale, convert its size to physical pixels.
+        let output_scale = output.current_scale().fractional_scale();
+        let size = rendered
+            .size
+            .to_f64()
+            .upscale(output_scale / f64::from(scale))
+            .to_i32_round::<i32>();
+
+        let x = (output_size.w / 2 - size.w / 2).max(0);
+        let y = (output_size.h / 2 - size.h / 2).max(0);
         let elem = RelocateRenderElement::from_element(elem, (x, y), Relocate::Absolute);
 
         Some(elem)
     }
 }
 
-fn render(config: &Config, comp_mod: CompositorMod, scale: i32) -> anyhow::Result<RenderedOverlay> {
+fn render(
+    config: &Config,
+    mode: Option<&BindMode>,
+    comp_mod: CompositorMod,
+    scale: i32,
+) -> anyhow::Result<RenderedOverlay> {
     let _span = tracy_client::span!("hotkey_overlay::render");
 
     // let margin = MARGIN * scale;
@@ -152,93 +183,11 @@ fn render(config: &Config, comp_mod: CompositorMod, scale: i32) -> anyhow::Resul
     // target_size.h -= margin * 2;
     // anyhow::ensure!(target_size.w > 0 && target_size.h > 0);
 
-    let binds = &config.binds.0;
-
-    // Collect actions that we want to show.
-    let mut actions = vec![
-        &Action::ShowHotkeyOverlay,
-        &Action::Quit,
-        &Action::CloseWindow,
-    ];
-
-    actions.extend(&[
-        &Action::FocusColumnLeft,
-        &Action::FocusColumnRight,
-        &Action::MoveColumnLeft,
-        &Action::MoveColumnRight,
-        &Action::FocusWorkspaceDown,
-        &Action::FocusWorkspaceUp,
-    ]);
-
-    // Prefer move-column-to-workspace-down, but fall back to move-window-to-workspace-down.
-    if binds
-        .iter()
-        .any(|bind| bind.actions.first() == Some(&Action::MoveColumnToWorkspaceDown))
-    {
-        actions.push(&Action::MoveColumnToWorkspaceDown);
-    } else if binds
-        .iter()
-        .any(|bind| bind.actions.first() == Some(&Action::MoveWindowToWorkspaceDown))
-    {
-        actions.push(&Action::MoveWindowToWorkspaceDown);
-    } else {
-        actions.push(&Action::MoveColumnToWorkspaceDown);
-    }
-
-    // Same for -up.
-    if binds
-        .iter()
-        .any(|bind| bind.actions.first() == Some(&Action::MoveColumnToWorkspaceUp))
-    {
-        actions.push(&Action::MoveColumnToWorkspaceUp);
-    } else if binds
-        .iter()
-        .any(|bind| bind.actions.first() == Some(&Action::MoveWindowToWorkspaceUp))
-    {
-        actions.push(&Action::MoveWindowToWorkspaceUp);
-    } else {
-        actions.push(&Action::MoveColumnToWorkspaceUp);
-    }
-
-    actions.extend(&[
-        &Action::SwitchPresetColumnWidth,
-        &Action::MaximizeColumn,
-        &Action::ConsumeWindowIntoColumn,
-        &Action::ExpelWindowFromColumn,
-    ]);
-
-    // Screenshot is not as important, can omit if not bound.
-    if binds
-        .iter()
-        .any(|bind| bind.actions.first() == Some(&Action::Screenshot))
-    {
-        actions.push(&Action::Screenshot);
-    }
-
-    // Add the spawn actions.
-    for bind in binds.iter().filter(|bind| {
-        matches!(bind.actions.first(), Some(Action::Spawn(_)))
-            // Only show binds with Mod or Super to filter out stuff like volume up/down.
-            && (bind.key.modifiers.contains(Modifiers::COMPOSITOR)
-                || bind.key.modifiers.contains(Modifiers::SUPER))
-    }) {
-        actions.push(bind.actions.first().unwrap());
-    }
-
-    let strings = actions
-        .into_iter()
-        .map(|action| {
-            let key = config
-                .binds
-                .0
-                .iter()
-                .find(|bind| bind.actions.first() == Some(action))
-                .map(|bind| key_name(comp_mod, &bind.key))
-                .unwrap_or_else(|| String::from("(not bound)"));
-
-            (format!(" {key} "), action_name(action))
-        })
-        .collect::<Vec<_>>();
+    let (title, strings) = match mode {
+        Some(mode) => (format!("Mode: {}", mode.name), mode_strings(mode, comp_mod)),
+        None => (String::from(TITLE), hotkey_strings(config, comp_mod)),
+    };
+    anyhow::ensure!(!strings.is_empty(), "no hotkeys to show");
 
     let mut font = FontDescription::from_string(FONT);
     font.set_absolute_size((font.size() * scale).into());
@@ -251,7 +200,7 @@ fn render(config: &Config, comp_mod: CompositorMod, scale: i32) -> anyhow::Resul
     let bold = AttrList::new();
     bold.insert(AttrInt::new_weight(Weight::Bold));
     layout.set_attributes(Some(&bold));
-    layout.set_text(TITLE);
+    layout.set_text(&title);
     let title_size = layout.pixel_size();
 
     let attrs = AttrList::new();
@@ -307,7 +256,7 @@ fn render(config: &Config, comp_mod: CompositorMod, scale: i32) -> anyhow::Resul
 
     cr.move_to(((width - title_size.0) / 2).into(), padding.into());
     layout.set_attributes(Some(&bold));
-    layout.set_text(TITLE);
+    layout.set_text(&title);
     pangocairo::functions::show_layout(&cr, &layout);
 
     cr.move_to(padding.into(), (padding + title_size.1 + padding).into());
@@ -356,6 +305,112 @@ fn render(config: &Config, comp_mod: CompositorMod, scale: i32) -> anyhow::Resul
     })
 }
 
+/// Returns the key and action strings for the important hotkeys.
+fn hotkey_strings(config: &Config, comp_mod: CompositorMod) -> Vec<(String, String)> {
+    let binds = &config.binds.0;
+
+    // Collect actions that we want to show.
+    let mut actions = vec![
+        &Action::ShowHotkeyOverlay,
+        &Action::Quit,
+        &Action::CloseWindow,
+    ];
+
+    actions.extend(&[
+        &Action::FocusColumnLeft,
+        &Action::FocusColumnRight,
+        &Action::MoveColumnLeft,
+        &Action::MoveColumnRight,
+        &Action::FocusWorkspaceDown,
+        &Action::FocusWorkspaceUp,
+    ]);
+
+    // Prefer move-column-to-workspace-down, but fall back to move-window-to-workspace-down.
+    if binds
+        .iter()
+        .any(|bind| bind.actions.first() == Some(&Action::MoveColumnToWorkspaceDown))
+    {
+        actions.push(&Action::MoveColumnToWorkspaceDown);
+    } else if binds
+        .iter()
+        .any(|bind| bind.actions.first() == Some(&Action::MoveWindowToWorkspaceDown))
+    {
+        actions.push(&Action::MoveWindowToWorkspaceDown);
+    } else {
+        actions.push(&Action::MoveColumnToWorkspaceDown);
+    }
+
+    // Same for -up.
+    if binds
+        .iter()
+        .any(|bind| bind.actions.first() == Some(&Action::MoveColumnToWorkspaceUp))
+    {
+        actions.push(&Action::MoveColumnToWorkspaceUp);
+    } else if binds
+        .iter()
+        .any(|bind| bind.actions.first() == Some(&Action::MoveWindowToWorkspaceUp))
+    {
+        actions.push(&Action::MoveWindowToWorkspaceUp);
+    } else {
+        actions.push(&Action::MoveColumnToWorkspaceUp);
+    }
+
+    actions.extend(&[
+        &Action::SwitchPresetColumnWidth,
+        &Action::MaximizeColumn,
+        &Action::ConsumeWindowIntoColumn,
+        &Action::ExpelWindowFromColumn,
+    ]);
+
+    // Screenshot is not as important, can omit if not bound.
+    if binds
+        .iter()
+        .any(|bind| bind.actions.first() == Some(&Action::Screenshot))
+    {
+        actions.push(&Action::Screenshot);
+    }
+
+    // Add the spawn and enter-mode actions.
+    for bind in binds.iter().filter(|bind| {
+        matches!(
+            bind.actions.first(),
+            Some(Action::Spawn(_) | Action::EnterMode(_))
+        )
+            // Only show binds with Mod or Super to filter out stuff like volume up/down.
+            && (bind.key.modifiers.contains(Modifiers::COMPOSITOR)
+                || bind.key.modifiers.contains(Modifiers::SUPER))
+    }) {
+        actions.push(bind.actions.first().unwrap());
+    }
+
+    actions
+        .into_iter()
+        .map(|action| {
+            let key = config
+                .binds
+                .0
+                .iter()
+                .find(|bind| bind.actions.first() == Some(action))
+                .map(|bind| key_name(comp_mod, &bind.key))
+                .unwrap_or_else(|| String::from("(not bound)"));
+
+            (format!(" {key} "), action_name(action))
+        })
+        .collect()
+}
+
+/// Returns the key and action strings for all binds of a mode.
+fn mode_strings(mode: &BindMode, comp_mod: CompositorMod) -> Vec<(String, String)> {
+    mode.binds
+        .iter()
+        .filter_map(|bind| {
+            let action = bind.actions.first()?;
+            let key = key_name(comp_mod, &bind.key);
+            Some((format!(" {key} "), action_name(action)))
+        })
+        .collect()
+}
+
 fn action_name(action: &Action) -> String {
     match action {
         Action::Quit => String::from("Exit niri"),
@@ -376,6 +431,20 @@ fn action_name(action: &Action) -> String {
         Action::ConsumeWindowIntoColumn => String::from("Consume Window Into Column"),
         Action::ExpelWindowFromColumn => String::from("Expel Window From Column"),
         Action::Screenshot => String::from("Take a Screenshot"),
+        Action::FocusWindowDown => String::from("Focus Window Down"),
+        Action::FocusWindowUp => String::from("Focus Window Up"),
+        Action::SetColumnWidth(change) => size_change_name("Column Width", *change),
+        Action::SetWindowHeight(change) => size_change_name("Window Height", *change),
+        Action::FocusWorkspace(WorkspaceReference::Index(idx)) => {
+            format!("Switch to Workspace {idx}")
+        }
+        Action::FocusWorkspace(WorkspaceReference::Name(name)) => {
+            format!("Switch to Workspace <span face='monospace' bgcolor='#000000'>{name}</span>")
+        }
+        Action::EnterMode(name) => {
+            format!("Enter Mode <span face='monospace' bgcolor='#000000'>{name}</span>")
+        }
+        Action::ExitMode => String::from("Exit Mode"),
         Action::Spawn(args) => format!(
             "Spawn <span face='monospace' bgcolor='#000000'>{}</span>",
             args.first().unwrap_or(&String::new())
@@ -384,6 +453,15 @@ fn action_name(action: &Action) -> String {
     }
 }
 
+fn size_change_name(what: &str, change: SizeChange) -> String {
+    match change {
+        SizeChange::SetFixed(px) => format!("Set {what} to {px}px"),
+        SizeChange::SetProportion(prop) => format!("Set {what} to {prop}%"),
+        SizeChange::AdjustFixed(px) => format!("Change {what} by {px:+}px"),
+        SizeChange::AdjustProportion(prop) => format!("Change {what} by {prop:+}%"),
+    }
+}
+
 fn key_name(comp_mod: CompositorMod, key: &Key) -> String {
     let mut name = String::new();
 
@@ -403,11 +481,26 @@ fn key_name(comp_mod: CompositorMod, key: &Key) -> String {
     if key.modifiers.contains(Modifiers::CTRL) {
         name.push_str("Ctrl + ");
     }
-    name.push_str(&prettify_keysym_name(&keysym_get_name(key.keysym)));
+    name.push_str(&trigger_name(key.trigger));
 
     name
 }
 
+fn trigger_name(trigger: Trigger) -> String {
+    match trigger {
+        Trigger::Keysym(keysym) => prettify_keysym_name(&keysym_get_name(keysym)),
+        Trigger::MouseLeft => String::from("Mouse Left"),
+        Trigger::MouseRight => String::from("Mouse Right"),
+        Trigger::MouseMiddle => String::from("Mouse Middle"),
+        Trigger::MouseBack => String::from("Mouse Back"),
+        Trigger::MouseForward => String::from("Mouse Forward"),
+        Trigger::WheelScrollDown => String::from("Wheel Scroll Down"),
+        Trigger::WheelScrollUp => String::from("Wheel Scroll Up"),
+        Trigger::WheelScrollLeft => String::from("Wheel Scroll Left"),
+        Trigger::WheelScrollRight => String::from("Wheel Scroll Right"),
+    }
+}
+
 fn prettify_keysym_name(name: &str) -> String {
     let name = match name {
         "slash" => "/",
@@ -0,0 +1,216 @@
+use niri_config::{GestureAction, GestureBind, GestureDirection, GestureKind, Gestures};
+
+/// Total finger movement after which a swipe commits to an axis.
+const SWIPE_LOCK_THRESHOLD: f64 = 16.;
+
+/// Finger movement along the locked axis needed to trigger a swipe action.
+const SWIPE_ACTION_THRESHOLD: f64 = 100.;
+
+/// Pinch scales past which a pinch counts as pinching in or out.
+const PINCH_IN_SCALE: f64 = 0.8;
+const PINCH_OUT_SCALE: f64 = 1.25;
+
+/// A touchpad gesture intercepted by the compositor.
+#[derive(Debug)]
+pub enum GestureState {
+    Swipe(SwipeState),
+    Pinch { fingers: u32, scale: f64 },
+    Hold { fingers: u32 },
+}
+
+#[derive(Debug)]
+pub struct SwipeState {
+    pub fingers: u32,
+    /// Cumulative finger movement, not adjusted for natural scrolling.
+    pub delta: (f64, f64),
+    /// Bind that the swipe committed to once it moved far enough.
+    pub locked: Option<LockedSwipe>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LockedSwipe {
+    /// The swipe drives an interactive gesture in the layout.
+    WorkspaceSwitch,
+    ViewScroll,
+    /// The swipe runs an action when it ends.
+    Action(GestureDirection),
+    /// Nothing is bound to this direction.
+    Nothing,
+}
+
+impl SwipeState {
+    pub fn new(fingers: u32) -> Self {
+        Self {
+            fingers,
+            delta: (0., 0.),
+            locked: None,
+        }
+    }
+
+    /// Adds finger movement and returns whether the swipe has just locked to a bind.
+    pub fn update(&mut self, gestures: &Gestures, dx: f64, dy: f64) -> bool {
+        self.delta.0 += dx;
+        self.delta.1 += dy;
+
+        if self.locked.is_some() || self.delta.0.abs() + self.delta.1.abs() < SWIPE_LOCK_THRESHOLD {
+            return false;
+        }
+
+        let direction = swipe_direction(self.delta);
+        let bind = find_bind(gestures, GestureKind::Swipe, self.fingers, Some(direction));
+        self.locked = Some(match bind.map(|bind| &bind.action) {
+            Some(GestureAction::WorkspaceSwitch) => LockedSwipe::WorkspaceSwitch,
+            Some(GestureAction::ViewScroll) => LockedSwipe::ViewScroll,
+            Some(GestureAction::Action(_)) => LockedSwipe::Action(direction),
+            None => LockedSwipe::Nothing,
+        });
+        true
+    }
+
+    /// Returns the direction of a finished swipe if it went far enough to run its action.
+    pub fn finished_direction(&self) -> Option<GestureDirection> {
+        let Some(LockedSwipe::Action(direction)) = self.locked else {
+            return None;
+        };
+
+        let distance = match direction {
+            GestureDirection::Left => -self.delta.0,
+            GestureDirection::Right => self.delta.0,
+            GestureDirection::Up => -self.delta.1,
+            GestureDirection::Down => self.delta.1,
+            _ => unreachable!(),
+        };
+        (distance >= SWIPE_ACTION_THRESHOLD).then_some(direction)
+    }
+}
+
+/// Returns the main direction of a finger movement.
+fn swipe_direction((dx, dy): (f64, f64)) -> GestureDirection {
+    if dx.abs() >= dy.abs() {
+        if dx < 0. {
+            GestureDirection::Left
+        } else {
+            GestureDirection::Right
+        }
+    } else if dy < 0. {
+        GestureDirection::Up
+    } else {
+        GestureDirection::Down
+    }
+}
+
+/// Returns the direction of a finished pinch, if it pinched far enough.
+pub fn pinch_direction(scale: f64) -> Option<GestureDirection> {
+    if scale <= PINCH_IN_SCALE {
+        Some(GestureDirection::In)
+    } else if scale >= PINCH_OUT_SCALE {
+        Some(GestureDirection::Out)
+    } else {
+        None
+    }
+}
+
+/// Returns whether any gesture is bound to this kind and finger count.
+pub fn has_bind(gestures: &Gestures, kind: GestureKind, fingers: u32) -> bool {
+    gestures
+        .0
+        .iter()
+        .any(|bind| bind.kind == kind && u32::from(bind.fingers) == fingers)
+}
+
+/// Finds the first bind matching a gesture.
+///
+/// Binds without a direction match gestures in any direction.
+pub fn find_bind(
+    gestures: &Gestures,
+    kind: GestureKind,
+    fingers: u32,
+    direction: Option<GestureDirection>,
+) -> Option<&GestureBind> {
+    gestures.0.iter().find(|bind| {
+        bind.kind == kind
+            && u32::from(bind.fingers) == fingers
+            && match (bind.direction, direction) {
+                (None, _) => true,
+                (Some(expected), Some(actual)) => expected.matches(actual),
+                (Some(_), None) => false,
+            }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use niri_config::Action;
+
+    use super::*;
+
+    fn gestures() -> Gestures {
+        Gestures(vec![
+            GestureBind {
+                kind: GestureKind::Swipe,
+                fingers: 3,
+                direction: Some(GestureDirection::Horizontal),
+                action: GestureAction::ViewScroll,
+            },
+            GestureBind {
+                kind: GestureKind::Swipe,
+                fingers: 3,
+                direction: Some(GestureDirection::Up),
+                action: GestureAction::Action(Action::ToggleOverview),
+            },
+            GestureBind {
+                kind: GestureKind::Pinch,
+                fingers: 4,
+                direction: None,
+                action: GestureAction::Action(Action::CloseWindow),
+            },
+        ])
+    }
+
+    #[test]
+    fn swipe_locks_to_direction() {
+        let gestures = gestures();
+
+        let mut swipe = SwipeState::new(3);
+        assert!(!swipe.update(&gestures, -5., 1.));
+        assert!(swipe.update(&gestures, -15., 2.));
+        assert_eq!(swipe.locked, Some(LockedSwipe::ViewScroll));
+        assert!(!swipe.update(&gestures, 0., -100.));
+        assert_eq!(swipe.locked, Some(LockedSwipe::ViewScroll));
+
+        let mut swipe = SwipeState::new(3);
+        assert!(swipe.update(&gestures, 0., 20.));
+        assert_eq!(swipe.locked, Some(LockedSwipe::Nothing));
+        assert_eq!(swipe.finished_direction(), None);
+
+        let mut swipe = SwipeState::new(3);
+        assert!(swipe.update(&gestures, 0., -20.));
+        assert_eq!(
+            swipe.locked,
+            Some(LockedSwipe::Action(GestureDirection::Up))
+        );
+        assert_eq!(swipe.finished_direction(), None);
+        swipe.update(&gestures, 10., -80.);
+        assert_eq!(swipe.finished_direction(), Some(GestureDirection::Up));
+    }
+
+    #[test]
+    fn binds_match_kind_fingers_and_direction() {
+        let gestures = gestures();
+
+        assert!(has_bind(&gestures, GestureKind::Swipe, 3));
+        assert!(!has_bind(&gestures, GestureKind::Swipe, 4));
+        assert!(!has_bind(&gestures, GestureKind::Hold, 3));
+
+        let left = Some(GestureDirection::Left);
+        let bind = find_bind(&gestures, GestureKind::Swipe, 3, left).unwrap();
+        assert_eq!(bind.action, GestureAction::ViewScroll);
+        let down = Some(GestureDirection::Down);
+        assert!(find_bind(&gestures, GestureKind::Swipe, 3, down).is_none());
+
+        assert_eq!(pinch_direction(1.), None);
+        let pinch = find_bind(&gestures, GestureKind::Pinch, 4, pinch_direction(0.5));
+        assert!(pinch.is_some());
+        assert!(find_bind(&gestures, GestureKind::Pinch, 3, pinch_direction(0.5)).is_none());
+    }
+}
@@ -1,30 +1,50 @@
 use std::any::Any;
 use std::collections::HashSet;
 
-use niri_config::{Action, Binds, Modifiers};
+use niri_config::{
+    Action, Bind, BindMode, Config, GestureAction, GestureDirection, GestureKind, Modifiers,
+    Trigger, WorkspaceReference,
+};
 use niri_ipc::LayoutSwitchTarget;
 use smithay::backend::input::{
     AbsolutePositionEvent, Axis, AxisSource, ButtonState, Device, DeviceCapability, Event,
     GestureBeginEvent, GestureEndEvent, GesturePinchUpdateEvent as _, GestureSwipeUpdateEvent as _,
-    InputBackend, InputEvent, KeyState, KeyboardKeyEvent, PointerAxisEvent, PointerButtonEvent,
-    PointerMotionEvent, ProximityState, TabletToolButtonEvent, TabletToolEvent,
-    TabletToolProximityEvent, TabletToolTipEvent, TabletToolTipState,
+    InputBackend, InputEvent, KeyState, KeyboardKeyEvent, MouseButton, PointerAxisEvent,
+    PointerButtonEvent, PointerMotionEvent, ProximityState, TabletToolButtonEvent, TabletToolEvent,
+    TabletToolProximityEvent, TabletToolTipEvent, TabletToolTipState, TouchEvent, TouchSlot,
 };
 use smithay::backend::libinput::LibinputInputBackend;
 use smithay::input::keyboard::{keysyms, FilterResult, Keysym, ModifiersState};
 use smithay::input::pointer::{
-    AxisFrame, ButtonEvent, CursorImageStatus, GestureHoldBeginEvent, GestureHoldEndEvent,
-    GesturePinchBeginEvent, GesturePinchEndEvent, GesturePinchUpdateEvent, GestureSwipeBeginEvent,
-    GestureSwipeEndEvent, GestureSwipeUpdateEvent, MotionEvent, RelativeMotionEvent,
+    AxisFrame, ButtonEvent, CursorIcon, CursorImageStatus, Focus, GestureHoldBeginEvent,
+    GestureHoldEndEvent, GesturePinchBeginEvent, GesturePinchEndEvent, GesturePinchUpdateEvent,
+    GestureSwipeBeginEvent, GestureSwipeEndEvent, GestureSwipeUpdateEvent,
+    GrabStartData as PointerGrabStartData, MotionEvent, RelativeMotionEvent,
 };
+use smithay::input::touch::{DownEvent, MotionEvent as TouchMotionEvent, UpEvent};
 use smithay::reexports::input;
 use smithay::utils::{Logical, Point, SERIAL_COUNTER};
 use smithay::wayland::pointer_constraints::{with_pointer_constraint, PointerConstraint};
 use smithay::wayland::tablet_manager::{TabletDescriptor, TabletSeatTrait};
 
-use crate::niri::State;
+use self::gesture::{
+    find_bind as find_gesture_bind, has_bind as has_gesture_bind, pinch_direction, GestureState,
+    LockedSwipe, SwipeState,
+};
+use self::move_grab::MoveGrab;
+use self::overview_grab::OverviewGrab;
+use self::resize_grab::ResizeGrab;
+use self::scroll_tracker::ScrollTracker;
+use crate::layout::WORKSPACE_GESTURE_MOVEMENT;
+use crate::niri::{BindModeState, State};
 use crate::screenshot_ui::ScreenshotUi;
-use crate::utils::{center, get_monotonic_time, spawn};
+use crate::utils::{center, get_monotonic_time, output_size, spawn};
+
+pub mod gesture;
+pub mod move_grab;
+pub mod overview_grab;
+pub mod resize_grab;
+pub mod scroll_tracker;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum CompositorMod {
@@ -37,6 +57,17 @@ pub struct TabletData {
     pub aspect_ratio: f64,
 }
 
+/// Touchscreen swipe from the edge of an output, driving a workspace switch gesture.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TouchEdgeSwipe {
+    pub slot: TouchSlot,
+    pub last_y: f64,
+    pub output_height: f64,
+}
+
+/// Distance from the top or bottom output edge where a touch starts a workspace switch.
+const TOUCH_EDGE_SIZE: f64 = 16.;
+
 impl State {
     pub fn process_input_event<I: InputBackend>(&mut self, event: InputEvent<I>)
     where
@@ -70,8 +101,14 @@ impl State {
             }
         }
 
-        let hide_hotkey_overlay =
-            self.niri.hotkey_overlay.is_open() && should_hide_hotkey_overlay(&event);
+        // Modes showing the hotkey overlay hide it themselves once they are left.
+        let hide_hotkey_overlay = self.niri.hotkey_overlay.is_open()
+            && !self
+                .niri
+                .bind_mode
+                .as_ref()
+                .is_some_and(|mode| mode.show_hotkey_overlay)
+            && should_hide_hotkey_overlay(&event);
 
         let hide_exit_confirm_dialog = self
             .niri
@@ -101,11 +138,11 @@ impl State {
             GesturePinchEnd { event } => self.on_gesture_pinch_end::<I>(event),
             GestureHoldBegin { event } => self.on_gesture_hold_begin::<I>(event),
             GestureHoldEnd { event } => self.on_gesture_hold_end::<I>(event),
-            TouchDown { .. } => (),
-            TouchMotion { .. } => (),
-            TouchUp { .. } => (),
-            TouchCancel { .. } => (),
-            TouchFrame { .. } => (),
+            TouchDown { event } => self.on_touch_down::<I>(event),
+            TouchMotion { event } => self.on_touch_motion::<I>(event),
+            TouchUp { event } => self.on_touch_up::<I>(event),
+            TouchCancel { event } => self.on_touch_cancel::<I>(event),
+            TouchFrame { .. } => self.on_touch_frame(),
             SwitchToggle { .. } => (),
             Special(_) => (),
         }
@@ -227,6 +264,26 @@ impl State {
         Some(pos + output_geo.loc.to_f64())
     }
 
+    /// Computes the touch position in the global space.
+    ///
+    /// Touchscreen coordinates follow the panel rather than the rotated output, so this undoes the
+    /// output transform.
+    fn compute_touch_position<I: InputBackend>(
+        &self,
+        event: &impl AbsolutePositionEvent<I>,
+    ) -> Option<Point<f64, Logical>> {
+        let output = self.niri.output_for_touch()?;
+        let output_geo = self.niri.global_space.output_geometry(output).unwrap();
+        let transform = output.current_transform();
+
+        let panel_size = transform.transform_size(output_geo.size);
+        let pos = event.position_transformed(panel_size);
+        let pos = transform
+            .invert()
+            .transform_point_in(pos, &panel_size.to_f64());
+        Some(pos + output_geo.loc.to_f64())
+    }
+
     fn on_keyboard<I: InputBackend>(&mut self, event: I::KeyboardKeyEvent) {
         let comp_mod = self.backend.mod_key();
 
@@ -241,7 +298,9 @@ impl State {
             serial,
             time,
             |this, mods, keysym| {
-                let bindings = &this.niri.config.borrow().binds;
+                let config = this.niri.config.borrow();
+                let bind_mode = this.niri.bind_mode.as_ref();
+                let oneshot_mode = active_mode(&config, bind_mode).is_some_and(|mode| mode.oneshot);
                 let key_code = event.key_code();
                 let modified = keysym.modified_sym();
                 let raw = keysym.raw_latin_sym_or_raw_current_sym();
@@ -254,7 +313,8 @@ impl State {
 
                 should_intercept_key(
                     &mut this.niri.suppressed_keys,
-                    bindings,
+                    active_binds(&config, bind_mode),
+                    oneshot_mode,
                     comp_mod,
                     key_code,
                     modified,
@@ -262,7 +322,7 @@ impl State {
                     pressed,
                     *mods,
                     &this.niri.screenshot_ui,
-                    this.niri.config.borrow().input.disable_power_key_handling,
+                    config.input.disable_power_key_handling,
                 )
             },
         ) else {
@@ -274,14 +334,45 @@ impl State {
             return;
         }
 
-        self.do_action(action);
+        self.do_bind_action(action);
+    }
+
+    /// Runs an action triggered by a bind, keeping track of the active bind mode.
+    fn do_bind_action(&mut self, action: Action) {
+        let oneshot = {
+            let config = self.niri.config.borrow();
+            active_mode(&config, self.niri.bind_mode.as_ref()).is_some_and(|mode| mode.oneshot)
+        };
+
+        if oneshot {
+            self.niri.exit_bind_mode();
+        } else {
+            self.niri.restart_bind_mode_timer();
+        }
+
+        self.do_user_action(action);
     }
 
-    pub fn do_action(&mut self, action: Action) {
+    /// Runs an action triggered by the user through a bind or a gesture.
+    fn do_user_action(&mut self, action: Action) {
+        // Pressing binds while locked is expected, so don't warn about it.
         if self.niri.is_locked() && !allowed_when_locked(&action) {
             return;
         }
 
+        if let Err(err) = self.do_action(action) {
+            warn!("error running action: {err}");
+        }
+    }
+
+    /// Runs an action, returning an error if it couldn't be run.
+    pub fn do_action(&mut self, action: Action) -> Result<(), String> {
+        if self.niri.is_locked() && !allowed_when_locked(&action) {
+            return Err(String::from(
+                "action is not allowed while the session is locked",
+            ));
+        }
+
         match action {
             Action::Quit => {
                 if let Some(dialog) = &mut self.niri.exit_confirm_dialog {
@@ -370,6 +461,10 @@ impl State {
                     window.toplevel().send_close();
                 }
             }
+            Action::CloseWindowById(id) => {
+                let window = self.niri.find_window_by_id(id)?;
+                window.toplevel().send_close();
+            }
             Action::FullscreenWindow => {
                 let focus = self.niri.layout.focus().cloned();
                 if let Some(window) = focus {
@@ -378,14 +473,71 @@ impl State {
                     self.niri.queue_redraw_all();
                 }
             }
+            Action::FullscreenWindowById(id) => {
+                let window = self.niri.find_window_by_id(id)?;
+                self.niri.layout.toggle_fullscreen(&window);
+                // FIXME: granular
+                self.niri.queue_redraw_all();
+            }
+            Action::FocusWindow(id) => {
+                let window = self.niri.find_window_by_id(id)?;
+                // Windows can only be focused while there are outputs.
+                let Some(prev_output) = self.niri.layout.active_output().cloned() else {
+                    return Err(String::from("windows can't be focused without outputs"));
+                };
+
+                self.niri.layout.activate_window(&window);
+
+                let output = self.niri.layout.active_output().cloned();
+                if let Some(output) = output.filter(|output| *output != prev_output) {
+                    self.move_cursor_to_output(&output);
+                }
+
+                // FIXME: granular
+                self.niri.queue_redraw_all();
+            }
             Action::SwitchLayout(action) => {
-                self.niri.seat.get_keyboard().unwrap().with_xkb_state(
-                    self,
-                    |mut state| match action {
-                        LayoutSwitchTarget::Next => state.cycle_next_layout(),
-                        LayoutSwitchTarget::Prev => state.cycle_prev_layout(),
-                    },
-                );
+                self.niri
+                    .seat
+                    .get_keyboard()
+                    .unwrap()
+                    .with_xkb_state(self, |mut state| match &action {
+                        LayoutSwitchTarget::Next => {
+                            state.cycle_next_layout();
+                            Ok(())
+                        }
+                        LayoutSwitchTarget::Prev => {
+                            state.cycle_prev_layout();
+                            Ok(())
+                        }
+                        LayoutSwitchTarget::Index(idx) => {
+                            let layout = {
+                                let xkb = state.xkb().lock().unwrap();
+                                xkb.layouts().find(|layout| layout.0 == u32::from(*idx))
+                            };
+                            match layout {
+                                Some(layout) => {
+                                    state.set_layout(layout);
+                                    Ok(())
+                                }
+                                None => Err(format!("keyboard layout {idx} does not exist")),
+                            }
+                        }
+                        LayoutSwitchTarget::Name(name) => {
+                            let layout = {
+                                let xkb = state.xkb().lock().unwrap();
+                                xkb.layouts()
+                                    .find(|layout| xkb.layout_name(*layout) == name)
+                            };
+                            match layout {
+                                Some(layout) => {
+                                    state.set_layout(layout);
+                                    Ok(())
+                                }
+                                None => Err(format!("keyboard layout {name:?} does not exist")),
+                            }
+                        }
+                    })?;
             }
             Action::MoveColumnLeft => {
                 self.niri.layout.move_left();
@@ -487,9 +639,40 @@ impl State {
                 // FIXME: granular
                 self.niri.queue_redraw_all();
             }
-            Action::MoveWindowToWorkspace(idx) => {
-                let idx = idx.saturating_sub(1) as usize;
-                self.niri.layout.move_to_workspace(idx);
+            Action::MoveWindowToWorkspace(reference) => {
+                let (output, index) = self.niri.find_output_and_workspace_index(reference)?;
+                if let Some(output) = output {
+                    self.niri
+                        .layout
+                        .move_to_output_workspace(&output, Some(index));
+                    self.move_cursor_to_output(&output);
+                } else {
+                    self.niri.layout.move_to_workspace(index);
+                }
+                // FIXME: granular
+                self.niri.queue_redraw_all();
+            }
+            Action::MoveWindowToWorkspaceById {
+                window_id,
+                reference,
+            } => {
+                let window = self.niri.find_window_by_id(window_id)?;
+
+                // Unlike find_output_and_workspace_index(), an index refers to the window's own
+                // output here rather than to the focused one.
+                let (output, index) = match reference {
+                    WorkspaceReference::Index(index) => (None, index.saturating_sub(1) as usize),
+                    WorkspaceReference::Name(name) => self
+                        .niri
+                        .layout
+                        .find_workspace_by_name(&name)
+                        .map(|(idx, ws)| (ws.current_output().cloned(), idx))
+                        .ok_or_else(|| format!("workspace {name:?} does not exist"))?,
+                };
+
+                self.niri
+                    .layout
+                    .move_window_to_workspace(&window, output.as_ref(), index);
                 // FIXME: granular
                 self.niri.queue_redraw_all();
             }
@@ -503,9 +686,16 @@ impl State {
                 // FIXME: granular
                 self.niri.queue_redraw_all();
             }
-            Action::MoveColumnToWorkspace(idx) => {
-                let idx = idx.saturating_sub(1) as usize;
-                self.niri.layout.move_column_to_workspace(idx);
+            Action::MoveColumnToWorkspace(reference) => {
+                let (output, index) = self.niri.find_output_and_workspace_index(reference)?;
+                if let Some(output) = output {
+                    self.niri
+                        .layout
+                        .move_column_to_output_workspace(&output, Some(index));
+                    self.move_cursor_to_output(&output);
+                } else {
+                    self.niri.layout.move_column_to_workspace(index);
+                }
                 // FIXME: granular
                 self.niri.queue_redraw_all();
             }
@@ -519,9 +709,15 @@ impl State {
                 // FIXME: granular
                 self.niri.queue_redraw_all();
             }
-            Action::FocusWorkspace(idx) => {
-                let idx = idx.saturating_sub(1) as usize;
-                self.niri.layout.switch_workspace(idx);
+            Action::FocusWorkspace(reference) => {
+                let (output, index) = self.niri.find_output_and_workspace_index(reference)?;
+                if let Some(output) = output {
+                    self.niri.layout.focus_output(&output);
+                    self.niri.layout.switch_workspace(index);
+                    self.move_cursor_to_output(&output);
+                } else {
+                    self.niri.layout.switch_workspace(index);
+                }
                 // FIXME: granular
                 self.niri.queue_redraw_all();
             }
@@ -546,7 +742,11 @@ impl State {
                 self.niri.queue_redraw_all();
             }
             Action::SwitchPresetColumnWidth => {
-                self.niri.layout.toggle_width();
+                self.niri.layout.toggle_width(None);
+            }
+            Action::SwitchPresetColumnWidthById(id) => {
+                let window = self.niri.find_window_by_id(id)?;
+                self.niri.layout.toggle_width(Some(&window));
             }
             Action::CenterColumn => {
                 self.niri.layout.center_column();
@@ -554,7 +754,11 @@ impl State {
                 self.niri.queue_redraw_all();
             }
             Action::MaximizeColumn => {
-                self.niri.layout.toggle_full_width();
+                self.niri.layout.toggle_full_width(None);
+            }
+            Action::MaximizeColumnById(id) => {
+                let window = self.niri.find_window_by_id(id)?;
+                self.niri.layout.toggle_full_width(Some(&window));
             }
             Action::FocusMonitorLeft => {
                 if let Some(output) = self.niri.output_left() {
@@ -629,16 +833,30 @@ impl State {
                 }
             }
             Action::SetColumnWidth(change) => {
-                self.niri.layout.set_column_width(change);
+                self.niri.layout.set_column_width(None, change);
+            }
+            Action::SetColumnWidthById { id, change } => {
+                let window = self.niri.find_window_by_id(id)?;
+                self.niri.layout.set_column_width(Some(&window), change);
             }
             Action::SetWindowHeight(change) => {
-                self.niri.layout.set_window_height(change);
+                self.niri.layout.set_window_height(None, change);
+            }
+            Action::SetWindowHeightById { id, change } => {
+                let window = self.niri.find_window_by_id(id)?;
+                self.niri.layout.set_window_height(Some(&window), change);
             }
             Action::ShowHotkeyOverlay => {
                 if self.niri.hotkey_overlay.show() {
                     self.niri.queue_redraw_all();
                 }
             }
+            Action::EnterMode(name) => {
+                self.niri.enter_bind_mode(&name);
+            }
+            Action::ExitMode => {
+                self.niri.exit_bind_mode();
+            }
             Action::MoveWorkspaceToMonitorLeft => {
                 if let Some(output) = self.niri.output_left() {
                     self.niri.layout.move_workspace_to_output(&output);
@@ -663,7 +881,29 @@ impl State {
                     self.move_cursor_to_output(&output);
                 }
             }
+            Action::ToggleWindowFloating => {
+                self.niri.layout.toggle_window_floating();
+                // FIXME: granular
+                self.niri.queue_redraw_all();
+            }
+            Action::SwitchFocusBetweenFloatingAndTiling => {
+                self.niri.layout.switch_focus_floating_tiling();
+                // FIXME: granular
+                self.niri.queue_redraw_all();
+            }
+            Action::ToggleColumnTabbedDisplay => {
+                self.niri.layout.toggle_column_tabbed_display();
+                // FIXME: granular
+                self.niri.queue_redraw_all();
+            }
+            Action::ToggleOverview => {
+                self.niri.layout.toggle_overview();
+                // FIXME: granular
+                self.niri.queue_redraw_all();
+            }
         }
+
+        Ok(())
     }
 
     fn on_pointer_motion<I: InputBackend>(&mut self, event: I::PointerMotionEvent) {
@@ -910,9 +1150,104 @@ impl State {
 
         let button_state = event.state();
 
+        let intercept = should_intercept_button(
+            &mut self.niri.suppressed_buttons,
+            active_binds(&self.niri.config.borrow(), self.niri.bind_mode.as_ref()),
+            self.backend.mod_key(),
+            button,
+            event.button(),
+            button_state == ButtonState::Pressed,
+            self.niri.seat.get_keyboard().unwrap().modifier_state(),
+            &self.niri.screenshot_ui,
+        );
+        if let FilterResult::Intercept(action) = intercept {
+            if let Some(action) = action {
+                self.do_bind_action(action);
+            }
+            return;
+        }
+
         if ButtonState::Pressed == button_state {
             if let Some(window) = self.niri.window_under_cursor() {
                 let window = window.clone();
+
+                // Check if we need to start an interactive move or resize.
+                let mods = self.niri.seat.get_keyboard().unwrap().modifier_state();
+                let mod_down = match self.backend.mod_key() {
+                    CompositorMod::Super => mods.logo,
+                    CompositorMod::Alt => mods.alt,
+                };
+
+                let location = pointer.current_location();
+                let overview_output = self
+                    .niri
+                    .output_under(location)
+                    .map(|(output, pos)| (output.clone(), pos))
+                    .filter(|(output, _)| self.niri.layout.is_overview_open(output));
+
+                if let Some((output, pos_within_output)) = overview_output {
+                    // In the overview, windows can be picked and dragged without the modifier.
+                    if event.button() == Some(MouseButton::Left) && !pointer.is_grabbed() {
+                        let start_data = PointerGrabStartData {
+                            focus: None,
+                            button,
+                            location,
+                        };
+                        let grab = OverviewGrab::new(
+                            start_data,
+                            window.clone(),
+                            output,
+                            pos_within_output,
+                        );
+                        pointer.set_grab(self, grab, serial, Focus::Clear);
+                    }
+                } else if mod_down && !pointer.is_grabbed() {
+                    if let Some((output, pos_within_output)) = self.niri.output_under(location) {
+                        let output = output.clone();
+                        let start_data = PointerGrabStartData {
+                            focus: None,
+                            button,
+                            location,
+                        };
+
+                        match event.button() {
+                            Some(MouseButton::Left) => {
+                                if self.niri.layout.interactive_move_begin(
+                                    &window,
+                                    &output,
+                                    pos_within_output,
+                                ) {
+                                    let grab = MoveGrab::new(start_data, window.clone());
+                                    pointer.set_grab(self, grab, serial, Focus::Clear);
+                                    self.niri.cursor_manager.set_cursor_image(
+                                        CursorImageStatus::Named(CursorIcon::Grabbing),
+                                    );
+                                }
+                            }
+                            Some(MouseButton::Right) => {
+                                let edges = self
+                                    .niri
+                                    .layout
+                                    .resize_edges_under(&output, pos_within_output);
+                                if let Some(edges) = edges {
+                                    if self
+                                        .niri
+                                        .layout
+                                        .interactive_resize_begin(window.clone(), edges)
+                                    {
+                                        let grab = ResizeGrab::new(start_data, window.clone());
+                                        pointer.set_grab(self, grab, serial, Focus::Clear);
+                                        self.niri.cursor_manager.set_cursor_image(
+                                            CursorImageStatus::Named(edges.cursor_icon()),
+                                        );
+                                    }
+                                }
+                            }
+                            _ => (),
+                        }
+                    }
+                }
+
                 self.niri.layout.activate_window(&window);
 
                 // FIXME: granular.
@@ -966,17 +1301,82 @@ impl State {
         pointer.frame(self);
     }
 
-    fn on_pointer_axis<I: InputBackend>(&mut self, event: I::PointerAxisEvent) {
+    fn on_pointer_axis<I: InputBackend>(&mut self, event: I::PointerAxisEvent)
+    where
+        I::Device: 'static,
+    {
         let source = event.source();
 
-        let horizontal_amount = event
+        let mut horizontal_actions = None;
+        let mut vertical_actions = None;
+        if matches!(source, AxisSource::Wheel | AxisSource::WheelTilt) {
+            let comp_mod = self.backend.mod_key();
+            let mods = self.niri.seat.get_keyboard().unwrap().modifier_state();
+            let config = self.niri.config.borrow();
+            let bindings = active_binds(&config, self.niri.bind_mode.as_ref());
+
+            horizontal_actions = wheel_actions(
+                &mut self.niri.horizontal_wheel_tracker,
+                bindings,
+                comp_mod,
+                event.amount_v120(Axis::Horizontal).unwrap_or(0.),
+                Trigger::WheelScrollRight,
+                Trigger::WheelScrollLeft,
+                mods,
+                &self.niri.screenshot_ui,
+            );
+            vertical_actions = wheel_actions(
+                &mut self.niri.vertical_wheel_tracker,
+                bindings,
+                comp_mod,
+                event.amount_v120(Axis::Vertical).unwrap_or(0.),
+                Trigger::WheelScrollDown,
+                Trigger::WheelScrollUp,
+                mods,
+                &self.niri.screenshot_ui,
+            );
+        }
+
+        // Scrolling along an axis with a bind doesn't reach the client.
+        let suppress_horizontal = horizontal_actions.is_some();
+        let suppress_vertical = vertical_actions.is_some();
+        for action in horizontal_actions
+            .into_iter()
+            .chain(vertical_actions)
+            .flatten()
+        {
+            self.do_bind_action(action);
+        }
+
+        let mut horizontal_amount = event
             .amount(Axis::Horizontal)
             .unwrap_or_else(|| event.amount_v120(Axis::Horizontal).unwrap_or(0.0) * 3.0 / 120.);
-        let vertical_amount = event
+        let mut vertical_amount = event
             .amount(Axis::Vertical)
             .unwrap_or_else(|| event.amount_v120(Axis::Vertical).unwrap_or(0.0) * 3.0 / 120.);
-        let horizontal_amount_discrete = event.amount_v120(Axis::Horizontal);
-        let vertical_amount_discrete = event.amount_v120(Axis::Vertical);
+        if suppress_horizontal {
+            horizontal_amount = 0.;
+        }
+        if suppress_vertical {
+            vertical_amount = 0.;
+        }
+        if (suppress_horizontal || suppress_vertical)
+            && horizontal_amount == 0.
+            && vertical_amount == 0.
+        {
+            return;
+        }
+        let mut horizontal_amount_discrete = event.amount_v120(Axis::Horizontal);
+        let mut vertical_amount_discrete = event.amount_v120(Axis::Vertical);
+
+        let device = event.device();
+        if let Some(device) = (&device as &dyn Any).downcast_ref::<input::Device>() {
+            let factor = scroll_factor(&self.niri.config.borrow().input, device);
+            horizontal_amount *= factor;
+            vertical_amount *= factor;
+            horizontal_amount_discrete = horizontal_amount_discrete.map(|x| x * factor);
+            vertical_amount_discrete = vertical_amount_discrete.map(|x| x * factor);
+        }
 
         let mut frame = AxisFrame::new(event.time_msec()).source(source);
         if horizontal_amount != 0.0 {
@@ -1155,15 +1555,161 @@ impl State {
         }
     }
 
-    fn on_gesture_swipe_begin<I: InputBackend>(&mut self, event: I::GestureSwipeBeginEvent) {
-        if event.fingers() == 3 {
-            if let Some(output) = self.niri.output_under_cursor() {
-                self.niri.layout.workspace_switch_gesture_begin(&output);
+    fn on_touch_down<I: InputBackend>(&mut self, event: I::TouchDownEvent) {
+        let Some(pos) = self.compute_touch_position(&event) else {
+            return;
+        };
 
-                // FIXME: granular. This one is awkward because this can cancel a gesture on
-                // multiple other outputs in theory.
-                self.niri.queue_redraw_all();
+        let can_swipe = self.niri.touch_edge_swipe.is_none()
+            && !self.niri.is_locked()
+            && !self.niri.screenshot_ui.is_open();
+        if can_swipe {
+            if let Some((output, pos_within_output)) = self.niri.output_under(pos) {
+                let output = output.clone();
+                let output_height = output_size(&output).h as f64;
+
+                let at_edge = pos_within_output.y < TOUCH_EDGE_SIZE
+                    || pos_within_output.y >= output_height - TOUCH_EDGE_SIZE;
+                if at_edge {
+                    self.niri.layout.workspace_switch_gesture_begin(&output);
+                    self.niri.touch_edge_swipe = Some(TouchEdgeSwipe {
+                        slot: event.slot(),
+                        last_y: pos.y,
+                        output_height,
+                    });
+
+                    // FIXME: granular.
+                    self.niri.queue_redraw_all();
+                    return;
+                }
             }
+        }
+
+        let Some(touch) = self.niri.seat.get_touch() else {
+            return;
+        };
+
+        if let Some(window) = self.niri.window_under(pos) {
+            let window = window.clone();
+            self.niri.layout.activate_window(&window);
+
+            // FIXME: granular.
+            self.niri.queue_redraw_all();
+        } else if let Some((output, _)) = self.niri.output_under(pos) {
+            let output = output.clone();
+            self.niri.layout.activate_output(&output);
+
+            // FIXME: granular.
+            self.niri.queue_redraw_all();
+        }
+
+        let under = self.niri.surface_under_and_global_space(pos);
+        let under = under.map(|u| u.surface);
+        touch.down(
+            self,
+            under,
+            &DownEvent {
+                slot: event.slot(),
+                location: pos,
+                serial: SERIAL_COUNTER.next_serial(),
+                time: event.time_msec(),
+            },
+        );
+    }
+
+    fn on_touch_motion<I: InputBackend>(&mut self, event: I::TouchMotionEvent) {
+        let Some(pos) = self.compute_touch_position(&event) else {
+            return;
+        };
+
+        let swipe = match self.niri.touch_edge_swipe {
+            Some(swipe) if swipe.slot == event.slot() => swipe,
+            _ => {
+                let Some(touch) = self.niri.seat.get_touch() else {
+                    return;
+                };
+                let under = self.niri.surface_under_and_global_space(pos);
+                let under = under.map(|u| u.surface);
+                touch.motion(
+                    self,
+                    under,
+                    &TouchMotionEvent {
+                        slot: event.slot(),
+                        location: pos,
+                        time: event.time_msec(),
+                    },
+                );
+                return;
+            }
+        };
+
+        // Scale the movement so that the workspaces follow the finger.
+        let delta_y = swipe.last_y - pos.y;
+        let delta_y = delta_y / swipe.output_height * WORKSPACE_GESTURE_MOVEMENT;
+        self.niri.touch_edge_swipe = Some(TouchEdgeSwipe {
+            last_y: pos.y,
+            ..swipe
+        });
+
+        if let Some(Some(output)) = self.niri.layout.workspace_switch_gesture_update(delta_y) {
+            self.niri.queue_redraw(output);
+        }
+    }
+
+    fn on_touch_up<I: InputBackend>(&mut self, event: I::TouchUpEvent) {
+        if self.end_touch_edge_swipe(event.slot(), false) {
+            return;
+        }
+
+        let Some(touch) = self.niri.seat.get_touch() else {
+            return;
+        };
+        touch.up(
+            self,
+            &UpEvent {
+                slot: event.slot(),
+                serial: SERIAL_COUNTER.next_serial(),
+                time: event.time_msec(),
+            },
+        );
+    }
+
+    fn on_touch_cancel<I: InputBackend>(&mut self, event: I::TouchCancelEvent) {
+        self.end_touch_edge_swipe(event.slot(), true);
+
+        if let Some(touch) = self.niri.seat.get_touch() {
+            touch.cancel(self);
+        }
+    }
+
+    fn on_touch_frame(&mut self) {
+        if let Some(touch) = self.niri.seat.get_touch() {
+            touch.frame(self);
+        }
+    }
+
+    /// Ends the edge swipe if it is driven by this touch slot, returning whether it was.
+    fn end_touch_edge_swipe(&mut self, slot: TouchSlot, cancelled: bool) -> bool {
+        if self.niri.touch_edge_swipe.map(|swipe| swipe.slot) != Some(slot) {
+            return false;
+        }
+        self.niri.touch_edge_swipe = None;
+
+        if let Some(output) = self.niri.layout.workspace_switch_gesture_end(cancelled) {
+            self.niri.queue_redraw(output);
+        }
+        true
+    }
+
+    fn on_gesture_swipe_begin<I: InputBackend>(&mut self, event: I::GestureSwipeBeginEvent) {
+        let fingers = event.fingers();
+        if has_gesture_bind(
+            &self.niri.config.borrow().gestures,
+            GestureKind::Swipe,
+            fingers,
+        ) {
+            // The layout gesture starts once we know which way the fingers are going.
+            self.niri.gesture = Some(GestureState::Swipe(SwipeState::new(fingers)));
 
             // We handled this event.
             return;
@@ -1181,7 +1727,7 @@ impl State {
             &GestureSwipeBeginEvent {
                 serial,
                 time: event.time_msec(),
-                fingers: event.fingers(),
+                fingers,
             },
         );
     }
@@ -1190,18 +1736,55 @@ impl State {
     where
         I::Device: 'static,
     {
-        let mut delta_y = event.delta_y();
+        if let Some(GestureState::Swipe(swipe)) = &mut self.niri.gesture {
+            let just_locked = swipe.update(
+                &self.niri.config.borrow().gestures,
+                event.delta_x(),
+                event.delta_y(),
+            );
 
-        let device = event.device();
-        if let Some(device) = (&device as &dyn Any).downcast_ref::<input::Device>() {
-            if device.config_scroll_natural_scroll_enabled() {
-                delta_y = -delta_y;
+            // When the swipe has just locked, feed the movement so far, so that the layout
+            // gesture starts right under the fingers.
+            let (mut delta_x, mut delta_y) = if just_locked {
+                swipe.delta
+            } else {
+                (event.delta_x(), event.delta_y())
+            };
+            let locked = swipe.locked;
+
+            let device = event.device();
+            if let Some(device) = (&device as &dyn Any).downcast_ref::<input::Device>() {
+                if device.config_scroll_natural_scroll_enabled() {
+                    delta_x = -delta_x;
+                    delta_y = -delta_y;
+                }
             }
-        }
 
-        let res = self.niri.layout.workspace_switch_gesture_update(delta_y);
-        if let Some(output) = res {
-            if let Some(output) = output {
+            let res = match locked {
+                Some(LockedSwipe::WorkspaceSwitch) => {
+                    if just_locked {
+                        if let Some(output) = self.niri.output_under_cursor() {
+                            self.niri.layout.workspace_switch_gesture_begin(&output);
+
+                            // FIXME: granular. This one is awkward because this can cancel a
+                            // gesture on multiple other outputs in theory.
+                            self.niri.queue_redraw_all();
+                        }
+                    }
+                    self.niri.layout.workspace_switch_gesture_update(delta_y)
+                }
+                Some(LockedSwipe::ViewScroll) => {
+                    if just_locked {
+                        if let Some(output) = self.niri.output_under_cursor() {
+                            self.niri.layout.view_offset_gesture_begin(&output);
+                            self.niri.queue_redraw_all();
+                        }
+                    }
+                    self.niri.layout.view_offset_gesture_update(delta_x)
+                }
+                _ => None,
+            };
+            if let Some(Some(output)) = res {
                 self.niri.queue_redraw(output);
             }
 
@@ -1225,12 +1808,31 @@ impl State {
     }
 
     fn on_gesture_swipe_end<I: InputBackend>(&mut self, event: I::GestureSwipeEndEvent) {
-        let res = self
-            .niri
-            .layout
-            .workspace_switch_gesture_end(event.cancelled());
-        if let Some(output) = res {
-            self.niri.queue_redraw(output);
+        if let Some(GestureState::Swipe(swipe)) = self.niri.gesture.take() {
+            let cancelled = event.cancelled();
+            match swipe.locked {
+                Some(LockedSwipe::WorkspaceSwitch) => {
+                    if let Some(output) = self.niri.layout.workspace_switch_gesture_end(cancelled) {
+                        self.niri.queue_redraw(output);
+                    }
+                }
+                Some(LockedSwipe::ViewScroll) => {
+                    if let Some(output) = self.niri.layout.view_offset_gesture_end(cancelled) {
+                        self.niri.queue_redraw(output);
+                    }
+                }
+                _ => {
+                    if !cancelled {
+                        if let Some(direction) = swipe.finished_direction() {
+                            self.run_gesture_bind(
+                                GestureKind::Swipe,
+                                swipe.fingers,
+                                Some(direction),
+                            );
+                        }
+                    }
+                }
+            }
 
             // We handled this event.
             return;
@@ -1253,7 +1855,38 @@ impl State {
         );
     }
 
+    /// Runs the action bound to a finished gesture, if any.
+    fn run_gesture_bind(
+        &mut self,
+        kind: GestureKind,
+        fingers: u32,
+        direction: Option<GestureDirection>,
+    ) {
+        let action = {
+            let config = self.niri.config.borrow();
+            let bind = find_gesture_bind(&config.gestures, kind, fingers, direction);
+            match bind.map(|bind| &bind.action) {
+                Some(GestureAction::Action(action)) => action.clone(),
+                _ => return,
+            }
+        };
+
+        self.do_user_action(action);
+    }
+
     fn on_gesture_pinch_begin<I: InputBackend>(&mut self, event: I::GesturePinchBeginEvent) {
+        let fingers = event.fingers();
+        if has_gesture_bind(
+            &self.niri.config.borrow().gestures,
+            GestureKind::Pinch,
+            fingers,
+        ) {
+            self.niri.gesture = Some(GestureState::Pinch { fingers, scale: 1. });
+
+            // We handled this event.
+            return;
+        }
+
         let serial = SERIAL_COUNTER.next_serial();
         let pointer = self.niri.seat.get_pointer().unwrap();
 
@@ -1272,6 +1905,13 @@ impl State {
     }
 
     fn on_gesture_pinch_update<I: InputBackend>(&mut self, event: I::GesturePinchUpdateEvent) {
+        if let Some(GestureState::Pinch { scale, .. }) = &mut self.niri.gesture {
+            *scale = event.scale();
+
+            // We handled this event.
+            return;
+        }
+
         let pointer = self.niri.seat.get_pointer().unwrap();
 
         if self.update_pointer_focus() {
@@ -1290,6 +1930,17 @@ impl State {
     }
 
     fn on_gesture_pinch_end<I: InputBackend>(&mut self, event: I::GesturePinchEndEvent) {
+        if let Some(GestureState::Pinch { fingers, scale }) = self.niri.gesture.take() {
+            if !event.cancelled() {
+                if let Some(direction) = pinch_direction(scale) {
+                    self.run_gesture_bind(GestureKind::Pinch, fingers, Some(direction));
+                }
+            }
+
+            // We handled this event.
+            return;
+        }
+
         let serial = SERIAL_COUNTER.next_serial();
         let pointer = self.niri.seat.get_pointer().unwrap();
 
@@ -1308,6 +1959,18 @@ impl State {
     }
 
     fn on_gesture_hold_begin<I: InputBackend>(&mut self, event: I::GestureHoldBeginEvent) {
+        let fingers = event.fingers();
+        if has_gesture_bind(
+            &self.niri.config.borrow().gestures,
+            GestureKind::Hold,
+            fingers,
+        ) {
+            self.niri.gesture = Some(GestureState::Hold { fingers });
+
+            // We handled this event.
+            return;
+        }
+
         let serial = SERIAL_COUNTER.next_serial();
         let pointer = self.niri.seat.get_pointer().unwrap();
 
@@ -1326,6 +1989,16 @@ impl State {
     }
 
     fn on_gesture_hold_end<I: InputBackend>(&mut self, event: I::GestureHoldEndEvent) {
+        if let Some(GestureState::Hold { fingers }) = self.niri.gesture.take() {
+            // Holds are cancelled when the fingers start moving.
+            if !event.cancelled() {
+                self.run_gesture_bind(GestureKind::Hold, fingers, None);
+            }
+
+            // We handled this event.
+            return;
+        }
+
         let serial = SERIAL_COUNTER.next_serial();
         let pointer = self.niri.seat.get_pointer().unwrap();
 
@@ -1347,10 +2020,13 @@ impl State {
 /// Check whether the key should be intercepted and mark intercepted
 /// pressed keys as `suppressed`, thus preventing `releases` corresponding
 /// to them from being delivered.
+///
+/// In a one-shot bind mode, pressing an unbound key leaves the mode instead of reaching the client.
 #[allow(clippy::too_many_arguments)]
 fn should_intercept_key(
     suppressed_keys: &mut HashSet<u32>,
-    bindings: &Binds,
+    bindings: &[Bind],
+    oneshot_mode: bool,
     comp_mod: CompositorMod,
     key_code: u32,
     modified: Keysym,
@@ -1376,6 +2052,11 @@ fn should_intercept_key(
         disable_power_key_handling,
     );
 
+    // Modifiers don't cancel a one-shot mode, since they are needed to press its binds.
+    if final_action.is_none() && oneshot_mode && pressed && !is_modifier_key(modified) {
+        final_action = Some(Action::ExitMode);
+    }
+
     // Allow only a subset of compositor actions while the screenshot UI is open, since the user
     // cannot see the screen.
     if screenshot_ui.is_open() {
@@ -1406,7 +2087,7 @@ fn should_intercept_key(
 }
 
 fn action(
-    bindings: &Binds,
+    bindings: &[Bind],
     comp_mod: CompositorMod,
     modified: Keysym,
     raw: Option<Keysym>,
@@ -1426,13 +2107,118 @@ fn action(
         _ => (),
     }
 
-    bound_action(bindings, comp_mod, raw, mods)
+    bound_action(bindings, comp_mod, Trigger::Keysym(raw?), mods)
+}
+
+/// Check whether the mouse button should be intercepted and mark intercepted
+/// pressed buttons as `suppressed`, thus preventing `releases` corresponding
+/// to them from being delivered.
+#[allow(clippy::too_many_arguments)]
+fn should_intercept_button(
+    suppressed_buttons: &mut HashSet<u32>,
+    bindings: &[Bind],
+    comp_mod: CompositorMod,
+    button_code: u32,
+    button: Option<MouseButton>,
+    pressed: bool,
+    mods: ModifiersState,
+    screenshot_ui: &ScreenshotUi,
+) -> FilterResult<Option<Action>> {
+    if !pressed {
+        return if suppressed_buttons.remove(&button_code) {
+            FilterResult::Intercept(None)
+        } else {
+            FilterResult::Forward
+        };
+    }
+
+    let trigger = match button {
+        Some(MouseButton::Left) => Trigger::MouseLeft,
+        Some(MouseButton::Right) => Trigger::MouseRight,
+        Some(MouseButton::Middle) => Trigger::MouseMiddle,
+        Some(MouseButton::Back) => Trigger::MouseBack,
+        Some(MouseButton::Forward) => Trigger::MouseForward,
+        None => return FilterResult::Forward,
+    };
+
+    let Some(action) = bound_action(bindings, comp_mod, trigger, mods) else {
+        return FilterResult::Forward;
+    };
+
+    // The screenshot UI needs the mouse buttons for selecting the region.
+    if screenshot_ui.is_open() && !allowed_during_screenshot(&action) {
+        return FilterResult::Forward;
+    }
+
+    suppressed_buttons.insert(button_code);
+    FilterResult::Intercept(Some(action))
+}
+
+/// Returns the actions bound to scrolling the wheel along one axis, or `None` if the scroll
+/// should go to the client.
+///
+/// One action is returned per whole wheel tick, which may be none at all for high-resolution
+/// wheels that haven't scrolled far enough yet.
+#[allow(clippy::too_many_arguments)]
+fn wheel_actions(
+    tracker: &mut ScrollTracker,
+    bindings: &[Bind],
+    comp_mod: CompositorMod,
+    amount_v120: f64,
+    positive: Trigger,
+    negative: Trigger,
+    mods: ModifiersState,
+    screenshot_ui: &ScreenshotUi,
+) -> Option<Vec<Action>> {
+    let trigger = if amount_v120 > 0. {
+        positive
+    } else if amount_v120 < 0. {
+        negative
+    } else {
+        return None;
+    };
+
+    let action = bound_action(bindings, comp_mod, trigger, mods)
+        .filter(|action| !screenshot_ui.is_open() || allowed_during_screenshot(action));
+    let Some(action) = action else {
+        tracker.reset();
+        return None;
+    };
+
+    let ticks = tracker.accumulate(amount_v120);
+    Some(vec![action; ticks.unsigned_abs() as usize])
+}
+
+/// Returns the active bind mode, if it is still in the config.
+fn active_mode<'a>(config: &'a Config, bind_mode: Option<&BindModeState>) -> Option<&'a BindMode> {
+    config.find_mode(&bind_mode?.name)
+}
+
+/// Returns the binds of the active bind mode, or the regular binds outside of modes.
+fn active_binds<'a>(config: &'a Config, bind_mode: Option<&BindModeState>) -> &'a [Bind] {
+    match active_mode(config, bind_mode) {
+        Some(mode) => &mode.binds,
+        None => &config.binds.0,
+    }
+}
+
+#[allow(non_upper_case_globals)]
+fn is_modifier_key(keysym: Keysym) -> bool {
+    use keysyms::*;
+
+    matches!(
+        keysym.raw(),
+        KEY_Shift_L..=KEY_Hyper_R
+            | KEY_ISO_Lock..=KEY_ISO_Level5_Lock
+            | KEY_Mode_switch
+            | KEY_Num_Lock
+    )
 }
 
 fn bound_action(
-    bindings: &Binds,
+    bindings: &[Bind],
     comp_mod: CompositorMod,
-    raw: Option<Keysym>,
+    trigger: Trigger,
     mods: ModifiersState,
 ) -> Option<Action> {
     // Handle configured binds.
@@ -1458,10 +2244,8 @@ fn bound_action(
         modifiers |= Modifiers::COMPOSITOR;
     }
 
-    let raw = raw?;
-
-    for bind in &bindings.0 {
-        if bind.key.keysym != raw {
+    for bind in bindings {
+        if bind.key.trigger != trigger {
             continue;
         }
 
@@ -1556,78 +2340,179 @@ fn allowed_during_screenshot(action: &Action) -> bool {
     )
 }
 
-pub fn apply_libinput_settings(config: &niri_config::Input, device: &mut input::Device) {
-    // According to Mutter code, this setting is specific to touchpads.
-    let is_touchpad = device.config_tap_finger_count() > 0;
-    if is_touchpad {
-        let c = &config.touchpad;
-        let _ = device.config_tap_set_enabled(c.tap);
-        let _ = device.config_dwt_set_enabled(c.dwt);
-        let _ = device.config_dwtp_set_enabled(c.dwtp);
-        let _ = device.config_scroll_set_natural_scroll_enabled(c.natural_scroll);
-        let _ = device.config_accel_set_speed(c.accel_speed);
-
-        if let Some(accel_profile) = c.accel_profile {
-            let _ = device.config_accel_set_profile(accel_profile.into());
-        } else if let Some(default) = device.config_accel_default_profile() {
-            let _ = device.config_accel_set_profile(default);
-        }
+/// Config section that applies to a libinput device.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum DeviceSection {
+    Touchpad,
+    Mouse,
+    Trackpoint,
+    Trackball,
+    Touch,
+    Other,
+}
 
-        if let Some(tap_button_map) = c.tap_button_map {
-            let _ = device.config_tap_set_button_map(tap_button_map.into());
-        } else if let Some(default) = device.config_tap_default_button_map() {
-            let _ = device.config_tap_set_button_map(default);
-        }
+fn device_section(device: &input::Device) -> DeviceSection {
+    // According to Mutter code, this setting is specific to touchpads.
+    if device.config_tap_finger_count() > 0 {
+        return DeviceSection::Touchpad;
     }
 
     // This is how Mutter tells apart mice.
-    let mut is_trackball = false;
-    let mut is_trackpoint = false;
     if let Some(udev_device) = unsafe { device.udev_device() } {
         if udev_device.property_value("ID_INPUT_TRACKBALL").is_some() {
-            is_trackball = true;
+            return DeviceSection::Trackball;
         }
         if udev_device
             .property_value("ID_INPUT_POINTINGSTICK")
             .is_some()
         {
-            is_trackpoint = true;
+            return DeviceSection::Trackpoint;
         }
     }
 
-    let is_mouse = device.has_capability(input::DeviceCapability::Pointer)
-        && !is_touchpad
-        && !is_trackball
-        && !is_trackpoint;
-    if is_mouse {
-        let c = &config.mouse;
+    if device.has_capability(input::DeviceCapability::Pointer) {
+        DeviceSection::Mouse
+    } else if device.has_capability(input::DeviceCapability::Touch) {
+        DeviceSection::Touch
+    } else {
+        DeviceSection::Other
+    }
+}
+
+pub fn apply_libinput_settings(config: &niri_config::Input, device: &mut input::Device) {
+    // A named device block replaces the settings of its section.
+    if let Some(c) = config.devices.iter().find(|c| c.name == device.name()) {
+        set_send_events(device, c.off);
+        let _ = device.config_tap_set_enabled(c.tap);
+        let _ = device.config_dwt_set_enabled(c.dwt);
+        let _ = device.config_dwtp_set_enabled(c.dwtp);
         let _ = device.config_scroll_set_natural_scroll_enabled(c.natural_scroll);
-        let _ = device.config_accel_set_speed(c.accel_speed);
+        let _ = device.config_left_handed_set(c.left_handed);
+        let _ = device.config_middle_emulation_set_enabled(c.middle_emulation);
+        set_accel(device, c.accel_speed, c.accel_profile);
+        set_scroll_method(device, c.scroll_method, c.scroll_button);
+        set_click_method(device, c.click_method);
+        set_tap_button_map(device, c.tap_button_map);
+        return;
+    }
 
-        if let Some(accel_profile) = c.accel_profile {
-            let _ = device.config_accel_set_profile(accel_profile.into());
-        } else if let Some(default) = device.config_accel_default_profile() {
-            let _ = device.config_accel_set_profile(default);
+    match device_section(device) {
+        DeviceSection::Touchpad => {
+            let c = &config.touchpad;
+            set_send_events(device, c.off);
+            let _ = device.config_tap_set_enabled(c.tap);
+            let _ = device.config_dwt_set_enabled(c.dwt);
+            let _ = device.config_dwtp_set_enabled(c.dwtp);
+            let _ = device.config_scroll_set_natural_scroll_enabled(c.natural_scroll);
+            let _ = device.config_left_handed_set(c.left_handed);
+            let _ = device.config_middle_emulation_set_enabled(c.middle_emulation);
+            set_accel(device, c.accel_speed, c.accel_profile);
+            set_scroll_method(device, c.scroll_method, None);
+            set_click_method(device, c.click_method);
+            set_tap_button_map(device, c.tap_button_map);
         }
+        DeviceSection::Mouse => apply_pointer_device_settings(device, &config.mouse),
+        DeviceSection::Trackpoint => apply_pointer_device_settings(device, &config.trackpoint),
+        DeviceSection::Trackball => apply_pointer_device_settings(device, &config.trackball),
+        DeviceSection::Touch => {
+            set_send_events(device, config.touch.off);
+        }
+        DeviceSection::Other => (),
+    }
+}
+
+fn apply_pointer_device_settings(device: &mut input::Device, c: &niri_config::PointerDevice) {
+    set_send_events(device, c.off);
+    let _ = device.config_scroll_set_natural_scroll_enabled(c.natural_scroll);
+    let _ = device.config_left_handed_set(c.left_handed);
+    let _ = device.config_middle_emulation_set_enabled(c.middle_emulation);
+    set_accel(device, c.accel_speed, c.accel_profile);
+    set_scroll_method(device, c.scroll_method, c.scroll_button);
+}
+
+/// Returns the factor to multiply scroll amounts from this device by.
+pub fn scroll_factor(config: &niri_config::Input, device: &input::Device) -> f64 {
+    if let Some(c) = config.devices.iter().find(|c| c.name == device.name()) {
+        return c.scroll_factor;
+    }
+
+    match device_section(device) {
+        DeviceSection::Touchpad => config.touchpad.scroll_factor,
+        DeviceSection::Mouse => config.mouse.scroll_factor,
+        DeviceSection::Trackpoint => config.trackpoint.scroll_factor,
+        DeviceSection::Trackball => config.trackball.scroll_factor,
+        DeviceSection::Touch | DeviceSection::Other => 1.,
+    }
+}
+
+fn set_send_events(device: &mut input::Device, off: bool) {
+    let mode = if off {
+        input::SendEventsMode::DISABLED
+    } else {
+        input::SendEventsMode::ENABLED
+    };
+    let _ = device.config_send_events_set_mode(mode);
+}
+
+fn set_accel(device: &mut input::Device, speed: f64, profile: Option<niri_config::AccelProfile>) {
+    let _ = device.config_accel_set_speed(speed);
+
+    if let Some(accel_profile) = profile {
+        let _ = device.config_accel_set_profile(accel_profile.into());
+    } else if let Some(default) = device.config_accel_default_profile() {
+        let _ = device.config_accel_set_profile(default);
+    }
+}
+
+fn set_scroll_method(
+    device: &mut input::Device,
+    method: Option<niri_config::ScrollMethod>,
+    button: Option<u32>,
+) {
+    if let Some(method) = method {
+        let _ = device.config_scroll_set_method(method.into());
+    } else if let Some(default) = device.config_scroll_default_method() {
+        let _ = device.config_scroll_set_method(default);
+    }
+
+    // The button only matters for the on-button-down scroll method.
+    let button = button.unwrap_or_else(|| device.config_scroll_default_button());
+    let _ = device.config_scroll_set_button(button);
+}
+
+fn set_click_method(device: &mut input::Device, method: Option<niri_config::ClickMethod>) {
+    if let Some(method) = method {
+        let _ = device.config_click_set_method(method.into());
+    } else if let Some(default) = device.config_click_default_method() {
+        let _ = device.config_click_set_method(default);
+    }
+}
+
+fn set_tap_button_map(device: &mut input::Device, map: Option<niri_config::TapButtonMap>) {
+    if let Some(tap_button_map) = map {
+        let _ = device.config_tap_set_button_map(tap_button_map.into());
+    } else if let Some(default) = device.config_tap_default_button_map() {
+        let _ = device.config_tap_set_button_map(default);
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use niri_config::{Action, Bind, Binds, Key, Modifiers};
+    use niri_config::{Action, Bind, Key, Modifiers, Trigger};
+    use niri_ipc::SizeChange;
 
     use super::*;
 
     #[test]
     fn bindings_suppress_keys() {
         let close_keysym = Keysym::q;
-        let bindings = Binds(vec![Bind {
+        let bindings = vec![Bind {
             key: Key {
-                keysym: close_keysym,
+                trigger: Trigger::Keysym(close_keysym),
                 modifiers: Modifiers::COMPOSITOR | Modifiers::CTRL,
             },
             actions: vec![Action::CloseWindow],
-        }]);
+        }];
 
         let comp_mod = CompositorMod::Super;
         let mut suppressed_keys = HashSet::new();
@@ -1643,6 +2528,7 @@ mod tests {
             should_intercept_key(
                 suppr,
                 &bindings,
+                false,
                 comp_mod,
                 close_key_code,
                 close_keysym,
@@ -1659,6 +2545,7 @@ mod tests {
             should_intercept_key(
                 suppr,
                 &bindings,
+                false,
                 comp_mod,
                 Keysym::l.into(),
                 Keysym::l,
@@ -1740,51 +2627,315 @@ mod tests {
         assert!(suppressed_keys.is_empty());
     }
 
+    #[test]
+    fn bind_modes_suppress_keys() {
+        let bind = |keysym, modifiers, action| Bind {
+            key: Key {
+                trigger: Trigger::Keysym(keysym),
+                modifiers,
+            },
+            actions: vec![action],
+        };
+        let regular = vec![bind(
+            Keysym::r,
+            Modifiers::COMPOSITOR,
+            Action::EnterMode(String::from("resize")),
+        )];
+        let resize = vec![
+            bind(
+                Keysym::h,
+                Modifiers::empty(),
+                Action::SetColumnWidth(SizeChange::AdjustProportion(-10.)),
+            ),
+            bind(Keysym::Escape, Modifiers::empty(), Action::ExitMode),
+        ];
+        let go = vec![bind(
+            Keysym::_1,
+            Modifiers::empty(),
+            Action::FocusWorkspace(WorkspaceReference::Index(1)),
+        )];
+
+        let screenshot_ui = ScreenshotUi::new();
+        let mut suppressed_keys = HashSet::new();
+        let mut key = |bindings: &[Bind], oneshot, keysym: Keysym, mods, pressed| {
+            should_intercept_key(
+                &mut suppressed_keys,
+                bindings,
+                oneshot,
+                CompositorMod::Super,
+                keysym.raw(),
+                keysym,
+                Some(keysym),
+                pressed,
+                mods,
+                &screenshot_ui,
+                false,
+            )
+        };
+
+        let super_down = ModifiersState {
+            logo: true,
+            ..Default::default()
+        };
+        let shift_down = ModifiersState {
+            shift: true,
+            ..Default::default()
+        };
+        let none = ModifiersState::default();
+
+        // Enter the mode, then release the key with the mode binds active.
+        let filter = key(&regular, false, Keysym::r, super_down, true);
+        assert!(matches!(
+            filter,
+            FilterResult::Intercept(Some(Action::EnterMode(_)))
+        ));
+        let filter = key(&resize, false, Keysym::r, super_down, false);
+        assert!(matches!(filter, FilterResult::Intercept(None)));
+
+        // Mode binds replace the regular ones, and unbound keys reach the client.
+        let filter = key(&resize, false, Keysym::h, none, true);
+        assert!(matches!(
+            filter,
+            FilterResult::Intercept(Some(Action::SetColumnWidth(_)))
+        ));
+        let filter = key(&resize, false, Keysym::h, none, false);
+        assert!(matches!(filter, FilterResult::Intercept(None)));
+        let filter = key(&resize, false, Keysym::l, none, true);
+        assert!(matches!(filter, FilterResult::Forward));
+        let filter = key(&resize, false, Keysym::l, none, false);
+        assert!(matches!(filter, FilterResult::Forward));
+
+        // The release of the key leaving the mode is suppressed with the regular binds active.
+        let filter = key(&resize, false, Keysym::Escape, none, true);
+        assert!(matches!(
+            filter,
+            FilterResult::Intercept(Some(Action::ExitMode))
+        ));
+        let filter = key(&regular, false, Keysym::Escape, none, false);
+        assert!(matches!(filter, FilterResult::Intercept(None)));
+
+        // In a one-shot mode, modifiers pass through and other unbound keys leave the mode.
+        let filter = key(&go, true, Keysym::Shift_L, none, true);
+        assert!(matches!(filter, FilterResult::Forward));
+        let filter = key(&go, true, Keysym::_1, shift_down, true);
+        assert!(matches!(
+            filter,
+            FilterResult::Intercept(Some(Action::ExitMode))
+        ));
+        let filter = key(&regular, false, Keysym::_1, shift_down, false);
+        assert!(matches!(filter, FilterResult::Intercept(None)));
+        let filter = key(&regular, false, Keysym::Shift_L, shift_down, false);
+        assert!(matches!(filter, FilterResult::Forward));
+
+        let filter = key(&go, true, Keysym::_1, none, true);
+        assert!(matches!(
+            filter,
+            FilterResult::Intercept(Some(Action::FocusWorkspace(_)))
+        ));
+        let filter = key(&regular, false, Keysym::_1, none, false);
+        assert!(matches!(filter, FilterResult::Intercept(None)));
+
+        assert!(suppressed_keys.is_empty());
+    }
+
+    #[test]
+    fn bindings_suppress_buttons() {
+        let bindings = vec![Bind {
+            key: Key {
+                trigger: Trigger::MouseMiddle,
+                modifiers: Modifiers::COMPOSITOR,
+            },
+            actions: vec![Action::CloseWindow],
+        }];
+
+        let comp_mod = CompositorMod::Super;
+        let mut suppressed_buttons = HashSet::new();
+        let screenshot_ui = ScreenshotUi::new();
+
+        let middle_code = 0x112;
+        let left_code = 0x110;
+        let button_event =
+            |suppr: &mut HashSet<u32>, code, button, mods: ModifiersState, pressed| {
+                should_intercept_button(
+                    suppr,
+                    &bindings,
+                    comp_mod,
+                    code,
+                    Some(button),
+                    pressed,
+                    mods,
+                    &screenshot_ui,
+                )
+            };
+
+        let mods = ModifiersState {
+            logo: true,
+            ..Default::default()
+        };
+
+        // Action press/release.
+        let filter = button_event(
+            &mut suppressed_buttons,
+            middle_code,
+            MouseButton::Middle,
+            mods,
+            true,
+        );
+        assert!(matches!(
+            filter,
+            FilterResult::Intercept(Some(Action::CloseWindow))
+        ));
+        assert!(suppressed_buttons.contains(&middle_code));
+
+        // The release is suppressed even after letting go of the modifier.
+        let filter = button_event(
+            &mut suppressed_buttons,
+            middle_code,
+            MouseButton::Middle,
+            ModifiersState::default(),
+            false,
+        );
+        assert!(matches!(filter, FilterResult::Intercept(None)));
+        assert!(suppressed_buttons.is_empty());
+
+        // Unbound button with the modifier.
+        let filter = button_event(
+            &mut suppressed_buttons,
+            left_code,
+            MouseButton::Left,
+            mods,
+            true,
+        );
+        assert!(matches!(filter, FilterResult::Forward));
+        let filter = button_event(
+            &mut suppressed_buttons,
+            left_code,
+            MouseButton::Left,
+            mods,
+            false,
+        );
+        assert!(matches!(filter, FilterResult::Forward));
+
+        // Bound button without the modifier.
+        let filter = button_event(
+            &mut suppressed_buttons,
+            middle_code,
+            MouseButton::Middle,
+            ModifiersState::default(),
+            true,
+        );
+        assert!(matches!(filter, FilterResult::Forward));
+        let filter = button_event(
+            &mut suppressed_buttons,
+            middle_code,
+            MouseButton::Middle,
+            mods,
+            false,
+        );
+        assert!(matches!(filter, FilterResult::Forward));
+
+        assert!(suppressed_buttons.is_empty());
+    }
+
+    #[test]
+    fn wheel_binds_accumulate_ticks() {
+        let bindings = vec![Bind {
+            key: Key {
+                trigger: Trigger::WheelScrollDown,
+                modifiers: Modifiers::COMPOSITOR,
+            },
+            actions: vec![Action::FocusWorkspaceDown],
+        }];
+
+        let comp_mod = CompositorMod::Super;
+        let mut tracker = ScrollTracker::new(120.);
+        let screenshot_ui = ScreenshotUi::new();
+
+        let mods = ModifiersState {
+            logo: true,
+            ..Default::default()
+        };
+        let mut scroll = |amount_v120, mods| {
+            wheel_actions(
+                &mut tracker,
+                &bindings,
+                comp_mod,
+                amount_v120,
+                Trigger::WheelScrollDown,
+                Trigger::WheelScrollUp,
+                mods,
+                &screenshot_ui,
+            )
+        };
+
+        // Regular wheel.
+        assert_eq!(scroll(120., mods), Some(vec![Action::FocusWorkspaceDown]));
+        assert_eq!(
+            scroll(240., mods),
+            Some(vec![Action::FocusWorkspaceDown, Action::FocusWorkspaceDown])
+        );
+
+        // High-resolution wheel: partial ticks are suppressed but don't trigger.
+        assert_eq!(scroll(60., mods), Some(vec![]));
+        assert_eq!(scroll(30., mods), Some(vec![]));
+        assert_eq!(scroll(30., mods), Some(vec![Action::FocusWorkspaceDown]));
+
+        // The unbound direction and scrolling without the modifier go to the client.
+        assert_eq!(scroll(-120., mods), None);
+        assert_eq!(scroll(120., ModifiersState::default()), None);
+
+        // Partial progress is discarded when the bind stops matching.
+        assert_eq!(scroll(90., mods), Some(vec![]));
+        assert_eq!(scroll(90., ModifiersState::default()), None);
+        assert_eq!(scroll(90., mods), Some(vec![]));
+        assert_eq!(scroll(0., mods), None);
+    }
+
     #[test]
     fn comp_mod_handling() {
-        let bindings = Binds(vec![
+        let bindings = vec![
             Bind {
                 key: Key {
-                    keysym: Keysym::q,
+                    trigger: Trigger::Keysym(Keysym::q),
                     modifiers: Modifiers::COMPOSITOR,
                 },
                 actions: vec![Action::CloseWindow],
             },
             Bind {
                 key: Key {
-                    keysym: Keysym::h,
+                    trigger: Trigger::Keysym(Keysym::h),
                     modifiers: Modifiers::SUPER,
                 },
                 actions: vec![Action::FocusColumnLeft],
             },
             Bind {
                 key: Key {
-                    keysym: Keysym::j,
+                    trigger: Trigger::Keysym(Keysym::j),
                     modifiers: Modifiers::empty(),
                 },
                 actions: vec![Action::FocusWindowDown],
             },
             Bind {
                 key: Key {
-                    keysym: Keysym::k,
+                    trigger: Trigger::Keysym(Keysym::k),
                     modifiers: Modifiers::COMPOSITOR | Modifiers::SUPER,
                 },
                 actions: vec![Action::FocusWindowUp],
             },
             Bind {
                 key: Key {
-                    keysym: Keysym::l,
+                    trigger: Trigger::Keysym(Keysym::l),
                     modifiers: Modifiers::SUPER | Modifiers::ALT,
                 },
                 actions: vec![Action::FocusColumnRight],
             },
-        ]);
+        ];
 
         assert_eq!(
             bound_action(
                 &bindings,
                 CompositorMod::Super,
-                Some(Keysym::q),
+                Trigger::Keysym(Keysym::q),
                 ModifiersState {
                     logo: true,
                     ..Default::default()
@@ -1796,7 +2947,7 @@ mod tests {
             bound_action(
                 &bindings,
                 CompositorMod::Super,
-                Some(Keysym::q),
+                Trigger::Keysym(Keysym::q),
                 ModifiersState::default(),
             ),
             None,
@@ -1806,7 +2957,7 @@ mod tests {
             bound_action(
                 &bindings,
                 CompositorMod::Super,
-                Some(Keysym::h),
+                Trigger::Keysym(Keysym::h),
                 ModifiersState {
                     logo: true,
                     ..Default::default()
@@ -1818,7 +2969,7 @@ mod tests {
             bound_action(
                 &bindings,
                 CompositorMod::Super,
-                Some(Keysym::h),
+                Trigger::Keysym(Keysym::h),
                 ModifiersState::default(),
             ),
             None,
@@ -1828,7 +2979,7 @@ mod tests {
             bound_action(
                 &bindings,
                 CompositorMod::Super,
-                Some(Keysym::j),
+                Trigger::Keysym(Keysym::j),
                 ModifiersState {
                     logo: true,
                     ..Default::default()
@@ -1840,7 +2991,7 @@ mod tests {
             bound_action(
                 &bindings,
                 CompositorMod::Super,
-                Some(Keysym::j),
+                Trigger::Keysym(Keysym::j),
                 ModifiersState::default(),
             ),
             Some(Action::FocusWindowDown)
@@ -1850,7 +3001,7 @@ mod tests {
             bound_action(
                 &bindings,
                 CompositorMod::Super,
-                Some(Keysym::k),
+                Trigger::Keysym(Keysym::k),
                 ModifiersState {
                     logo: true,
                     ..Default::default()
@@ -1862,7 +3013,7 @@ mod tests {
             bound_action(
                 &bindings,
                 CompositorMod::Super,
-                Some(Keysym::k),
+                Trigger::Keysym(Keysym::k),
                 ModifiersState::default(),
             ),
             None,
@@ -1872,7 +3023,7 @@ mod tests {
             bound_action(
                 &bindings,
                 CompositorMod::Super,
-                Some(Keysym::l),
+                Trigger::Keysym(Keysym::l),
                 ModifiersState {
                     logo: true,
                     alt: true,
@@ -1885,7 +3036,7 @@ mod tests {
             bound_action(
                 &bindings,
                 CompositorMod::Super,
-                Some(Keysym::l),
+                Trigger::Keysym(Keysym::l),
                 ModifiersState {
                     logo: true,
                     ..Default::default()
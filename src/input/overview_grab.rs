@@ -0,0 +1,242 @@
+use smithay::desktop::Window;
+use smithay::input::pointer::{
+    AxisFrame, ButtonEvent, CursorIcon, CursorImageStatus, GestureHoldBeginEvent,
+    GestureHoldEndEvent, GesturePinchBeginEvent, GesturePinchEndEvent, GesturePinchUpdateEvent,
+    GestureSwipeBeginEvent, GestureSwipeEndEvent, GestureSwipeUpdateEvent,
+    GrabStartData as PointerGrabStartData, MotionEvent, PointerGrab, PointerInnerHandle,
+    RelativeMotionEvent,
+};
+use smithay::input::SeatHandler;
+use smithay::output::Output;
+use smithay::utils::{IsAlive, Logical, Point, Serial};
+
+use crate::niri::State;
+
+/// Distance in logical pixels the pointer needs to travel to start dragging the window.
+const DRAG_THRESHOLD: f64 = 8.;
+
+/// Pointer grab for clicking or dragging a window in the overview.
+///
+/// A click focuses the window and closes the overview, while a drag moves the window, possibly
+/// to another workspace.
+pub struct OverviewGrab {
+    start_data: PointerGrabStartData<State>,
+    window: Window,
+    /// Output of the overview where the grab started.
+    output: Output,
+    /// Pointer position within the output at the start of the grab.
+    start_pos_within_output: Point<f64, Logical>,
+    /// Whether the pointer moved far enough to start an interactive move.
+    is_moving: bool,
+}
+
+impl OverviewGrab {
+    pub fn new(
+        start_data: PointerGrabStartData<State>,
+        window: Window,
+        output: Output,
+        start_pos_within_output: Point<f64, Logical>,
+    ) -> Self {
+        Self {
+            start_data,
+            window,
+            output,
+            start_pos_within_output,
+            is_moving: false,
+        }
+    }
+
+    fn ungrab(
+        &mut self,
+        data: &mut State,
+        handle: &mut PointerInnerHandle<'_, State>,
+        serial: Serial,
+        time: u32,
+    ) {
+        handle.unset_grab(data, serial, time, true);
+
+        if self.is_moving {
+            data.niri.layout.interactive_move_end(&self.window);
+            data.niri
+                .cursor_manager
+                .set_cursor_image(CursorImageStatus::default_named());
+        } else if self.window.alive() {
+            // This was a click, so focus the window and zoom back in.
+            data.niri.layout.activate_window(&self.window);
+            data.niri.layout.close_overview(&self.output);
+        }
+
+        // FIXME: granular.
+        data.niri.queue_redraw_all();
+    }
+}
+
+impl PointerGrab<State> for OverviewGrab {
+    fn motion(
+        &mut self,
+        data: &mut State,
+        handle: &mut PointerInnerHandle<'_, State>,
+        _focus: Option<(<State as SeatHandler>::PointerFocus, Point<i32, Logical>)>,
+        event: &MotionEvent,
+    ) {
+        // While the grab is active, no client has pointer focus.
+        handle.motion(data, None, event);
+
+        if !self.window.alive() {
+            self.ungrab(data, handle, event.serial, event.time);
+            return;
+        }
+
+        let Some((output, pos_within_output)) = data.niri.output_under(event.location) else {
+            // The pointer is outside of any output; keep the window where it was.
+            return;
+        };
+        let output = output.clone();
+
+        if !self.is_moving {
+            let delta = event.location - self.start_data.location;
+            if delta.x.hypot(delta.y) < DRAG_THRESHOLD {
+                return;
+            }
+
+            if !data.niri.layout.interactive_move_begin(
+                &self.window,
+                &self.output,
+                self.start_pos_within_output,
+            ) {
+                return;
+            }
+
+            self.is_moving = true;
+            data.niri
+                .cursor_manager
+                .set_cursor_image(CursorImageStatus::Named(CursorIcon::Grabbing));
+        }
+
+        let ongoing =
+            data.niri
+                .layout
+                .interactive_move_update(&self.window, &output, pos_within_output);
+        if ongoing {
+            // FIXME: granular.
+            data.niri.queue_redraw_all();
+            return;
+        }
+
+        // The move is no longer ongoing.
+        self.ungrab(data, handle, event.serial, event.time);
+    }
+
+    fn relative_motion(
+        &mut self,
+        data: &mut State,
+        handle: &mut PointerInnerHandle<'_, State>,
+        _focus: Option<(<State as SeatHandler>::PointerFocus, Point<i32, Logical>)>,
+        event: &RelativeMotionEvent,
+    ) {
+        handle.relative_motion(data, None, event);
+    }
+
+    fn button(
+        &mut self,
+        data: &mut State,
+        handle: &mut PointerInnerHandle<'_, State>,
+        event: &ButtonEvent,
+    ) {
+        handle.button(data, event);
+
+        if handle.current_pressed().is_empty() {
+            // No more buttons are pressed, drop or focus the window.
+            self.ungrab(data, handle, event.serial, event.time);
+        }
+    }
+
+    fn axis(
+        &mut self,
+        data: &mut State,
+        handle: &mut PointerInnerHandle<'_, State>,
+        details: AxisFrame,
+    ) {
+        handle.axis(data, details);
+    }
+
+    fn frame(&mut self, data: &mut State, handle: &mut PointerInnerHandle<'_, State>) {
+        handle.frame(data);
+    }
+
+    fn gesture_swipe_begin(
+        &mut self,
+        data: &mut State,
+        handle: &mut PointerInnerHandle<'_, State>,
+        event: &GestureSwipeBeginEvent,
+    ) {
+        handle.gesture_swipe_begin(data, event);
+    }
+
+    fn gesture_swipe_update(
+        &mut self,
+        data: &mut State,
+        handle: &mut PointerInnerHandle<'_, State>,
+        event: &GestureSwipeUpdateEvent,
+    ) {
+        handle.gesture_swipe_update(data, event);
+    }
+
+    fn gesture_swipe_end(
+        &mut self,
+        data: &mut State,
+        handle: &mut PointerInnerHandle<'_, State>,
+        event: &GestureSwipeEndEvent,
+    ) {
+        handle.gesture_swipe_end(data, event);
+    }
+
+    fn gesture_pinch_begin(
+        &mut self,
+        data: &mut State,
+        handle: &mut PointerInnerHandle<'_, State>,
+        event: &GesturePinchBeginEvent,
+    ) {
+        handle.gesture_pinch_begin(data, event);
+    }
+
+    fn gesture_pinch_update(
+        &mut self,
+        data: &mut State,
+        handle: &mut PointerInnerHandle<'_, State>,
+        event: &GesturePinchUpdateEvent,
+    ) {
+        handle.gesture_pinch_update(data, event);
+    }
+
+    fn gesture_pinch_end(
+        &mut self,
+        data: &mut State,
+        handle: &mut PointerInnerHandle<'_, State>,
+        event: &GesturePinchEndEvent,
+    ) {
+        handle.gesture_pinch_end(data, event);
+    }
+
+    fn gesture_hold_begin(
+        &mut self,
+        data: &mut State,
+        handle: &mut PointerInnerHandle<'_, State>,
+        event: &GestureHoldBeginEvent,
+    ) {
+        handle.gesture_hold_begin(data, event);
+    }
+
+    fn gesture_hold_end(
+        &mut self,
+        data: &mut State,
+        handle: &mut PointerInnerHandle<'_, State>,
+        event: &GestureHoldEndEvent,
+    ) {
+        handle.gesture_hold_end(data, event);
+    }
+
+    fn start_data(&self) -> &PointerGrabStartData<State> {
+        &self.start_data
+    }
+}
@@ -0,0 +1,72 @@
+/// Accumulates scroll amounts into discrete ticks.
+///
+/// High-resolution wheels send fractions of a tick at a time, so this sums them up until a full
+/// tick is reached. Scrolling in the opposite direction discards the partial progress.
+#[derive(Debug)]
+pub struct ScrollTracker {
+    tick: f64,
+    last: f64,
+    acc: f64,
+}
+
+impl ScrollTracker {
+    /// Creates a tracker that emits one tick per `tick` units of scrolling.
+    pub fn new(tick: f64) -> Self {
+        Self {
+            tick,
+            last: 0.,
+            acc: 0.,
+        }
+    }
+
+    /// Adds a scroll amount and returns the number of whole ticks it completed.
+    ///
+    /// The result is positive for scrolling in the positive direction and negative otherwise.
+    pub fn accumulate(&mut self, amount: f64) -> i32 {
+        let changed_direction = (self.last > 0. && amount < 0.) || (self.last < 0. && amount > 0.);
+        if changed_direction {
+            self.acc = 0.;
+        }
+
+        self.last = amount;
+        self.acc += amount;
+
+        let ticks = (self.acc / self.tick).trunc();
+        self.acc -= ticks * self.tick;
+        ticks as i32
+    }
+
+    pub fn reset(&mut self) {
+        self.last = 0.;
+        self.acc = 0.;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn accumulates_partial_ticks() {
+        let mut tracker = ScrollTracker::new(120.);
+        assert_eq!(tracker.accumulate(60.), 0);
+        assert_eq!(tracker.accumulate(30.), 0);
+        assert_eq!(tracker.accumulate(30.), 1);
+        assert_eq!(tracker.accumulate(300.), 2);
+        assert_eq!(tracker.accumulate(60.), 1);
+        assert_eq!(tracker.accumulate(-120.), -1);
+    }
+
+    #[test]
+    fn direction_change_discards_progress() {
+        let mut tracker = ScrollTracker::new(120.);
+        assert_eq!(tracker.accumulate(100.), 0);
+        assert_eq!(tracker.accumulate(-60.), 0);
+        assert_eq!(tracker.accumulate(-60.), -1);
+        assert_eq!(tracker.accumulate(100.), 0);
+
+        tracker.reset();
+        assert_eq!(tracker.accumulate(100.), 0);
+        assert_eq!(tracker.accumulate(20.), 1);
+    }
+}
@@ -1,55 +1,150 @@
-use std::env;
-use std::io::{Read, Write};
-use std::net::Shutdown;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, Write};
 use std::os::unix::net::UnixStream;
+use std::path::Path;
+use std::{env, fmt};
 
 use anyhow::{bail, Context};
-use niri_ipc::{Mode, Output, Request, Response};
+use clap::Parser;
+use niri_ipc::{Event, KeyboardLayouts, Mode, Output, Reply, Request, Response, Window, Workspace};
 
-use crate::cli::Msg;
+use crate::cli::{BatchLine, Msg};
+use crate::utils::version;
+
+/// Exit code when niri replied with an error.
+pub const EXIT_CODE_REPLY_ERROR: i32 = 1;
+/// Exit code when communicating with niri failed.
+pub const EXIT_CODE_IPC_ERROR: i32 = 2;
+
+/// Error reported by niri in its reply.
+#[derive(Debug)]
+pub struct ReplyError(pub String);
+
+impl fmt::Display for ReplyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl std::error::Error for ReplyError {}
+
+/// Returns the exit code for an error returned from [`handle_msg`].
+pub fn exit_code(err: &anyhow::Error) -> i32 {
+    if err.is::<ReplyError>() {
+        EXIT_CODE_REPLY_ERROR
+    } else {
+        EXIT_CODE_IPC_ERROR
+    }
+}
 
 pub fn handle_msg(msg: Msg, json: bool) -> anyhow::Result<()> {
-    let socket_path = env::var_os(niri_ipc::SOCKET_PATH_ENV).with_context(|| {
-        format!(
-            "{} is not set, are you running this within niri?",
-            niri_ipc::SOCKET_PATH_ENV
-        )
-    })?;
+    if let Msg::Batch { file } = &msg {
+        return handle_batch(file.as_deref(), json);
+    }
+
+    let mut socket = Socket::connect()?;
+    let reply = socket.send(&make_request(&msg))?;
+    let response = reply.map_err(ReplyError)?;
+    handle_response(&msg, response, json)?;
+
+    if matches!(msg, Msg::EventStream) {
+        print_events(socket.stream, json)?;
+    }
+
+    Ok(())
+}
+
+fn handle_batch(path: Option<&Path>, json: bool) -> anyhow::Result<()> {
+    let input: Box<dyn BufRead> = match path {
+        Some(path) if path != Path::new("-") => {
+            let file = File::open(path).with_context(|| format!("error opening {path:?}"))?;
+            Box::new(BufReader::new(file))
+        }
+        _ => Box::new(io::stdin().lock()),
+    };
 
-    let mut stream =
-        UnixStream::connect(socket_path).context("error connecting to {socket_path}")?;
+    // All requests go over a single connection, in order.
+    let mut socket = Socket::connect()?;
+    for (idx, line) in input.lines().enumerate() {
+        let line = line.context("error reading batch input")?;
+        handle_batch_line(&mut socket, &line, json)
+            .with_context(|| format!("error on line {}", idx + 1))?;
+    }
+
+    Ok(())
+}
+
+fn handle_batch_line(socket: &mut Socket, line: &str, json: bool) -> anyhow::Result<()> {
+    let line = line.trim();
+    if line.is_empty() || line.starts_with('#') {
+        return Ok(());
+    }
+
+    let args = shlex::split(line).context("error splitting line into arguments")?;
+    let msg = BatchLine::try_parse_from(args)
+        .context("error parsing line")?
+        .msg;
+
+    match msg {
+        Msg::EventStream => bail!("event-stream cannot be used in a batch"),
+        Msg::Batch { .. } => bail!("batch cannot be used in a batch"),
+        _ => (),
+    }
 
-    let request = match &msg {
+    let reply = socket.send(&make_request(&msg))?;
+    let response = reply.map_err(ReplyError)?;
+    handle_response(&msg, response, json)
+}
+
+fn make_request(msg: &Msg) -> Request {
+    match msg {
+        Msg::Version => Request::Version,
         Msg::Outputs => Request::Outputs,
+        Msg::Workspaces => Request::Workspaces,
+        Msg::Windows => Request::Windows,
+        Msg::FocusedWindow => Request::FocusedWindow,
+        Msg::KeyboardLayouts => Request::KeyboardLayouts,
+        Msg::BindMode => Request::BindMode,
+        Msg::EventStream => Request::EventStream,
         Msg::Action { action } => Request::Action(action.clone()),
-    };
-    let mut buf = serde_json::to_vec(&request).unwrap();
-    stream
-        .write_all(&buf)
-        .context("error writing IPC request")?;
-    stream
-        .shutdown(Shutdown::Write)
-        .context("error closing IPC stream for writing")?;
-
-    buf.clear();
-    stream
-        .read_to_end(&mut buf)
-        .context("error reading IPC response")?;
-
-    if matches!(msg, Msg::Action { .. }) {
-        if buf.is_empty() {
-            return Ok(());
-        } else {
-            bail!("unexpected response: expected no response, got {buf:?}");
-        }
+        Msg::Output { output, action } => Request::Output {
+            output: output.clone(),
+            action: action.clone(),
+        },
+        Msg::Batch { .. } => unreachable!(),
     }
+}
 
-    let response = serde_json::from_slice(&buf).context("error parsing IPC response")?;
+/// Prints the response to a message.
+fn handle_response(msg: &Msg, response: Response, json: bool) -> anyhow::Result<()> {
     match msg {
+        Msg::Version => {
+            let Response::Version(compositor) = response else {
+                bail!("unexpected response: expected Version, got {response:?}");
+            };
+
+            if json {
+                let compositor =
+                    serde_json::to_string(&compositor).context("error formatting response")?;
+                println!("{compositor}");
+                return Ok(());
+            }
+
+            println!("Compositor: {}", compositor.compositor);
+            println!("Client: {}", version());
+
+            if compositor.protocol != niri_ipc::PROTOCOL_VERSION {
+                println!();
+                println!(
+                    "Warning: the compositor uses IPC protocol version {}, but the client uses \
+                     version {}. Restart niri after updating.",
+                    compositor.protocol,
+                    niri_ipc::PROTOCOL_VERSION,
+                );
+            }
+        }
         Msg::Outputs => {
-            #[allow(irrefutable_let_patterns)]
-            let Response::Outputs(outputs) = response
-            else {
+            let Response::Outputs(outputs) = response else {
                 bail!("unexpected response: expected Outputs, got {response:?}");
             };
 
@@ -109,8 +204,325 @@ pub fn handle_msg(msg: Msg, json: bool) -> anyhow::Result<()> {
                 println!();
             }
         }
-        Msg::Action { .. } => unreachable!(),
+        Msg::Workspaces => {
+            let Response::Workspaces(mut workspaces) = response else {
+                bail!("unexpected response: expected Workspaces, got {response:?}");
+            };
+
+            if json {
+                let workspaces =
+                    serde_json::to_string(&workspaces).context("error formatting response")?;
+                println!("{workspaces}");
+                return Ok(());
+            }
+
+            workspaces.sort_by(|a, b| a.output.cmp(&b.output).then(a.idx.cmp(&b.idx)));
+
+            let mut current_output = None;
+            for workspace in workspaces {
+                let Workspace {
+                    idx,
+                    name,
+                    output,
+                    is_active,
+                    is_focused,
+                } = workspace;
+
+                if current_output.as_ref() != Some(&output) {
+                    if current_output.is_some() {
+                        println!();
+                    }
+
+                    if let Some(output) = &output {
+                        println!(r#"Output "{output}":"#);
+                    } else {
+                        println!("No output:");
+                    }
+                    current_output = Some(output);
+                }
+
+                let marker = if is_focused {
+                    '*'
+                } else if is_active {
+                    '+'
+                } else {
+                    ' '
+                };
+
+                if let Some(name) = name {
+                    println!(r#" {marker} {idx} "{name}""#);
+                } else {
+                    println!(" {marker} {idx}");
+                }
+            }
+        }
+        Msg::Windows => {
+            let Response::Windows(windows) = response else {
+                bail!("unexpected response: expected Windows, got {response:?}");
+            };
+
+            if json {
+                let windows =
+                    serde_json::to_string(&windows).context("error formatting response")?;
+                println!("{windows}");
+                return Ok(());
+            }
+
+            for window in windows {
+                print_window(window);
+                println!();
+            }
+        }
+        Msg::FocusedWindow => {
+            let Response::FocusedWindow(window) = response else {
+                bail!("unexpected response: expected FocusedWindow, got {response:?}");
+            };
+
+            if json {
+                let window = serde_json::to_string(&window).context("error formatting response")?;
+                println!("{window}");
+                return Ok(());
+            }
+
+            if let Some(window) = window {
+                print_window(window);
+            } else {
+                println!("No window is focused.");
+            }
+        }
+        Msg::KeyboardLayouts => {
+            let Response::KeyboardLayouts(layouts) = response else {
+                bail!("unexpected response: expected KeyboardLayouts, got {response:?}");
+            };
+
+            if json {
+                let layouts =
+                    serde_json::to_string(&layouts).context("error formatting response")?;
+                println!("{layouts}");
+                return Ok(());
+            }
+
+            let KeyboardLayouts { names, current_idx } = layouts;
+            println!("Keyboard layouts:");
+            for (idx, name) in names.iter().enumerate() {
+                let marker = if idx == usize::from(current_idx) {
+                    '*'
+                } else {
+                    ' '
+                };
+                println!(" {marker} {idx} {name}");
+            }
+        }
+        Msg::BindMode => {
+            let Response::BindMode(name) = response else {
+                bail!("unexpected response: expected BindMode, got {response:?}");
+            };
+
+            if json {
+                let name = serde_json::to_string(&name).context("error formatting response")?;
+                println!("{name}");
+                return Ok(());
+            }
+
+            if let Some(name) = name {
+                println!(r#"Bind mode: "{name}""#);
+            } else {
+                println!("No bind mode is active.");
+            }
+        }
+        Msg::EventStream | Msg::Action { .. } | Msg::Output { .. } => {
+            let Response::Handled = response else {
+                bail!("unexpected response: expected Handled, got {response:?}");
+            };
+        }
+        Msg::Batch { .. } => unreachable!(),
     }
 
     Ok(())
 }
+
+/// Connection to the running niri instance.
+struct Socket {
+    stream: BufReader<UnixStream>,
+}
+
+impl Socket {
+    fn connect() -> anyhow::Result<Self> {
+        let socket_path = env::var_os(niri_ipc::SOCKET_PATH_ENV).with_context(|| {
+            format!(
+                "{} is not set, are you running this within niri?",
+                niri_ipc::SOCKET_PATH_ENV
+            )
+        })?;
+
+        let stream = UnixStream::connect(&socket_path)
+            .with_context(|| format!("error connecting to {socket_path:?}"))?;
+
+        Ok(Self {
+            stream: BufReader::new(stream),
+        })
+    }
+
+    /// Sends a request to niri and reads its reply.
+    ///
+    /// Any further data, such as events, can be read from `self.stream` afterwards.
+    fn send(&mut self, request: &Request) -> anyhow::Result<Reply> {
+        let mut buf = serde_json::to_vec(request).unwrap();
+        buf.push(b'\n');
+        self.stream
+            .get_mut()
+            .write_all(&buf)
+            .context("error writing IPC request")?;
+
+        let mut line = String::new();
+        self.stream
+            .read_line(&mut line)
+            .context("error reading IPC reply")?;
+
+        serde_json::from_str(&line).map_err(|err| {
+            let err = anyhow::Error::new(err).context("error parsing IPC reply");
+            if matches!(request, Request::Version) {
+                err.context(
+                    "the running niri is likely older than niri msg and does not support \
+                     version negotiation; restart niri after updating",
+                )
+            } else {
+                err.context(version_mismatch_hint())
+            }
+        })
+    }
+}
+
+/// Figures out why niri sent an unexpected reply.
+fn version_mismatch_hint() -> String {
+    let reply = Socket::connect().and_then(|mut socket| socket.send(&Request::Version));
+    match reply {
+        Ok(Ok(Response::Version(compositor))) => {
+            if compositor.protocol == niri_ipc::PROTOCOL_VERSION {
+                format!("niri {} sent an invalid reply", compositor.compositor)
+            } else {
+                format!(
+                    "niri {} uses IPC protocol version {}, but niri msg {} uses version {}; \
+                     restart niri after updating",
+                    compositor.compositor,
+                    compositor.protocol,
+                    version(),
+                    niri_ipc::PROTOCOL_VERSION,
+                )
+            }
+        }
+        _ => "the running niri is likely older than niri msg and does not support version \
+              negotiation; restart niri after updating"
+            .to_owned(),
+    }
+}
+
+fn print_events(stream: BufReader<UnixStream>, json: bool) -> anyhow::Result<()> {
+    for line in stream.lines() {
+        let line = line.context("error reading IPC event")?;
+
+        if json {
+            println!("{line}");
+            continue;
+        }
+
+        let event: Event = serde_json::from_str(&line).context("error parsing IPC event")?;
+        match event {
+            Event::WorkspaceActivated { workspace } => {
+                let Workspace {
+                    idx,
+                    name,
+                    output,
+                    is_focused,
+                    ..
+                } = workspace;
+                let name = name.map(|name| format!(r#" "{name}""#)).unwrap_or_default();
+                let output = output.unwrap_or_default();
+                let focused = if is_focused { ", focused" } else { "" };
+                println!(r#"Workspace activated: {idx}{name} on "{output}"{focused}"#);
+            }
+            Event::WindowOpened { window } => {
+                println!("Window opened: {}", describe_window(&window));
+            }
+            Event::WindowClosed { window } => {
+                println!("Window closed: {}", describe_window(&window));
+            }
+            Event::WindowFocusChanged { window } => match window {
+                Some(window) => println!("Window focused: {}", describe_window(&window)),
+                None => println!("Window focused: none"),
+            },
+            Event::WindowTitleChanged { window } => {
+                println!("Window title changed: {}", describe_window(&window));
+            }
+            Event::KeyboardLayoutSwitched { idx, name } => {
+                println!("Keyboard layout switched: {idx} {name}");
+            }
+            Event::BindModeChanged { name } => match name {
+                Some(name) => println!(r#"Bind mode entered: "{name}""#),
+                None => println!("Bind mode left"),
+            },
+            Event::OutputConnected { connector, .. } => {
+                println!(r#"Output connected: "{connector}""#);
+            }
+            Event::OutputDisconnected { connector } => {
+                println!(r#"Output disconnected: "{connector}""#);
+            }
+            Event::ConfigReloaded { failed } => {
+                if failed {
+                    println!("Config reload failed");
+                } else {
+                    println!("Config reloaded");
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn describe_window(window: &Window) -> String {
+    let title = window.title.as_deref().unwrap_or_default();
+    let app_id = window.app_id.as_deref().unwrap_or_default();
+    format!(r#"{} "{title}" ({app_id})"#, window.id)
+}
+
+fn print_window(window: Window) {
+    let Window {
+        id,
+        title,
+        app_id,
+        output,
+        workspace_idx,
+        column_idx,
+        tile_idx,
+        size,
+        is_fullscreen,
+        keyboard_layout_idx,
+    } = window;
+
+    let title = title.unwrap_or_default();
+    let app_id = app_id.unwrap_or_default();
+    println!(r#"Window {id}: "{title}" ({app_id})"#);
+
+    if let Some(output) = output {
+        println!(r#"  Output: "{output}", workspace {workspace_idx}"#);
+    } else {
+        println!("  Output: none, workspace {workspace_idx}");
+    }
+
+    match (column_idx, tile_idx) {
+        (Some(column), Some(tile)) => println!("  Column {column}, tile {tile}"),
+        _ => println!("  Floating"),
+    }
+
+    let (width, height) = size;
+    println!("  Size: {width}x{height}");
+
+    if is_fullscreen {
+        println!("  Fullscreen");
+    }
+
+    if let Some(idx) = keyboard_layout_idx {
+        println!("  Keyboard layout: {idx}");
+    }
+}
@@ -5,25 +5,53 @@ use std::path::PathBuf;
 use std::rc::Rc;
 use std::{env, io, process};
 
-use anyhow::Context;
+use anyhow::{bail, Context};
+use async_channel::{Sender, TrySendError};
 use calloop::io::Async;
 use directories::BaseDirs;
 use futures_util::io::{AsyncReadExt, BufReader};
 use futures_util::{AsyncBufReadExt, AsyncWriteExt};
-use niri_ipc::{Request, Response};
+use niri_config::TrackLayout;
+use niri_ipc::{Event, Reply, Request, Response};
+use smithay::desktop::Window;
 use smithay::reexports::calloop::generic::Generic;
 use smithay::reexports::calloop::{Interest, LoopHandle, Mode, PostAction};
 use smithay::reexports::rustix::fs::unlink;
+use smithay::reexports::wayland_server::protocol::wl_surface::WlSurface;
+use smithay::wayland::compositor::with_states;
+use smithay::wayland::shell::xdg::XdgToplevelSurfaceData;
 
-use crate::niri::State;
+use crate::layout::{LayoutElement, WindowLocation};
+use crate::niri::{stored_keyboard_layout, State};
+use crate::utils::version;
+use crate::window::WindowId;
+
+/// Number of events an event stream client can fall behind before it is disconnected.
+const EVENT_STREAM_BUFFER_SIZE: usize = 64;
 
 pub struct IpcServer {
     pub socket_path: PathBuf,
+    /// Senders to the clients listening to the event stream.
+    event_streams: Rc<RefCell<Vec<Sender<Event>>>>,
+    /// State last seen by the event stream clients, used to compute the events.
+    event_stream_state: Option<EventStreamState>,
 }
 
 struct ClientCtx {
     event_loop: LoopHandle<'static, State>,
     ipc_outputs: Rc<RefCell<HashMap<String, niri_ipc::Output>>>,
+    /// Event stream senders of the server, if it's running.
+    event_streams: Option<Rc<RefCell<Vec<Sender<Event>>>>>,
+}
+
+/// Snapshot of the state reported over the event stream.
+struct EventStreamState {
+    workspaces: Vec<niri_ipc::Workspace>,
+    windows: HashMap<WlSurface, niri_ipc::Window>,
+    focused_window: Option<WlSurface>,
+    keyboard_layout: (u8, String),
+    bind_mode: Option<String>,
+    outputs: HashMap<String, niri_ipc::Output>,
 }
 
 impl IpcServer {
@@ -55,7 +83,25 @@ impl IpcServer {
             })
             .unwrap();
 
-        Ok(Self { socket_path })
+        Ok(Self {
+            socket_path,
+            event_streams: Rc::new(RefCell::new(Vec::new())),
+            event_stream_state: None,
+        })
+    }
+
+    /// Sends an event to all event stream clients.
+    pub fn send_event(&self, event: Event) {
+        self.event_streams
+            .borrow_mut()
+            .retain(|tx| match tx.try_send(event.clone()) {
+                Ok(()) => true,
+                Err(TrySendError::Full(_)) => {
+                    warn!("IPC event stream client is not reading events, disconnecting");
+                    false
+                }
+                Err(TrySendError::Closed(_)) => false,
+            });
     }
 }
 
@@ -73,7 +119,10 @@ fn socket_dir() -> PathBuf {
         .unwrap_or_else(env::temp_dir)
 }
 
-fn on_new_ipc_client(state: &mut State, stream: UnixStream) {
+/// Serves IPC requests coming over the stream.
+///
+/// Clients normally connect through the server socket, but tests hand their streams directly.
+pub fn on_new_ipc_client(state: &mut State, stream: UnixStream) {
     let _span = tracy_client::span!("on_new_ipc_client");
     trace!("new IPC client connected");
 
@@ -88,6 +137,11 @@ fn on_new_ipc_client(state: &mut State, stream: UnixStream) {
     let ctx = ClientCtx {
         event_loop: state.niri.event_loop.clone(),
         ipc_outputs: state.backend.ipc_outputs(),
+        event_streams: state
+            .niri
+            .ipc_server
+            .as_ref()
+            .map(|server| server.event_streams.clone()),
     };
 
     let future = async move {
@@ -102,35 +156,423 @@ fn on_new_ipc_client(state: &mut State, stream: UnixStream) {
 
 async fn handle_client(ctx: ClientCtx, stream: Async<'_, UnixStream>) -> anyhow::Result<()> {
     let (read, mut write) = stream.split();
+    let mut read = BufReader::new(read);
     let mut buf = String::new();
 
-    // Read a single line to allow extensibility in the future to keep reading.
-    BufReader::new(read)
-        .read_line(&mut buf)
-        .await
-        .context("error reading request")?;
+    // Process newline-delimited requests in order until the client closes the connection.
+    loop {
+        buf.clear();
+        let len = read
+            .read_line(&mut buf)
+            .await
+            .context("error reading request")?;
+        if len == 0 {
+            return Ok(());
+        }
+        if buf.trim().is_empty() {
+            continue;
+        }
+
+        let request = serde_json::from_str::<Request>(&buf)
+            .context("error parsing request")
+            .map_err(|err| format!("{err:#}"));
+        let is_event_stream = matches!(request, Ok(Request::EventStream));
+
+        let reply: Reply = match request {
+            Ok(request) => process(&ctx, request)
+                .await
+                .map_err(|err| format!("{err:#}")),
+            Err(err) => Err(err),
+        };
+
+        let mut reply_buf = serde_json::to_vec(&reply).context("error formatting reply")?;
+        reply_buf.push(b'\n');
+        write
+            .write_all(&reply_buf)
+            .await
+            .context("error writing reply")?;
 
-    let request: Request = serde_json::from_str(&buf).context("error parsing request")?;
+        // The event stream takes over the connection, no more requests are read.
+        if is_event_stream && reply.is_ok() {
+            let (tx, rx) = async_channel::bounded(EVENT_STREAM_BUFFER_SIZE);
+            ctx.event_streams.as_ref().unwrap().borrow_mut().push(tx);
 
+            // The stream ends when the server drops the sender, or when writing fails because
+            // the client went away.
+            while let Ok(event) = rx.recv().await {
+                let mut buf = serde_json::to_vec(&event).context("error formatting event")?;
+                buf.push(b'\n');
+                write.write_all(&buf).await.context("error writing event")?;
+            }
+
+            return Ok(());
+        }
+    }
+}
+
+async fn process(ctx: &ClientCtx, request: Request) -> anyhow::Result<Response> {
     let response = match request {
+        Request::Version => Response::Version(niri_ipc::Version {
+            compositor: version(),
+            protocol: niri_ipc::PROTOCOL_VERSION,
+        }),
         Request::Outputs => {
             let ipc_outputs = ctx.ipc_outputs.borrow().clone();
             Response::Outputs(ipc_outputs)
         }
+        Request::Workspaces => {
+            let (tx, rx) = async_channel::bounded(1);
+            ctx.event_loop.insert_idle(move |state| {
+                let workspaces = state.niri.layout.ipc_workspaces();
+                let _ = tx.send_blocking(workspaces);
+            });
+            let workspaces = rx.recv().await.context("error getting workspace info")?;
+            Response::Workspaces(workspaces)
+        }
+        Request::Windows => {
+            let (tx, rx) = async_channel::bounded(1);
+            ctx.event_loop.insert_idle(move |state| {
+                let keyboard_layout = window_keyboard_layouts(state);
+                let mut windows = Vec::new();
+                state
+                    .niri
+                    .layout
+                    .with_windows_and_locations(|window, location| {
+                        windows.push(make_ipc_window(window, location, keyboard_layout(window)));
+                    });
+                let _ = tx.send_blocking(windows);
+            });
+            let windows = rx.recv().await.context("error getting window info")?;
+            Response::Windows(windows)
+        }
+        Request::FocusedWindow => {
+            let (tx, rx) = async_channel::bounded(1);
+            ctx.event_loop.insert_idle(move |state| {
+                let keyboard_layout = window_keyboard_layouts(state);
+                let layout = &state.niri.layout;
+                let mut focused = None;
+                if let Some(focus) = layout.focus() {
+                    layout.with_windows_and_locations(|window, location| {
+                        if window == focus {
+                            let keyboard_layout_idx = keyboard_layout(window);
+                            focused = Some(make_ipc_window(window, location, keyboard_layout_idx));
+                        }
+                    });
+                }
+                let _ = tx.send_blocking(focused);
+            });
+            let window = rx
+                .recv()
+                .await
+                .context("error getting focused window info")?;
+            Response::FocusedWindow(window)
+        }
+        Request::KeyboardLayouts => {
+            let (tx, rx) = async_channel::bounded(1);
+            ctx.event_loop.insert_idle(move |state| {
+                let keyboard = state.niri.seat.get_keyboard().unwrap();
+                let layouts = keyboard.with_xkb_state(state, |context| {
+                    let xkb = context.xkb().lock().unwrap();
+                    niri_ipc::KeyboardLayouts {
+                        names: xkb
+                            .layouts()
+                            .map(|layout| xkb.layout_name(layout).to_owned())
+                            .collect(),
+                        current_idx: u8::try_from(xkb.active_layout().0).unwrap_or(u8::MAX),
+                    }
+                });
+                let _ = tx.send_blocking(layouts);
+            });
+            let layouts = rx
+                .recv()
+                .await
+                .context("error getting keyboard layout info")?;
+            Response::KeyboardLayouts(layouts)
+        }
+        Request::BindMode => {
+            let (tx, rx) = async_channel::bounded(1);
+            ctx.event_loop.insert_idle(move |state| {
+                let name = state.niri.bind_mode.as_ref().map(|mode| mode.name.clone());
+                let _ = tx.send_blocking(name);
+            });
+            let name = rx.recv().await.context("error getting bind mode")?;
+            Response::BindMode(name)
+        }
+        // The events themselves are sent by handle_client() after the reply.
+        Request::EventStream => {
+            if ctx.event_streams.is_none() {
+                bail!("the event stream requires the IPC server");
+            }
+            Response::Handled
+        }
+        Request::Output { output, action } => {
+            let (tx, rx) = async_channel::bounded(1);
+            ctx.event_loop.insert_idle(move |state| {
+                let result = state.apply_ipc_output_action(&output, action);
+                let _ = tx.send_blocking(result);
+            });
+            let result = rx.recv().await.context("error changing output config")?;
+            result.map_err(anyhow::Error::msg)?;
+            Response::Handled
+        }
         Request::Action(action) => {
             let action = niri_config::Action::from(action);
+            let (tx, rx) = async_channel::bounded(1);
             ctx.event_loop.insert_idle(move |state| {
-                state.do_action(action);
+                let result = state.do_action(action);
+                let _ = tx.send_blocking(result);
             });
-            return Ok(());
+            let result = rx.recv().await.context("error running action")?;
+            result.map_err(anyhow::Error::msg)?;
+            Response::Handled
         }
     };
 
-    let buf = serde_json::to_vec(&response).context("error formatting response")?;
-    write
-        .write_all(&buf)
-        .await
-        .context("error writing response")?;
+    Ok(response)
+}
+
+/// Sends events for the changes since the last refresh to the event stream clients.
+pub fn refresh(state: &mut State) {
+    let _span = tracy_client::span!("ipc::server::refresh");
+
+    let Some(server) = &mut state.niri.ipc_server else {
+        return;
+    };
+
+    if server.event_streams.borrow().is_empty() {
+        // Nobody is listening; start from a fresh snapshot once someone connects.
+        server.event_stream_state = None;
+        return;
+    }
+
+    let new = EventStreamState::new(state);
 
-    Ok(())
+    let server = state.niri.ipc_server.as_mut().unwrap();
+    if let Some(old) = &server.event_stream_state {
+        for event in old.events_since(&new) {
+            server.send_event(event);
+        }
+    }
+    server.event_stream_state = Some(new);
+}
+
+impl EventStreamState {
+    fn new(state: &mut State) -> Self {
+        let keyboard_layout = window_keyboard_layouts(state);
+        let layout = &state.niri.layout;
+
+        let mut windows = HashMap::new();
+        layout.with_windows_and_locations(|window, location| {
+            let wl_surface = window.toplevel().wl_surface().clone();
+            let window = make_ipc_window(window, location, keyboard_layout(window));
+            windows.insert(wl_surface, window);
+        });
+
+        let focused_window = layout
+            .focus()
+            .map(|window| window.toplevel().wl_surface().clone());
+
+        let workspaces = layout.ipc_workspaces();
+        let outputs = state.backend.ipc_outputs().borrow().clone();
+
+        let keyboard = state.niri.seat.get_keyboard().unwrap();
+        let keyboard_layout = keyboard.with_xkb_state(state, |context| {
+            let xkb = context.xkb().lock().unwrap();
+            let layout = xkb.active_layout();
+            let idx = u8::try_from(layout.0).unwrap_or(u8::MAX);
+            (idx, xkb.layout_name(layout).to_owned())
+        });
+
+        let bind_mode = state.niri.bind_mode.as_ref().map(|mode| mode.name.clone());
+
+        Self {
+            workspaces,
+            windows,
+            focused_window,
+            keyboard_layout,
+            bind_mode,
+            outputs,
+        }
+    }
+
+    fn events_since(&self, new: &Self) -> Vec<Event> {
+        let mut events = Vec::new();
+
+        for (connector, output) in &new.outputs {
+            if !self.outputs.contains_key(connector) {
+                events.push(Event::OutputConnected {
+                    connector: connector.clone(),
+                    output: output.clone(),
+                });
+            }
+        }
+        for connector in self.outputs.keys() {
+            if !new.outputs.contains_key(connector) {
+                events.push(Event::OutputDisconnected {
+                    connector: connector.clone(),
+                });
+            }
+        }
+
+        for ws in new.workspaces.iter().filter(|ws| ws.is_active) {
+            let old = self
+                .workspaces
+                .iter()
+                .find(|old| old.is_active && old.output == ws.output);
+            let activated = old.map_or(true, |old| {
+                old.idx != ws.idx || old.name != ws.name || (ws.is_focused && !old.is_focused)
+            });
+            if activated {
+                events.push(Event::WorkspaceActivated {
+                    workspace: ws.clone(),
+                });
+            }
+        }
+
+        for (wl_surface, window) in &self.windows {
+            if !new.windows.contains_key(wl_surface) {
+                events.push(Event::WindowClosed {
+                    window: window.clone(),
+                });
+            }
+        }
+        for (wl_surface, window) in &new.windows {
+            match self.windows.get(wl_surface) {
+                None => events.push(Event::WindowOpened {
+                    window: window.clone(),
+                }),
+                Some(old) if old.title != window.title => {
+                    events.push(Event::WindowTitleChanged {
+                        window: window.clone(),
+                    });
+                }
+                Some(_) => (),
+            }
+        }
+
+        if self.focused_window != new.focused_window {
+            let window = new
+                .focused_window
+                .as_ref()
+                .and_then(|wl_surface| new.windows.get(wl_surface))
+                .cloned();
+            events.push(Event::WindowFocusChanged { window });
+        }
+
+        if self.keyboard_layout != new.keyboard_layout {
+            let (idx, name) = new.keyboard_layout.clone();
+            events.push(Event::KeyboardLayoutSwitched { idx, name });
+        }
+
+        if self.bind_mode != new.bind_mode {
+            let name = new.bind_mode.clone();
+            events.push(Event::BindModeChanged { name });
+        }
+
+        events
+    }
+}
+
+/// Returns a function giving the keyboard layout index of a window.
+///
+/// The function returns `None` unless keyboard layouts are tracked per window.
+fn window_keyboard_layouts(state: &mut State) -> impl Fn(&Window) -> Option<u8> {
+    let per_window = state.niri.config.borrow().input.keyboard.track_layout == TrackLayout::Window;
+    let keyboard = state.niri.seat.get_keyboard().unwrap();
+    let active = keyboard.with_xkb_state(state, |context| {
+        context.xkb().lock().unwrap().active_layout()
+    });
+    let focus = state.niri.keyboard_focus.clone();
+
+    move |window| {
+        if !per_window {
+            return None;
+        }
+
+        // The focused window's layout is only stored when it loses focus.
+        let surface = window.toplevel().wl_surface();
+        let layout = if focus.as_ref() == Some(surface) {
+            active
+        } else {
+            stored_keyboard_layout(surface)
+        };
+        Some(u8::try_from(layout.0).unwrap_or(u8::MAX))
+    }
+}
+
+fn make_ipc_window(
+    window: &Window,
+    location: WindowLocation,
+    keyboard_layout_idx: Option<u8>,
+) -> niri_ipc::Window {
+    let (title, app_id) = with_states(window.toplevel().wl_surface(), |states| {
+        let role = states
+            .data_map
+            .get::<XdgToplevelSurfaceData>()
+            .unwrap()
+            .lock()
+            .unwrap();
+        (role.title.clone(), role.app_id.clone())
+    });
+
+    let size = window.size();
+    let (column_idx, tile_idx) = location
+        .column_and_tile_idx
+        .map_or((None, None), |(column, tile)| {
+            (Some(column + 1), Some(tile + 1))
+        });
+
+    niri_ipc::Window {
+        id: WindowId::of(window).get(),
+        title,
+        app_id,
+        output: location.output.map(|output| output.name()),
+        workspace_idx: u8::try_from(location.workspace_idx + 1).unwrap_or(u8::MAX),
+        column_idx,
+        tile_idx,
+        size: (size.w, size.h),
+        is_fullscreen: window.is_fullscreen(),
+        keyboard_layout_idx,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use niri_ipc::{Action, WorkspaceReferenceArg};
+
+    use super::*;
+
+    #[test]
+    fn parse_workspace_reference() {
+        let parse = |json: &str| match serde_json::from_str(json).unwrap() {
+            Request::Action(Action::FocusWorkspace { reference }) => reference,
+            request => panic!("unexpected request: {request:?}"),
+        };
+
+        assert_eq!(
+            parse(r#"{"Action":{"FocusWorkspace":{"reference":{"Index":2}}}}"#),
+            WorkspaceReferenceArg::Index(2)
+        );
+        assert_eq!(
+            parse(r#"{"Action":{"FocusWorkspace":{"reference":{"Name":"chat"}}}}"#),
+            WorkspaceReferenceArg::Name(String::from("chat"))
+        );
+
+        // The format from before workspaces could be referenced by name.
+        assert_eq!(
+            parse(r#"{"Action":{"FocusWorkspace":{"index":2}}}"#),
+            WorkspaceReferenceArg::Index(2)
+        );
+
+        // What the server parses must round-trip.
+        let action = Action::MoveWindowToWorkspace {
+            window_id: None,
+            reference: WorkspaceReferenceArg::Name(String::from("chat")),
+        };
+        let json = serde_json::to_string(&Request::Action(action.clone())).unwrap();
+        match serde_json::from_str(&json).unwrap() {
+            Request::Action(parsed) => assert_eq!(parsed, action),
+            request => panic!("unexpected request: {request:?}"),
+        }
+    }
 }
@@ -0,0 +1,434 @@
+use std::cmp::{max, min};
+use std::iter::zip;
+use std::rc::Rc;
+use std::time::Duration;
+
+use niri_ipc::SizeChange;
+use smithay::utils::{Logical, Point, Rectangle, Scale, Size};
+
+use super::tile::{Tile, TileRenderElement};
+use super::workspace::compute_toplevel_bounds;
+use super::{LayoutElement, Options, ResizeEdge};
+use crate::render_helpers::renderer::NiriRenderer;
+
+/// Space for floating windows on a workspace.
+///
+/// Floating windows are positioned freely and drawn above the scrolling columns.
+#[derive(Debug)]
+pub struct FloatingSpace<W: LayoutElement> {
+    /// Tiles in this space.
+    ///
+    /// Tiles are ordered from top to bottom. The first tile is the active one.
+    pub tiles: Vec<Tile<W>>,
+
+    /// Locations of the tiles relative to the view.
+    ///
+    /// Must have the same number of elements as `tiles`.
+    locations: Vec<Point<i32, Logical>>,
+
+    /// Latest known view size for this space's workspace.
+    view_size: Size<i32, Logical>,
+
+    /// Latest known working area for this space's workspace.
+    working_area: Rectangle<i32, Logical>,
+
+    /// Configurable properties of the layout.
+    options: Rc<Options>,
+}
+
+impl<W: LayoutElement> FloatingSpace<W> {
+    pub fn new(
+        view_size: Size<i32, Logical>,
+        working_area: Rectangle<i32, Logical>,
+        options: Rc<Options>,
+    ) -> Self {
+        Self {
+            tiles: vec![],
+            locations: vec![],
+            view_size,
+            working_area,
+            options,
+        }
+    }
+
+    pub fn set_view_size(
+        &mut self,
+        size: Size<i32, Logical>,
+        working_area: Rectangle<i32, Logical>,
+    ) {
+        if self.view_size == size && self.working_area == working_area {
+            return;
+        }
+
+        self.view_size = size;
+        self.working_area = working_area;
+
+        for idx in 0..self.tiles.len() {
+            self.clamp_location(idx);
+        }
+    }
+
+    pub fn update_config(&mut self, options: Rc<Options>) {
+        for tile in &mut self.tiles {
+            tile.update_config(options.clone());
+        }
+
+        self.options = options;
+    }
+
+    pub fn advance_animations(&mut self, current_time: Duration, is_active: bool) {
+        for (idx, tile) in self.tiles.iter_mut().enumerate() {
+            tile.advance_animations(current_time, is_active && idx == 0);
+        }
+    }
+
+    pub fn are_animations_ongoing(&self) -> bool {
+        self.tiles.iter().any(Tile::are_animations_ongoing)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.tiles.is_empty()
+    }
+
+    pub fn windows(&self) -> impl Iterator<Item = &W> + '_ {
+        self.tiles.iter().map(Tile::window)
+    }
+
+    pub fn contains(&self, window: &W) -> bool {
+        self.windows().any(|win| win == window)
+    }
+
+    fn position(&self, window: &W) -> Option<usize> {
+        self.windows().position(|win| win == window)
+    }
+
+    /// Returns the active floating window, if any.
+    pub fn focus(&self) -> Option<&W> {
+        self.tiles.first().map(Tile::window)
+    }
+
+    pub fn add_window(&mut self, window: W, activate: bool) {
+        self.add_window_at(window, None, activate);
+    }
+
+    /// Adds a window with its tile at the given location, or centered in the working area if
+    /// `None`.
+    pub fn add_window_at(&mut self, window: W, loc: Option<Point<i32, Logical>>, activate: bool) {
+        let tile = Tile::new(window, self.options.clone());
+
+        // Keep the size that the window currently has, but make sure it fits into the working
+        // area. A zero size lets the window pick its own size.
+        let bounds = compute_toplevel_bounds(&self.options, self.working_area);
+        let mut size = tile.window_size();
+        size.w = min(size.w, bounds.w);
+        size.h = min(size.h, bounds.h);
+        tile.window().request_size(size);
+
+        // Center new floating windows in the working area.
+        let loc = loc.unwrap_or_else(|| {
+            let tile_size = tile.tile_size();
+            let area = self.working_area;
+            Point::from((
+                area.loc.x + (area.size.w - tile_size.w) / 2,
+                area.loc.y + (area.size.h - tile_size.h) / 2,
+            ))
+        });
+
+        // Inactive windows go right below the active one.
+        let idx = if activate {
+            0
+        } else {
+            min(1, self.tiles.len())
+        };
+        self.tiles.insert(idx, tile);
+        self.locations.insert(idx, loc);
+        self.clamp_location(idx);
+    }
+
+    pub fn remove_window(&mut self, window: &W) -> W {
+        let idx = self.position(window).unwrap();
+        self.remove_tile_by_idx(idx)
+    }
+
+    pub fn remove_active_window(&mut self) -> Option<W> {
+        if self.tiles.is_empty() {
+            return None;
+        }
+
+        Some(self.remove_tile_by_idx(0))
+    }
+
+    fn remove_tile_by_idx(&mut self, idx: usize) -> W {
+        self.locations.remove(idx);
+        self.tiles.remove(idx).into_window()
+    }
+
+    /// Updates the window after a commit.
+    ///
+    /// `resize_edges` are the edges of an ongoing interactive resize of this window, if any. When
+    /// resizing from the left or top edge, the opposite edge stays in place.
+    pub fn update_window(&mut self, window: &W, resize_edges: Option<ResizeEdge>) {
+        let idx = self.position(window).unwrap();
+        let prev_size = self.tiles[idx].tile_size();
+        self.tiles[idx].update_window();
+
+        if let Some(edges) = resize_edges {
+            let size = self.tiles[idx].tile_size();
+            let loc = &mut self.locations[idx];
+            if edges.contains(ResizeEdge::LEFT) {
+                loc.x -= size.w - prev_size.w;
+            }
+            if edges.contains(ResizeEdge::TOP) {
+                loc.y -= size.h - prev_size.h;
+            }
+        }
+
+        // The window might have resized past the working area.
+        self.clamp_location(idx);
+    }
+
+    pub fn activate_window(&mut self, window: &W) {
+        let idx = self.position(window).unwrap();
+        self.activate_tile(idx);
+    }
+
+    fn activate_tile(&mut self, idx: usize) {
+        // Raise the tile to the top.
+        let tile = self.tiles.remove(idx);
+        let loc = self.locations.remove(idx);
+        self.tiles.insert(0, tile);
+        self.locations.insert(0, loc);
+    }
+
+    /// Moves the tile so that it stays within the working area where possible.
+    fn clamp_location(&mut self, idx: usize) {
+        let size = self.tiles[idx].tile_size();
+        let area = self.working_area;
+        let loc = &mut self.locations[idx];
+
+        // If the tile is larger than the working area, keep its top-left corner visible.
+        loc.x = max(area.loc.x, min(loc.x, area.loc.x + area.size.w - size.w));
+        loc.y = max(area.loc.y, min(loc.y, area.loc.y + area.size.h - size.h));
+    }
+
+    fn tile_center(&self, idx: usize) -> Point<i32, Logical> {
+        let size = self.tiles[idx].tile_size();
+        self.locations[idx] + Point::from((size.w / 2, size.h / 2))
+    }
+
+    /// Focuses the closest tile whose center lies in the given direction from the active one.
+    ///
+    /// Returns `false` if there was no such tile.
+    fn focus_directional(
+        &mut self,
+        in_direction: impl Fn(Point<i32, Logical>, Point<i32, Logical>) -> bool,
+    ) -> bool {
+        if self.tiles.is_empty() {
+            return false;
+        }
+
+        let from = self.tile_center(0);
+        let target = (1..self.tiles.len())
+            .map(|idx| (idx, self.tile_center(idx)))
+            .filter(|(_, to)| in_direction(from, *to))
+            .min_by_key(|(_, to)| (to.x - from.x).abs() + (to.y - from.y).abs());
+
+        let Some((idx, _)) = target else {
+            return false;
+        };
+
+        self.activate_tile(idx);
+        true
+    }
+
+    pub fn focus_left(&mut self) -> bool {
+        self.focus_directional(|from, to| to.x < from.x)
+    }
+
+    pub fn focus_right(&mut self) -> bool {
+        self.focus_directional(|from, to| to.x > from.x)
+    }
+
+    pub fn focus_up(&mut self) -> bool {
+        self.focus_directional(|from, to| to.y < from.y)
+    }
+
+    pub fn focus_down(&mut self) -> bool {
+        self.focus_directional(|from, to| to.y > from.y)
+    }
+
+    pub fn set_window_width(&mut self, window: Option<&W>, change: SizeChange) {
+        let idx = match window {
+            Some(window) => self.position(window),
+            None => (!self.tiles.is_empty()).then_some(0),
+        };
+        let Some(idx) = idx else {
+            return;
+        };
+        let tile = &self.tiles[idx];
+
+        let available = self.working_area.size.w - self.options.gaps * 2;
+        let current = tile.window_size();
+        let win = tile.window();
+        let width = resolve_size_change(
+            change,
+            current.w,
+            available,
+            win.min_size().w,
+            win.max_size().w,
+        );
+        win.request_size(Size::from((width, current.h)));
+    }
+
+    pub fn set_window_height(&mut self, window: Option<&W>, change: SizeChange) {
+        let idx = match window {
+            Some(window) => self.position(window),
+            None => (!self.tiles.is_empty()).then_some(0),
+        };
+        let Some(idx) = idx else {
+            return;
+        };
+        let tile = &self.tiles[idx];
+
+        let available = self.working_area.size.h - self.options.gaps * 2;
+        let current = tile.window_size();
+        let win = tile.window();
+        let height = resolve_size_change(
+            change,
+            current.h,
+            available,
+            win.min_size().h,
+            win.max_size().h,
+        );
+        win.request_size(Size::from((current.w, height)));
+    }
+
+    /// Requests a new window size, clamped to the window's size constraints.
+    pub fn request_window_size(&mut self, window: &W, size: Size<i32, Logical>) {
+        let Some(idx) = self.position(window) else {
+            return;
+        };
+
+        let tile = &self.tiles[idx];
+        let current = tile.window_size();
+        let win = tile.window();
+        let (min_size, max_size) = (win.min_size(), win.max_size());
+        let width = resolve_size_change(
+            SizeChange::SetFixed(size.w),
+            current.w,
+            self.working_area.size.w,
+            min_size.w,
+            max_size.w,
+        );
+        let height = resolve_size_change(
+            SizeChange::SetFixed(size.h),
+            current.h,
+            self.working_area.size.h,
+            min_size.h,
+            max_size.h,
+        );
+        win.request_size(Size::from((width, height)));
+    }
+
+    /// Returns the location of the window's tile relative to the view.
+    pub fn tile_position(&self, window: &W) -> Option<Point<i32, Logical>> {
+        let idx = self.position(window)?;
+        Some(self.locations[idx])
+    }
+
+    pub fn window_y(&self, window: &W) -> Option<i32> {
+        let idx = self.position(window)?;
+        Some(self.locations[idx].y + self.tiles[idx].window_loc().y)
+    }
+
+    pub fn start_open_animation(&mut self, window: &W) -> bool {
+        let Some(idx) = self.position(window) else {
+            return false;
+        };
+
+        self.tiles[idx].start_open_animation();
+        true
+    }
+
+    pub fn window_under(
+        &self,
+        pos: Point<f64, Logical>,
+    ) -> Option<(&W, Option<Point<i32, Logical>>)> {
+        // Tiles are ordered top to bottom, so the first match is the visible one.
+        zip(&self.tiles, &self.locations).find_map(|(tile, tile_pos)| {
+            let pos_within_tile = pos - tile_pos.to_f64();
+
+            if tile.is_in_input_region(pos_within_tile) {
+                let pos_within_surface = *tile_pos + tile.buf_loc();
+                return Some((tile.window(), Some(pos_within_surface)));
+            } else if tile.is_in_activation_region(pos_within_tile) {
+                return Some((tile.window(), None));
+            }
+
+            None
+        })
+    }
+
+    pub fn render_elements<R: NiriRenderer>(
+        &self,
+        renderer: &mut R,
+        scale: Scale<f64>,
+        focus_ring: bool,
+    ) -> Vec<TileRenderElement<R>> {
+        let mut rv = vec![];
+
+        for (idx, (tile, tile_pos)) in zip(&self.tiles, &self.locations).enumerate() {
+            // For the active tile (which comes first), draw the focus ring.
+            let focus_ring = focus_ring && idx == 0;
+            rv.extend(tile.render(renderer, *tile_pos, scale, focus_ring));
+        }
+
+        rv
+    }
+
+    #[cfg(test)]
+    pub fn verify_invariants(&self) {
+        assert_eq!(self.tiles.len(), self.locations.len());
+
+        for tile in &self.tiles {
+            assert!(
+                !tile.window().is_pending_fullscreen(),
+                "floating windows can't be fullscreen"
+            );
+        }
+    }
+}
+
+fn resolve_size_change(
+    change: SizeChange,
+    current: i32,
+    available: i32,
+    min_size: i32,
+    max_size: i32,
+) -> i32 {
+    // FIXME: fix overflows then remove limits.
+    const MAX_PX: i32 = 100000;
+
+    let available = max(available, 1);
+    let mut size = match change {
+        SizeChange::SetFixed(fixed) => fixed,
+        SizeChange::SetProportion(proportion) => {
+            (available as f64 * proportion / 100.).round() as i32
+        }
+        SizeChange::AdjustFixed(delta) => current.saturating_add(delta),
+        SizeChange::AdjustProportion(delta) => {
+            let proportion = current as f64 / available as f64 + delta / 100.;
+            (available as f64 * proportion).round() as i32
+        }
+    };
+
+    // Clamp it against the window size constraints.
+    if max_size > 0 {
+        size = min(size, max_size);
+    }
+    if min_size > 0 {
+        size = max(size, min_size);
+    }
+
+    size.clamp(1, MAX_PX)
+}
@@ -29,40 +29,48 @@
 //! compromise we only keep the first workspace there, and move the rest to the primary output,
 //! making the primary output their original output.
 
-use std::cmp::min;
+use std::cmp::{max, min};
 use std::mem;
 use std::rc::Rc;
 use std::time::Duration;
 
-use niri_config::{self, CenterFocusedColumn, Config, Struts};
+use niri_config::{self, CenterFocusedColumn, Config, Struts, Workspace as WorkspaceConfig};
 use niri_ipc::SizeChange;
 use smithay::backend::renderer::element::solid::SolidColorRenderElement;
 use smithay::backend::renderer::element::surface::WaylandSurfaceRenderElement;
 use smithay::backend::renderer::element::{AsRenderElements, Id};
 use smithay::desktop::space::SpaceElement;
 use smithay::desktop::Window;
-use smithay::output::Output;
+use smithay::input::pointer::CursorIcon;
+use smithay::output::{self, Output};
 use smithay::reexports::wayland_protocols::xdg::decoration::zv1::server::zxdg_toplevel_decoration_v1;
 use smithay::reexports::wayland_protocols::xdg::shell::server::xdg_toplevel;
 use smithay::reexports::wayland_server::protocol::wl_surface::WlSurface;
 use smithay::utils::{Logical, Point, Rectangle, Scale, Size, Transform};
-use smithay::wayland::compositor::{send_surface_state, with_states};
+use smithay::wayland::compositor::with_states;
 use smithay::wayland::shell::xdg::SurfaceCachedState;
 
 pub use self::monitor::MonitorRenderElement;
 use self::monitor::{Monitor, WorkspaceSwitch, WorkspaceSwitchGesture};
+use self::tile::{Tile, TileRenderElement};
 use self::workspace::{compute_working_area, Column, ColumnWidth, OutputId, Workspace};
 use crate::animation::Animation;
 use crate::niri::WindowOffscreenId;
 use crate::niri_render_elements;
 use crate::render_helpers::renderer::NiriRenderer;
-use crate::utils::output_size;
+use crate::utils::{output_size, send_scale_transform};
+use crate::window::{ResolvedWindowRules, WindowId};
 
+pub mod floating;
 pub mod focus_ring;
 pub mod monitor;
+pub mod tab_indicator;
 pub mod tile;
 pub mod workspace;
 
+/// Gesture movement that switches by one workspace, like in GNOME Shell.
+pub const WORKSPACE_GESTURE_MOVEMENT: f64 = 400.;
+
 niri_render_elements! {
     LayoutElementRenderElement => {
         Wayland = WaylandSurfaceRenderElement<R>,
@@ -102,9 +110,10 @@ pub trait LayoutElement: PartialEq {
     fn request_fullscreen(&self, size: Size<i32, Logical>);
     fn min_size(&self) -> Size<i32, Logical>;
     fn max_size(&self) -> Size<i32, Logical>;
+    fn rules(&self) -> ResolvedWindowRules;
     fn is_wl_surface(&self, wl_surface: &WlSurface) -> bool;
     fn has_ssd(&self) -> bool;
-    fn set_preferred_scale_transform(&self, scale: i32, transform: Transform);
+    fn set_preferred_scale_transform(&self, scale: output::Scale, transform: Transform);
     fn output_enter(&self, output: &Output);
     fn output_leave(&self, output: &Output);
     fn set_offscreen_element_id(&self, id: Option<Id>);
@@ -124,10 +133,45 @@ pub trait LayoutElement: PartialEq {
 pub struct Layout<W: LayoutElement> {
     /// Monitors and workspaes in the layout.
     monitor_set: MonitorSet<W>,
+    /// Window being moved interactively with the pointer, if any.
+    ///
+    /// While being moved, the window is not on any workspace.
+    interactive_move: Option<InteractiveMove<W>>,
     /// Configurable properties of the layout.
     options: Rc<Options>,
 }
 
+#[derive(Debug)]
+struct InteractiveMove<W: LayoutElement> {
+    /// Tile of the window being moved.
+    tile: Tile<W>,
+    /// Output that the pointer is on.
+    output: Output,
+    /// Pointer position relative to the output.
+    pointer_pos_within_output: Point<f64, Logical>,
+    /// Pointer position relative to the tile.
+    pointer_pos_within_tile: Point<f64, Logical>,
+    /// Width of the window's column before the move.
+    width: ColumnWidth,
+    /// Whether the window's column was full-width before the move.
+    is_full_width: bool,
+    /// Whether the window was floating before the move.
+    is_floating: bool,
+}
+
+/// Location of a window in the layout.
+#[derive(Debug, Clone, Copy)]
+pub struct WindowLocation<'a> {
+    /// Output of the window's workspace, if any.
+    pub output: Option<&'a Output>,
+    /// Index of the window's workspace on its output.
+    pub workspace_idx: usize,
+    /// Index of the window's column and of the window within that column.
+    ///
+    /// `None` for floating windows and for the window being moved interactively.
+    pub column_and_tile_idx: Option<(usize, usize)>,
+}
+
 #[derive(Debug)]
 enum MonitorSet<W: LayoutElement> {
     /// At least one output is connected.
@@ -146,6 +190,26 @@ enum MonitorSet<W: LayoutElement> {
     },
 }
 
+bitflags::bitflags! {
+    /// Edges of a window being resized interactively.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub struct ResizeEdge: u32 {
+        const TOP = 0b0001;
+        const BOTTOM = 0b0010;
+        const LEFT = 0b0100;
+        const RIGHT = 0b1000;
+
+        const TOP_LEFT = Self::TOP.bits() | Self::LEFT.bits();
+        const BOTTOM_LEFT = Self::BOTTOM.bits() | Self::LEFT.bits();
+
+        const TOP_RIGHT = Self::TOP.bits() | Self::RIGHT.bits();
+        const BOTTOM_RIGHT = Self::BOTTOM.bits() | Self::RIGHT.bits();
+
+        const LEFT_RIGHT = Self::LEFT.bits() | Self::RIGHT.bits();
+        const TOP_BOTTOM = Self::TOP.bits() | Self::BOTTOM.bits();
+    }
+}
+
 #[derive(Debug, PartialEq)]
 pub struct Options {
     /// Padding around windows in logical pixels.
@@ -154,6 +218,8 @@ pub struct Options {
     pub struts: Struts,
     pub focus_ring: niri_config::FocusRing,
     pub border: niri_config::FocusRing,
+    pub tab_indicator: niri_config::TabIndicator,
+    pub insert_hint: niri_config::InsertHint,
     pub center_focused_column: CenterFocusedColumn,
     /// Column widths that `toggle_width()` switches between.
     pub preset_widths: Vec<ColumnWidth>,
@@ -169,6 +235,8 @@ impl Default for Options {
             struts: Default::default(),
             focus_ring: Default::default(),
             border: niri_config::default_border(),
+            tab_indicator: Default::default(),
+            insert_hint: Default::default(),
             center_focused_column: Default::default(),
             preset_widths: vec![
                 ColumnWidth::Proportion(1. / 3.),
@@ -209,6 +277,8 @@ impl Options {
             struts: layout.struts,
             focus_ring: layout.focus_ring,
             border: layout.border,
+            tab_indicator: layout.tab_indicator,
+            insert_hint: layout.insert_hint,
             center_focused_column: layout.center_focused_column,
             preset_widths,
             default_width,
@@ -217,6 +287,29 @@ impl Options {
     }
 }
 
+impl From<xdg_toplevel::ResizeEdge> for ResizeEdge {
+    fn from(edge: xdg_toplevel::ResizeEdge) -> Self {
+        // The xdg_toplevel values are bitmasks of the same edges.
+        Self::from_bits_truncate(edge as u32)
+    }
+}
+
+impl ResizeEdge {
+    pub fn cursor_icon(self) -> CursorIcon {
+        match self {
+            Self::TOP => CursorIcon::NResize,
+            Self::BOTTOM => CursorIcon::SResize,
+            Self::LEFT => CursorIcon::WResize,
+            Self::RIGHT => CursorIcon::EResize,
+            Self::TOP_LEFT => CursorIcon::NwResize,
+            Self::TOP_RIGHT => CursorIcon::NeResize,
+            Self::BOTTOM_LEFT => CursorIcon::SwResize,
+            Self::BOTTOM_RIGHT => CursorIcon::SeResize,
+            _ => CursorIcon::Default,
+        }
+    }
+}
+
 impl LayoutElement for Window {
     fn size(&self) -> Size<i32, Logical> {
         self.geometry().size
@@ -261,26 +354,61 @@ impl LayoutElement for Window {
     }
 
     fn min_size(&self) -> Size<i32, Logical> {
-        with_states(self.toplevel().wl_surface(), |state| {
+        let mut size = with_states(self.toplevel().wl_surface(), |state| {
             let curr = state.cached_state.current::<SurfaceCachedState>();
             curr.min_size
-        })
+        });
+
+        ResolvedWindowRules::with(self, |rules| {
+            if let Some(x) = rules.min_width {
+                size.w = max(size.w, i32::from(x));
+            }
+            if let Some(x) = rules.min_height {
+                size.h = max(size.h, i32::from(x));
+            }
+        });
+
+        size
     }
 
     fn max_size(&self) -> Size<i32, Logical> {
-        with_states(self.toplevel().wl_surface(), |state| {
+        let mut size = with_states(self.toplevel().wl_surface(), |state| {
             let curr = state.cached_state.current::<SurfaceCachedState>();
             curr.max_size
-        })
+        });
+
+        // Zero means unbounded.
+        ResolvedWindowRules::with(self, |rules| {
+            if let Some(x) = rules.max_width {
+                if size.w == 0 {
+                    size.w = i32::from(x);
+                } else if x > 0 {
+                    size.w = min(size.w, i32::from(x));
+                }
+            }
+            if let Some(x) = rules.max_height {
+                if size.h == 0 {
+                    size.h = i32::from(x);
+                } else if x > 0 {
+                    size.h = min(size.h, i32::from(x));
+                }
+            }
+        });
+
+        size
+    }
+
+    fn rules(&self) -> ResolvedWindowRules {
+        ResolvedWindowRules::of(self)
     }
 
     fn is_wl_surface(&self, wl_surface: &WlSurface) -> bool {
         self.toplevel().wl_surface() == wl_surface
     }
 
-    fn set_preferred_scale_transform(&self, scale: i32, transform: Transform) {
+    fn set_preferred_scale_transform(&self, scale: output::Scale, transform: Transform) {
         self.with_surfaces(|surface, data| {
-            send_surface_state(surface, data, scale, transform);
+            send_scale_transform(surface, data, scale, transform);
         });
     }
 
@@ -318,16 +446,33 @@ impl LayoutElement for Window {
 
 impl<W: LayoutElement> Layout<W> {
     pub fn new(config: &Config) -> Self {
-        Self::with_options(Options::from_config(config))
+        Self::with_options_and_workspaces(config, Options::from_config(config))
     }
 
     pub fn with_options(options: Options) -> Self {
         Self {
             monitor_set: MonitorSet::NoOutputs { workspaces: vec![] },
+            interactive_move: None,
             options: Rc::new(options),
         }
     }
 
+    fn with_options_and_workspaces(config: &Config, options: Options) -> Self {
+        let options = Rc::new(options);
+
+        let workspaces = config
+            .workspaces
+            .iter()
+            .map(|ws| Workspace::new_with_config_no_outputs(Some(ws.clone()), options.clone()))
+            .collect();
+
+        Self {
+            monitor_set: MonitorSet::NoOutputs { workspaces },
+            interactive_move: None,
+            options,
+        }
+    }
+
     pub fn add_output(&mut self, output: Output) {
         let id = OutputId::new(&output);
 
@@ -357,7 +502,7 @@ impl<W: LayoutElement> Layout<W> {
                         // The user could've closed a window while remaining on this workspace, on
                         // another monitor. However, we will add an empty workspace in the end
                         // instead.
-                        if ws.has_windows() {
+                        if ws.has_windows_or_name() {
                             workspaces.push(ws);
                         }
 
@@ -390,7 +535,7 @@ impl<W: LayoutElement> Layout<W> {
                 }
             }
             MonitorSet::NoOutputs { mut workspaces } => {
-                // We know there are no empty workspaces there, so add one.
+                // We know there are no empty unnamed workspaces there, so add one.
                 workspaces.push(Workspace::new(output.clone(), self.options.clone()));
 
                 for workspace in &mut workspaces {
@@ -409,6 +554,15 @@ impl<W: LayoutElement> Layout<W> {
     }
 
     pub fn remove_output(&mut self, output: &Output) {
+        // Drop the window being moved while its output still exists.
+        if self
+            .interactive_move
+            .as_ref()
+            .map_or(false, |move_| &move_.output == output)
+        {
+            self.finish_interactive_move();
+        }
+
         self.monitor_set = match mem::take(&mut self.monitor_set) {
             MonitorSet::Normal {
                 mut monitors,
@@ -426,8 +580,8 @@ impl<W: LayoutElement> Layout<W> {
                     ws.set_output(None);
                 }
 
-                // Get rid of empty workspaces.
-                workspaces.retain(|ws| ws.has_windows());
+                // Get rid of empty workspaces, but keep the named ones.
+                workspaces.retain(|ws| ws.has_windows_or_name());
 
                 if monitors.is_empty() {
                     // Removed the last monitor.
@@ -563,11 +717,12 @@ impl<W: LayoutElement> Layout<W> {
                 Some(&mon.output)
             }
             MonitorSet::NoOutputs { workspaces } => {
-                let ws = if let Some(ws) = workspaces.get_mut(0) {
+                // Named workspaces only get windows explicitly opened or moved there.
+                let ws = if let Some(ws) = workspaces.iter_mut().find(|ws| ws.name.is_none()) {
                     ws
                 } else {
                     workspaces.push(Workspace::new_no_outputs(self.options.clone()));
-                    &mut workspaces[0]
+                    workspaces.last_mut().unwrap()
                 };
                 ws.add_window(window, true, width, is_full_width);
                 None
@@ -575,6 +730,98 @@ impl<W: LayoutElement> Layout<W> {
         }
     }
 
+    /// Adds a new window to the active workspace of the given output.
+    ///
+    /// The active output does not change.
+    pub fn add_window_on_output(
+        &mut self,
+        output: &Output,
+        window: W,
+        width: Option<ColumnWidth>,
+        is_full_width: bool,
+    ) {
+        let width = width
+            .or(self.options.default_width)
+            .unwrap_or_else(|| ColumnWidth::Fixed(window.size().w));
+
+        let MonitorSet::Normal { monitors, .. } = &mut self.monitor_set else {
+            panic!()
+        };
+
+        let mon = monitors
+            .iter_mut()
+            .find(|mon| &mon.output == output)
+            .unwrap();
+
+        // Don't steal focus from an active fullscreen window.
+        let mut activate = true;
+        let ws = &mon.workspaces[mon.active_workspace_idx];
+        if !ws.columns.is_empty() && ws.columns[ws.active_column_idx].is_fullscreen {
+            activate = false;
+        }
+
+        mon.add_window(
+            mon.active_workspace_idx,
+            window,
+            activate,
+            width,
+            is_full_width,
+        );
+    }
+
+    /// Adds a new window to the named workspace.
+    ///
+    /// Returns an output that the window was added to, if there were any outputs. Returns `None`
+    /// and drops the window if there's no workspace with this name, so check for it beforehand.
+    pub fn add_window_to_named_workspace(
+        &mut self,
+        workspace_name: &str,
+        window: W,
+        width: Option<ColumnWidth>,
+        is_full_width: bool,
+    ) -> Option<&Output> {
+        let width = width
+            .or(self.options.default_width)
+            .unwrap_or_else(|| ColumnWidth::Fixed(window.size().w));
+
+        match &mut self.monitor_set {
+            MonitorSet::Normal {
+                monitors,
+                active_monitor_idx,
+                ..
+            } => {
+                let (mon_idx, ws_idx) =
+                    monitors.iter().enumerate().find_map(|(mon_idx, mon)| {
+                        mon.workspaces
+                            .iter()
+                            .position(|ws| ws.name.as_deref() == Some(workspace_name))
+                            .map(|ws_idx| (mon_idx, ws_idx))
+                    })?;
+
+                let mon = &mut monitors[mon_idx];
+
+                // Only activate the window if its workspace is the one currently visible on the
+                // active monitor, and don't steal focus from an active fullscreen window.
+                let mut activate =
+                    mon_idx == *active_monitor_idx && ws_idx == mon.active_workspace_idx;
+                let ws = &mon.workspaces[ws_idx];
+                if !ws.columns.is_empty() && ws.columns[ws.active_column_idx].is_fullscreen {
+                    activate = false;
+                }
+
+                mon.add_window(ws_idx, window, activate, width, is_full_width);
+                Some(&mon.output)
+            }
+            MonitorSet::NoOutputs { workspaces } => {
+                let ws = workspaces
+                    .iter_mut()
+                    .find(|ws| ws.name.as_deref() == Some(workspace_name))?;
+                ws.add_window(window, true, width, is_full_width);
+                None
+            }
+        }
+    }
+
     /// Adds a new window to the layout immediately to the right of another window.
     ///
     /// If that another window was active, activates the new window.
@@ -587,6 +834,15 @@ impl<W: LayoutElement> Layout<W> {
         width: Option<ColumnWidth>,
         is_full_width: bool,
     ) -> Option<&Output> {
+        if self
+            .interactive_move
+            .as_ref()
+            .map_or(false, |move_| move_.tile.window() == right_of)
+        {
+            // The window being moved is not in any column, so add the new window normally.
+            return self.add_window(window, width, is_full_width);
+        }
+
         let width = width
             .or(self.options.default_width)
             .unwrap_or_else(|| ColumnWidth::Fixed(window.size().w));
@@ -613,6 +869,16 @@ impl<W: LayoutElement> Layout<W> {
     }
 
     pub fn remove_window(&mut self, window: &W) {
+        if self
+            .interactive_move
+            .as_ref()
+            .map_or(false, |move_| move_.tile.window() == window)
+        {
+            self.interactive_move = None;
+            self.update_insert_hints();
+            return;
+        }
+
         match &mut self.monitor_set {
             MonitorSet::Normal { monitors, .. } => {
                 for mon in monitors {
@@ -621,7 +887,7 @@ impl<W: LayoutElement> Layout<W> {
                             ws.remove_window(window);
 
                             // Clean up empty workspaces that are not active and not last.
-                            if !ws.has_windows()
+                            if !ws.has_windows_or_name()
                                 && idx != mon.active_workspace_idx
                                 && idx != mon.workspaces.len() - 1
                                 && mon.workspace_switch.is_none()
@@ -644,7 +910,7 @@ impl<W: LayoutElement> Layout<W> {
                         ws.remove_window(window);
 
                         // Clean up empty workspaces.
-                        if !ws.has_windows() {
+                        if !ws.has_windows_or_name() {
                             workspaces.remove(idx);
                         }
 
@@ -656,6 +922,13 @@ impl<W: LayoutElement> Layout<W> {
     }
 
     pub fn update_window(&mut self, window: &W) {
+        if let Some(move_) = &mut self.interactive_move {
+            if move_.tile.window() == window {
+                move_.tile.update_window();
+                return;
+            }
+        }
+
         match &mut self.monitor_set {
             MonitorSet::Normal { monitors, .. } => {
                 for mon in monitors {
@@ -679,6 +952,12 @@ impl<W: LayoutElement> Layout<W> {
     }
 
     pub fn find_window_and_output(&self, wl_surface: &WlSurface) -> Option<(&W, &Output)> {
+        if let Some(move_) = &self.interactive_move {
+            if move_.tile.window().is_wl_surface(wl_surface) {
+                return Some((move_.tile.window(), &move_.output));
+            }
+        }
+
         if let MonitorSet::Normal { monitors, .. } = &self.monitor_set {
             for mon in monitors {
                 for ws in &mon.workspaces {
@@ -693,24 +972,26 @@ impl<W: LayoutElement> Layout<W> {
     }
 
     pub fn window_y(&self, window: &W) -> Option<i32> {
+        if let Some(move_) = &self.interactive_move {
+            if move_.tile.window() == window {
+                return Some(move_.tile.window_loc().y);
+            }
+        }
+
         match &self.monitor_set {
             MonitorSet::Normal { monitors, .. } => {
                 for mon in monitors {
                     for ws in &mon.workspaces {
-                        for col in &ws.columns {
-                            if let Some(idx) = col.position(window) {
-                                return Some(col.window_y(idx));
-                            }
+                        if let Some(y) = ws.window_y(window) {
+                            return Some(y);
                         }
                     }
                 }
             }
             MonitorSet::NoOutputs { workspaces, .. } => {
                 for ws in workspaces {
-                    for col in &ws.columns {
-                        if let Some(idx) = col.position(window) {
-                            return Some(col.window_y(idx));
-                        }
+                    if let Some(y) = ws.window_y(window) {
+                        return Some(y);
                     }
                 }
             }
@@ -742,6 +1023,15 @@ impl<W: LayoutElement> Layout<W> {
     }
 
     pub fn activate_window(&mut self, window: &W) {
+        if self
+            .interactive_move
+            .as_ref()
+            .map_or(false, |move_| move_.tile.window() == window)
+        {
+            // The window being moved is already focused.
+            return;
+        }
+
         let MonitorSet::Normal {
             monitors,
             active_monitor_idx,
@@ -812,7 +1102,154 @@ impl<W: LayoutElement> Layout<W> {
         Some(&mon.workspaces[mon.active_workspace_idx])
     }
 
+    /// Finds a named workspace.
+    ///
+    /// Returns the index of the workspace on its monitor, or among the workspaces when there are
+    /// no outputs.
+    pub fn find_workspace_by_name(&self, workspace_name: &str) -> Option<(usize, &Workspace<W>)> {
+        let matches = |ws: &&Workspace<W>| ws.name.as_deref() == Some(workspace_name);
+
+        match &self.monitor_set {
+            MonitorSet::Normal { monitors, .. } => monitors.iter().find_map(|mon| {
+                mon.workspaces
+                    .iter()
+                    .enumerate()
+                    .find(|(_, ws)| matches(ws))
+            }),
+            MonitorSet::NoOutputs { workspaces } => {
+                workspaces.iter().enumerate().find(|(_, ws)| matches(ws))
+            }
+        }
+    }
+
+    /// Creates a named workspace, unless one with this name already exists.
+    ///
+    /// An existing workspace is moved if its configured output changed.
+    pub fn ensure_named_workspace(&mut self, ws_config: &WorkspaceConfig) {
+        if self.find_workspace_by_name(&ws_config.name).is_some() {
+            self.update_named_workspace_output(ws_config);
+            return;
+        }
+
+        let options = self.options.clone();
+
+        match &mut self.monitor_set {
+            MonitorSet::Normal {
+                monitors,
+                primary_idx,
+                ..
+            } => {
+                // Named workspaces for disconnected outputs live on the primary monitor, same as
+                // other workspaces of disconnected outputs.
+                let mon_idx = ws_config
+                    .open_on_output
+                    .as_deref()
+                    .and_then(|name| monitors.iter().position(|mon| mon.output.name() == name))
+                    .unwrap_or(*primary_idx);
+                let mon = &mut monitors[mon_idx];
+
+                let ws = Workspace::new_with_config(
+                    mon.output.clone(),
+                    Some(ws_config.clone()),
+                    options,
+                );
+
+                mon.insert_workspace_before_last(ws);
+            }
+            MonitorSet::NoOutputs { workspaces } => {
+                let ws = Workspace::new_with_config_no_outputs(Some(ws_config.clone()), options);
+                workspaces.push(ws);
+            }
+        }
+    }
+
+    /// Applies a changed `open-on-output` to an existing named workspace.
+    fn update_named_workspace_output(&mut self, ws_config: &WorkspaceConfig) {
+        // Without an output in the config, the workspace stays where it is.
+        let Some(output_name) = ws_config.open_on_output.as_deref() else {
+            return;
+        };
+        let original_output = OutputId::from_name(output_name);
+        let is_named = |ws: &Workspace<W>| ws.name.as_deref() == Some(&ws_config.name);
+
+        match &mut self.monitor_set {
+            MonitorSet::Normal {
+                monitors,
+                primary_idx,
+                ..
+            } => {
+                let Some((src_idx, ws_idx)) = monitors.iter().enumerate().find_map(|(idx, mon)| {
+                    let ws_idx = mon.workspaces.iter().position(is_named)?;
+                    Some((idx, ws_idx))
+                }) else {
+                    return;
+                };
+
+                monitors[src_idx].workspaces[ws_idx].original_output = original_output;
+
+                // Same as new named workspaces, those for disconnected outputs live on the
+                // primary monitor.
+                let target_idx = monitors
+                    .iter()
+                    .position(|mon| mon.output.name() == output_name)
+                    .unwrap_or(*primary_idx);
+                if target_idx == src_idx {
+                    return;
+                }
+
+                let ws = monitors[src_idx].remove_workspace(ws_idx);
+                monitors[target_idx].insert_workspace_before_last(ws);
+            }
+            MonitorSet::NoOutputs { workspaces } => {
+                if let Some(ws) = workspaces.iter_mut().find(|ws| is_named(ws)) {
+                    ws.original_output = original_output;
+                }
+            }
+        }
+    }
+
+    /// Turns a named workspace into a regular one.
+    ///
+    /// The workspace is removed if it is empty and not in use.
+    pub fn unname_workspace(&mut self, workspace_name: &str) {
+        match &mut self.monitor_set {
+            MonitorSet::Normal { monitors, .. } => {
+                for mon in monitors {
+                    if let Some(ws) = mon
+                        .workspaces
+                        .iter_mut()
+                        .find(|ws| ws.name.as_deref() == Some(workspace_name))
+                    {
+                        ws.name = None;
+
+                        if mon.workspace_switch.is_none() {
+                            mon.clean_up_workspaces();
+                        }
+
+                        return;
+                    }
+                }
+            }
+            MonitorSet::NoOutputs { workspaces } => {
+                if let Some(idx) = workspaces
+                    .iter()
+                    .position(|ws| ws.name.as_deref() == Some(workspace_name))
+                {
+                    workspaces[idx].name = None;
+
+                    if !workspaces[idx].has_windows() {
+                        workspaces.remove(idx);
+                    }
+                }
+            }
+        }
+    }
+
     pub fn active_window(&self) -> Option<(&W, &Output)> {
+        if let Some(move_) = &self.interactive_move {
+            return Some((move_.tile.window(), &move_.output));
+        }
+
         let MonitorSet::Normal {
             monitors,
             active_monitor_idx,
@@ -824,13 +1261,8 @@ impl<W: LayoutElement> Layout<W> {
 
         let mon = &monitors[*active_monitor_idx];
         let ws = &mon.workspaces[mon.active_workspace_idx];
-
-        if ws.columns.is_empty() {
-            return None;
-        }
-
-        let col = &ws.columns[ws.active_column_idx];
-        Some((&col.tiles[col.active_tile_idx].window(), &mon.output))
+        let window = ws.focus()?;
+        Some((window, &mon.output))
     }
 
     pub fn windows_for_output(&self, output: &Output) -> impl Iterator<Item = &W> + '_ {
@@ -839,10 +1271,22 @@ impl<W: LayoutElement> Layout<W> {
         };
 
         let mon = monitors.iter().find(|mon| &mon.output == output).unwrap();
-        mon.workspaces.iter().flat_map(|ws| ws.windows())
+        let moving = self
+            .interactive_move
+            .as_ref()
+            .filter(|move_| &move_.output == output)
+            .map(|move_| move_.tile.window());
+        mon.workspaces
+            .iter()
+            .flat_map(|ws| ws.windows())
+            .chain(moving)
     }
 
     pub fn with_windows(&self, mut f: impl FnMut(&W, Option<&Output>)) {
+        if let Some(move_) = &self.interactive_move {
+            f(move_.tile.window(), Some(&move_.output));
+        }
+
         match &self.monitor_set {
             MonitorSet::Normal { monitors, .. } => {
                 for mon in monitors {
@@ -863,6 +1307,99 @@ impl<W: LayoutElement> Layout<W> {
         }
     }
 
+    /// Calls `f` for every window along with its location in the layout.
+    pub fn with_windows_and_locations(&self, mut f: impl FnMut(&W, WindowLocation<'_>)) {
+        if let Some(move_) = &self.interactive_move {
+            // The window being moved is shown on top of the active workspace of its output.
+            let workspace_idx = self
+                .monitor_for_output(&move_.output)
+                .map_or(0, |mon| mon.active_workspace_idx);
+            let location = WindowLocation {
+                output: Some(&move_.output),
+                workspace_idx,
+                column_and_tile_idx: None,
+            };
+            f(move_.tile.window(), location);
+        }
+
+        let mut visit = |ws: &Workspace<W>, output: Option<&Output>, workspace_idx: usize| {
+            for (column_idx, col) in ws.columns.iter().enumerate() {
+                for (tile_idx, tile) in col.tiles.iter().enumerate() {
+                    let location = WindowLocation {
+                        output,
+                        workspace_idx,
+                        column_and_tile_idx: Some((column_idx, tile_idx)),
+                    };
+                    f(tile.window(), location);
+                }
+            }
+
+            for win in ws.floating.windows() {
+                let location = WindowLocation {
+                    output,
+                    workspace_idx,
+                    column_and_tile_idx: None,
+                };
+                f(win, location);
+            }
+        };
+
+        match &self.monitor_set {
+            MonitorSet::Normal { monitors, .. } => {
+                for mon in monitors {
+                    for (idx, ws) in mon.workspaces.iter().enumerate() {
+                        visit(ws, Some(&mon.output), idx);
+                    }
+                }
+            }
+            MonitorSet::NoOutputs { workspaces } => {
+                for (idx, ws) in workspaces.iter().enumerate() {
+                    visit(ws, None, idx);
+                }
+            }
+        }
+    }
+
+    /// Returns information about all workspaces for IPC.
+    pub fn ipc_workspaces(&self) -> Vec<niri_ipc::Workspace> {
+        let ipc_idx = |idx: usize| u8::try_from(idx + 1).unwrap_or(u8::MAX);
+
+        match &self.monitor_set {
+            MonitorSet::Normal {
+                monitors,
+                active_monitor_idx,
+                ..
+            } => monitors
+                .iter()
+                .enumerate()
+                .flat_map(|(mon_idx, mon)| {
+                    let is_focused_mon = mon_idx == *active_monitor_idx;
+                    mon.workspaces
+                        .iter()
+                        .enumerate()
+                        .map(move |(idx, ws)| niri_ipc::Workspace {
+                            idx: ipc_idx(idx),
+                            name: ws.name.clone(),
+                            output: Some(mon.output.name()),
+                            is_active: idx == mon.active_workspace_idx,
+                            is_focused: is_focused_mon && idx == mon.active_workspace_idx,
+                        })
+                })
+                .collect(),
+            MonitorSet::NoOutputs { workspaces } => workspaces
+                .iter()
+                .enumerate()
+                .map(|(idx, ws)| niri_ipc::Workspace {
+                    idx: ipc_idx(idx),
+                    name: ws.name.clone(),
+                    output: None,
+                    is_active: false,
+                    is_focused: false,
+                })
+                .collect(),
+        }
+    }
+
     fn active_monitor(&mut self) -> Option<&mut Monitor<W>> {
         let MonitorSet::Normal {
             monitors,
@@ -1041,13 +1578,76 @@ impl<W: LayoutElement> Layout<W> {
         monitor.move_to_workspace(idx);
     }
 
-    pub fn move_column_to_workspace_up(&mut self) {
-        let Some(monitor) = self.active_monitor() else {
-            return;
-        };
-        monitor.move_column_to_workspace_up();
-    }
-
+    /// Moves the window to a workspace without changing the focus.
+    ///
+    /// The workspace is on `output`, or on the window's current output if `None`.
+    pub fn move_window_to_workspace(
+        &mut self,
+        window: &W,
+        output: Option<&Output>,
+        workspace_idx: usize,
+    ) {
+        if self
+            .interactive_move
+            .as_ref()
+            .map_or(false, |move_| move_.tile.window() == window)
+        {
+            return;
+        }
+
+        let MonitorSet::Normal { monitors, .. } = &mut self.monitor_set else {
+            return;
+        };
+
+        let source = monitors.iter().enumerate().find_map(|(mon_idx, mon)| {
+            mon.workspaces
+                .iter()
+                .position(|ws| ws.has_window(window))
+                .map(|ws_idx| (mon_idx, ws_idx))
+        });
+        let Some((mon_idx, ws_idx)) = source else {
+            return;
+        };
+
+        let new_mon_idx = match output {
+            Some(output) => {
+                let Some(idx) = monitors.iter().position(|mon| &mon.output == output) else {
+                    return;
+                };
+                idx
+            }
+            None => mon_idx,
+        };
+        let new_ws_idx = min(workspace_idx, monitors[new_mon_idx].workspaces.len() - 1);
+        if (new_mon_idx, new_ws_idx) == (mon_idx, ws_idx) {
+            return;
+        }
+
+        let ws = &mut monitors[mon_idx].workspaces[ws_idx];
+        if ws.floating.contains(window) {
+            let window = ws.remove_floating_window(window);
+            monitors[new_mon_idx].add_floating_window(new_ws_idx, window, false);
+        } else {
+            let column = ws.columns.iter().find(|col| col.contains(window)).unwrap();
+            let width = column.width;
+            let is_full_width = column.is_full_width;
+            let window = ws.remove_window(window);
+            monitors[new_mon_idx].add_window(new_ws_idx, window, false, width, is_full_width);
+        }
+
+        let mon = &mut monitors[mon_idx];
+        if mon.workspace_switch.is_none() {
+            mon.clean_up_workspaces();
+        }
+    }
+
+    pub fn move_column_to_workspace_up(&mut self) {
+        let Some(monitor) = self.active_monitor() else {
+            return;
+        };
+        monitor.move_column_to_workspace_up();
+    }
+
     pub fn move_column_to_workspace_down(&mut self) {
         let Some(monitor) = self.active_monitor() else {
             return;
@@ -1105,6 +1705,10 @@ impl<W: LayoutElement> Layout<W> {
     }
 
     pub fn focus(&self) -> Option<&W> {
+        if let Some(move_) = &self.interactive_move {
+            return Some(move_.tile.window());
+        }
+
         let MonitorSet::Normal {
             monitors,
             active_monitor_idx,
@@ -1139,6 +1743,24 @@ impl<W: LayoutElement> Layout<W> {
 
     #[cfg(test)]
     fn verify_invariants(&self) {
+        if let Some(move_) = &self.interactive_move {
+            let MonitorSet::Normal { monitors, .. } = &self.monitor_set else {
+                panic!("interactive move requires outputs");
+            };
+
+            assert!(
+                monitors.iter().any(|mon| mon.output == move_.output),
+                "interactive move output must exist"
+            );
+            assert!(
+                !monitors
+                    .iter()
+                    .flat_map(|mon| &mon.workspaces)
+                    .any(|ws| ws.has_window(move_.tile.window())),
+                "window being moved must not be on a workspace"
+            );
+        }
+
         let (monitors, &primary_idx, &active_monitor_idx) = match &self.monitor_set {
             MonitorSet::Normal {
                 monitors,
@@ -1148,8 +1770,8 @@ impl<W: LayoutElement> Layout<W> {
             MonitorSet::NoOutputs { workspaces } => {
                 for workspace in workspaces {
                     assert!(
-                        workspace.has_windows(),
-                        "with no outputs there cannot be empty workspaces"
+                        workspace.has_windows_or_name(),
+                        "with no outputs there cannot be empty unnamed workspaces"
                     );
 
                     assert_eq!(
@@ -1215,8 +1837,8 @@ impl<W: LayoutElement> Layout<W> {
             }
 
             assert!(
-                monitor.workspaces.last().unwrap().columns.is_empty(),
-                "monitor must have an empty workspace in the end"
+                !monitor.workspaces.last().unwrap().has_windows_or_name(),
+                "monitor must have an empty unnamed workspace in the end"
             );
 
             // If there's no workspace switch in progress, there can't be any non-last non-active
@@ -1225,8 +1847,8 @@ impl<W: LayoutElement> Layout<W> {
                 for (idx, ws) in monitor.workspaces.iter().enumerate().rev().skip(1) {
                     if idx != monitor.active_workspace_idx {
                         assert!(
-                            !ws.columns.is_empty(),
-                            "non-active workspace can't be empty except the last one"
+                            ws.has_windows_or_name(),
+                            "non-active workspace can't be empty and unnamed except the last one"
                         );
                     }
                 }
@@ -1249,6 +1871,10 @@ impl<W: LayoutElement> Layout<W> {
     pub fn advance_animations(&mut self, current_time: Duration) {
         let _span = tracy_client::span!("Layout::advance_animations");
 
+        if let Some(move_) = &mut self.interactive_move {
+            move_.tile.advance_animations(current_time, true);
+        }
+
         match &mut self.monitor_set {
             MonitorSet::Normal {
                 monitors,
@@ -1270,6 +1896,10 @@ impl<W: LayoutElement> Layout<W> {
     pub fn update_config(&mut self, config: &Config) {
         let options = Rc::new(Options::from_config(config));
 
+        if let Some(move_) = &mut self.interactive_move {
+            move_.tile.update_config(options.clone());
+        }
+
         match &mut self.monitor_set {
             MonitorSet::Normal { monitors, .. } => {
                 for mon in monitors {
@@ -1286,32 +1916,129 @@ impl<W: LayoutElement> Layout<W> {
         self.options = options;
     }
 
-    pub fn toggle_width(&mut self) {
+    /// Returns the workspace with the window, unless the window is being moved interactively.
+    fn workspace_with_window_mut(&mut self, window: &W) -> Option<&mut Workspace<W>> {
+        if self
+            .interactive_move
+            .as_ref()
+            .map_or(false, |move_| move_.tile.window() == window)
+        {
+            return None;
+        }
+
+        match &mut self.monitor_set {
+            MonitorSet::Normal { monitors, .. } => monitors
+                .iter_mut()
+                .flat_map(|mon| &mut mon.workspaces)
+                .find(|ws| ws.has_window(window)),
+            MonitorSet::NoOutputs { workspaces, .. } => {
+                workspaces.iter_mut().find(|ws| ws.has_window(window))
+            }
+        }
+    }
+
+    /// Switches the width of the window's column, or of the focused column if `None`, to the
+    /// next preset.
+    pub fn toggle_width(&mut self, window: Option<&W>) {
+        let Some(window) = window else {
+            let Some(monitor) = self.active_monitor() else {
+                return;
+            };
+            monitor.toggle_width();
+            return;
+        };
+
+        if let Some(ws) = self.workspace_with_window_mut(window) {
+            ws.toggle_width(Some(window));
+        }
+    }
+
+    /// Toggles full width of the window's column, or of the focused column if `None`.
+    pub fn toggle_full_width(&mut self, window: Option<&W>) {
+        let Some(window) = window else {
+            let Some(monitor) = self.active_monitor() else {
+                return;
+            };
+            monitor.toggle_full_width();
+            return;
+        };
+
+        if let Some(ws) = self.workspace_with_window_mut(window) {
+            ws.toggle_full_width(Some(window));
+        }
+    }
+
+    /// Changes the width of the window's column, or of the focused column if `None`.
+    pub fn set_column_width(&mut self, window: Option<&W>, change: SizeChange) {
+        let Some(window) = window else {
+            let Some(monitor) = self.active_monitor() else {
+                return;
+            };
+            monitor.set_column_width(change);
+            return;
+        };
+
+        if let Some(ws) = self.workspace_with_window_mut(window) {
+            ws.set_column_width(Some(window), change);
+        }
+    }
+
+    /// Changes the height of the window, or of the focused window if `None`.
+    pub fn set_window_height(&mut self, window: Option<&W>, change: SizeChange) {
+        let Some(window) = window else {
+            let Some(monitor) = self.active_monitor() else {
+                return;
+            };
+            monitor.set_window_height(change);
+            return;
+        };
+
+        if let Some(ws) = self.workspace_with_window_mut(window) {
+            ws.set_window_height(Some(window), change);
+        }
+    }
+
+    pub fn toggle_window_floating(&mut self) {
         let Some(monitor) = self.active_monitor() else {
             return;
         };
-        monitor.toggle_width();
+        monitor.toggle_window_floating();
     }
 
-    pub fn toggle_full_width(&mut self) {
+    pub fn switch_focus_floating_tiling(&mut self) {
         let Some(monitor) = self.active_monitor() else {
             return;
         };
-        monitor.toggle_full_width();
+        monitor.switch_focus_floating_tiling();
     }
 
-    pub fn set_column_width(&mut self, change: SizeChange) {
+    pub fn toggle_column_tabbed_display(&mut self) {
         let Some(monitor) = self.active_monitor() else {
             return;
         };
-        monitor.set_column_width(change);
+        monitor.toggle_column_tabbed_display();
     }
 
-    pub fn set_window_height(&mut self, change: SizeChange) {
+    pub fn toggle_overview(&mut self) {
         let Some(monitor) = self.active_monitor() else {
             return;
         };
-        monitor.set_window_height(change);
+        monitor.toggle_overview();
+    }
+
+    pub fn is_overview_open(&self, output: &Output) -> bool {
+        self.monitor_for_output(output)
+            .is_some_and(|mon| mon.is_overview_open())
+    }
+
+    pub fn close_overview(&mut self, output: &Output) {
+        let MonitorSet::Normal { monitors, .. } = &mut self.monitor_set else {
+            return;
+        };
+
+        if let Some(mon) = monitors.iter_mut().find(|mon| &mon.output == output) {
+            mon.set_overview_open(false);
+        }
     }
 
     pub fn focus_output(&mut self, output: &Output) {
@@ -1331,6 +2058,13 @@ impl<W: LayoutElement> Layout<W> {
     }
 
     pub fn move_to_output(&mut self, output: &Output) {
+        self.move_to_output_workspace(output, None);
+    }
+
+    /// Moves the active window to a workspace on another output.
+    ///
+    /// `None` means the active workspace of that output.
+    pub fn move_to_output_workspace(&mut self, output: &Output, workspace_idx: Option<usize>) {
         if let MonitorSet::Normal {
             monitors,
             active_monitor_idx,
@@ -1344,7 +2078,18 @@ impl<W: LayoutElement> Layout<W> {
 
             let current = &mut monitors[*active_monitor_idx];
             let ws = current.active_workspace();
-            if !ws.has_windows() {
+            if ws.floating_is_active {
+                let window = ws.remove_active_floating_window().unwrap();
+
+                let target = &mut monitors[new_idx];
+                let workspace_idx = workspace_idx.map_or(target.active_workspace_idx, |idx| {
+                    min(idx, target.workspaces.len() - 1)
+                });
+                target.add_floating_window(workspace_idx, window, true);
+                *active_monitor_idx = new_idx;
+                return;
+            }
+            if ws.columns.is_empty() {
                 return;
             }
             let column = &ws.columns[ws.active_column_idx];
@@ -1352,12 +2097,26 @@ impl<W: LayoutElement> Layout<W> {
             let is_full_width = column.is_full_width;
             let window = ws.remove_window_by_idx(ws.active_column_idx, column.active_tile_idx);
 
-            let workspace_idx = monitors[new_idx].active_workspace_idx;
+            let target = &monitors[new_idx];
+            let workspace_idx = workspace_idx.map_or(target.active_workspace_idx, |idx| {
+                min(idx, target.workspaces.len() - 1)
+            });
             self.add_window_by_idx(new_idx, workspace_idx, window, true, width, is_full_width);
         }
     }
 
     pub fn move_column_to_output(&mut self, output: &Output) {
+        self.move_column_to_output_workspace(output, None);
+    }
+
+    /// Moves the active column to a workspace on another output.
+    ///
+    /// `None` means the active workspace of that output.
+    pub fn move_column_to_output_workspace(
+        &mut self,
+        output: &Output,
+        workspace_idx: Option<usize>,
+    ) {
         if let MonitorSet::Normal {
             monitors,
             active_monitor_idx,
@@ -1371,12 +2130,15 @@ impl<W: LayoutElement> Layout<W> {
 
             let current = &mut monitors[*active_monitor_idx];
             let ws = current.active_workspace();
-            if !ws.has_windows() {
+            if ws.columns.is_empty() || ws.floating_is_active {
                 return;
             }
             let column = ws.remove_column_by_idx(ws.active_column_idx);
 
-            let workspace_idx = monitors[new_idx].active_workspace_idx;
+            let target = &monitors[new_idx];
+            let workspace_idx = workspace_idx.map_or(target.active_workspace_idx, |idx| {
+                min(idx, target.workspaces.len() - 1)
+            });
             self.add_column_by_idx(new_idx, workspace_idx, column, true);
         }
     }
@@ -1457,6 +2219,15 @@ impl<W: LayoutElement> Layout<W> {
     }
 
     pub fn set_fullscreen(&mut self, window: &W, is_fullscreen: bool) {
+        if self
+            .interactive_move
+            .as_ref()
+            .map_or(false, |move_| move_.tile.window() == window)
+        {
+            // FIXME: fullscreen the window once it's dropped.
+            return;
+        }
+
         match &mut self.monitor_set {
             MonitorSet::Normal { monitors, .. } => {
                 for mon in monitors {
@@ -1480,6 +2251,14 @@ impl<W: LayoutElement> Layout<W> {
     }
 
     pub fn toggle_fullscreen(&mut self, window: &W) {
+        if self
+            .interactive_move
+            .as_ref()
+            .map_or(false, |move_| move_.tile.window() == window)
+        {
+            return;
+        }
+
         match &mut self.monitor_set {
             MonitorSet::Normal { monitors, .. } => {
                 for mon in monitors {
@@ -1517,6 +2296,11 @@ impl<W: LayoutElement> Layout<W> {
                 continue;
             }
 
+            // All workspaces are already visible in the overview.
+            if monitor.overview.is_some() {
+                continue;
+            }
+
             let center_idx = monitor.active_workspace_idx;
             let current_idx = monitor
                 .workspace_switch
@@ -1540,8 +2324,7 @@ impl<W: LayoutElement> Layout<W> {
 
         for monitor in monitors {
             if let Some(WorkspaceSwitch::Gesture(gesture)) = &mut monitor.workspace_switch {
-                // Normalize like GNOME Shell's workspace switching.
-                let delta_y = delta_y / 400.;
+                let delta_y = delta_y / WORKSPACE_GESTURE_MOVEMENT;
 
                 let min = gesture.center_idx.saturating_sub(1) as f64;
                 let max = (gesture.center_idx + 1).min(monitor.workspaces.len() - 1) as f64;
@@ -1592,6 +2375,62 @@ impl<W: LayoutElement> Layout<W> {
         None
     }
 
+    pub fn view_offset_gesture_begin(&mut self, output: &Output) {
+        let monitors = match &mut self.monitor_set {
+            MonitorSet::Normal { monitors, .. } => monitors,
+            MonitorSet::NoOutputs { .. } => unreachable!(),
+        };
+
+        for monitor in monitors {
+            // The overview scales down the workspaces, so scrolling them doesn't make sense.
+            let is_target = &monitor.output == output && monitor.overview.is_none();
+
+            for (idx, ws) in monitor.workspaces.iter_mut().enumerate() {
+                // Cancel the gesture on other workspaces.
+                if !is_target || idx != monitor.active_workspace_idx {
+                    ws.view_offset_gesture_end(true);
+                    continue;
+                }
+
+                ws.view_offset_gesture_begin();
+            }
+        }
+    }
+
+    pub fn view_offset_gesture_update(&mut self, delta_x: f64) -> Option<Option<Output>> {
+        let monitors = match &mut self.monitor_set {
+            MonitorSet::Normal { monitors, .. } => monitors,
+            MonitorSet::NoOutputs { .. } => return None,
+        };
+
+        for monitor in monitors {
+            for ws in &mut monitor.workspaces {
+                if let Some(moved) = ws.view_offset_gesture_update(delta_x) {
+                    return Some(moved.then(|| monitor.output.clone()));
+                }
+            }
+        }
+
+        None
+    }
+
+    pub fn view_offset_gesture_end(&mut self, cancelled: bool) -> Option<Output> {
+        let monitors = match &mut self.monitor_set {
+            MonitorSet::Normal { monitors, .. } => monitors,
+            MonitorSet::NoOutputs { .. } => return None,
+        };
+
+        for monitor in monitors {
+            for ws in &mut monitor.workspaces {
+                if ws.view_offset_gesture_end(cancelled) {
+                    return Some(monitor.output.clone());
+                }
+            }
+        }
+
+        None
+    }
+
     pub fn move_workspace_down(&mut self) {
         let Some(monitor) = self.active_monitor() else {
             return;
@@ -1606,95 +2445,427 @@ impl<W: LayoutElement> Layout<W> {
         monitor.move_workspace_up();
     }
 
-    pub fn start_open_animation_for_window(&mut self, window: &W) {
-        match &mut self.monitor_set {
-            MonitorSet::Normal { monitors, .. } => {
-                for mon in monitors {
-                    for ws in &mut mon.workspaces {
-                        for col in &mut ws.columns {
-                            for tile in &mut col.tiles {
-                                if tile.window() == window {
-                                    tile.start_open_animation();
-                                    return;
-                                }
-                            }
-                        }
-                    }
-                }
-            }
-            MonitorSet::NoOutputs { workspaces, .. } => {
-                for ws in workspaces {
-                    if ws.has_window(window) {
-                        for col in &mut ws.columns {
-                            for tile in &mut col.tiles {
-                                if tile.window() == window {
-                                    tile.start_open_animation();
-                                    return;
-                                }
-                            }
-                        }
-                    }
-                }
-            }
+    /// Starts an interactive move of the window, grabbed at the given pointer position.
+    ///
+    /// Returns `false` if the window can't be moved.
+    pub fn interactive_move_begin(
+        &mut self,
+        window: &W,
+        output: &Output,
+        pointer_pos_within_output: Point<f64, Logical>,
+    ) -> bool {
+        if self.interactive_move.is_some() {
+            return false;
         }
-    }
-}
 
-impl Layout<Window> {
-    pub fn refresh(&self) {
-        let _span = tracy_client::span!("MonitorSet::refresh");
+        let MonitorSet::Normal { monitors, .. } = &mut self.monitor_set else {
+            return false;
+        };
 
-        match &self.monitor_set {
-            MonitorSet::Normal {
-                monitors,
-                active_monitor_idx,
-                ..
-            } => {
-                for (idx, mon) in monitors.iter().enumerate() {
-                    let is_active = idx == *active_monitor_idx;
-                    for ws in &mon.workspaces {
-                        ws.refresh(is_active);
-                    }
-                }
-            }
-            MonitorSet::NoOutputs { workspaces, .. } => {
-                for ws in workspaces {
-                    ws.refresh(false);
-                }
-            }
+        let Some(mon) = monitors.iter_mut().find(|mon| &mon.output == output) else {
+            return false;
+        };
+
+        // Only windows on a workspace that is fully visible can be grabbed.
+        if mon.workspace_switch.is_some() {
+            return false;
         }
-    }
-}
 
-impl<W: LayoutElement> Default for MonitorSet<W> {
-    fn default() -> Self {
-        Self::NoOutputs { workspaces: vec![] }
-    }
-}
+        // In the overview, the window can be on any workspace.
+        let Some((ws_idx, pointer_pos_within_ws)) = mon.workspace_under(pointer_pos_within_output)
+        else {
+            return false;
+        };
 
-#[cfg(test)]
-mod tests {
-    use std::cell::Cell;
+        let Some(tile_pos) = mon.workspaces[ws_idx].tile_position(window) else {
+            return false;
+        };
 
-    use proptest::prelude::*;
-    use proptest_derive::Arbitrary;
-    use smithay::output::{Mode, PhysicalProperties, Subpixel};
+        let ws = &mon.workspaces[ws_idx];
 
-    use super::*;
+        let is_floating = ws.floating.contains(window);
+        let (width, is_full_width) = if is_floating {
+            (ColumnWidth::Fixed(window.size().w), false)
+        } else {
+            let col = ws.columns.iter().find(|col| col.contains(window)).unwrap();
 
-    impl<W: LayoutElement> Default for Layout<W> {
-        fn default() -> Self {
-            Self::with_options(Default::default())
+            // Fullscreen windows stay in place.
+            if col.is_fullscreen {
+                return false;
+            }
+
+            (col.width, col.is_full_width)
+        };
+
+        if ws_idx != mon.active_workspace_idx {
+            mon.switch_workspace(ws_idx);
         }
+
+        let window = mon.active_workspace().remove_window(window);
+        window.output_enter(output);
+
+        self.interactive_move = Some(InteractiveMove {
+            tile: Tile::new(window, self.options.clone()),
+            output: output.clone(),
+            pointer_pos_within_output,
+            pointer_pos_within_tile: pointer_pos_within_ws - tile_pos.to_f64(),
+            width,
+            is_full_width,
+            is_floating,
+        });
+        self.update_insert_hints();
+
+        true
     }
 
-    #[derive(Debug)]
-    struct TestWindowInner {
-        id: usize,
-        bbox: Cell<Rectangle<i32, Logical>>,
-        initial_bbox: Rectangle<i32, Logical>,
-        requested_size: Cell<Option<Size<i32, Logical>>>,
-        min_size: Size<i32, Logical>,
+    /// Updates the pointer position of an interactive move.
+    ///
+    /// Returns `false` if this window is not being moved.
+    pub fn interactive_move_update(
+        &mut self,
+        window: &W,
+        output: &Output,
+        pointer_pos_within_output: Point<f64, Logical>,
+    ) -> bool {
+        let Some(move_) = &mut self.interactive_move else {
+            return false;
+        };
+
+        if move_.tile.window() != window {
+            return false;
+        }
+
+        if &move_.output != output {
+            move_.tile.window().output_leave(&move_.output);
+            move_.tile.window().output_enter(output);
+            move_.output = output.clone();
+        }
+
+        move_.pointer_pos_within_output = pointer_pos_within_output;
+        self.update_insert_hints();
+
+        true
+    }
+
+    /// Ends an interactive move, dropping the window under the pointer.
+    pub fn interactive_move_end(&mut self, window: &W) {
+        if self
+            .interactive_move
+            .as_ref()
+            .map_or(false, |move_| move_.tile.window() == window)
+        {
+            self.finish_interactive_move();
+        }
+    }
+
+    fn finish_interactive_move(&mut self) {
+        let Some(move_) = self.interactive_move.take() else {
+            return;
+        };
+        self.update_insert_hints();
+
+        let InteractiveMove {
+            tile,
+            output,
+            pointer_pos_within_output,
+            pointer_pos_within_tile,
+            width,
+            is_full_width,
+            is_floating,
+        } = move_;
+
+        let window = tile.into_window();
+        window.output_leave(&output);
+
+        let MonitorSet::Normal {
+            monitors,
+            active_monitor_idx,
+            ..
+        } = &mut self.monitor_set
+        else {
+            // Moves only happen with outputs, and they are finished before the last output goes.
+            unreachable!()
+        };
+
+        let mon_idx = monitors
+            .iter()
+            .position(|mon| mon.output == output)
+            .unwrap_or(*active_monitor_idx);
+        *active_monitor_idx = mon_idx;
+
+        let mon = &mut monitors[mon_idx];
+        let (ws_idx, pointer_pos_within_ws) = mon
+            .workspace_under(pointer_pos_within_output)
+            .unwrap_or((mon.active_workspace_idx, pointer_pos_within_output));
+        let tile_pos = (pointer_pos_within_ws - pointer_pos_within_tile).to_i32_round();
+
+        if is_floating {
+            mon.add_floating_window_at(ws_idx, window, Some(tile_pos), true);
+        } else {
+            let position = mon.workspaces[ws_idx].insert_position(pointer_pos_within_ws);
+            mon.add_window_at(ws_idx, position, window, true, width, is_full_width);
+        }
+
+        // Activating the workspace in the overview can clean up others and shift the indices.
+        let ws_idx = mon.active_workspace_idx;
+        mon.workspaces[ws_idx].animate_focus_move_from(tile_pos);
+    }
+
+    fn update_insert_hints(&mut self) {
+        let MonitorSet::Normal { monitors, .. } = &mut self.monitor_set else {
+            return;
+        };
+
+        for mon in monitors.iter_mut() {
+            for ws in &mut mon.workspaces {
+                ws.set_insert_hint(None);
+            }
+        }
+
+        let Some(move_) = &self.interactive_move else {
+            return;
+        };
+
+        // Floating windows are dropped as is.
+        if move_.is_floating {
+            return;
+        }
+
+        if let Some(mon) = monitors.iter_mut().find(|mon| mon.output == move_.output) {
+            if let Some((ws_idx, pos)) = mon.workspace_under(move_.pointer_pos_within_output) {
+                let ws = &mut mon.workspaces[ws_idx];
+                let position = ws.insert_position(pos);
+                ws.set_insert_hint(Some(position));
+            }
+        }
+    }
+
+    /// Returns the edges to interactively resize the window under the pointer from.
+    ///
+    /// The edges are picked depending on which part of the window the pointer is in.
+    pub fn resize_edges_under(
+        &self,
+        output: &Output,
+        pointer_pos_within_output: Point<f64, Logical>,
+    ) -> Option<ResizeEdge> {
+        let MonitorSet::Normal { monitors, .. } = &self.monitor_set else {
+            return None;
+        };
+
+        let mon = monitors.iter().find(|mon| &mon.output == output)?;
+        if mon.workspace_switch.is_some() || mon.overview.is_some() {
+            return None;
+        }
+
+        let (window, _) = mon.window_under(pointer_pos_within_output)?;
+        let ws = &mon.workspaces[mon.active_workspace_idx];
+        let geo = ws.window_geometry(window)?;
+
+        let size = geo.size.to_f64();
+        let pos = pointer_pos_within_output - geo.loc.to_f64();
+
+        let mut edges = if pos.x < size.w / 2. {
+            ResizeEdge::LEFT
+        } else {
+            ResizeEdge::RIGHT
+        };
+        if pos.y < size.h / 3. {
+            edges |= ResizeEdge::TOP;
+        } else if pos.y > size.h * 2. / 3. {
+            edges |= ResizeEdge::BOTTOM;
+        }
+
+        Some(edges)
+    }
+
+    /// Starts an interactive resize of the window from the given edges.
+    ///
+    /// Returns `false` if the window can't be resized.
+    pub fn interactive_resize_begin(&mut self, window: W, edges: ResizeEdge) -> bool {
+        match &mut self.monitor_set {
+            MonitorSet::Normal { monitors, .. } => {
+                for mon in monitors {
+                    for ws in &mut mon.workspaces {
+                        if ws.has_window(&window) {
+                            return ws.interactive_resize_begin(window, edges);
+                        }
+                    }
+                }
+            }
+            MonitorSet::NoOutputs { workspaces, .. } => {
+                for ws in workspaces {
+                    if ws.has_window(&window) {
+                        return ws.interactive_resize_begin(window, edges);
+                    }
+                }
+            }
+        }
+
+        false
+    }
+
+    /// Updates an interactive resize with the pointer moved by `delta` since the start.
+    ///
+    /// Returns `false` if this window is not being resized.
+    pub fn interactive_resize_update(&mut self, window: &W, delta: Point<f64, Logical>) -> bool {
+        match &mut self.monitor_set {
+            MonitorSet::Normal { monitors, .. } => {
+                for mon in monitors {
+                    for ws in &mut mon.workspaces {
+                        if ws.has_window(window) {
+                            return ws.interactive_resize_update(window, delta);
+                        }
+                    }
+                }
+            }
+            MonitorSet::NoOutputs { workspaces, .. } => {
+                for ws in workspaces {
+                    if ws.has_window(window) {
+                        return ws.interactive_resize_update(window, delta);
+                    }
+                }
+            }
+        }
+
+        false
+    }
+
+    pub fn interactive_resize_end(&mut self, window: &W) {
+        match &mut self.monitor_set {
+            MonitorSet::Normal { monitors, .. } => {
+                for mon in monitors {
+                    for ws in &mut mon.workspaces {
+                        if ws.has_window(window) {
+                            ws.interactive_resize_end(window);
+                            return;
+                        }
+                    }
+                }
+            }
+            MonitorSet::NoOutputs { workspaces, .. } => {
+                for ws in workspaces {
+                    if ws.has_window(window) {
+                        ws.interactive_resize_end(window);
+                        return;
+                    }
+                }
+            }
+        }
+    }
+
+    pub fn render_interactive_move_for_output<R: NiriRenderer>(
+        &self,
+        renderer: &mut R,
+        output: &Output,
+    ) -> impl Iterator<Item = TileRenderElement<R>> {
+        let scale = Scale::from(output.current_scale().fractional_scale());
+
+        let elements = self
+            .interactive_move
+            .as_ref()
+            .filter(|move_| &move_.output == output)
+            .map(|move_| {
+                let loc = move_.pointer_pos_within_output - move_.pointer_pos_within_tile;
+                move_.tile.render(renderer, loc.to_i32_round(), scale, true)
+            });
+        elements.into_iter().flatten()
+    }
+
+    pub fn start_open_animation_for_window(&mut self, window: &W) {
+        match &mut self.monitor_set {
+            MonitorSet::Normal { monitors, .. } => {
+                for mon in monitors {
+                    for ws in &mut mon.workspaces {
+                        if ws.start_open_animation(window) {
+                            return;
+                        }
+                    }
+                }
+            }
+            MonitorSet::NoOutputs { workspaces, .. } => {
+                for ws in workspaces {
+                    if ws.start_open_animation(window) {
+                        return;
+                    }
+                }
+            }
+        }
+    }
+}
+
+impl Layout<Window> {
+    pub fn find_window_by_id(&self, id: WindowId) -> Option<Window> {
+        let mut found = None;
+        self.with_windows(|window, _| {
+            if found.is_none() && WindowId::of(window) == id {
+                found = Some(window.clone());
+            }
+        });
+        found
+    }
+
+    pub fn refresh(&self) {
+        let _span = tracy_client::span!("MonitorSet::refresh");
+
+        match &self.monitor_set {
+            MonitorSet::Normal {
+                monitors,
+                active_monitor_idx,
+                ..
+            } => {
+                for (idx, mon) in monitors.iter().enumerate() {
+                    // While a window is being moved, it is the only active one.
+                    let is_active = idx == *active_monitor_idx && self.interactive_move.is_none();
+                    for ws in &mon.workspaces {
+                        ws.refresh(is_active);
+                    }
+                }
+            }
+            MonitorSet::NoOutputs { workspaces, .. } => {
+                for ws in workspaces {
+                    ws.refresh(false);
+                }
+            }
+        }
+
+        if let Some(move_) = &self.interactive_move {
+            let win = move_.tile.window();
+            win.set_activated(true);
+            win.toplevel().send_pending_configure();
+            win.refresh();
+        }
+    }
+}
+
+impl<W: LayoutElement> Default for MonitorSet<W> {
+    fn default() -> Self {
+        Self::NoOutputs { workspaces: vec![] }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::Cell;
+
+    use proptest::prelude::*;
+    use proptest_derive::Arbitrary;
+    use smithay::output::{Mode, PhysicalProperties, Subpixel};
+
+    use super::*;
+
+    impl<W: LayoutElement> Default for Layout<W> {
+        fn default() -> Self {
+            Self::with_options(Default::default())
+        }
+    }
+
+    #[derive(Debug)]
+    struct TestWindowInner {
+        id: usize,
+        bbox: Cell<Rectangle<i32, Logical>>,
+        initial_bbox: Rectangle<i32, Logical>,
+        requested_size: Cell<Option<Size<i32, Logical>>>,
+        min_size: Size<i32, Logical>,
         max_size: Size<i32, Logical>,
         pending_fullscreen: Cell<bool>,
     }
@@ -1788,11 +2959,15 @@ mod tests {
             self.0.max_size
         }
 
+        fn rules(&self) -> ResolvedWindowRules {
+            ResolvedWindowRules::default()
+        }
+
         fn is_wl_surface(&self, _wl_surface: &WlSurface) -> bool {
             false
         }
 
-        fn set_preferred_scale_transform(&self, _scale: i32, _transform: Transform) {}
+        fn set_preferred_scale_transform(&self, _scale: output::Scale, _transform: Transform) {}
 
         fn has_ssd(&self) -> bool {
             false
@@ -1848,6 +3023,10 @@ mod tests {
         })
     }
 
+    fn arbitrary_resize_edge() -> impl Strategy<Value = ResizeEdge> {
+        (0u32..16).prop_map(ResizeEdge::from_bits_truncate)
+    }
+
     #[derive(Debug, Clone, Copy, Arbitrary)]
     enum Op {
         AddOutput(#[proptest(strategy = "1..=5usize")] usize),
@@ -1871,6 +3050,26 @@ mod tests {
             #[proptest(strategy = "arbitrary_min_max_size()")]
             min_max_size: (Size<i32, Logical>, Size<i32, Logical>),
         },
+        AddWindowToNamedWorkspace {
+            #[proptest(strategy = "1..=5usize")]
+            id: usize,
+            #[proptest(strategy = "1..=5usize")]
+            ws_name: usize,
+            #[proptest(strategy = "arbitrary_bbox()")]
+            bbox: Rectangle<i32, Logical>,
+            #[proptest(strategy = "arbitrary_min_max_size()")]
+            min_max_size: (Size<i32, Logical>, Size<i32, Logical>),
+        },
+        AddNamedWorkspace {
+            #[proptest(strategy = "1..=5usize")]
+            ws_name: usize,
+            #[proptest(strategy = "prop::option::of(1..=5usize)")]
+            output_name: Option<usize>,
+        },
+        UnnameWorkspace {
+            #[proptest(strategy = "1..=5usize")]
+            ws_name: usize,
+        },
         CloseWindow(#[proptest(strategy = "1..=5usize")] usize),
         FullscreenWindow(#[proptest(strategy = "1..=5usize")] usize),
         FocusColumnLeft,
@@ -1911,20 +3110,98 @@ mod tests {
         MaximizeColumn,
         SetColumnWidth(#[proptest(strategy = "arbitrary_size_change()")] SizeChange),
         SetWindowHeight(#[proptest(strategy = "arbitrary_size_change()")] SizeChange),
+        SetColumnWidthById {
+            #[proptest(strategy = "1..=5usize")]
+            id: usize,
+            #[proptest(strategy = "arbitrary_size_change()")]
+            change: SizeChange,
+        },
+        SetWindowHeightById {
+            #[proptest(strategy = "1..=5usize")]
+            id: usize,
+            #[proptest(strategy = "arbitrary_size_change()")]
+            change: SizeChange,
+        },
+        FocusWindow(#[proptest(strategy = "1..=5usize")] usize),
+        MoveWindowToWorkspaceById {
+            #[proptest(strategy = "1..=5usize")]
+            id: usize,
+            #[proptest(strategy = "prop::option::of(1..=5u8)")]
+            output: Option<u8>,
+            #[proptest(strategy = "0..=4usize")]
+            workspace_idx: usize,
+        },
+        ToggleWindowFloating,
+        SwitchFocusFloatingTiling,
+        ToggleColumnTabbedDisplay,
+        ToggleOverview,
         Communicate(#[proptest(strategy = "1..=5usize")] usize),
         MoveWorkspaceToOutput(#[proptest(strategy = "1..=5u8")] u8),
-    }
-
-    impl Op {
-        fn apply(self, layout: &mut Layout<TestWindow>) {
-            match self {
-                Op::AddOutput(id) => {
-                    let name = format!("output{id}");
-                    if layout.outputs().any(|o| o.name() == name) {
-                        return;
-                    }
-
-                    let output = Output::new(
+        InteractiveMoveBegin {
+            #[proptest(strategy = "1..=5usize")]
+            window: usize,
+            #[proptest(strategy = "1..=5usize")]
+            output_idx: usize,
+            #[proptest(strategy = "-20000f64..20000f64")]
+            px: f64,
+            #[proptest(strategy = "-20000f64..20000f64")]
+            py: f64,
+        },
+        InteractiveMoveUpdate {
+            #[proptest(strategy = "1..=5usize")]
+            window: usize,
+            #[proptest(strategy = "1..=5usize")]
+            output_idx: usize,
+            #[proptest(strategy = "-20000f64..20000f64")]
+            px: f64,
+            #[proptest(strategy = "-20000f64..20000f64")]
+            py: f64,
+        },
+        InteractiveMoveEnd {
+            #[proptest(strategy = "1..=5usize")]
+            window: usize,
+        },
+        InteractiveResizeBegin {
+            #[proptest(strategy = "1..=5usize")]
+            window: usize,
+            #[proptest(strategy = "arbitrary_resize_edge()")]
+            edges: ResizeEdge,
+        },
+        InteractiveResizeUpdate {
+            #[proptest(strategy = "1..=5usize")]
+            window: usize,
+            #[proptest(strategy = "-20000f64..20000f64")]
+            dx: f64,
+            #[proptest(strategy = "-20000f64..20000f64")]
+            dy: f64,
+        },
+        InteractiveResizeEnd {
+            #[proptest(strategy = "1..=5usize")]
+            window: usize,
+        },
+        ViewOffsetGestureBegin {
+            #[proptest(strategy = "1..=5usize")]
+            output_idx: usize,
+        },
+        ViewOffsetGestureUpdate {
+            #[proptest(strategy = "-2000f64..2000f64")]
+            delta: f64,
+        },
+        ViewOffsetGestureEnd {
+            cancelled: bool,
+        },
+    }
+
+    impl Op {
+        fn apply(self, layout: &mut Layout<TestWindow>) {
+            match self {
+                Op::AddOutput(id) => {
+                    let name = format!("output{id}");
+                    if layout.outputs().any(|o| o.name() == name) {
+                        return;
+                    }
+
+                    let output = Output::new(
                         name,
                         PhysicalProperties {
                             size: Size::from((1280, 720)),
@@ -1965,6 +3242,12 @@ mod tests {
                     bbox,
                     min_max_size,
                 } => {
+                    if let Some(move_) = &layout.interactive_move {
+                        if move_.tile.window().0.id == id {
+                            return;
+                        }
+                    }
+
                     match &mut layout.monitor_set {
                         MonitorSet::Normal { monitors, .. } => {
                             for mon in monitors {
@@ -1999,6 +3282,16 @@ mod tests {
                 } => {
                     let mut found_right_of = false;
 
+                    if let Some(move_) = &layout.interactive_move {
+                        if move_.tile.window().0.id == id {
+                            return;
+                        }
+
+                        if move_.tile.window().0.id == right_of_id {
+                            found_right_of = true;
+                        }
+                    }
+
                     match &mut layout.monitor_set {
                         MonitorSet::Normal { monitors, .. } => {
                             for mon in monitors {
@@ -2043,6 +3336,61 @@ mod tests {
                     let win = TestWindow::new(id, bbox, min_max_size.0, min_max_size.1);
                     layout.add_window_right_of(&right_of_win, win, None, false);
                 }
+                Op::AddWindowToNamedWorkspace {
+                    id,
+                    ws_name,
+                    bbox,
+                    min_max_size,
+                } => {
+                    let ws_name = format!("ws{ws_name}");
+                    if layout.find_workspace_by_name(&ws_name).is_none() {
+                        return;
+                    }
+
+                    if let Some(move_) = &layout.interactive_move {
+                        if move_.tile.window().0.id == id {
+                            return;
+                        }
+                    }
+
+                    match &mut layout.monitor_set {
+                        MonitorSet::Normal { monitors, .. } => {
+                            for mon in monitors {
+                                for ws in &mut mon.workspaces {
+                                    for win in ws.windows() {
+                                        if win.0.id == id {
+                                            return;
+                                        }
+                                    }
+                                }
+                            }
+                        }
+                        MonitorSet::NoOutputs { workspaces, .. } => {
+                            for ws in workspaces {
+                                for win in ws.windows() {
+                                    if win.0.id == id {
+                                        return;
+                                    }
+                                }
+                            }
+                        }
+                    }
+
+                    let win = TestWindow::new(id, bbox, min_max_size.0, min_max_size.1);
+                    layout.add_window_to_named_workspace(&ws_name, win, None, false);
+                }
+                Op::AddNamedWorkspace {
+                    ws_name,
+                    output_name,
+                } => {
+                    layout.ensure_named_workspace(&WorkspaceConfig {
+                        name: format!("ws{ws_name}"),
+                        open_on_output: output_name.map(|n| format!("output{n}")),
+                    });
+                }
+                Op::UnnameWorkspace { ws_name } => {
+                    layout.unname_workspace(&format!("ws{ws_name}"));
+                }
                 Op::CloseWindow(id) => {
                     let dummy =
                         TestWindow::new(id, Rectangle::default(), Size::default(), Size::default());
@@ -2101,10 +3449,52 @@ mod tests {
                 }
                 Op::MoveWorkspaceDown => layout.move_workspace_down(),
                 Op::MoveWorkspaceUp => layout.move_workspace_up(),
-                Op::SwitchPresetColumnWidth => layout.toggle_width(),
-                Op::MaximizeColumn => layout.toggle_full_width(),
-                Op::SetColumnWidth(change) => layout.set_column_width(change),
-                Op::SetWindowHeight(change) => layout.set_window_height(change),
+                Op::SwitchPresetColumnWidth => layout.toggle_width(None),
+                Op::MaximizeColumn => layout.toggle_full_width(None),
+                Op::SetColumnWidth(change) => layout.set_column_width(None, change),
+                Op::SetColumnWidthById { id, change } => {
+                    let dummy =
+                        TestWindow::new(id, Rectangle::default(), Size::default(), Size::default());
+                    layout.set_column_width(Some(&dummy), change);
+                }
+                Op::SetWindowHeight(change) => layout.set_window_height(None, change),
+                Op::SetWindowHeightById { id, change } => {
+                    let dummy =
+                        TestWindow::new(id, Rectangle::default(), Size::default(), Size::default());
+                    layout.set_window_height(Some(&dummy), change);
+                }
+                Op::FocusWindow(id) => {
+                    let dummy =
+                        TestWindow::new(id, Rectangle::default(), Size::default(), Size::default());
+                    if matches!(layout.monitor_set, MonitorSet::Normal { .. }) {
+                        layout.activate_window(&dummy);
+                    }
+                }
+                Op::MoveWindowToWorkspaceById {
+                    id,
+                    output,
+                    workspace_idx,
+                } => {
+                    let output = match output {
+                        Some(id) => {
+                            let name = format!("output{id}");
+                            let Some(output) = layout.outputs().find(|o| o.name() == name).cloned()
+                            else {
+                                return;
+                            };
+                            Some(output)
+                        }
+                        None => None,
+                    };
+
+                    let dummy =
+                        TestWindow::new(id, Rectangle::default(), Size::default(), Size::default());
+                    layout.move_window_to_workspace(&dummy, output.as_ref(), workspace_idx);
+                }
+                Op::ToggleWindowFloating => layout.toggle_window_floating(),
+                Op::SwitchFocusFloatingTiling => layout.switch_focus_floating_tiling(),
+                Op::ToggleColumnTabbedDisplay => layout.toggle_column_tabbed_display(),
+                Op::ToggleOverview => layout.toggle_overview(),
                 Op::Communicate(id) => {
                     let mut window = None;
                     match &mut layout.monitor_set {
@@ -2148,6 +3538,94 @@ mod tests {
 
                     layout.move_workspace_to_output(&output);
                 }
+                Op::InteractiveMoveBegin {
+                    window,
+                    output_idx,
+                    px,
+                    py,
+                } => {
+                    let name = format!("output{output_idx}");
+                    let Some(output) = layout.outputs().find(|o| o.name() == name).cloned() else {
+                        return;
+                    };
+
+                    let dummy = TestWindow::new(
+                        window,
+                        Rectangle::default(),
+                        Size::default(),
+                        Size::default(),
+                    );
+                    layout.interactive_move_begin(&dummy, &output, Point::from((px, py)));
+                }
+                Op::InteractiveMoveUpdate {
+                    window,
+                    output_idx,
+                    px,
+                    py,
+                } => {
+                    let name = format!("output{output_idx}");
+                    let Some(output) = layout.outputs().find(|o| o.name() == name).cloned() else {
+                        return;
+                    };
+
+                    let dummy = TestWindow::new(
+                        window,
+                        Rectangle::default(),
+                        Size::default(),
+                        Size::default(),
+                    );
+                    layout.interactive_move_update(&dummy, &output, Point::from((px, py)));
+                }
+                Op::InteractiveMoveEnd { window } => {
+                    let dummy = TestWindow::new(
+                        window,
+                        Rectangle::default(),
+                        Size::default(),
+                        Size::default(),
+                    );
+                    layout.interactive_move_end(&dummy);
+                }
+                Op::InteractiveResizeBegin { window, edges } => {
+                    let dummy = TestWindow::new(
+                        window,
+                        Rectangle::default(),
+                        Size::default(),
+                        Size::default(),
+                    );
+                    layout.interactive_resize_begin(dummy, edges);
+                }
+                Op::InteractiveResizeUpdate { window, dx, dy } => {
+                    let dummy = TestWindow::new(
+                        window,
+                        Rectangle::default(),
+                        Size::default(),
+                        Size::default(),
+                    );
+                    layout.interactive_resize_update(&dummy, Point::from((dx, dy)));
+                }
+                Op::InteractiveResizeEnd { window } => {
+                    let dummy = TestWindow::new(
+                        window,
+                        Rectangle::default(),
+                        Size::default(),
+                        Size::default(),
+                    );
+                    layout.interactive_resize_end(&dummy);
+                }
+                Op::ViewOffsetGestureBegin { output_idx } => {
+                    let name = format!("output{output_idx}");
+                    let Some(output) = layout.outputs().find(|o| o.name() == name).cloned() else {
+                        return;
+                    };
+
+                    layout.view_offset_gesture_begin(&output);
+                }
+                Op::ViewOffsetGestureUpdate { delta } => {
+                    layout.view_offset_gesture_update(delta);
+                }
+                Op::ViewOffsetGestureEnd { cancelled } => {
+                    layout.view_offset_gesture_end(cancelled);
+                }
             }
         }
     }
@@ -2210,12 +3688,58 @@ mod tests {
                 bbox: Rectangle::from_loc_and_size((0, 0), (100, 200)),
                 min_max_size: Default::default(),
             },
+            Op::AddNamedWorkspace {
+                ws_name: 1,
+                output_name: None,
+            },
+            Op::AddNamedWorkspace {
+                ws_name: 2,
+                output_name: Some(1),
+            },
+            Op::AddNamedWorkspace {
+                ws_name: 3,
+                output_name: Some(2),
+            },
+            Op::AddWindowToNamedWorkspace {
+                id: 5,
+                ws_name: 1,
+                bbox: Rectangle::from_loc_and_size((0, 0), (100, 200)),
+                min_max_size: Default::default(),
+            },
+            Op::AddWindowToNamedWorkspace {
+                id: 6,
+                ws_name: 2,
+                bbox: Rectangle::from_loc_and_size((0, 0), (100, 200)),
+                min_max_size: Default::default(),
+            },
+            Op::UnnameWorkspace { ws_name: 1 },
+            Op::UnnameWorkspace { ws_name: 3 },
             Op::CloseWindow(0),
             Op::CloseWindow(1),
             Op::CloseWindow(2),
             Op::FullscreenWindow(1),
             Op::FullscreenWindow(2),
             Op::FullscreenWindow(3),
+            Op::FocusWindow(0),
+            Op::FocusWindow(3),
+            Op::MoveWindowToWorkspaceById {
+                id: 0,
+                output: None,
+                workspace_idx: 1,
+            },
+            Op::MoveWindowToWorkspaceById {
+                id: 1,
+                output: Some(2),
+                workspace_idx: 0,
+            },
+            Op::SetWindowHeightById {
+                id: 0,
+                change: SizeChange::SetFixed(100),
+            },
+            Op::SetColumnWidthById {
+                id: 1,
+                change: SizeChange::SetFixed(300),
+            },
             Op::FocusColumnLeft,
             Op::FocusColumnRight,
             Op::FocusWindowUp,
@@ -2249,6 +3773,38 @@ mod tests {
             Op::ConsumeOrExpelWindowLeft,
             Op::ConsumeOrExpelWindowRight,
             Op::MoveWorkspaceToOutput(1),
+            Op::ToggleWindowFloating,
+            Op::SwitchFocusFloatingTiling,
+            Op::ToggleColumnTabbedDisplay,
+            Op::ToggleOverview,
+            Op::InteractiveMoveBegin {
+                window: 1,
+                output_idx: 1,
+                px: 0.,
+                py: 0.,
+            },
+            Op::InteractiveMoveUpdate {
+                window: 1,
+                output_idx: 1,
+                px: 600.,
+                py: 0.,
+            },
+            Op::InteractiveMoveEnd { window: 1 },
+            Op::InteractiveResizeBegin {
+                window: 1,
+                edges: ResizeEdge::BOTTOM_RIGHT,
+            },
+            Op::InteractiveResizeUpdate {
+                window: 1,
+                dx: 100.,
+                dy: 50.,
+            },
+            Op::InteractiveResizeEnd { window: 1 },
+            Op::ViewOffsetGestureBegin { output_idx: 1 },
+            Op::ViewOffsetGestureUpdate { delta: -500. },
+            Op::ViewOffsetGestureUpdate { delta: 3000. },
+            Op::ViewOffsetGestureEnd { cancelled: false },
+            Op::ViewOffsetGestureEnd { cancelled: true },
         ];
 
         for third in every_op {
@@ -2355,12 +3911,47 @@ mod tests {
                 bbox: Rectangle::from_loc_and_size((0, 0), (100, 200)),
                 min_max_size: Default::default(),
             },
+            Op::AddNamedWorkspace {
+                ws_name: 1,
+                output_name: None,
+            },
+            Op::AddNamedWorkspace {
+                ws_name: 2,
+                output_name: Some(2),
+            },
+            Op::AddWindowToNamedWorkspace {
+                id: 8,
+                ws_name: 1,
+                bbox: Rectangle::from_loc_and_size((0, 0), (100, 200)),
+                min_max_size: Default::default(),
+            },
+            Op::UnnameWorkspace { ws_name: 1 },
             Op::CloseWindow(0),
             Op::CloseWindow(1),
             Op::CloseWindow(2),
             Op::FullscreenWindow(1),
             Op::FullscreenWindow(2),
             Op::FullscreenWindow(3),
+            Op::FocusWindow(0),
+            Op::FocusWindow(3),
+            Op::MoveWindowToWorkspaceById {
+                id: 0,
+                output: None,
+                workspace_idx: 1,
+            },
+            Op::MoveWindowToWorkspaceById {
+                id: 1,
+                output: Some(2),
+                workspace_idx: 0,
+            },
+            Op::SetWindowHeightById {
+                id: 0,
+                change: SizeChange::SetFixed(100),
+            },
+            Op::SetColumnWidthById {
+                id: 1,
+                change: SizeChange::SetFixed(300),
+            },
             Op::FocusColumnLeft,
             Op::FocusColumnRight,
             Op::FocusWindowUp,
@@ -2393,6 +3984,38 @@ mod tests {
             Op::MoveWindowUpOrToWorkspaceUp,
             Op::ConsumeOrExpelWindowLeft,
             Op::ConsumeOrExpelWindowRight,
+            Op::ToggleWindowFloating,
+            Op::SwitchFocusFloatingTiling,
+            Op::ToggleColumnTabbedDisplay,
+            Op::ToggleOverview,
+            Op::InteractiveMoveBegin {
+                window: 1,
+                output_idx: 1,
+                px: 0.,
+                py: 0.,
+            },
+            Op::InteractiveMoveUpdate {
+                window: 1,
+                output_idx: 1,
+                px: 600.,
+                py: 0.,
+            },
+            Op::InteractiveMoveEnd { window: 1 },
+            Op::InteractiveResizeBegin {
+                window: 1,
+                edges: ResizeEdge::BOTTOM_RIGHT,
+            },
+            Op::InteractiveResizeUpdate {
+                window: 1,
+                dx: 100.,
+                dy: 50.,
+            },
+            Op::InteractiveResizeEnd { window: 1 },
+            Op::ViewOffsetGestureBegin { output_idx: 1 },
+            Op::ViewOffsetGestureUpdate { delta: -500. },
+            Op::ViewOffsetGestureUpdate { delta: 3000. },
+            Op::ViewOffsetGestureEnd { cancelled: false },
+            Op::ViewOffsetGestureEnd { cancelled: true },
         ];
 
         for third in every_op {
@@ -2508,6 +4131,43 @@ mod tests {
         assert!(monitors[0].workspaces[0].has_windows());
     }
 
+    #[test]
+    fn move_window_to_workspace_by_id_keeps_focus() {
+        let ops = [
+            Op::AddOutput(1),
+            Op::AddWindow {
+                id: 0,
+                bbox: Rectangle::from_loc_and_size((0, 0), (100, 200)),
+                min_max_size: Default::default(),
+            },
+            Op::AddWindow {
+                id: 1,
+                bbox: Rectangle::from_loc_and_size((0, 0), (100, 200)),
+                min_max_size: Default::default(),
+            },
+            Op::MoveWindowToWorkspaceById {
+                id: 0,
+                output: None,
+                workspace_idx: 1,
+            },
+        ];
+
+        let mut layout = Layout::default();
+        for op in ops {
+            op.apply(&mut layout);
+        }
+        layout.verify_invariants();
+
+        assert_eq!(layout.focus().unwrap().0.id, 1);
+
+        let MonitorSet::Normal { monitors, .. } = layout.monitor_set else {
+            unreachable!()
+        };
+
+        assert_eq!(monitors[0].active_workspace_idx, 0);
+        assert_eq!(monitors[0].workspaces[1].windows().next().unwrap().0.id, 0);
+    }
+
     #[test]
     fn focus_workspace_by_idx_does_not_leave_empty_workspaces() {
         let ops = [
@@ -2774,6 +4434,685 @@ mod tests {
         );
     }
 
+    #[test]
+    fn toggle_window_floating() {
+        let ops = [
+            Op::AddOutput(1),
+            Op::AddWindow {
+                id: 1,
+                bbox: Rectangle::from_loc_and_size((0, 0), (100, 200)),
+                min_max_size: Default::default(),
+            },
+            Op::AddWindow {
+                id: 2,
+                bbox: Rectangle::from_loc_and_size((0, 0), (100, 200)),
+                min_max_size: Default::default(),
+            },
+            Op::ToggleWindowFloating,
+        ];
+
+        let mut layout = Layout::default();
+        for op in ops {
+            op.apply(&mut layout);
+            layout.verify_invariants();
+        }
+
+        let ws = layout.active_workspace().unwrap();
+        assert_eq!(ws.columns.len(), 1);
+        assert!(
+            ws.floating_is_active,
+            "the floating window must remain focused"
+        );
+        assert_eq!(layout.focus().unwrap().0.id, 2);
+
+        layout.switch_focus_floating_tiling();
+        layout.verify_invariants();
+        assert_eq!(layout.focus().unwrap().0.id, 1);
+
+        layout.switch_focus_floating_tiling();
+        layout.toggle_window_floating();
+        layout.verify_invariants();
+
+        let ws = layout.active_workspace().unwrap();
+        assert_eq!(ws.columns.len(), 2);
+        assert!(!ws.floating_is_active);
+        assert_eq!(layout.focus().unwrap().0.id, 2);
+    }
+
+    #[test]
+    fn floating_window_under_is_on_top() {
+        let ops = [
+            Op::AddOutput(1),
+            Op::AddWindow {
+                id: 1,
+                bbox: Rectangle::from_loc_and_size((0, 0), (1280, 720)),
+                min_max_size: Default::default(),
+            },
+            Op::AddWindow {
+                id: 2,
+                bbox: Rectangle::from_loc_and_size((0, 0), (100, 200)),
+                min_max_size: Default::default(),
+            },
+            Op::ToggleWindowFloating,
+            Op::Communicate(2),
+        ];
+
+        let mut layout = Layout::default();
+        for op in ops {
+            op.apply(&mut layout);
+        }
+
+        // Floating windows are centered in the working area.
+        let output = layout.active_output().unwrap().clone();
+        let (win, _) = layout
+            .window_under(&output, Point::from((640., 360.)))
+            .unwrap();
+        assert_eq!(win.0.id, 2);
+    }
+
+    #[test]
+    fn fullscreen_floating_window() {
+        let ops = [
+            Op::AddOutput(1),
+            Op::AddWindow {
+                id: 1,
+                bbox: Rectangle::from_loc_and_size((0, 0), (100, 200)),
+                min_max_size: Default::default(),
+            },
+            Op::ToggleWindowFloating,
+            Op::FullscreenWindow(1),
+            Op::ToggleWindowFloating,
+        ];
+
+        check_ops(&ops);
+    }
+
+    #[test]
+    fn tabbed_column() {
+        let ops = [
+            Op::AddOutput(1),
+            Op::AddWindow {
+                id: 1,
+                bbox: Rectangle::from_loc_and_size((0, 0), (100, 200)),
+                min_max_size: Default::default(),
+            },
+            Op::AddWindow {
+                id: 2,
+                bbox: Rectangle::from_loc_and_size((0, 0), (100, 200)),
+                min_max_size: Default::default(),
+            },
+            Op::ConsumeWindowIntoColumn,
+            Op::Communicate(1),
+            Op::Communicate(2),
+        ];
+
+        let mut layout = Layout::default();
+        for op in ops {
+            op.apply(&mut layout);
+            layout.verify_invariants();
+        }
+
+        let apply = |layout: &mut Layout<TestWindow>, ops: &[Op]| {
+            for op in ops {
+                op.apply(layout);
+                layout.verify_invariants();
+            }
+        };
+        let heights = |layout: &Layout<TestWindow>| {
+            let col = &layout.active_workspace().unwrap().columns[0];
+            col.tiles
+                .iter()
+                .map(|tile| tile.tile_size().h)
+                .collect::<Vec<_>>()
+        };
+
+        let gaps = layout.options.gaps;
+        let col = &layout.active_workspace().unwrap().columns[0];
+        assert_eq!(col.active_tile_idx, 1);
+        assert!(col.is_tile_visible(0) && col.is_tile_visible(1));
+        let normal_height = (720 - gaps * 3) / 2;
+        assert_eq!(heights(&layout), [normal_height, normal_height]);
+
+        // In tabbed display, only the active tab shows and every tab takes the full height.
+        apply(
+            &mut layout,
+            &[
+                Op::ToggleColumnTabbedDisplay,
+                Op::Communicate(1),
+                Op::Communicate(2),
+            ],
+        );
+        let col = &layout.active_workspace().unwrap().columns[0];
+        assert_eq!(col.active_tile_idx, 1);
+        assert!(!col.is_tile_visible(0) && col.is_tile_visible(1));
+        let tab_height = 720 - gaps * 2;
+        assert_eq!(heights(&layout), [tab_height, tab_height]);
+
+        // Focus wraps around the tabs.
+        apply(&mut layout, &[Op::FocusWindowDown]);
+        let col = &layout.active_workspace().unwrap().columns[0];
+        assert_eq!(col.active_tile_idx, 0);
+        assert!(col.is_tile_visible(0) && !col.is_tile_visible(1));
+        apply(&mut layout, &[Op::FocusWindowUp]);
+        assert_eq!(
+            layout.active_workspace().unwrap().columns[0].active_tile_idx,
+            1
+        );
+
+        // Back to normal display, the focus stays and the tiles share the height again.
+        apply(
+            &mut layout,
+            &[
+                Op::ToggleColumnTabbedDisplay,
+                Op::Communicate(1),
+                Op::Communicate(2),
+            ],
+        );
+        let col = &layout.active_workspace().unwrap().columns[0];
+        assert_eq!(col.active_tile_idx, 1);
+        assert!(col.is_tile_visible(0) && col.is_tile_visible(1));
+        assert_eq!(heights(&layout), [normal_height, normal_height]);
+        apply(&mut layout, &[Op::FocusWindowDown]);
+        assert_eq!(
+            layout.active_workspace().unwrap().columns[0].active_tile_idx,
+            1
+        );
+
+        apply(&mut layout, &[Op::CloseWindow(1)]);
+        let col = &layout.active_workspace().unwrap().columns[0];
+        assert_eq!(col.tiles.len(), 1);
+        assert!(col.is_tile_visible(0));
+    }
+
+    #[test]
+    fn interactive_move_into_column() {
+        let ops = [
+            Op::AddOutput(1),
+            Op::AddWindow {
+                id: 1,
+                bbox: Rectangle::from_loc_and_size((0, 0), (100, 200)),
+                min_max_size: Default::default(),
+            },
+            Op::AddWindow {
+                id: 2,
+                bbox: Rectangle::from_loc_and_size((0, 0), (100, 200)),
+                min_max_size: Default::default(),
+            },
+            Op::InteractiveMoveBegin {
+                window: 2,
+                output_idx: 1,
+                px: 700.,
+                py: 100.,
+            },
+            Op::InteractiveMoveUpdate {
+                window: 2,
+                output_idx: 1,
+                px: 300.,
+                py: 600.,
+            },
+            Op::InteractiveMoveEnd { window: 2 },
+        ];
+
+        let mut layout = Layout::default();
+        for op in ops {
+            op.apply(&mut layout);
+            layout.verify_invariants();
+        }
+
+        let ws = layout.active_workspace().unwrap();
+        assert_eq!(ws.columns.len(), 1);
+        assert_eq!(ws.columns[0].tiles.len(), 2);
+    }
+
+    #[test]
+    fn interactive_move_output_removed() {
+        let ops = [
+            Op::AddOutput(1),
+            Op::AddOutput(2),
+            Op::AddWindow {
+                id: 1,
+                bbox: Rectangle::from_loc_and_size((0, 0), (100, 200)),
+                min_max_size: Default::default(),
+            },
+            Op::InteractiveMoveBegin {
+                window: 1,
+                output_idx: 1,
+                px: 100.,
+                py: 100.,
+            },
+            Op::InteractiveMoveUpdate {
+                window: 1,
+                output_idx: 2,
+                px: 100.,
+                py: 100.,
+            },
+            Op::RemoveOutput(2),
+            Op::InteractiveMoveEnd { window: 1 },
+            Op::RemoveOutput(1),
+        ];
+
+        check_ops(&ops);
+    }
+
+    #[test]
+    fn interactive_resize_clamped_to_min_size() {
+        let ops = [
+            Op::AddOutput(1),
+            Op::AddWindow {
+                id: 1,
+                bbox: Rectangle::from_loc_and_size((0, 0), (400, 200)),
+                min_max_size: (Size::from((300, 150)), Size::default()),
+            },
+            Op::Communicate(1),
+            Op::InteractiveResizeBegin {
+                window: 1,
+                edges: ResizeEdge::TOP_LEFT,
+            },
+            Op::InteractiveResizeUpdate {
+                window: 1,
+                dx: 10000.,
+                dy: 10000.,
+            },
+            Op::Communicate(1),
+            Op::InteractiveResizeEnd { window: 1 },
+        ];
+
+        let mut layout = Layout::default();
+        for op in ops {
+            op.apply(&mut layout);
+            layout.verify_invariants();
+        }
+
+        let ws = layout.active_workspace().unwrap();
+        let window = ws.columns[0].tiles[0].window();
+        assert_eq!(window.size(), Size::from((300, 150)));
+    }
+
+    #[test]
+    fn view_offset_gesture_snaps_to_columns() {
+        let mut ops = vec![Op::AddOutput(1)];
+        for id in 1..=3 {
+            ops.push(Op::AddWindow {
+                id,
+                bbox: Rectangle::from_loc_and_size((0, 0), (1000, 200)),
+                min_max_size: Default::default(),
+            });
+        }
+        ops.push(Op::FocusColumnFirst);
+
+        let mut layout = Layout::default();
+        for op in ops {
+            op.apply(&mut layout);
+        }
+        assert_eq!(layout.active_workspace().unwrap().active_column_idx, 0);
+
+        // A short swipe snaps back and keeps the active column.
+        let ops = [
+            Op::ViewOffsetGestureBegin { output_idx: 1 },
+            Op::ViewOffsetGestureUpdate { delta: 50. },
+            Op::ViewOffsetGestureEnd { cancelled: false },
+        ];
+        for op in ops {
+            op.apply(&mut layout);
+            layout.verify_invariants();
+        }
+        assert_eq!(layout.active_workspace().unwrap().active_column_idx, 0);
+
+        // A long swipe past the end rubber-bands and snaps to the last column.
+        let ops = [
+            Op::ViewOffsetGestureBegin { output_idx: 1 },
+            Op::ViewOffsetGestureUpdate { delta: 10000. },
+            Op::ViewOffsetGestureEnd { cancelled: false },
+        ];
+        for op in ops {
+            op.apply(&mut layout);
+            layout.verify_invariants();
+        }
+        assert_eq!(layout.active_workspace().unwrap().active_column_idx, 2);
+
+        // A cancelled swipe doesn't change the active column.
+        let ops = [
+            Op::ViewOffsetGestureBegin { output_idx: 1 },
+            Op::ViewOffsetGestureUpdate { delta: -10000. },
+            Op::ViewOffsetGestureEnd { cancelled: true },
+        ];
+        for op in ops {
+            op.apply(&mut layout);
+            layout.verify_invariants();
+        }
+        assert_eq!(layout.active_workspace().unwrap().active_column_idx, 2);
+    }
+
+    #[test]
+    fn named_workspace_survives_clean_up() {
+        let ops = [
+            Op::AddOutput(1),
+            Op::AddNamedWorkspace {
+                ws_name: 1,
+                output_name: None,
+            },
+            Op::FocusWorkspace(1),
+            Op::FocusWorkspace(0),
+            Op::AddWindowToNamedWorkspace {
+                id: 1,
+                ws_name: 1,
+                bbox: Rectangle::from_loc_and_size((0, 0), (100, 200)),
+                min_max_size: Default::default(),
+            },
+            Op::CloseWindow(1),
+            Op::FocusWorkspace(1),
+        ];
+
+        let mut layout = Layout::default();
+        for op in ops {
+            op.apply(&mut layout);
+            layout.verify_invariants();
+        }
+
+        let (idx, ws) = layout.find_workspace_by_name("ws1").unwrap();
+        assert_eq!(idx, 0);
+        assert!(!ws.has_windows());
+
+        Op::UnnameWorkspace { ws_name: 1 }.apply(&mut layout);
+        layout.verify_invariants();
+
+        let MonitorSet::Normal { monitors, .. } = &layout.monitor_set else {
+            unreachable!()
+        };
+        assert_eq!(monitors[0].workspaces.len(), 1);
+    }
+
+    #[test]
+    fn named_workspace_follows_its_output() {
+        let ops = [
+            Op::AddNamedWorkspace {
+                ws_name: 1,
+                output_name: Some(2),
+            },
+            Op::AddOutput(1),
+            Op::AddOutput(2),
+        ];
+
+        let mut layout = Layout::default();
+        for op in ops {
+            op.apply(&mut layout);
+            layout.verify_invariants();
+        }
+
+        let output_of_ws1 = |layout: &Layout<TestWindow>| {
+            let (_, ws) = layout.find_workspace_by_name("ws1").unwrap();
+            ws.current_output().map(|output| output.name())
+        };
+
+        assert_eq!(output_of_ws1(&layout).as_deref(), Some("output2"));
+
+        Op::RemoveOutput(2).apply(&mut layout);
+        layout.verify_invariants();
+        assert_eq!(output_of_ws1(&layout).as_deref(), Some("output1"));
+
+        Op::RemoveOutput(1).apply(&mut layout);
+        layout.verify_invariants();
+        assert_eq!(output_of_ws1(&layout), None);
+
+        Op::AddOutput(1).apply(&mut layout);
+        Op::AddOutput(2).apply(&mut layout);
+        layout.verify_invariants();
+        assert_eq!(output_of_ws1(&layout).as_deref(), Some("output2"));
+    }
+
+    #[test]
+    fn named_workspace_output_change() {
+        let ops = [
+            Op::AddOutput(1),
+            Op::AddOutput(2),
+            Op::AddNamedWorkspace {
+                ws_name: 1,
+                output_name: Some(1),
+            },
+            Op::AddWindowToNamedWorkspace {
+                id: 1,
+                ws_name: 1,
+                bbox: Rectangle::from_loc_and_size((0, 0), (100, 200)),
+                min_max_size: Default::default(),
+            },
+            // Config reload with a different output.
+            Op::AddNamedWorkspace {
+                ws_name: 1,
+                output_name: Some(2),
+            },
+        ];
+
+        let mut layout = Layout::default();
+        for op in ops {
+            op.apply(&mut layout);
+            layout.verify_invariants();
+        }
+
+        let output_of_ws1 = |layout: &Layout<TestWindow>| {
+            let (_, ws) = layout.find_workspace_by_name("ws1").unwrap();
+            ws.current_output().map(|output| output.name())
+        };
+
+        assert_eq!(output_of_ws1(&layout).as_deref(), Some("output2"));
+
+        // Adding a window didn't make the workspace forget its configured output.
+        Op::RemoveOutput(2).apply(&mut layout);
+        layout.verify_invariants();
+        assert_eq!(output_of_ws1(&layout).as_deref(), Some("output1"));
+
+        Op::AddOutput(2).apply(&mut layout);
+        layout.verify_invariants();
+        assert_eq!(output_of_ws1(&layout).as_deref(), Some("output2"));
+    }
+
+    #[test]
+    fn named_workspace_reload_keeps_workspace_switch() {
+        let ops = [
+            Op::AddOutput(1),
+            Op::AddWindow {
+                id: 1,
+                bbox: Rectangle::from_loc_and_size((0, 0), (100, 200)),
+                min_max_size: Default::default(),
+            },
+            Op::FocusWorkspaceDown,
+            Op::AddWindow {
+                id: 2,
+                bbox: Rectangle::from_loc_and_size((0, 0), (100, 200)),
+                min_max_size: Default::default(),
+            },
+            Op::FocusWorkspaceUp,
+        ];
+
+        let mut layout = Layout::default();
+        for op in ops {
+            op.apply(&mut layout);
+            layout.verify_invariants();
+        }
+
+        let check = |layout: &Layout<TestWindow>| {
+            let MonitorSet::Normal { monitors, .. } = &layout.monitor_set else {
+                unreachable!()
+            };
+            assert_eq!(monitors[0].active_workspace_idx, 0);
+            assert!(monitors[0].workspace_switch.is_some());
+        };
+        check(&layout);
+
+        // A new named workspace goes below the active one.
+        let reload = Op::AddNamedWorkspace {
+            ws_name: 1,
+            output_name: None,
+        };
+        reload.apply(&mut layout);
+        layout.verify_invariants();
+        check(&layout);
+
+        // Reloading an unchanged config does nothing.
+        reload.apply(&mut layout);
+        layout.verify_invariants();
+        check(&layout);
+    }
+
+    #[test]
+    fn overview_drag_window_to_other_workspace() {
+        let options = Options {
+            animations: niri_config::Animations {
+                overview_open_close: niri_config::Animation {
+                    off: true,
+                    ..niri_config::Animation::default()
+                },
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+
+        let ops = [
+            Op::AddOutput(1),
+            Op::AddWindow {
+                id: 1,
+                bbox: Rectangle::from_loc_and_size((0, 0), (100, 200)),
+                min_max_size: Default::default(),
+            },
+            Op::MaximizeColumn,
+            Op::AddWindow {
+                id: 2,
+                bbox: Rectangle::from_loc_and_size((0, 0), (100, 200)),
+                min_max_size: Default::default(),
+            },
+            Op::MoveWindowToWorkspaceDown,
+            Op::FocusWorkspaceUp,
+            Op::ToggleOverview,
+            // The three workspaces are stacked in the middle of the output, so the first one is
+            // in its upper part.
+            Op::InteractiveMoveBegin {
+                window: 1,
+                output_idx: 1,
+                px: 640.,
+                py: 129.,
+            },
+            Op::InteractiveMoveUpdate {
+                window: 1,
+                output_idx: 1,
+                px: 640.,
+                py: 360.,
+            },
+            Op::InteractiveMoveEnd { window: 1 },
+        ];
+
+        let mut layout = Layout::with_options(options);
+        for op in ops {
+            op.apply(&mut layout);
+            layout.verify_invariants();
+        }
+
+        // The first workspace became empty and got cleaned up.
+        let MonitorSet::Normal { monitors, .. } = &layout.monitor_set else {
+            unreachable!()
+        };
+        let mon = &monitors[0];
+        assert!(mon.is_overview_open());
+        assert_eq!(mon.workspaces.len(), 2);
+        assert_eq!(mon.active_workspace_idx, 0);
+        assert_eq!(mon.workspaces[0].windows().count(), 2);
+    }
+
+    #[test]
+    fn resize_edges_exclude_border() {
+        let options = Options {
+            border: niri_config::FocusRing {
+                off: false,
+                width: 40,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+
+        let ops = [
+            Op::AddOutput(1),
+            Op::AddWindow {
+                id: 1,
+                bbox: Rectangle::from_loc_and_size((0, 0), (100, 200)),
+                min_max_size: Default::default(),
+            },
+            Op::Communicate(1),
+        ];
+
+        let mut layout = Layout::with_options(options);
+        for op in ops {
+            op.apply(&mut layout);
+            layout.verify_invariants();
+        }
+
+        let output = layout.outputs().next().unwrap().clone();
+        let ws = layout.active_workspace().unwrap();
+        let geo = ws.window_geometry(ws.windows().next().unwrap()).unwrap();
+
+        // Just left of the window center and inside its top third. Measuring from the tile
+        // origin instead would shift the point by the border width and pick the wrong edges.
+        let pos = geo.loc.to_f64()
+            + Point::from((
+                f64::from(geo.size.w) / 2. - 10.,
+                f64::from(geo.size.h) / 3. - 10.,
+            ));
+        let edges = layout.resize_edges_under(&output, pos).unwrap();
+        assert_eq!(edges, ResizeEdge::LEFT | ResizeEdge::TOP);
+    }
+
+    #[test]
+    fn windows_and_workspaces_for_ipc() {
+        let ops = [
+            Op::AddOutput(1),
+            Op::AddWindow {
+                id: 1,
+                bbox: Rectangle::from_loc_and_size((0, 0), (100, 200)),
+                min_max_size: Default::default(),
+            },
+            Op::AddWindow {
+                id: 2,
+                bbox: Rectangle::from_loc_and_size((0, 0), (100, 200)),
+                min_max_size: Default::default(),
+            },
+            Op::FocusColumnLeft,
+            Op::ConsumeWindowIntoColumn,
+            Op::AddWindow {
+                id: 3,
+                bbox: Rectangle::from_loc_and_size((0, 0), (100, 200)),
+                min_max_size: Default::default(),
+            },
+            Op::MoveWindowToWorkspaceDown,
+        ];
+
+        let mut layout = Layout::default();
+        for op in ops {
+            op.apply(&mut layout);
+            layout.verify_invariants();
+        }
+
+        let mut locations = Vec::new();
+        layout.with_windows_and_locations(|win, location| {
+            assert_eq!(location.output.unwrap().name(), "output1");
+            locations.push((
+                win.0.id,
+                location.workspace_idx,
+                location.column_and_tile_idx,
+            ));
+        });
+        assert_eq!(
+            locations,
+            [
+                (1, 0, Some((0, 0))),
+                (2, 0, Some((0, 1))),
+                (3, 1, Some((0, 0)))
+            ]
+        );
+
+        let workspaces = layout.ipc_workspaces();
+        assert_eq!(workspaces.len(), 3);
+        assert_eq!(workspaces[1].idx, 2);
+        assert!(workspaces[1].is_active && workspaces[1].is_focused);
+        assert!(!workspaces[0].is_active);
+    }
+
     fn arbitrary_spacing() -> impl Strategy<Value = u16> {
         // Give equal weight to:
         // - 0: the element is disabled
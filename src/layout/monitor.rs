@@ -4,16 +4,18 @@ use std::time::Duration;
 
 use niri_ipc::SizeChange;
 use smithay::backend::renderer::element::utils::{
-    CropRenderElement, Relocate, RelocateRenderElement,
+    CropRenderElement, Relocate, RelocateRenderElement, RescaleRenderElement,
 };
 use smithay::output::Output;
-use smithay::utils::{Logical, Point, Rectangle, Scale};
+use smithay::utils::{Logical, Physical, Point, Rectangle, Scale};
 
 use super::workspace::{
-    compute_working_area, Column, ColumnWidth, OutputId, Workspace, WorkspaceRenderElement,
+    compute_working_area, Column, ColumnWidth, InsertPosition, OutputId, Workspace,
+    WorkspaceRenderElement,
 };
 use super::{LayoutElement, Options};
 use crate::animation::Animation;
+use crate::niri_render_elements;
 use crate::render_helpers::renderer::NiriRenderer;
 use crate::utils::output_size;
 
@@ -27,6 +29,8 @@ pub struct Monitor<W: LayoutElement> {
     pub active_workspace_idx: usize,
     /// In-progress switch between workspaces.
     pub workspace_switch: Option<WorkspaceSwitch>,
+    /// Zoomed-out overview of all workspaces, if open or closing.
+    pub overview: Option<Overview>,
     /// Configurable properties of the layout.
     pub options: Rc<Options>,
 }
@@ -45,8 +49,20 @@ pub struct WorkspaceSwitchGesture {
     pub current_idx: f64,
 }
 
-pub type MonitorRenderElement<R> =
-    RelocateRenderElement<CropRenderElement<WorkspaceRenderElement<R>>>;
+#[derive(Debug)]
+pub struct Overview {
+    /// Whether the overview is open, as opposed to closing.
+    pub is_open: bool,
+    /// Zoom animation, from 0 for the normal view to 1 for the fully open overview.
+    anim: Option<Animation>,
+}
+
+niri_render_elements! {
+    MonitorRenderElement => {
+        Workspace = RelocateRenderElement<CropRenderElement<WorkspaceRenderElement<R>>>,
+        Overview = RelocateRenderElement<RescaleRenderElement<WorkspaceRenderElement<R>>>,
+    }
+}
 
 impl WorkspaceSwitch {
     pub fn current_idx(&self) -> f64 {
@@ -65,6 +81,23 @@ impl WorkspaceSwitch {
     }
 }
 
+impl Overview {
+    /// Returns how far the overview is open, from 0 to 1.
+    pub fn progress(&self) -> f64 {
+        match &self.anim {
+            // Zero-duration animations can return NaN values, so check is_done() first.
+            Some(anim) if !anim.is_done() => anim.value(),
+            _ => {
+                if self.is_open {
+                    1.
+                } else {
+                    0.
+                }
+            }
+        }
+    }
+}
+
 impl<W: LayoutElement> Monitor<W> {
     pub fn new(output: Output, workspaces: Vec<Workspace<W>>, options: Rc<Options>) -> Self {
         Self {
@@ -72,6 +105,7 @@ impl<W: LayoutElement> Monitor<W> {
             workspaces,
             active_workspace_idx: 0,
             workspace_switch: None,
+            overview: None,
             options,
         }
     }
@@ -85,6 +119,14 @@ impl<W: LayoutElement> Monitor<W> {
             return;
         }
 
+        if self.overview.is_some() {
+            // All workspaces are visible in the overview, so there's nothing to animate.
+            self.active_workspace_idx = idx;
+            self.workspace_switch = None;
+            self.clean_up_workspaces();
+            return;
+        }
+
         let current_idx = self
             .workspace_switch
             .as_ref()
@@ -110,11 +152,47 @@ impl<W: LayoutElement> Monitor<W> {
         is_full_width: bool,
     ) {
         let workspace = &mut self.workspaces[workspace_idx];
-
         workspace.add_window(window, activate, width, is_full_width);
+        self.on_window_added(workspace_idx, activate);
+    }
 
-        // After adding a new window, workspace becomes this output's own.
-        workspace.original_output = OutputId::new(&self.output);
+    pub fn add_window_at(
+        &mut self,
+        workspace_idx: usize,
+        position: InsertPosition,
+        window: W,
+        activate: bool,
+        width: ColumnWidth,
+        is_full_width: bool,
+    ) {
+        let workspace = &mut self.workspaces[workspace_idx];
+        workspace.add_window_at(position, window, activate, width, is_full_width);
+        self.on_window_added(workspace_idx, activate);
+    }
+
+    pub fn add_floating_window(&mut self, workspace_idx: usize, window: W, activate: bool) {
+        self.add_floating_window_at(workspace_idx, window, None, activate);
+    }
+
+    pub fn add_floating_window_at(
+        &mut self,
+        workspace_idx: usize,
+        window: W,
+        loc: Option<Point<i32, Logical>>,
+        activate: bool,
+    ) {
+        let workspace = &mut self.workspaces[workspace_idx];
+        workspace.add_floating_window_at(window, loc, activate);
+        self.on_window_added(workspace_idx, activate);
+    }
+
+    fn on_window_added(&mut self, workspace_idx: usize, activate: bool) {
+        // After adding a new window, workspace becomes this output's own. Named workspaces keep
+        // the output they were configured with.
+        let workspace = &mut self.workspaces[workspace_idx];
+        if workspace.name.is_none() {
+            workspace.original_output = OutputId::new(&self.output);
+        }
 
         if workspace_idx == self.workspaces.len() - 1 {
             // Insert a new empty workspace.
@@ -144,7 +222,9 @@ impl<W: LayoutElement> Monitor<W> {
         workspace.add_window_right_of(right_of, window, width, is_full_width);
 
         // After adding a new window, workspace becomes this output's own.
-        workspace.original_output = OutputId::new(&self.output);
+        if workspace.name.is_none() {
+            workspace.original_output = OutputId::new(&self.output);
+        }
     }
 
     pub fn add_column(&mut self, workspace_idx: usize, column: Column<W>, activate: bool) {
@@ -153,7 +233,9 @@ impl<W: LayoutElement> Monitor<W> {
         workspace.add_column(column, activate);
 
         // After adding a new window, workspace becomes this output's own.
-        workspace.original_output = OutputId::new(&self.output);
+        if workspace.name.is_none() {
+            workspace.original_output = OutputId::new(&self.output);
+        }
 
         if workspace_idx == self.workspaces.len() - 1 {
             // Insert a new empty workspace.
@@ -166,6 +248,36 @@ impl<W: LayoutElement> Monitor<W> {
         }
     }
 
+    /// Inserts a workspace right before the last, empty, one.
+    pub fn insert_workspace_before_last(&mut self, mut ws: Workspace<W>) {
+        ws.set_output(Some(self.output.clone()));
+
+        let idx = self.workspaces.len() - 1;
+        self.workspaces.insert(idx, ws);
+
+        // A workspace switch in progress can keep going unless the active workspace moved.
+        if idx <= self.active_workspace_idx {
+            self.active_workspace_idx += 1;
+            self.workspace_switch = None;
+        }
+    }
+
+    /// Removes a workspace other than the last, empty, one.
+    pub fn remove_workspace(&mut self, idx: usize) -> Workspace<W> {
+        assert!(idx < self.workspaces.len() - 1);
+
+        let ws = self.workspaces.remove(idx);
+
+        if idx <= self.active_workspace_idx {
+            // When removing the active workspace, the one above it becomes active.
+            self.active_workspace_idx = self.active_workspace_idx.saturating_sub(1);
+            self.workspace_switch = None;
+            self.clean_up_workspaces();
+        }
+
+        ws
+    }
+
     pub fn clean_up_workspaces(&mut self) {
         assert!(self.workspace_switch.is_none());
 
@@ -174,7 +286,7 @@ impl<W: LayoutElement> Monitor<W> {
                 continue;
             }
 
-            if !self.workspaces[idx].has_windows() {
+            if !self.workspaces[idx].has_windows_or_name() {
                 self.workspaces.remove(idx);
                 if self.active_workspace_idx > idx {
                     self.active_workspace_idx -= 1;
@@ -209,6 +321,11 @@ impl<W: LayoutElement> Monitor<W> {
 
     pub fn move_down_or_to_workspace_down(&mut self) {
         let workspace = self.active_workspace();
+        if workspace.floating_is_active {
+            // Floating windows have nowhere to move within the workspace.
+            self.move_to_workspace_down();
+            return;
+        }
         if workspace.columns.is_empty() {
             return;
         }
@@ -224,6 +341,11 @@ impl<W: LayoutElement> Monitor<W> {
 
     pub fn move_up_or_to_workspace_up(&mut self) {
         let workspace = self.active_workspace();
+        if workspace.floating_is_active {
+            // Floating windows have nowhere to move within the workspace.
+            self.move_to_workspace_up();
+            return;
+        }
         if workspace.columns.is_empty() {
             return;
         }
@@ -270,7 +392,11 @@ impl<W: LayoutElement> Monitor<W> {
 
     pub fn focus_window_or_workspace_down(&mut self) {
         let workspace = self.active_workspace();
-        if workspace.columns.is_empty() {
+        if workspace.floating_is_active {
+            if !workspace.floating.focus_down() {
+                self.switch_workspace_down();
+            }
+        } else if workspace.columns.is_empty() {
             self.switch_workspace_down();
         } else {
             let column = &workspace.columns[workspace.active_column_idx];
@@ -286,7 +412,11 @@ impl<W: LayoutElement> Monitor<W> {
 
     pub fn focus_window_or_workspace_up(&mut self) {
         let workspace = self.active_workspace();
-        if workspace.columns.is_empty() {
+        if workspace.floating_is_active {
+            if !workspace.floating.focus_up() {
+                self.switch_workspace_up();
+            }
+        } else if workspace.columns.is_empty() {
             self.switch_workspace_up();
         } else {
             let curr_idx = workspace.columns[workspace.active_column_idx].active_tile_idx;
@@ -308,6 +438,12 @@ impl<W: LayoutElement> Monitor<W> {
         }
 
         let workspace = &mut self.workspaces[source_workspace_idx];
+        if workspace.floating_is_active {
+            let window = workspace.remove_active_floating_window().unwrap();
+            self.add_floating_window(new_idx, window, true);
+            return;
+        }
+
         if workspace.columns.is_empty() {
             return;
         }
@@ -330,6 +466,12 @@ impl<W: LayoutElement> Monitor<W> {
         }
 
         let workspace = &mut self.workspaces[source_workspace_idx];
+        if workspace.floating_is_active {
+            let window = workspace.remove_active_floating_window().unwrap();
+            self.add_floating_window(new_idx, window, true);
+            return;
+        }
+
         if workspace.columns.is_empty() {
             return;
         }
@@ -352,17 +494,22 @@ impl<W: LayoutElement> Monitor<W> {
         }
 
         let workspace = &mut self.workspaces[source_workspace_idx];
-        if workspace.columns.is_empty() {
-            return;
-        }
+        if workspace.floating_is_active {
+            let window = workspace.remove_active_floating_window().unwrap();
+            self.add_floating_window(new_idx, window, true);
+        } else {
+            if workspace.columns.is_empty() {
+                return;
+            }
 
-        let column = &workspace.columns[workspace.active_column_idx];
-        let width = column.width;
-        let is_full_width = column.is_full_width;
-        let window =
-            workspace.remove_window_by_idx(workspace.active_column_idx, column.active_tile_idx);
+            let column = &workspace.columns[workspace.active_column_idx];
+            let width = column.width;
+            let is_full_width = column.is_full_width;
+            let window =
+                workspace.remove_window_by_idx(workspace.active_column_idx, column.active_tile_idx);
 
-        self.add_window(new_idx, window, true, width, is_full_width);
+            self.add_window(new_idx, window, true, width, is_full_width);
+        }
 
         // Don't animate this action.
         self.workspace_switch = None;
@@ -379,7 +526,7 @@ impl<W: LayoutElement> Monitor<W> {
         }
 
         let workspace = &mut self.workspaces[source_workspace_idx];
-        if workspace.columns.is_empty() {
+        if workspace.columns.is_empty() || workspace.floating_is_active {
             return;
         }
 
@@ -396,7 +543,7 @@ impl<W: LayoutElement> Monitor<W> {
         }
 
         let workspace = &mut self.workspaces[source_workspace_idx];
-        if workspace.columns.is_empty() {
+        if workspace.columns.is_empty() || workspace.floating_is_active {
             return;
         }
 
@@ -413,7 +560,7 @@ impl<W: LayoutElement> Monitor<W> {
         }
 
         let workspace = &mut self.workspaces[source_workspace_idx];
-        if workspace.columns.is_empty() {
+        if workspace.columns.is_empty() || workspace.floating_is_active {
             return;
         }
 
@@ -458,13 +605,7 @@ impl<W: LayoutElement> Monitor<W> {
     }
 
     pub fn focus(&self) -> Option<&W> {
-        let workspace = &self.workspaces[self.active_workspace_idx];
-        if !workspace.has_windows() {
-            return None;
-        }
-
-        let column = &workspace.columns[workspace.active_column_idx];
-        Some(column.tiles[column.active_tile_idx].window())
+        self.workspaces[self.active_workspace_idx].focus()
     }
 
     pub fn advance_animations(&mut self, current_time: Duration, is_active: bool) {
@@ -476,6 +617,19 @@ impl<W: LayoutElement> Monitor<W> {
             }
         }
 
+        if let Some(overview) = &mut self.overview {
+            if let Some(anim) = &mut overview.anim {
+                anim.set_current_time(current_time);
+                if anim.is_done() {
+                    overview.anim = None;
+                }
+            }
+
+            if !overview.is_open && overview.anim.is_none() {
+                self.overview = None;
+            }
+        }
+
         for ws in &mut self.workspaces {
             ws.advance_animations(current_time, is_active);
         }
@@ -485,12 +639,121 @@ impl<W: LayoutElement> Monitor<W> {
         self.workspace_switch
             .as_ref()
             .is_some_and(|s| s.is_animation())
+            || self.overview.as_ref().is_some_and(|o| o.anim.is_some())
             || self.workspaces.iter().any(|ws| ws.are_animations_ongoing())
     }
 
     pub fn are_transitions_ongoing(&self) -> bool {
         self.workspace_switch.is_some()
-            || self.workspaces.iter().any(|ws| ws.are_animations_ongoing())
+            || self.overview.as_ref().is_some_and(|o| o.anim.is_some())
+            || self
+                .workspaces
+                .iter()
+                .any(|ws| ws.are_transitions_ongoing())
+    }
+
+    pub fn is_overview_open(&self) -> bool {
+        self.overview.as_ref().is_some_and(|o| o.is_open)
+    }
+
+    pub fn toggle_overview(&mut self) {
+        self.set_overview_open(!self.is_overview_open());
+    }
+
+    pub fn set_overview_open(&mut self, open: bool) {
+        if self.is_overview_open() == open {
+            return;
+        }
+
+        let current = self.overview.as_ref().map_or(0., |o| o.progress());
+
+        // The overview shows every workspace in place, so finish any switch right away.
+        self.workspace_switch = None;
+        self.clean_up_workspaces();
+
+        self.overview = Some(Overview {
+            is_open: open,
+            anim: Some(Animation::new(
+                current,
+                if open { 1. } else { 0. },
+                self.options.animations.overview_open_close,
+                niri_config::Animation::default_overview_open_close(),
+            )),
+        });
+    }
+
+    /// Computes the zoom level at which all workspaces fit on the output.
+    fn overview_zoom(&self) -> f64 {
+        let size = output_size(&self.output).to_f64();
+        let gaps = f64::from(self.options.gaps);
+
+        let max_width = self
+            .workspaces
+            .iter()
+            .map(|ws| {
+                let (left, right) = ws.content_x_range();
+                right - left
+            })
+            .max()
+            .unwrap_or(0)
+            .max(1);
+
+        let count = self.workspaces.len() as f64;
+        let total_height = count * size.h + (count - 1.) * gaps;
+
+        let zoom_x = (size.w - gaps * 2.) / f64::from(max_width);
+        let zoom_y = (size.h - gaps * 2.) / total_height.max(1.);
+        zoom_x.min(zoom_y).clamp(0.01, 1.)
+    }
+
+    /// Computes the location and the scale of a workspace within the output.
+    ///
+    /// `progress` interpolates between the normal view at 0 and the fully open overview at 1.
+    fn overview_workspace_geometry(&self, idx: usize, progress: f64) -> (Point<f64, Logical>, f64) {
+        let size = output_size(&self.output).to_f64();
+        let gaps = f64::from(self.options.gaps);
+        let zoom = self.overview_zoom();
+
+        // In the normal view, workspaces are stacked vertically around the active one.
+        let closed_y = (idx as f64 - self.active_workspace_idx as f64) * size.h;
+
+        // In the overview, every column strip is centered horizontally, and the workspaces are
+        // centered vertically as a whole.
+        let (left, right) = self.workspaces[idx].content_x_range();
+        let open_x = (size.w - f64::from(right - left) * zoom) / 2. - f64::from(left) * zoom;
+
+        let count = self.workspaces.len() as f64;
+        let total_height = count * size.h + (count - 1.) * gaps;
+        let open_y = (size.h - total_height * zoom) / 2. + idx as f64 * (size.h + gaps) * zoom;
+
+        let loc = Point::from((open_x * progress, closed_y + (open_y - closed_y) * progress));
+        let scale = 1. + (zoom - 1.) * progress;
+        (loc, scale)
+    }
+
+    /// Returns the workspace under the given position, along with the position within it.
+    ///
+    /// Outside the overview, this is always the active workspace.
+    pub fn workspace_under(
+        &self,
+        pos_within_output: Point<f64, Logical>,
+    ) -> Option<(usize, Point<f64, Logical>)> {
+        let Some(overview) = &self.overview else {
+            return Some((self.active_workspace_idx, pos_within_output));
+        };
+
+        let progress = overview.progress();
+        let height = f64::from(output_size(&self.output).h);
+
+        self.workspaces.iter().enumerate().find_map(|(idx, ws)| {
+            let (loc, scale) = self.overview_workspace_geometry(idx, progress);
+            let pos = (pos_within_output - loc).downscale(scale);
+
+            let (left, right) = ws.content_x_range();
+            let bounds =
+                Rectangle::from_extemities((f64::from(left), 0.), (f64::from(right), height));
+            bounds.contains(pos).then_some((idx, pos))
+        })
     }
 
     pub fn update_config(&mut self, options: Rc<Options>) {
@@ -511,19 +774,31 @@ impl<W: LayoutElement> Monitor<W> {
     }
 
     pub fn toggle_width(&mut self) {
-        self.active_workspace().toggle_width();
+        self.active_workspace().toggle_width(None);
     }
 
     pub fn toggle_full_width(&mut self) {
-        self.active_workspace().toggle_full_width();
+        self.active_workspace().toggle_full_width(None);
     }
 
     pub fn set_column_width(&mut self, change: SizeChange) {
-        self.active_workspace().set_column_width(change);
+        self.active_workspace().set_column_width(None, change);
     }
 
     pub fn set_window_height(&mut self, change: SizeChange) {
-        self.active_workspace().set_window_height(change);
+        self.active_workspace().set_window_height(None, change);
+    }
+
+    pub fn toggle_window_floating(&mut self) {
+        self.active_workspace().toggle_window_floating();
+    }
+
+    pub fn switch_focus_floating_tiling(&mut self) {
+        self.active_workspace().switch_focus_floating_tiling();
+    }
+
+    pub fn toggle_column_tabbed_display(&mut self) {
+        self.active_workspace().toggle_column_tabbed_display();
     }
 
     pub fn move_workspace_down(&mut self) {
@@ -570,6 +845,13 @@ impl<W: LayoutElement> Monitor<W> {
         &self,
         pos_within_output: Point<f64, Logical>,
     ) -> Option<(&W, Option<Point<i32, Logical>>)> {
+        if self.overview.is_some() {
+            let (idx, pos) = self.workspace_under(pos_within_output)?;
+            let (win, _) = self.workspaces[idx].window_under(pos)?;
+            // Windows in the overview don't receive pointer input, they can only be activated.
+            return Some((win, None));
+        }
+
         match &self.workspace_switch {
             Some(switch) => {
                 let size = output_size(&self.output);
@@ -598,6 +880,11 @@ impl<W: LayoutElement> Monitor<W> {
     }
 
     pub fn render_above_top_layer(&self) -> bool {
+        // The overview always covers the top layer.
+        if self.overview.is_some() {
+            return true;
+        }
+
         // Render above the top layer only if the view is stationary.
         if self.workspace_switch.is_some() {
             return false;
@@ -618,6 +905,31 @@ impl<W: LayoutElement> Monitor<W> {
         let output_mode = self.output.current_mode().unwrap();
         let size = output_transform.transform_size(output_mode.size);
 
+        if let Some(overview) = &self.overview {
+            let progress = overview.progress();
+            let height = f64::from(output_size(&self.output).h);
+
+            let mut rv = Vec::new();
+            for (idx, ws) in self.workspaces.iter().enumerate() {
+                let (loc, scale) = self.overview_workspace_geometry(idx, progress);
+
+                // Skip workspaces that are entirely off-screen.
+                if loc.y + height * scale <= 0. || height <= loc.y {
+                    continue;
+                }
+
+                let loc: Point<i32, Physical> = loc.to_physical_precise_round(output_scale);
+                rv.extend(ws.render_elements(renderer).into_iter().map(|elem| {
+                    MonitorRenderElement::Overview(RelocateRenderElement::from_element(
+                        RescaleRenderElement::from_element(elem, Point::from((0, 0)), scale),
+                        loc,
+                        Relocate::Relative,
+                    ))
+                }));
+            }
+            return rv;
+        }
+
         match &self.workspace_switch {
             Some(switch) => {
                 let render_idx = switch.current_idx();
@@ -651,7 +963,10 @@ impl<W: LayoutElement> Monitor<W> {
                         Relocate::Relative,
                     ))
                 });
-                before.chain(after).collect()
+                before
+                    .chain(after)
+                    .map(MonitorRenderElement::from)
+                    .collect()
             }
             None => {
                 let elements = self.workspaces[self.active_workspace_idx].render_elements(renderer);
@@ -675,6 +990,7 @@ impl<W: LayoutElement> Monitor<W> {
                             Relocate::Relative,
                         ))
                     })
+                    .map(MonitorRenderElement::from)
                     .collect()
             }
         }
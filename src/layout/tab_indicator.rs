@@ -0,0 +1,103 @@
+use std::cmp::max;
+use std::iter::zip;
+
+use niri_config::{self, Color};
+use smithay::backend::renderer::element::solid::{SolidColorBuffer, SolidColorRenderElement};
+use smithay::backend::renderer::element::Kind;
+use smithay::utils::{Logical, Point, Scale, Size};
+
+/// Indicator of the tabs in a tabbed column.
+///
+/// Drawn as a vertical bar to the left of the column, split into one segment per tab.
+#[derive(Debug)]
+pub struct TabIndicator {
+    buffers: Vec<SolidColorBuffer>,
+    locations: Vec<Point<i32, Logical>>,
+    is_off: bool,
+    width: i32,
+    gap: i32,
+    active_color: Color,
+    inactive_color: Color,
+}
+
+pub type TabIndicatorRenderElement = SolidColorRenderElement;
+
+impl TabIndicator {
+    pub fn new(config: niri_config::TabIndicator) -> Self {
+        Self {
+            buffers: vec![],
+            locations: vec![],
+            is_off: config.off,
+            width: config.width.into(),
+            gap: config.gap.into(),
+            active_color: config.active_color,
+            inactive_color: config.inactive_color,
+        }
+    }
+
+    pub fn update_config(&mut self, config: niri_config::TabIndicator) {
+        self.is_off = config.off;
+        self.width = config.width.into();
+        self.gap = config.gap.into();
+        self.active_color = config.active_color;
+        self.inactive_color = config.inactive_color;
+    }
+
+    /// Updates the indicator for a column with the given tile size.
+    ///
+    /// `offset` is the distance from the tile's left edge to leave free, for example, for the
+    /// focus ring.
+    pub fn update(
+        &mut self,
+        tile_size: Size<i32, Logical>,
+        offset: i32,
+        tab_count: usize,
+        active_idx: usize,
+    ) {
+        self.buffers.resize_with(tab_count, Default::default);
+        self.locations.resize(tab_count, Point::default());
+
+        if tab_count == 0 {
+            return;
+        }
+
+        let count = tab_count as i32;
+        let spacing = self.gap;
+        let height = max(1, (tile_size.h - spacing * (count - 1)) / count);
+        let x = -(offset + self.gap + self.width);
+
+        for (idx, (buf, loc)) in zip(&mut self.buffers, &mut self.locations).enumerate() {
+            buf.resize((self.width, height));
+            *loc = Point::from((x, idx as i32 * (height + spacing)));
+
+            let color = if idx == active_idx {
+                self.active_color
+            } else {
+                self.inactive_color
+            };
+            buf.set_color(color.into());
+        }
+    }
+
+    pub fn render(
+        &self,
+        location: Point<i32, Logical>,
+        scale: Scale<f64>,
+    ) -> impl Iterator<Item = TabIndicatorRenderElement> + '_ {
+        let buffers = if self.is_off {
+            &[][..]
+        } else {
+            &self.buffers[..]
+        };
+
+        zip(buffers, &self.locations).map(move |(buf, loc)| {
+            SolidColorRenderElement::from_buffer(
+                buf,
+                (location + *loc).to_physical_precise_round(scale),
+                scale,
+                1.,
+                Kind::Unspecified,
+            )
+        })
+    }
+}
@@ -46,10 +46,20 @@ pub struct Tile<W: LayoutElement> {
     /// The animation upon opening a window.
     open_animation: Option<Animation>,
 
+    /// The animation of the tile moving into its new position.
+    move_animation: Option<MoveAnimation>,
+
     /// Configurable properties of the layout.
     options: Rc<Options>,
 }
 
+#[derive(Debug)]
+struct MoveAnimation {
+    anim: Animation,
+    /// Offset from the new position that the tile is moving from.
+    from: Point<i32, Logical>,
+}
+
 niri_render_elements! {
     TileRenderElement => {
         LayoutElement = LayoutElementRenderElement<R>,
@@ -60,22 +70,26 @@ niri_render_elements! {
 
 impl<W: LayoutElement> Tile<W> {
     pub fn new(window: W, options: Rc<Options>) -> Self {
+        let rules = window.rules();
+        let border = rules.border.resolve_against(options.border);
+        let focus_ring = rules.focus_ring.resolve_against(options.focus_ring);
+
         Self {
             window,
-            border: FocusRing::new(options.border),
-            focus_ring: FocusRing::new(options.focus_ring),
+            border: FocusRing::new(border),
+            focus_ring: FocusRing::new(focus_ring),
             is_fullscreen: false, // FIXME: up-to-date fullscreen right away, but we need size.
             fullscreen_backdrop: SolidColorBuffer::new((0, 0), [0., 0., 0., 1.]),
             fullscreen_size: Default::default(),
             open_animation: None,
+            move_animation: None,
             options,
         }
     }
 
     pub fn update_config(&mut self, options: Rc<Options>) {
-        self.border.update_config(options.border);
-        self.focus_ring.update_config(options.focus_ring);
         self.options = options;
+        self.update_rules();
     }
 
     pub fn update_window(&mut self) {
@@ -83,6 +97,19 @@ impl<W: LayoutElement> Tile<W> {
         if self.fullscreen_size != Size::from((0, 0)) {
             self.is_fullscreen = self.window.is_fullscreen();
         }
+
+        self.update_rules();
+    }
+
+    /// Applies the window rule overrides on top of the layout options.
+    fn update_rules(&mut self) {
+        let rules = self.window.rules();
+
+        let border = rules.border.resolve_against(self.options.border);
+        self.border.update_config(border);
+
+        let focus_ring = rules.focus_ring.resolve_against(self.options.focus_ring);
+        self.focus_ring.update_config(focus_ring);
     }
 
     pub fn advance_animations(&mut self, current_time: Duration, is_active: bool) {
@@ -107,10 +134,17 @@ impl<W: LayoutElement> Tile<W> {
             }
             None => (),
         }
+
+        if let Some(move_) = &mut self.move_animation {
+            move_.anim.set_current_time(current_time);
+            if move_.anim.is_done() {
+                self.move_animation = None;
+            }
+        }
     }
 
     pub fn are_animations_ongoing(&self) -> bool {
-        self.open_animation.is_some()
+        self.open_animation.is_some() || self.move_animation.is_some()
     }
 
     pub fn start_open_animation(&mut self) {
@@ -122,6 +156,32 @@ impl<W: LayoutElement> Tile<W> {
         ));
     }
 
+    /// Starts an animation of the tile moving from the given offset to its current position.
+    pub fn animate_move_from(&mut self, from: Point<i32, Logical>) {
+        self.move_animation = Some(MoveAnimation {
+            anim: Animation::new(
+                1.,
+                0.,
+                self.options.animations.window_movement,
+                niri_config::Animation::default_window_movement(),
+            ),
+            from,
+        });
+    }
+
+    /// Returns the current offset of the tile from its position due to the move animation.
+    pub fn render_offset(&self) -> Point<i32, Logical> {
+        let Some(move_) = &self.move_animation else {
+            return Point::from((0, 0));
+        };
+
+        let value = move_.anim.value();
+        Point::from((
+            (f64::from(move_.from.x) * value).round() as i32,
+            (f64::from(move_.from.y) * value).round() as i32,
+        ))
+    }
+
     pub fn window(&self) -> &W {
         &self.window
     }
@@ -356,6 +416,8 @@ impl<W: LayoutElement> Tile<W> {
         scale: Scale<f64>,
         focus_ring: bool,
     ) -> impl Iterator<Item = TileRenderElement<R>> {
+        let location = location + self.render_offset();
+
         if let Some(anim) = &self.open_animation {
             let renderer = renderer.as_gles_renderer();
             let elements = self.render_inner(renderer, location, scale, focus_ring);
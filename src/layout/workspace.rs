@@ -3,20 +3,25 @@ use std::iter::{self, zip};
 use std::rc::Rc;
 use std::time::Duration;
 
-use niri_config::{CenterFocusedColumn, PresetWidth, Struts};
+use niri_config::{CenterFocusedColumn, PresetWidth, Struts, Workspace as WorkspaceConfig};
 use niri_ipc::SizeChange;
+use smithay::backend::renderer::element::solid::{SolidColorBuffer, SolidColorRenderElement};
+use smithay::backend::renderer::element::Kind;
 use smithay::desktop::space::SpaceElement;
 use smithay::desktop::{layer_map_for_output, Window};
 use smithay::output::Output;
 use smithay::reexports::wayland_server::protocol::wl_surface::WlSurface;
 use smithay::utils::{Logical, Point, Rectangle, Scale, Size};
 
+use super::floating::FloatingSpace;
+use super::tab_indicator::TabIndicator;
 use super::tile::{Tile, TileRenderElement};
-use super::{LayoutElement, Options};
+use super::{LayoutElement, Options, ResizeEdge};
 use crate::animation::Animation;
 use crate::niri_render_elements;
 use crate::render_helpers::renderer::NiriRenderer;
 use crate::utils::output_size;
+use crate::window::ResolvedWindowRules;
 
 #[derive(Debug)]
 pub struct Workspace<W: LayoutElement> {
@@ -26,6 +31,11 @@ pub struct Workspace<W: LayoutElement> {
     /// disconnection, it may remain pointing to the disconnected output.
     pub original_output: OutputId,
 
+    /// Name of this workspace, if it was declared in the config.
+    ///
+    /// Named workspaces always exist, even when empty.
+    pub name: Option<String>,
+
     /// Current output of this workspace.
     output: Option<Output>,
 
@@ -54,8 +64,8 @@ pub struct Workspace<W: LayoutElement> {
     /// for natural handling of fullscreen windows, which must ignore work area padding.
     view_offset: i32,
 
-    /// Animation of the view offset, if one is currently ongoing.
-    view_offset_anim: Option<Animation>,
+    /// Adjustment of the view offset, if one is currently ongoing.
+    view_offset_adj: Option<ViewOffsetAdjustment>,
 
     /// Whether to activate the previous, rather than the next, column upon column removal.
     ///
@@ -67,6 +77,28 @@ pub struct Workspace<W: LayoutElement> {
     /// index of the previous column to activate.
     activate_prev_column_on_removal: bool,
 
+    /// Floating windows on this workspace, drawn above the columns.
+    pub floating: FloatingSpace<W>,
+
+    /// Whether the keyboard focus is on the floating windows rather than on the columns.
+    ///
+    /// Can only be `true` if there are floating windows, and must be `true` if there are floating
+    /// windows but no columns.
+    pub floating_is_active: bool,
+
+    /// Where an interactively moved window would be inserted, if it's being moved over this
+    /// workspace.
+    insert_hint: Option<InsertPosition>,
+
+    /// Area of the insert hint relative to the view.
+    insert_hint_area: Rectangle<i32, Logical>,
+
+    /// Buffer for drawing the insert hint.
+    insert_hint_buffer: SolidColorBuffer,
+
+    /// Window being resized interactively, if any.
+    interactive_resize: Option<InteractiveResize<W>>,
+
     /// Configurable properties of the layout.
     pub options: Rc<Options>,
 }
@@ -74,9 +106,35 @@ pub struct Workspace<W: LayoutElement> {
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct OutputId(String);
 
+/// Amount of touchpad movement to scroll the view by the width of the working area.
+const VIEW_GESTURE_WORKING_AREA_MOVEMENT: f64 = 1200.;
+
+#[derive(Debug)]
+enum ViewOffsetAdjustment {
+    Animation(Animation),
+    Gesture(ViewGesture),
+}
+
+#[derive(Debug)]
+struct ViewGesture {
+    /// View position following the fingers, before applying the rubber band.
+    tracked_view_pos: f64,
+}
+
+#[derive(Debug)]
+struct InteractiveResize<W: LayoutElement> {
+    /// Window being resized.
+    window: W,
+    /// Edges being dragged.
+    edges: ResizeEdge,
+    /// Size of the window when the resize started.
+    original_window_size: Size<i32, Logical>,
+}
+
 niri_render_elements! {
     WorkspaceRenderElement => {
         Tile = TileRenderElement<R>,
+        SolidColor = SolidColorRenderElement,
     }
 }
 
@@ -114,6 +172,26 @@ pub enum WindowHeight {
     Fixed(i32),
 }
 
+/// Position in the workspace to insert a window at.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InsertPosition {
+    /// In a new column at this index.
+    NewColumn(usize),
+    /// In the column at this index, at this tile index.
+    InColumn(usize, usize),
+}
+
+/// How the windows in a column are displayed.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum ColumnDisplay {
+    /// Windows are stacked vertically.
+    #[default]
+    Normal,
+    /// Only the active window is shown, at the full column height, with a tab indicator on the
+    /// side.
+    Tabbed,
+}
+
 #[derive(Debug)]
 pub struct Column<W: LayoutElement> {
     /// Tiles in this column.
@@ -143,6 +221,12 @@ pub struct Column<W: LayoutElement> {
     /// Whether this column contains a single full-screened window.
     pub is_fullscreen: bool,
 
+    /// How the windows in this column are displayed.
+    pub display_mode: ColumnDisplay,
+
+    /// The tab indicator shown in tabbed display mode.
+    tab_indicator: TabIndicator,
+
     /// Latest known view size for this column's workspace.
     view_size: Size<i32, Logical>,
 
@@ -153,10 +237,25 @@ pub struct Column<W: LayoutElement> {
     options: Rc<Options>,
 }
 
+impl ViewOffsetAdjustment {
+    /// Returns `true` if the view offset adjustment is [`Animation`].
+    ///
+    /// [`Animation`]: ViewOffsetAdjustment::Animation
+    #[must_use]
+    fn is_animation(&self) -> bool {
+        matches!(self, Self::Animation(..))
+    }
+}
+
 impl OutputId {
     pub fn new(output: &Output) -> Self {
         Self(output.name())
     }
+
+    /// Id of an output that may not be connected.
+    pub fn from_name(name: &str) -> Self {
+        Self(name.to_owned())
+    }
 }
 
 impl ColumnWidth {
@@ -182,56 +281,115 @@ impl From<PresetWidth> for ColumnWidth {
 
 impl<W: LayoutElement> Workspace<W> {
     pub fn new(output: Output, options: Rc<Options>) -> Self {
+        Self::new_with_config(output, None, options)
+    }
+
+    pub fn new_with_config(
+        output: Output,
+        config: Option<WorkspaceConfig>,
+        options: Rc<Options>,
+    ) -> Self {
+        let original_output = config
+            .as_ref()
+            .and_then(|c| c.open_on_output.clone())
+            .map(OutputId)
+            .unwrap_or_else(|| OutputId::new(&output));
+
         let working_area = compute_working_area(&output, options.struts);
+        let view_size = output_size(&output);
         Self {
-            original_output: OutputId::new(&output),
-            view_size: output_size(&output),
+            original_output,
+            name: config.map(|c| c.name),
+            view_size,
             working_area,
             output: Some(output),
             columns: vec![],
             active_column_idx: 0,
             view_offset: 0,
-            view_offset_anim: None,
+            view_offset_adj: None,
             activate_prev_column_on_removal: false,
+            floating: FloatingSpace::new(view_size, working_area, options.clone()),
+            floating_is_active: false,
+            insert_hint: None,
+            insert_hint_area: Rectangle::default(),
+            insert_hint_buffer: SolidColorBuffer::default(),
+            interactive_resize: None,
             options,
         }
     }
 
     pub fn new_no_outputs(options: Rc<Options>) -> Self {
+        Self::new_with_config_no_outputs(None, options)
+    }
+
+    pub fn new_with_config_no_outputs(
+        config: Option<WorkspaceConfig>,
+        options: Rc<Options>,
+    ) -> Self {
+        let original_output = OutputId(
+            config
+                .as_ref()
+                .and_then(|c| c.open_on_output.clone())
+                .unwrap_or_default(),
+        );
+
+        let view_size = Size::from((1280, 720));
+        let working_area = Rectangle::from_loc_and_size((0, 0), (1280, 720));
         Self {
             output: None,
-            original_output: OutputId(String::new()),
-            view_size: Size::from((1280, 720)),
-            working_area: Rectangle::from_loc_and_size((0, 0), (1280, 720)),
+            original_output,
+            name: config.map(|c| c.name),
+            view_size,
+            working_area,
             columns: vec![],
             active_column_idx: 0,
             view_offset: 0,
-            view_offset_anim: None,
+            view_offset_adj: None,
             activate_prev_column_on_removal: false,
+            floating: FloatingSpace::new(view_size, working_area, options.clone()),
+            floating_is_active: false,
+            insert_hint: None,
+            insert_hint_area: Rectangle::default(),
+            insert_hint_buffer: SolidColorBuffer::default(),
+            interactive_resize: None,
             options,
         }
     }
 
     pub fn advance_animations(&mut self, current_time: Duration, is_active: bool) {
-        match &mut self.view_offset_anim {
-            Some(anim) => {
-                anim.set_current_time(current_time);
-                self.view_offset = anim.value().round() as i32;
-                if anim.is_done() {
-                    self.view_offset_anim = None;
-                }
+        if let Some(ViewOffsetAdjustment::Animation(anim)) = &mut self.view_offset_adj {
+            anim.set_current_time(current_time);
+            self.view_offset = anim.value().round() as i32;
+            if anim.is_done() {
+                self.view_offset_adj = None;
             }
-            None => (),
         }
 
         for (col_idx, col) in self.columns.iter_mut().enumerate() {
-            let is_active = is_active && col_idx == self.active_column_idx;
+            let is_active =
+                is_active && !self.floating_is_active && col_idx == self.active_column_idx;
             col.advance_animations(current_time, is_active);
         }
+
+        self.floating
+            .advance_animations(current_time, is_active && self.floating_is_active);
+
+        // The view might have moved, so the insert hint needs to follow.
+        self.update_insert_hint();
     }
 
     pub fn are_animations_ongoing(&self) -> bool {
-        self.view_offset_anim.is_some() || self.columns.iter().any(Column::are_animations_ongoing)
+        self.view_offset_adj
+            .as_ref()
+            .is_some_and(ViewOffsetAdjustment::is_animation)
+            || self.columns.iter().any(Column::are_animations_ongoing)
+            || self.floating.are_animations_ongoing()
+    }
+
+    pub fn are_transitions_ongoing(&self) -> bool {
+        self.view_offset_adj.is_some()
+            || self.columns.iter().any(Column::are_animations_ongoing)
+            || self.floating.are_animations_ongoing()
     }
 
     pub fn update_config(&mut self, options: Rc<Options>) {
@@ -239,6 +397,8 @@ impl<W: LayoutElement> Workspace<W> {
             column.update_config(options.clone());
         }
 
+        self.floating.update_config(options.clone());
+
         self.options = options;
     }
 
@@ -247,6 +407,7 @@ impl<W: LayoutElement> Workspace<W> {
             .iter()
             .flat_map(|col| col.tiles.iter())
             .map(Tile::window)
+            .chain(self.floating.windows())
     }
 
     pub fn set_output(&mut self, output: Option<Output>) {
@@ -294,6 +455,9 @@ impl<W: LayoutElement> Workspace<W> {
         for col in &mut self.columns {
             col.set_view_size(self.view_size, self.working_area);
         }
+
+        self.floating
+            .set_view_size(self.view_size, self.working_area);
     }
 
     pub fn view_size(&self) -> Size<i32, Logical> {
@@ -310,22 +474,34 @@ impl<W: LayoutElement> Workspace<W> {
     }
 
     fn toplevel_bounds(&self) -> Size<i32, Logical> {
-        let mut border = 0;
-        if !self.options.border.off {
-            border = self.options.border.width as i32 * 2;
-        }
+        compute_toplevel_bounds(&self.options, self.working_area)
+    }
 
-        Size::from((
-            max(self.working_area.size.w - self.options.gaps * 2 - border, 1),
-            max(self.working_area.size.h - self.options.gaps * 2 - border, 1),
-        ))
+    /// Resolves a window rule default width against the layout default width.
+    pub fn resolve_default_width(
+        &self,
+        default_width: Option<Option<ColumnWidth>>,
+    ) -> Option<ColumnWidth> {
+        match default_width {
+            Some(width) => width,
+            None => self.options.default_width,
+        }
     }
 
-    pub fn new_window_size(&self) -> Size<i32, Logical> {
-        let width = if let Some(width) = self.options.default_width {
+    /// Returns the size to configure a new window with.
+    ///
+    /// A `None` width lets the window pick its own width.
+    pub fn new_window_size(
+        &self,
+        width: Option<ColumnWidth>,
+        rules: &ResolvedWindowRules,
+    ) -> Size<i32, Logical> {
+        let border = rules.border.resolve_against(self.options.border);
+
+        let width = if let Some(width) = width {
             let mut width = width.resolve(&self.options, self.working_area.size.w);
-            if !self.options.border.off {
-                width -= self.options.border.width as i32 * 2;
+            if !border.off {
+                width -= border.width as i32 * 2;
             }
             max(1, width)
         } else {
@@ -333,15 +509,20 @@ impl<W: LayoutElement> Workspace<W> {
         };
 
         let mut height = self.working_area.size.h - self.options.gaps * 2;
-        if !self.options.border.off {
-            height -= self.options.border.width as i32 * 2;
+        if !border.off {
+            height -= border.width as i32 * 2;
         }
 
         Size::from((width, max(height, 1)))
     }
 
-    pub fn configure_new_window(&self, window: &Window) {
-        let size = self.new_window_size();
+    pub fn configure_new_window(
+        &self,
+        window: &Window,
+        width: Option<ColumnWidth>,
+        rules: &ResolvedWindowRules,
+    ) {
+        let size = self.new_window_size(width, rules);
         let bounds = self.toplevel_bounds();
 
         if let Some(output) = self.output.as_ref() {
@@ -361,7 +542,7 @@ impl<W: LayoutElement> Workspace<W> {
 
         let new_col_x = self.column_x(idx);
 
-        let final_x = if let Some(anim) = &self.view_offset_anim {
+        let final_x = if let Some(ViewOffsetAdjustment::Animation(anim)) = &self.view_offset_adj {
             current_x - self.view_offset + anim.to().round() as i32
         } else {
             current_x
@@ -385,7 +566,7 @@ impl<W: LayoutElement> Workspace<W> {
         self.view_offset = from_view_offset;
 
         // If we're already animating towards that, don't restart it.
-        if let Some(anim) = &self.view_offset_anim {
+        if let Some(ViewOffsetAdjustment::Animation(anim)) = &self.view_offset_adj {
             if anim.value().round() as i32 == self.view_offset
                 && anim.to().round() as i32 == new_view_offset
             {
@@ -395,16 +576,16 @@ impl<W: LayoutElement> Workspace<W> {
 
         // If our view offset is already this, we don't need to do anything.
         if self.view_offset == new_view_offset {
-            self.view_offset_anim = None;
+            self.view_offset_adj = None;
             return;
         }
 
-        self.view_offset_anim = Some(Animation::new(
+        self.view_offset_adj = Some(ViewOffsetAdjustment::Animation(Animation::new(
             self.view_offset as f64,
             new_view_offset as f64,
             self.options.animations.horizontal_view_movement,
             niri_config::Animation::default_horizontal_view_movement(),
-        ));
+        )));
     }
 
     fn animate_view_offset_to_column(&mut self, current_x: i32, idx: usize) {
@@ -490,6 +671,14 @@ impl<W: LayoutElement> Workspace<W> {
         self.windows().next().is_some()
     }
 
+    pub fn has_windows_or_name(&self) -> bool {
+        self.has_windows() || self.name.is_some()
+    }
+
+    pub fn current_output(&self) -> Option<&Output> {
+        self.output.as_ref()
+    }
+
     pub fn has_window(&self, window: &W) -> bool {
         self.windows().any(|win| win == window)
     }
@@ -526,16 +715,59 @@ impl<W: LayoutElement> Workspace<W> {
         width: ColumnWidth,
         is_full_width: bool,
     ) {
-        self.enter_output_for_window(&window);
-
-        let was_empty = self.columns.is_empty();
-
         let idx = if self.columns.is_empty() {
             0
         } else {
             self.active_column_idx + 1
         };
 
+        self.add_window_at(
+            InsertPosition::NewColumn(idx),
+            window,
+            activate,
+            width,
+            is_full_width,
+        );
+    }
+
+    /// Adds a window at the given position.
+    ///
+    /// `width` and `is_full_width` are only used when creating a new column.
+    pub fn add_window_at(
+        &mut self,
+        position: InsertPosition,
+        window: W,
+        activate: bool,
+        width: ColumnWidth,
+        is_full_width: bool,
+    ) {
+        self.enter_output_for_window(&window);
+
+        let idx = match position {
+            InsertPosition::NewColumn(idx) => idx,
+            InsertPosition::InColumn(col_idx, tile_idx) => {
+                let column = &mut self.columns[col_idx];
+
+                // Windows can't be added into a fullscreen column, so put them next to it.
+                if !column.is_fullscreen {
+                    column.add_window_at(tile_idx, window);
+
+                    if activate {
+                        column.active_tile_idx = tile_idx;
+                        self.activate_column(col_idx);
+                        self.floating_is_active = false;
+                    }
+
+                    return;
+                }
+
+                col_idx + 1
+            }
+        };
+
+        let was_empty = self.columns.is_empty();
+        let prev_active_idx = self.active_column_idx;
+
         let column = Column::new(
             window,
             self.view_size,
@@ -547,6 +779,12 @@ impl<W: LayoutElement> Workspace<W> {
         let width = column.width();
         self.columns.insert(idx, column);
 
+        if !was_empty && idx <= self.active_column_idx {
+            // Keep the view in place as the active column shifts to the right.
+            self.active_column_idx += 1;
+            self.view_offset -= width + self.options.gaps;
+        }
+
         if activate {
             // If this is the first window on an empty workspace, skip the animation from whatever
             // view_offset was left over.
@@ -559,11 +797,13 @@ impl<W: LayoutElement> Workspace<W> {
                     // exclusive zones.
                     self.view_offset = self.compute_new_view_offset_for_column(self.column_x(0), 0);
                 }
-                self.view_offset_anim = None;
+                self.view_offset_adj = None;
             }
 
             self.activate_column(idx);
-            self.activate_prev_column_on_removal = true;
+            // Only go back to the previous column if the new one was right next to it.
+            self.activate_prev_column_on_removal = was_empty || idx == prev_active_idx + 1;
+            self.floating_is_active = false;
         }
     }
 
@@ -574,6 +814,13 @@ impl<W: LayoutElement> Workspace<W> {
         width: ColumnWidth,
         is_full_width: bool,
     ) {
+        if self.floating.contains(right_of) {
+            // Keep windows opened from a floating window together with it.
+            let activate = self.floating_is_active && self.floating.focus() == Some(right_of);
+            self.add_floating_window(window, activate);
+            return;
+        }
+
         self.enter_output_for_window(&window);
 
         let right_of_idx = self
@@ -631,11 +878,35 @@ impl<W: LayoutElement> Workspace<W> {
                     // exclusive zones.
                     self.view_offset = self.compute_new_view_offset_for_column(self.column_x(0), 0);
                 }
-                self.view_offset_anim = None;
+                self.view_offset_adj = None;
             }
 
             self.activate_column(idx);
             self.activate_prev_column_on_removal = true;
+            self.floating_is_active = false;
+        }
+    }
+
+    pub fn add_floating_window(&mut self, window: W, activate: bool) {
+        self.add_floating_window_at(window, None, activate);
+    }
+
+    /// Adds a floating window with its tile at the given location relative to the view, or
+    /// centered if `None`.
+    pub fn add_floating_window_at(
+        &mut self,
+        window: W,
+        loc: Option<Point<i32, Logical>>,
+        activate: bool,
+    ) {
+        self.enter_output_for_window(&window);
+
+        // The first floating window on an otherwise empty workspace always gets focus.
+        let activate = activate || (self.columns.is_empty() && self.floating.is_empty());
+        self.floating.add_window_at(window, loc, activate);
+
+        if activate {
+            self.floating_is_active = true;
         }
     }
 
@@ -643,6 +914,13 @@ impl<W: LayoutElement> Workspace<W> {
         let column = &mut self.columns[column_idx];
         let window = column.tiles.remove(window_idx).into_window();
         column.heights.remove(window_idx);
+        if self
+            .interactive_resize
+            .as_ref()
+            .map_or(false, |resize| resize.window == window)
+        {
+            self.interactive_resize = None;
+        }
 
         if let Some(output) = &self.output {
             window.output_leave(output);
@@ -660,6 +938,7 @@ impl<W: LayoutElement> Workspace<W> {
             // view jumps.
             self.columns.remove(column_idx);
             if self.columns.is_empty() {
+                self.floating_is_active = !self.floating.is_empty();
                 return window;
             }
 
@@ -692,6 +971,14 @@ impl<W: LayoutElement> Workspace<W> {
             }
         }
 
+        if self
+            .interactive_resize
+            .as_ref()
+            .map_or(false, |resize| column.contains(&resize.window))
+        {
+            self.interactive_resize = None;
+        }
+
         if column_idx + 1 == self.active_column_idx {
             // The previous column, that we were going to activate upon removal of the active
             // column, has just been itself removed.
@@ -702,6 +989,7 @@ impl<W: LayoutElement> Workspace<W> {
         // position, which can include the column we're removing here. This leads to unwanted
         // view jumps.
         if self.columns.is_empty() {
+            self.floating_is_active = !self.floating.is_empty();
             return column;
         }
 
@@ -719,7 +1007,35 @@ impl<W: LayoutElement> Workspace<W> {
         column
     }
 
-    pub fn remove_window(&mut self, window: &W) {
+    pub fn remove_floating_window(&mut self, window: &W) -> W {
+        let window = self.floating.remove_window(window);
+        self.on_floating_window_removed(&window);
+        window
+    }
+
+    pub fn remove_active_floating_window(&mut self) -> Option<W> {
+        let window = self.floating.remove_active_window()?;
+        self.on_floating_window_removed(&window);
+        Some(window)
+    }
+
+    fn on_floating_window_removed(&mut self, window: &W) {
+        if let Some(output) = &self.output {
+            window.output_leave(output);
+        }
+
+        self.interactive_resize_end(window);
+
+        if self.floating.is_empty() {
+            self.floating_is_active = false;
+        }
+    }
+
+    pub fn remove_window(&mut self, window: &W) -> W {
+        if self.floating.contains(window) {
+            return self.remove_floating_window(window);
+        }
+
         let column_idx = self
             .columns
             .iter()
@@ -728,20 +1044,39 @@ impl<W: LayoutElement> Workspace<W> {
         let column = &self.columns[column_idx];
 
         let window_idx = column.position(window).unwrap();
-        self.remove_window_by_idx(column_idx, window_idx);
+        self.remove_window_by_idx(column_idx, window_idx)
     }
 
     pub fn update_window(&mut self, window: &W) {
+        // Windows resized from the left or top edge grow towards that edge.
+        let resize_edges = self
+            .interactive_resize
+            .as_ref()
+            .filter(|resize| resize.window == *window)
+            .map(|resize| resize.edges);
+
+        if self.floating.contains(window) {
+            self.floating.update_window(window, resize_edges);
+            return;
+        }
+
         let (idx, column) = self
             .columns
             .iter_mut()
             .enumerate()
             .find(|(_, col)| col.contains(window))
             .unwrap();
+        let prev_width = column.width();
         column.update_window(window);
         column.update_tile_sizes();
+        let width_change = column.width() - prev_width;
 
         if idx == self.active_column_idx {
+            if resize_edges.map_or(false, |edges| edges.contains(ResizeEdge::LEFT)) {
+                // Keep the right edge of the column in place.
+                self.view_offset += width_change;
+            }
+
             // We might need to move the view to ensure the resized window is still visible.
             let current_x = self.view_pos();
 
@@ -756,6 +1091,12 @@ impl<W: LayoutElement> Workspace<W> {
     }
 
     pub fn activate_window(&mut self, window: &W) {
+        if self.floating.contains(window) {
+            self.floating.activate_window(window);
+            self.floating_is_active = true;
+            return;
+        }
+
         let column_idx = self
             .columns
             .iter()
@@ -765,6 +1106,176 @@ impl<W: LayoutElement> Workspace<W> {
 
         column.activate_window(window);
         self.activate_column(column_idx);
+        self.floating_is_active = false;
+    }
+
+    /// Returns the focused window on this workspace, if any.
+    pub fn focus(&self) -> Option<&W> {
+        if self.floating_is_active {
+            return self.floating.focus();
+        }
+
+        if self.columns.is_empty() {
+            return None;
+        }
+
+        let column = &self.columns[self.active_column_idx];
+        Some(column.tiles[column.active_tile_idx].window())
+    }
+
+    /// Returns the position of the window's tile relative to the view, if it's visible.
+    pub fn tile_position(&self, window: &W) -> Option<Point<i32, Logical>> {
+        if let Some(pos) = self.floating.tile_position(window) {
+            return Some(pos);
+        }
+
+        if self.columns.is_empty() {
+            return None;
+        }
+
+        self.tiles_in_render_order()
+            .find(|(tile, _)| tile.window() == window)
+            .map(|(_, pos)| pos)
+    }
+
+    /// Returns the visual geometry of the window relative to the view.
+    ///
+    /// Unlike the tile position, this excludes the border around the window.
+    pub fn window_geometry(&self, window: &W) -> Option<Rectangle<i32, Logical>> {
+        let tile = self
+            .floating
+            .tiles
+            .iter()
+            .chain(self.columns.iter().flat_map(|col| &col.tiles))
+            .find(|tile| tile.window() == window)?;
+        let tile_pos = self.tile_position(window)?;
+
+        Some(Rectangle::from_loc_and_size(
+            tile_pos + tile.window_loc(),
+            tile.window_size(),
+        ))
+    }
+
+    /// Animates the focused window moving into place from the given tile position relative to
+    /// the view.
+    pub fn animate_focus_move_from(&mut self, from: Point<i32, Logical>) {
+        let Some(pos) = self.focus().and_then(|win| self.tile_position(win)) else {
+            return;
+        };
+
+        let tile = if self.floating_is_active {
+            &mut self.floating.tiles[0]
+        } else {
+            let column = &mut self.columns[self.active_column_idx];
+            &mut column.tiles[column.active_tile_idx]
+        };
+        tile.animate_move_from(from - pos);
+    }
+
+    /// Computes where a window dropped at the given position relative to the view would go.
+    pub fn insert_position(&self, pos: Point<f64, Logical>) -> InsertPosition {
+        // Work in the same coordinates as column_x().
+        let x = pos.x + f64::from(self.view_pos());
+
+        let mut col_x = 0;
+        for (col_idx, col) in self.columns.iter().enumerate() {
+            let width = f64::from(col.width());
+
+            // The outer quarters of a column, and the gap before it, make a new column.
+            if x < f64::from(col_x) + width / 4. {
+                return InsertPosition::NewColumn(col_idx);
+            }
+
+            if x < f64::from(col_x) + width * 3. / 4. {
+                if col.is_fullscreen {
+                    return InsertPosition::NewColumn(col_idx + 1);
+                }
+
+                return InsertPosition::InColumn(col_idx, col.insert_idx_at_y(pos.y));
+            }
+
+            col_x += col.width() + self.options.gaps;
+        }
+
+        InsertPosition::NewColumn(self.columns.len())
+    }
+
+    /// Sets the position to show the insert hint at, or hides it if `None`.
+    pub fn set_insert_hint(&mut self, position: Option<InsertPosition>) {
+        self.insert_hint = position;
+        self.update_insert_hint();
+    }
+
+    fn update_insert_hint(&mut self) {
+        let Some(position) = self.insert_hint else {
+            return;
+        };
+
+        const THICKNESS: i32 = 8;
+
+        let gaps = self.options.gaps;
+        let view_pos = self.view_pos();
+
+        let area = match position {
+            InsertPosition::NewColumn(col_idx) => {
+                // In the middle of the gap before the column.
+                let x = self.column_x(col_idx) - gaps / 2 - view_pos;
+                Rectangle::from_loc_and_size(
+                    (x - THICKNESS / 2, self.working_area.loc.y + gaps),
+                    (THICKNESS, max(self.working_area.size.h - gaps * 2, 1)),
+                )
+            }
+            InsertPosition::InColumn(col_idx, tile_idx) => {
+                let col = &self.columns[col_idx];
+                let x = self.column_x(col_idx) - view_pos;
+
+                // In the middle of the gap before the tile.
+                let y = if tile_idx == 0 {
+                    col.tile_y(0) - gaps / 2
+                } else {
+                    let prev_idx = tile_idx - 1;
+                    col.tile_y(prev_idx) + col.tiles[prev_idx].tile_size().h + gaps / 2
+                };
+
+                Rectangle::from_loc_and_size(
+                    (x, y - THICKNESS / 2),
+                    (max(col.width(), 1), THICKNESS),
+                )
+            }
+        };
+
+        self.insert_hint_area = area;
+        self.insert_hint_buffer.resize(area.size);
+        self.insert_hint_buffer
+            .set_color(self.options.insert_hint.color.into());
+    }
+
+    pub fn window_y(&self, window: &W) -> Option<i32> {
+        if let Some(y) = self.floating.window_y(window) {
+            return Some(y);
+        }
+
+        self.columns.iter().find_map(|col| {
+            let idx = col.position(window)?;
+            Some(col.window_y(idx))
+        })
+    }
+
+    pub fn start_open_animation(&mut self, window: &W) -> bool {
+        if self.floating.start_open_animation(window) {
+            return true;
+        }
+
+        for col in &mut self.columns {
+            for tile in &mut col.tiles {
+                if tile.window() == window {
+                    tile.start_open_animation();
+                    return true;
+                }
+            }
+        }
+
+        false
     }
 
     #[cfg(test)]
@@ -779,13 +1290,44 @@ impl<W: LayoutElement> Workspace<W> {
                 column.verify_invariants();
             }
         }
+
+        if self.floating.is_empty() {
+            assert!(
+                !self.floating_is_active,
+                "floating can't be active without floating windows"
+            );
+        } else if self.columns.is_empty() {
+            assert!(
+                self.floating_is_active,
+                "floating must be active when there are no columns"
+            );
+        }
+
+        self.floating.verify_invariants();
+
+        if let Some(resize) = &self.interactive_resize {
+            assert!(
+                self.has_window(&resize.window),
+                "interactively resized window must be on this workspace"
+            );
+        }
     }
 
     pub fn focus_left(&mut self) {
+        if self.floating_is_active {
+            self.floating.focus_left();
+            return;
+        }
+
         self.activate_column(self.active_column_idx.saturating_sub(1));
     }
 
     pub fn focus_right(&mut self) {
+        if self.floating_is_active {
+            self.floating.focus_right();
+            return;
+        }
+
         if self.columns.is_empty() {
             return;
         }
@@ -794,11 +1336,15 @@ impl<W: LayoutElement> Workspace<W> {
     }
 
     pub fn focus_column_first(&mut self) {
+        if self.floating_is_active {
+            return;
+        }
+
         self.activate_column(0);
     }
 
     pub fn focus_column_last(&mut self) {
-        if self.columns.is_empty() {
+        if self.columns.is_empty() || self.floating_is_active {
             return;
         }
 
@@ -806,6 +1352,11 @@ impl<W: LayoutElement> Workspace<W> {
     }
 
     pub fn focus_down(&mut self) {
+        if self.floating_is_active {
+            self.floating.focus_down();
+            return;
+        }
+
         if self.columns.is_empty() {
             return;
         }
@@ -814,11 +1365,52 @@ impl<W: LayoutElement> Workspace<W> {
     }
 
     pub fn focus_up(&mut self) {
+        if self.floating_is_active {
+            self.floating.focus_up();
+            return;
+        }
+
         if self.columns.is_empty() {
             return;
         }
-
-        self.columns[self.active_column_idx].focus_up();
+
+        self.columns[self.active_column_idx].focus_up();
+    }
+
+    /// Moves the focused window between the floating layer and the columns.
+    pub fn toggle_window_floating(&mut self) {
+        if self.floating_is_active {
+            let window = self.remove_active_floating_window().unwrap();
+            let width = self
+                .options
+                .default_width
+                .unwrap_or_else(|| ColumnWidth::Fixed(window.size().w));
+            self.add_window(window, true, width, false);
+            return;
+        }
+
+        if self.columns.is_empty() {
+            return;
+        }
+
+        let column = &self.columns[self.active_column_idx];
+        if column.is_fullscreen {
+            // Floating windows can't be fullscreen.
+            return;
+        }
+
+        let window = self.remove_window_by_idx(self.active_column_idx, column.active_tile_idx);
+        self.add_floating_window(window, true);
+    }
+
+    pub fn switch_focus_floating_tiling(&mut self) {
+        if self.floating_is_active {
+            if !self.columns.is_empty() {
+                self.floating_is_active = false;
+            }
+        } else if !self.floating.is_empty() {
+            self.floating_is_active = true;
+        }
     }
 
     fn move_column_to(&mut self, new_idx: usize) {
@@ -839,12 +1431,16 @@ impl<W: LayoutElement> Workspace<W> {
     }
 
     pub fn move_left(&mut self) {
+        if self.floating_is_active {
+            return;
+        }
+
         let new_idx = self.active_column_idx.saturating_sub(1);
         self.move_column_to(new_idx);
     }
 
     pub fn move_right(&mut self) {
-        if self.columns.is_empty() {
+        if self.columns.is_empty() || self.floating_is_active {
             return;
         }
 
@@ -853,11 +1449,15 @@ impl<W: LayoutElement> Workspace<W> {
     }
 
     pub fn move_column_to_first(&mut self) {
+        if self.floating_is_active {
+            return;
+        }
+
         self.move_column_to(0);
     }
 
     pub fn move_column_to_last(&mut self) {
-        if self.columns.is_empty() {
+        if self.columns.is_empty() || self.floating_is_active {
             return;
         }
 
@@ -866,7 +1466,7 @@ impl<W: LayoutElement> Workspace<W> {
     }
 
     pub fn move_down(&mut self) {
-        if self.columns.is_empty() {
+        if self.columns.is_empty() || self.floating_is_active {
             return;
         }
 
@@ -874,7 +1474,7 @@ impl<W: LayoutElement> Workspace<W> {
     }
 
     pub fn move_up(&mut self) {
-        if self.columns.is_empty() {
+        if self.columns.is_empty() || self.floating_is_active {
             return;
         }
 
@@ -882,7 +1482,7 @@ impl<W: LayoutElement> Workspace<W> {
     }
 
     pub fn consume_or_expel_window_left(&mut self) {
-        if self.columns.is_empty() {
+        if self.columns.is_empty() || self.floating_is_active {
             return;
         }
 
@@ -915,7 +1515,7 @@ impl<W: LayoutElement> Workspace<W> {
     }
 
     pub fn consume_or_expel_window_right(&mut self) {
-        if self.columns.is_empty() {
+        if self.columns.is_empty() || self.floating_is_active {
             return;
         }
 
@@ -946,7 +1546,7 @@ impl<W: LayoutElement> Workspace<W> {
     }
 
     pub fn consume_into_column(&mut self) {
-        if self.columns.len() < 2 {
+        if self.columns.len() < 2 || self.floating_is_active {
             return;
         }
 
@@ -963,7 +1563,7 @@ impl<W: LayoutElement> Workspace<W> {
     }
 
     pub fn expel_from_column(&mut self) {
-        if self.columns.is_empty() {
+        if self.columns.is_empty() || self.floating_is_active {
             return;
         }
 
@@ -981,6 +1581,10 @@ impl<W: LayoutElement> Workspace<W> {
     }
 
     pub fn center_column(&mut self) {
+        if self.floating_is_active {
+            return;
+        }
+
         let center_x = self.view_pos();
         self.animate_view_offset_to_column_centered(center_x, self.active_column_idx);
     }
@@ -989,6 +1593,161 @@ impl<W: LayoutElement> Workspace<W> {
         self.column_x(self.active_column_idx) + self.view_offset
     }
 
+    /// Returns the view positions where some column is aligned with the working area, together
+    /// with the index of that column.
+    fn view_snap_points(&self) -> Vec<(i32, usize)> {
+        let area = self.working_area;
+        let mut points = Vec::new();
+
+        for (idx, col) in self.columns.iter().enumerate() {
+            let col_x = self.column_x(idx);
+
+            if col.is_fullscreen {
+                points.push((col_x, idx));
+                continue;
+            }
+
+            let width = col.width();
+            if area.size.w <= width {
+                points.push((col_x - area.loc.x, idx));
+                continue;
+            }
+
+            if self.options.center_focused_column == CenterFocusedColumn::Always {
+                points.push((col_x - (area.size.w - width) / 2 - area.loc.x, idx));
+                continue;
+            }
+
+            // Same padding as in compute_new_view_offset().
+            let padding = ((area.size.w - width) / 2).clamp(0, self.options.gaps);
+            points.push((col_x - padding - area.loc.x, idx));
+            points.push((col_x + width + padding - area.size.w - area.loc.x, idx));
+        }
+
+        points
+    }
+
+    pub fn view_offset_gesture_begin(&mut self) {
+        if self.columns.is_empty() {
+            return;
+        }
+
+        let gesture = ViewGesture {
+            tracked_view_pos: self.view_pos() as f64,
+        };
+        self.view_offset_adj = Some(ViewOffsetAdjustment::Gesture(gesture));
+    }
+
+    /// Moves the view along with the gesture.
+    ///
+    /// Returns `None` if there's no gesture on this workspace, and otherwise whether the view
+    /// has moved.
+    pub fn view_offset_gesture_update(&mut self, delta_x: f64) -> Option<bool> {
+        let Some(ViewOffsetAdjustment::Gesture(gesture)) = &mut self.view_offset_adj else {
+            return None;
+        };
+
+        let norm_factor = self.working_area.size.w as f64 / VIEW_GESTURE_WORKING_AREA_MOVEMENT;
+        gesture.tracked_view_pos += delta_x * norm_factor;
+        let tracked_view_pos = gesture.tracked_view_pos;
+
+        let points = self.view_snap_points();
+        let (Some(min_pos), Some(max_pos)) = (
+            points.iter().map(|(pos, _)| *pos).min(),
+            points.iter().map(|(pos, _)| *pos).max(),
+        ) else {
+            return Some(false);
+        };
+
+        let pos = rubber_band(
+            tracked_view_pos,
+            min_pos as f64,
+            max_pos as f64,
+            self.working_area.size.w as f64 / 4.,
+        );
+        let new_view_offset = pos.round() as i32 - self.column_x(self.active_column_idx);
+        if self.view_offset == new_view_offset {
+            return Some(false);
+        }
+
+        self.view_offset = new_view_offset;
+        Some(true)
+    }
+
+    /// Finishes the gesture by snapping the view to the nearest column edge.
+    ///
+    /// Returns `false` if there was no gesture on this workspace.
+    pub fn view_offset_gesture_end(&mut self, cancelled: bool) -> bool {
+        let Some(ViewOffsetAdjustment::Gesture(_)) = &self.view_offset_adj else {
+            return false;
+        };
+        self.view_offset_adj = None;
+
+        if self.columns.is_empty() {
+            return true;
+        }
+
+        let current_x = self.view_pos();
+
+        if cancelled {
+            self.animate_view_offset_to_column(current_x, self.active_column_idx);
+            return true;
+        }
+
+        // FIXME: keep track of gesture velocity and use it to pick the snap point.
+        let (snap_pos, snap_idx) = self
+            .view_snap_points()
+            .into_iter()
+            .min_by_key(|(pos, _)| pos.abs_diff(current_x))
+            .unwrap();
+
+        // Keep the active column if it stays fully visible, otherwise activate the snapped one.
+        let active_x = self.column_x(self.active_column_idx) - snap_pos;
+        let active_col = &self.columns[self.active_column_idx];
+        let active_visible = if active_col.is_fullscreen {
+            active_x == 0
+        } else {
+            self.working_area.loc.x <= active_x
+                && active_x + active_col.width()
+                    <= self.working_area.loc.x + self.working_area.size.w
+        };
+
+        let idx = if active_visible {
+            self.active_column_idx
+        } else {
+            snap_idx
+        };
+
+        if idx != self.active_column_idx {
+            self.activate_column(idx);
+            self.floating_is_active = false;
+        }
+
+        // Snap to the picked point rather than to where activate_column() scrolled.
+        let new_view_offset = snap_pos - self.column_x(idx);
+        self.animate_view_offset(current_x, idx, new_view_offset);
+
+        true
+    }
+
+    /// Returns the horizontal extent of the view together with the whole column strip, relative
+    /// to the current view position.
+    ///
+    /// Used by the overview to fit all columns on screen.
+    pub fn content_x_range(&self) -> (i32, i32) {
+        let view_width = self.view_size.w;
+        if self.columns.is_empty() {
+            return (0, view_width);
+        }
+
+        let view_pos = self.visual_column_x(self.active_column_idx) + self.view_offset;
+        let strip_width = self.visual_column_x(self.columns.len()) - self.options.gaps;
+
+        let left = min(0, -view_pos);
+        let right = max(view_width, -view_pos + strip_width);
+        (left, right)
+    }
+
     fn tiles_in_render_order(&self) -> impl Iterator<Item = (&'_ Tile<W>, Point<i32, Logical>)> {
         let view_pos = self.visual_column_x(self.active_column_idx) + self.view_offset;
 
@@ -1020,6 +1779,10 @@ impl<W: LayoutElement> Workspace<W> {
                             return None;
                         }
 
+                        if !col.is_tile_visible(tile_idx) {
+                            return None;
+                        }
+
                         let tile_pos = Point::from((x, y));
                         Some((tile, tile_pos))
                     },
@@ -1032,6 +1795,11 @@ impl<W: LayoutElement> Workspace<W> {
         &self,
         pos: Point<f64, Logical>,
     ) -> Option<(&W, Option<Point<i32, Logical>>)> {
+        // Floating windows are drawn on top, so check them first.
+        if let Some(rv) = self.floating.window_under(pos) {
+            return Some(rv);
+        }
+
         if self.columns.is_empty() {
             return None;
         }
@@ -1050,39 +1818,224 @@ impl<W: LayoutElement> Workspace<W> {
         })
     }
 
-    pub fn toggle_width(&mut self) {
-        if self.columns.is_empty() {
-            return;
+    /// Returns the index of the column with the window, or of the active column if `None`.
+    ///
+    /// Returns `None` if the window is floating, or if there's no window.
+    fn column_idx_for(&self, window: Option<&W>) -> Option<usize> {
+        match window {
+            Some(window) => self.columns.iter().position(|col| col.contains(window)),
+            None => (!self.columns.is_empty() && !self.floating_is_active)
+                .then_some(self.active_column_idx),
         }
+    }
+
+    /// Switches the width of the column with the window, or of the active column if `None`, to
+    /// the next preset.
+    pub fn toggle_width(&mut self, window: Option<&W>) {
+        let Some(idx) = self.column_idx_for(window) else {
+            return;
+        };
 
-        self.columns[self.active_column_idx].toggle_width();
+        self.columns[idx].toggle_width();
     }
 
-    pub fn toggle_full_width(&mut self) {
-        if self.columns.is_empty() {
+    /// Toggles full width of the column with the window, or of the active column if `None`.
+    pub fn toggle_full_width(&mut self, window: Option<&W>) {
+        let Some(idx) = self.column_idx_for(window) else {
+            return;
+        };
+
+        self.columns[idx].toggle_full_width();
+    }
+
+    pub fn toggle_column_tabbed_display(&mut self) {
+        if self.columns.is_empty() || self.floating_is_active {
             return;
         }
 
-        self.columns[self.active_column_idx].toggle_full_width();
+        self.columns[self.active_column_idx].toggle_tabbed_display();
     }
 
-    pub fn set_column_width(&mut self, change: SizeChange) {
-        if self.columns.is_empty() {
+    /// Changes the width of the column with the window, or of the active column if `None`.
+    ///
+    /// Floating windows have their own width changed instead.
+    pub fn set_column_width(&mut self, window: Option<&W>, change: SizeChange) {
+        let floating = match window {
+            Some(window) => self.floating.contains(window),
+            None => self.floating_is_active,
+        };
+        if floating {
+            self.floating.set_window_width(window, change);
             return;
         }
 
-        self.columns[self.active_column_idx].set_column_width(change);
+        let Some(idx) = self.column_idx_for(window) else {
+            return;
+        };
+
+        self.columns[idx].set_column_width(change);
     }
 
-    pub fn set_window_height(&mut self, change: SizeChange) {
+    /// Changes the height of the window, or of the active window if `None`.
+    pub fn set_window_height(&mut self, window: Option<&W>, change: SizeChange) {
+        if let Some(window) = window {
+            if self.floating.contains(window) {
+                self.floating.set_window_height(Some(window), change);
+                return;
+            }
+
+            let (col, tile_idx) = self
+                .columns
+                .iter_mut()
+                .find_map(|col| col.position(window).map(|tile_idx| (col, tile_idx)))
+                .unwrap();
+            col.set_window_height(change, Some(tile_idx));
+            return;
+        }
+
+        if self.floating_is_active {
+            self.floating.set_window_height(None, change);
+            return;
+        }
+
         if self.columns.is_empty() {
             return;
         }
 
-        self.columns[self.active_column_idx].set_window_height(change);
+        self.columns[self.active_column_idx].set_window_height(change, None);
+    }
+
+    /// Starts an interactive resize of the window from the given edges.
+    ///
+    /// Returns `false` if the window can't be resized.
+    pub fn interactive_resize_begin(&mut self, window: W, edges: ResizeEdge) -> bool {
+        if self.interactive_resize.is_some() || edges.is_empty() {
+            return false;
+        }
+
+        let original_window_size = if let Some(tile) = self
+            .floating
+            .tiles
+            .iter()
+            .find(|tile| *tile.window() == window)
+        {
+            tile.window_size()
+        } else {
+            let Some(col) = self.columns.iter().find(|col| col.contains(&window)) else {
+                return false;
+            };
+
+            // Fullscreen windows take up the whole view.
+            if col.is_fullscreen {
+                return false;
+            }
+
+            col.tiles[col.position(&window).unwrap()].window_size()
+        };
+
+        self.interactive_resize = Some(InteractiveResize {
+            window,
+            edges,
+            original_window_size,
+        });
+
+        true
+    }
+
+    /// Updates an interactive resize with the pointer moved by `delta` since the start.
+    ///
+    /// Returns `false` if this window is not being resized.
+    pub fn interactive_resize_update(&mut self, window: &W, delta: Point<f64, Logical>) -> bool {
+        let Some(resize) = &self.interactive_resize else {
+            return false;
+        };
+
+        if resize.window != *window {
+            return false;
+        }
+
+        let edges = resize.edges;
+        let delta: Point<i32, Logical> = delta.to_i32_round();
+        let mut size = resize.original_window_size;
+        if edges.contains(ResizeEdge::LEFT) {
+            size.w = size.w.saturating_sub(delta.x);
+        } else if edges.contains(ResizeEdge::RIGHT) {
+            size.w = size.w.saturating_add(delta.x);
+        }
+        if edges.contains(ResizeEdge::TOP) {
+            size.h = size.h.saturating_sub(delta.y);
+        } else if edges.contains(ResizeEdge::BOTTOM) {
+            size.h = size.h.saturating_add(delta.y);
+        }
+
+        if self.floating.contains(window) {
+            self.floating.request_window_size(window, size);
+            return true;
+        }
+
+        let col = self
+            .columns
+            .iter_mut()
+            .find(|col| col.contains(window))
+            .unwrap();
+
+        // The window may have been fullscreened during the resize.
+        if col.is_fullscreen {
+            return true;
+        }
+
+        let tile_idx = col.position(window).unwrap();
+
+        if edges.intersects(ResizeEdge::LEFT_RIGHT) {
+            // Clamp it against the window width constraints.
+            let win = col.tiles[tile_idx].window();
+            let min_w = win.min_size().w;
+            let max_w = win.max_size().w;
+
+            let mut width = size.w;
+            if max_w > 0 {
+                width = min(width, max_w);
+            }
+            if min_w > 0 {
+                width = max(width, min_w);
+            }
+
+            col.set_column_width(SizeChange::SetFixed(width));
+        }
+
+        if edges.intersects(ResizeEdge::TOP_BOTTOM) {
+            col.set_window_height(SizeChange::SetFixed(size.h), Some(tile_idx));
+        }
+
+        true
+    }
+
+    pub fn interactive_resize_end(&mut self, window: &W) {
+        if self
+            .interactive_resize
+            .as_ref()
+            .map_or(false, |resize| resize.window == *window)
+        {
+            self.interactive_resize = None;
+        }
     }
 
     pub fn set_fullscreen(&mut self, window: &W, is_fullscreen: bool) {
+        if self.floating.contains(window) {
+            if !is_fullscreen {
+                return;
+            }
+
+            // Fullscreen windows live in the columns, so move the window there first.
+            let activate = self.floating_is_active && self.floating.focus() == Some(window);
+            let removed = self.remove_floating_window(window);
+            let width = self
+                .options
+                .default_width
+                .unwrap_or_else(|| ColumnWidth::Fixed(removed.size().w));
+            self.add_window(removed, activate, width, false);
+        }
+
         let (mut col_idx, tile_idx) = self
             .columns
             .iter()
@@ -1125,6 +2078,11 @@ impl<W: LayoutElement> Workspace<W> {
     }
 
     pub fn toggle_fullscreen(&mut self, window: &W) {
+        if self.floating.contains(window) {
+            self.set_fullscreen(window, true);
+            return;
+        }
+
         let col = self
             .columns
             .iter_mut()
@@ -1140,7 +2098,7 @@ impl<W: LayoutElement> Workspace<W> {
             return false;
         }
 
-        if self.view_offset_anim.is_some() {
+        if self.view_offset_adj.is_some() {
             return false;
         }
 
@@ -1151,10 +2109,6 @@ impl<W: LayoutElement> Workspace<W> {
         &self,
         renderer: &mut R,
     ) -> Vec<WorkspaceRenderElement<R>> {
-        if self.columns.is_empty() {
-            return vec![];
-        }
-
         // FIXME: workspaces should probably cache their last used scale so they can be correctly
         // rendered even with no outputs connected.
         let output_scale = self
@@ -1163,11 +2117,41 @@ impl<W: LayoutElement> Workspace<W> {
             .map(|o| Scale::from(o.current_scale().fractional_scale()))
             .unwrap_or(Scale::from(1.));
 
-        let mut rv = vec![];
-        let mut first = true;
+        let mut rv: Vec<WorkspaceRenderElement<R>> = vec![];
+
+        // The insert hint goes on top of everything.
+        if self.insert_hint.is_some() && !self.options.insert_hint.off {
+            rv.push(
+                SolidColorRenderElement::from_buffer(
+                    &self.insert_hint_buffer,
+                    self.insert_hint_area
+                        .loc
+                        .to_physical_precise_round(output_scale),
+                    output_scale,
+                    1.,
+                    Kind::Unspecified,
+                )
+                .into(),
+            );
+        }
+
+        // Then the floating windows.
+        rv.extend(
+            self.floating
+                .render_elements(renderer, output_scale, self.floating_is_active)
+                .into_iter()
+                .map(Into::into),
+        );
+
+        if self.columns.is_empty() {
+            return rv;
+        }
+
+        let mut first = !self.floating_is_active;
 
         for (tile, tile_pos) in self.tiles_in_render_order() {
-            // For the active tile (which comes first), draw the focus ring.
+            // For the active tile (which comes first), draw the focus ring, unless the focus is on
+            // a floating window.
             let focus_ring = first;
             first = false;
 
@@ -1177,6 +2161,17 @@ impl<W: LayoutElement> Workspace<W> {
             );
         }
 
+        // Draw the tab indicators for tabbed columns.
+        let mut x = -(self.visual_column_x(self.active_column_idx) + self.view_offset);
+        for col in &self.columns {
+            if col.display_mode == ColumnDisplay::Tabbed && !col.is_fullscreen {
+                let loc = Point::from((x, col.tile_y(col.active_tile_idx)));
+                rv.extend(col.tab_indicator.render(loc, output_scale).map(Into::into));
+            }
+
+            x += col.visual_width() + self.options.gaps;
+        }
+
         rv
     }
 }
@@ -1189,6 +2184,7 @@ impl Workspace<Window> {
             for (tile_idx, tile) in col.tiles.iter().enumerate() {
                 let win = tile.window();
                 let active = is_active
+                    && !self.floating_is_active
                     && self.active_column_idx == col_idx
                     && col.active_tile_idx == tile_idx;
                 win.set_activated(active);
@@ -1201,6 +2197,19 @@ impl Workspace<Window> {
                 win.refresh();
             }
         }
+
+        for (idx, tile) in self.floating.tiles.iter().enumerate() {
+            let win = tile.window();
+            let active = is_active && self.floating_is_active && idx == 0;
+            win.set_activated(active);
+
+            win.toplevel().with_pending_state(|state| {
+                state.bounds = Some(bounds);
+            });
+
+            win.toplevel().send_pending_configure();
+            win.refresh();
+        }
     }
 }
 
@@ -1220,6 +2229,8 @@ impl<W: LayoutElement> Column<W> {
             width,
             is_full_width,
             is_fullscreen: false,
+            display_mode: ColumnDisplay::Normal,
+            tab_indicator: TabIndicator::new(options.tab_indicator),
             view_size,
             working_area,
             options,
@@ -1271,6 +2282,8 @@ impl<W: LayoutElement> Column<W> {
             tile.update_config(options.clone());
         }
 
+        self.tab_indicator.update_config(options.tab_indicator);
+
         self.options = options;
 
         if update_sizes {
@@ -1289,6 +2302,16 @@ impl<W: LayoutElement> Column<W> {
             let is_active = is_active && tile_idx == self.active_tile_idx;
             tile.advance_animations(current_time, is_active);
         }
+
+        // Leave room for the focus ring, which is drawn around the tile.
+        let mut offset = 0;
+        if !self.options.focus_ring.off {
+            offset = i32::from(self.options.focus_ring.width);
+        }
+
+        let tile_size = self.tiles[self.active_tile_idx].tile_size();
+        self.tab_indicator
+            .update(tile_size, offset, self.tiles.len(), self.active_tile_idx);
     }
 
     pub fn are_animations_ongoing(&self) -> bool {
@@ -1312,13 +2335,34 @@ impl<W: LayoutElement> Column<W> {
     }
 
     fn add_window(&mut self, window: W) {
+        self.add_window_at(self.tiles.len(), window);
+    }
+
+    fn add_window_at(&mut self, idx: usize, window: W) {
         let tile = Tile::new(window, self.options.clone());
         self.is_fullscreen = false;
-        self.tiles.push(tile);
-        self.heights.push(WindowHeight::Auto);
+        self.tiles.insert(idx, tile);
+        self.heights.insert(idx, WindowHeight::Auto);
+
+        if idx <= self.active_tile_idx && self.tiles.len() > 1 {
+            self.active_tile_idx += 1;
+        }
+
         self.update_tile_sizes();
     }
 
+    /// Returns the tile index to insert a window at for the given Y position.
+    fn insert_idx_at_y(&self, y: f64) -> usize {
+        // Only the active tab is visible, so insert right after it.
+        if self.display_mode == ColumnDisplay::Tabbed {
+            return self.active_tile_idx + 1;
+        }
+
+        zip(&self.tiles, self.tile_ys())
+            .position(|(tile, tile_y)| y < f64::from(tile_y) + f64::from(tile.tile_size().h) / 2.)
+            .unwrap_or(self.tiles.len())
+    }
+
     fn update_window(&mut self, window: &W) {
         let tile = self
             .tiles
@@ -1373,6 +2417,25 @@ impl<W: LayoutElement> Column<W> {
         let width = width.resolve(&self.options, self.working_area.size.w);
         let width = max(min(width, max_width), min_width);
 
+        if self.display_mode == ColumnDisplay::Tabbed {
+            // Every tab takes the full column height, since only one is visible at a time.
+            let height = self.working_area.size.h - self.options.gaps * 2;
+
+            for (tile, (min_size, max_size)) in zip(&mut self.tiles, zip(&min_size, &max_size)) {
+                let mut height = height;
+                if max_size.h > 0 {
+                    height = min(height, max_size.h);
+                }
+                if min_size.h > 0 {
+                    height = max(height, min_size.h);
+                }
+
+                tile.request_tile_size(Size::from((width, max(height, 1))));
+            }
+
+            return;
+        }
+
         // Compute the tile heights. Start by converting window heights to tile heights.
         let mut heights = zip(&self.tiles, &self.heights)
             .map(|(tile, height)| match *height {
@@ -1502,10 +2565,23 @@ impl<W: LayoutElement> Column<W> {
     }
 
     fn focus_up(&mut self) {
+        // Tabs wrap around, since they all sit at the same position.
+        if self.display_mode == ColumnDisplay::Tabbed && self.active_tile_idx == 0 {
+            self.active_tile_idx = self.tiles.len() - 1;
+            return;
+        }
+
         self.active_tile_idx = self.active_tile_idx.saturating_sub(1);
     }
 
     fn focus_down(&mut self) {
+        if self.display_mode == ColumnDisplay::Tabbed
+            && self.active_tile_idx == self.tiles.len() - 1
+        {
+            self.active_tile_idx = 0;
+            return;
+        }
+
         self.active_tile_idx = min(self.active_tile_idx + 1, self.tiles.len() - 1);
     }
 
@@ -1628,9 +2704,11 @@ impl<W: LayoutElement> Column<W> {
         self.set_width(width);
     }
 
-    fn set_window_height(&mut self, change: SizeChange) {
-        let current = self.heights[self.active_tile_idx];
-        let tile = &self.tiles[self.active_tile_idx];
+    fn set_window_height(&mut self, change: SizeChange, tile_idx: Option<usize>) {
+        let tile_idx = tile_idx.unwrap_or(self.active_tile_idx);
+
+        let current = self.heights[tile_idx];
+        let tile = &self.tiles[tile_idx];
         let current_window_px = match current {
             WindowHeight::Auto => tile.window_size().h,
             WindowHeight::Fixed(height) => height,
@@ -1663,7 +2741,7 @@ impl<W: LayoutElement> Column<W> {
         };
 
         // Clamp it against the window height constraints.
-        let win = &self.tiles[self.active_tile_idx].window();
+        let win = &self.tiles[tile_idx].window();
         let min_h = win.min_size().h;
         let max_h = win.max_size().h;
 
@@ -1674,7 +2752,15 @@ impl<W: LayoutElement> Column<W> {
             window_height = window_height.max(min_h);
         }
 
-        self.heights[self.active_tile_idx] = WindowHeight::Fixed(window_height.clamp(1, MAX_PX));
+        self.heights[tile_idx] = WindowHeight::Fixed(window_height.clamp(1, MAX_PX));
+        self.update_tile_sizes();
+    }
+
+    fn toggle_tabbed_display(&mut self) {
+        self.display_mode = match self.display_mode {
+            ColumnDisplay::Normal => ColumnDisplay::Tabbed,
+            ColumnDisplay::Tabbed => ColumnDisplay::Normal,
+        };
         self.update_tile_sizes();
     }
 
@@ -1684,6 +2770,11 @@ impl<W: LayoutElement> Column<W> {
         self.update_tile_sizes();
     }
 
+    /// Returns whether the tile is shown, which in tabbed display is only the active tab.
+    pub fn is_tile_visible(&self, tile_idx: usize) -> bool {
+        self.display_mode != ColumnDisplay::Tabbed || tile_idx == self.active_tile_idx
+    }
+
     pub fn window_y(&self, tile_idx: usize) -> i32 {
         let (tile, tile_y) = zip(&self.tiles, self.tile_ys()).nth(tile_idx).unwrap();
         tile_y + tile.window_loc().y
@@ -1700,9 +2791,14 @@ impl<W: LayoutElement> Column<W> {
             y = self.working_area.loc.y + self.options.gaps;
         }
 
+        // Tabs are all shown at the same position.
+        let is_tabbed = self.display_mode == ColumnDisplay::Tabbed;
+
         self.tiles.iter().map(move |tile| {
             let pos = y;
-            y += tile.tile_size().h + self.options.gaps;
+            if !is_tabbed {
+                y += tile.tile_size().h + self.options.gaps;
+            }
             pos
         })
     }
@@ -1742,13 +2838,41 @@ fn compute_new_view_offset(
     }
 }
 
+/// Applies resistance to positions beyond `min` and `max`, approaching `limit` past the edge.
+fn rubber_band(pos: f64, min: f64, max: f64, limit: f64) -> f64 {
+    let resist = |overshoot: f64| limit * (1. - 1. / (overshoot / limit + 1.));
+
+    if pos < min {
+        min - resist(min - pos)
+    } else if max < pos {
+        max + resist(pos - max)
+    } else {
+        pos
+    }
+}
+
 fn set_preferred_scale_transform(window: &impl LayoutElement, output: &Output) {
     // FIXME: cache this on the workspace.
-    let scale = output.current_scale().integer_scale();
+    let scale = output.current_scale();
     let transform = output.current_transform();
     window.set_preferred_scale_transform(scale, transform);
 }
 
+pub fn compute_toplevel_bounds(
+    options: &Options,
+    working_area: Rectangle<i32, Logical>,
+) -> Size<i32, Logical> {
+    let mut border = 0;
+    if !options.border.off {
+        border = options.border.width as i32 * 2;
+    }
+
+    Size::from((
+        max(working_area.size.w - options.gaps * 2 - border, 1),
+        max(working_area.size.h - options.gaps * 2 - border, 1),
+    ))
+}
+
 pub fn compute_working_area(output: &Output, struts: Struts) -> Rectangle<i32, Logical> {
     // Start with the layer-shell non-exclusive zone.
     let mut working_area = layer_map_for_output(output).non_exclusive_zone();
@@ -21,6 +21,10 @@ pub mod render_helpers;
 pub mod screenshot_ui;
 pub mod utils;
 pub mod watcher;
+pub mod window;
+
+#[cfg(test)]
+mod tests;
 
 #[cfg(not(feature = "xdp-gnome-screencast"))]
 pub mod dummy_pw_utils;
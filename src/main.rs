@@ -4,7 +4,7 @@ extern crate tracing;
 use std::fs::{self, File};
 use std::io::{self, Write};
 use std::path::PathBuf;
-use std::process::Command;
+use std::process::{self, Command};
 use std::{env, mem};
 
 use clap::Parser;
@@ -13,7 +13,7 @@ use niri::animation;
 use niri::cli::{Cli, Sub};
 #[cfg(feature = "dbus")]
 use niri::dbus;
-use niri::ipc::client::handle_msg;
+use niri::ipc::client::{self, handle_msg};
 use niri::niri::State;
 use niri::utils::{
     cause_panic, spawn, version, REMOVE_ENV_RUST_BACKTRACE, REMOVE_ENV_RUST_LIB_BACKTRACE,
@@ -79,7 +79,10 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
                 return Ok(());
             }
             Sub::Msg { msg, json } => {
-                handle_msg(msg, json)?;
+                if let Err(err) = handle_msg(msg, json) {
+                    eprintln!("Error: {err:?}");
+                    process::exit(client::exit_code(&err));
+                }
                 return Ok(());
             }
             Sub::Panic => cause_panic(),
@@ -129,16 +132,19 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     });
 
     let mut config_errored = false;
+    let mut config_files = Vec::new();
     let mut config = path
         .as_deref()
-        .and_then(|path| match Config::load(path) {
-            Ok(config) => Some(config),
-            Err(err) => {
-                warn!("{err:?}");
-                config_errored = true;
-                None
-            }
-        })
+        .and_then(
+            |path| match Config::load_with_files(path, &mut config_files) {
+                Ok(config) => Some(config),
+                Err(err) => {
+                    warn!("{err:?}");
+                    config_errored = true;
+                    None
+                }
+            },
+        )
         .unwrap_or_default();
 
     let slowdown = if config.animations.off {
@@ -158,11 +164,22 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
         event_loop.handle(),
         event_loop.get_signal(),
         display,
+        cli.headless,
+        true,
     )
     .unwrap();
 
+    if cli.headless {
+        let headless = state.backend.headless();
+        // Rendering is optional, clients work fine without it.
+        if let Err(err) = headless.add_renderer(&mut state.niri) {
+            warn!("error creating headless renderer: {err:?}");
+        }
+        headless.add_output(&mut state.niri, 1, (1920, 1080));
+    }
+
     // Set WAYLAND_DISPLAY for children.
-    let socket_name = &state.niri.socket_name;
+    let socket_name = state.niri.socket_name.as_ref().unwrap();
     env::set_var("WAYLAND_DISPLAY", socket_name);
     info!(
         "listening on Wayland socket: {}",
@@ -197,20 +214,21 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     };
 
     // Set up config file watcher.
-    let _watcher = if let Some(path) = path.clone() {
-        let (tx, rx) = calloop::channel::sync_channel(1);
-        let watcher = Watcher::new(path.clone(), tx);
-        event_loop
-            .handle()
-            .insert_source(rx, move |event, _, state| match event {
-                calloop::channel::Event::Msg(()) => state.reload_config(path.clone()),
-                calloop::channel::Event::Closed => (),
-            })
-            .unwrap();
-        Some(watcher)
-    } else {
-        None
-    };
+    if let Some(path) = path.clone() {
+        match Watcher::new(config_files) {
+            Ok(watcher) => {
+                event_loop
+                    .handle()
+                    .insert_source(watcher, move |(), files, state| {
+                        // Includes may have changed, so watch whatever the new config was read
+                        // from.
+                        *files = state.reload_config(path.clone());
+                    })
+                    .unwrap();
+            }
+            Err(err) => warn!("error creating config file watcher: {err:?}"),
+        }
+    }
 
     // Spawn commands from cli and auto-start.
     spawn(cli.command);
@@ -1,6 +1,7 @@
 use std::cell::{Cell, RefCell};
 use std::collections::{HashMap, HashSet};
 use std::ffi::OsString;
+use std::os::unix::net::UnixStream;
 use std::path::PathBuf;
 use std::rc::Rc;
 use std::sync::atomic::Ordering;
@@ -11,7 +12,7 @@ use std::{env, mem, thread};
 use _server_decoration::server::org_kde_kwin_server_decoration_manager::Mode as KdeDecorationsMode;
 use anyhow::Context;
 use calloop::futures::Scheduler;
-use niri_config::{Config, TrackLayout};
+use niri_config::{Config, OutputOverride, TrackLayout, WorkspaceReference};
 use smithay::backend::allocator::Fourcc;
 use smithay::backend::renderer::element::memory::MemoryRenderBufferRenderElement;
 use smithay::backend::renderer::element::solid::{SolidColorBuffer, SolidColorRenderElement};
@@ -56,11 +57,12 @@ use smithay::utils::{
     SERIAL_COUNTER,
 };
 use smithay::wayland::compositor::{
-    send_surface_state, with_states, with_surface_tree_downward, CompositorClientState,
-    CompositorState, SurfaceData, TraversalAction,
+    with_states, with_surface_tree_downward, CompositorClientState, CompositorState, SurfaceData,
+    TraversalAction,
 };
 use smithay::wayland::cursor_shape::CursorShapeManagerState;
 use smithay::wayland::dmabuf::DmabufState;
+use smithay::wayland::fractional_scale::FractionalScaleManagerState;
 use smithay::wayland::idle_inhibit::IdleInhibitManagerState;
 use smithay::wayland::idle_notify::IdleNotifierState;
 use smithay::wayland::input_method::{InputMethodManagerState, InputMethodSeat};
@@ -82,10 +84,11 @@ use smithay::wayland::shm::ShmState;
 use smithay::wayland::socket::ListeningSocketSource;
 use smithay::wayland::tablet_manager::{TabletManagerState, TabletSeatTrait};
 use smithay::wayland::text_input::TextInputManagerState;
+use smithay::wayland::viewporter::ViewporterState;
 use smithay::wayland::virtual_keyboard::VirtualKeyboardManagerState;
 
 use crate::backend::tty::SurfaceDmabufFeedback;
-use crate::backend::{Backend, RenderResult, Tty, Winit};
+use crate::backend::{Backend, Headless, RenderResult, Tty, Winit};
 use crate::config_error_notification::ConfigErrorNotification;
 use crate::cursor::{CursorManager, CursorTextureCache, RenderCursor, XCursor};
 #[cfg(feature = "dbus")]
@@ -96,17 +99,24 @@ use crate::exit_confirm_dialog::ExitConfirmDialog;
 use crate::frame_clock::FrameClock;
 use crate::handlers::configure_lock_surface;
 use crate::hotkey_overlay::HotkeyOverlay;
-use crate::input::{apply_libinput_settings, TabletData};
-use crate::ipc::server::IpcServer;
+use crate::input::gesture::GestureState;
+use crate::input::scroll_tracker::ScrollTracker;
+use crate::input::{apply_libinput_settings, TabletData, TouchEdgeSwipe};
+use crate::ipc::server::{self as ipc, IpcServer};
+use crate::layout::tile::TileRenderElement;
+use crate::layout::workspace::Workspace;
 use crate::layout::{Layout, MonitorRenderElement};
 use crate::protocols::foreign_toplevel::{self, ForeignToplevelManagerState};
+use crate::protocols::output_management::{self, OutputManagementManagerState};
 use crate::pw_utils::{Cast, PipeWire};
 use crate::render_helpers::renderer::NiriRenderer;
 use crate::render_helpers::{render_to_texture, render_to_vec};
 use crate::screenshot_ui::{ScreenshotUi, ScreenshotUiRenderElement};
 use crate::utils::{
-    center, get_monotonic_time, make_screenshot_path, output_size, write_png_rgba8,
+    center, closest_representable_scale, get_monotonic_time, make_screenshot_path, output_size,
+    refresh_rate_matches, send_scale_transform, write_png_rgba8,
 };
+use crate::window::{ResolvedWindowRules, WindowId};
 use crate::{animation, niri_render_elements};
 
 const CLEAR_COLOR: [f32; 4] = [0.2, 0.2, 0.2, 1.];
@@ -115,11 +125,19 @@ const CLEAR_COLOR_LOCKED: [f32; 4] = [0.3, 0.1, 0.1, 1.];
 pub struct Niri {
     pub config: Rc<RefCell<Config>>,
 
+    /// Output config from the config file, without the runtime overrides.
+    ///
+    /// `config.outputs` holds this config with `output_overrides` applied on top.
+    pub config_file_outputs: Vec<niri_config::Output>,
+    /// Output config changes made at runtime, keyed by output name.
+    pub output_overrides: HashMap<String, OutputOverride>,
+
     pub event_loop: LoopHandle<'static, State>,
     pub scheduler: Scheduler<()>,
     pub stop_signal: LoopSignal,
     pub display_handle: DisplayHandle,
-    pub socket_name: OsString,
+    /// Name of the Wayland socket, if one was created.
+    pub socket_name: Option<OsString>,
 
     pub start_time: Instant,
 
@@ -142,6 +160,7 @@ pub struct Niri {
 
     pub devices: HashSet<input::Device>,
     pub tablets: HashMap<input::Device, TabletData>,
+    pub touch_edge_swipe: Option<TouchEdgeSwipe>,
 
     // Smithay state.
     pub compositor_state: CompositorState,
@@ -151,6 +170,7 @@ pub struct Niri {
     pub layer_shell_state: WlrLayerShellState,
     pub session_lock_state: SessionLockManagerState,
     pub foreign_toplevel_state: ForeignToplevelManagerState,
+    pub output_management_state: OutputManagementManagerState,
     pub shm_state: ShmState,
     pub output_manager_state: OutputManagerState,
     pub dmabuf_state: DmabufState,
@@ -171,10 +191,20 @@ pub struct Niri {
     pub popup_grab: Option<PopupGrabState>,
     pub presentation_state: PresentationState,
     pub security_context_state: SecurityContextState,
+    pub fractional_scale_manager_state: FractionalScaleManagerState,
+    pub viewporter_state: ViewporterState,
 
     pub seat: Seat<State>,
     /// Scancodes of the keys to suppress.
     pub suppressed_keys: HashSet<u32>,
+    /// Button codes of the mouse buttons to suppress.
+    pub suppressed_buttons: HashSet<u32>,
+    pub horizontal_wheel_tracker: ScrollTracker,
+    pub vertical_wheel_tracker: ScrollTracker,
+    /// Touchpad gesture currently intercepted by the compositor.
+    pub gesture: Option<GestureState>,
+    /// Bind mode whose binds replace the regular ones.
+    pub bind_mode: Option<BindModeState>,
     // This is always a toplevel surface focused as far as niri's logic is concerned, even when
     // popup grabs are active (which means the real keyboard focus is on a popup descending from
     // this toplevel surface).
@@ -257,6 +287,14 @@ pub struct PointerFocus {
     pub surface: (WlSurface, Point<i32, Logical>),
 }
 
+pub struct BindModeState {
+    pub name: String,
+    /// Whether the mode shows the hotkey overlay for as long as it is active.
+    pub show_hotkey_overlay: bool,
+    /// Timer leaving the mode once its timeout runs out.
+    timer: Option<RegistrationToken>,
+}
+
 #[derive(Default)]
 pub enum LockState {
     #[default]
@@ -303,6 +341,8 @@ impl State {
         event_loop: LoopHandle<'static, State>,
         stop_signal: LoopSignal,
         display: Display<State>,
+        headless: bool,
+        create_wayland_socket: bool,
     ) -> Result<Self, Box<dyn std::error::Error>> {
         let _span = tracy_client::span!("State::new");
 
@@ -311,7 +351,10 @@ impl State {
         let has_display =
             env::var_os("WAYLAND_DISPLAY").is_some() || env::var_os("DISPLAY").is_some();
 
-        let mut backend = if has_display {
+        let mut backend = if headless {
+            let headless = Headless::new();
+            Backend::Headless(headless)
+        } else if has_display {
             let winit = Winit::new(config.clone(), event_loop.clone())?;
             Backend::Winit(winit)
         } else {
@@ -320,7 +363,14 @@ impl State {
             Backend::Tty(tty)
         };
 
-        let mut niri = Niri::new(config.clone(), event_loop, stop_signal, display, &backend);
+        let mut niri = Niri::new(
+            config.clone(),
+            event_loop,
+            stop_signal,
+            display,
+            &backend,
+            create_wayland_socket,
+        );
         backend.init(&mut niri);
 
         Ok(Self { backend, niri })
@@ -339,6 +389,8 @@ impl State {
         self.update_keyboard_focus();
         self.refresh_pointer_focus();
         foreign_toplevel::refresh(self);
+        output_management::refresh(self);
+        ipc::refresh(self);
 
         {
             let _span = tracy_client::span!("flush_clients");
@@ -535,28 +587,19 @@ impl State {
                 let current_layout =
                     keyboard.with_xkb_state(self, |context| context.active_layout());
 
-                let mut new_layout = current_layout;
-                // Store the currently active layout for the surface.
+                // Store the currently active layout for the surface, unless it was just closed.
                 if let Some(current_focus) = self.niri.keyboard_focus.as_ref() {
-                    with_states(current_focus, |data| {
-                        let cell = data
-                            .data_map
-                            .get_or_insert::<Cell<KeyboardLayout>, _>(Cell::default);
-                        cell.set(current_layout);
-                    });
+                    if current_focus.is_alive() {
+                        store_keyboard_layout(current_focus, current_layout);
+                    }
                 }
 
-                if let Some(focus) = focus.as_ref() {
-                    new_layout = with_states(focus, |data| {
-                        let cell = data.data_map.get_or_insert::<Cell<KeyboardLayout>, _>(|| {
-                            // The default layout is effectively the first layout in the
-                            // keymap, so use it for new windows.
-                            Cell::new(KeyboardLayout::default())
-                        });
-                        cell.get()
-                    });
-                }
-                if new_layout != current_layout && focus.is_some() {
+                // Go back to the default layout when nothing is focused, so that the layout of a
+                // closed window doesn't linger.
+                let new_layout = focus
+                    .as_ref()
+                    .map_or_else(KeyboardLayout::default, stored_keyboard_layout);
+                if new_layout != current_layout {
                     keyboard.set_focus(self, None, SERIAL_COUNTER.next_serial());
                     keyboard.with_xkb_state(self, |mut context| {
                         context.set_layout(new_layout);
@@ -572,21 +615,31 @@ impl State {
         }
     }
 
-    pub fn reload_config(&mut self, path: PathBuf) {
+    /// Reloads the config, returning the paths of all files it was read from.
+    pub fn reload_config(&mut self, path: PathBuf) -> Vec<PathBuf> {
         let _span = tracy_client::span!("State::reload_config");
 
-        let config = match Config::load(&path) {
+        let mut files = Vec::new();
+        let mut config = match Config::load_with_files(&path, &mut files) {
             Ok(config) => config,
             Err(err) => {
                 warn!("{:?}", err.context("error loading config"));
                 self.niri.config_error_notification.show();
                 self.niri.queue_redraw_all();
-                return;
+
+                if let Some(server) = &self.niri.ipc_server {
+                    server.send_event(niri_ipc::Event::ConfigReloaded { failed: true });
+                }
+                return files;
             }
         };
 
         self.niri.config_error_notification.hide();
 
+        // Keep the runtime output changes on top of the new config file.
+        self.niri.config_file_outputs = config.outputs.clone();
+        config.outputs = self.niri.outputs_with_overrides();
+
         self.niri.layout.update_config(&config);
 
         let slowdown = if config.animations.off {
@@ -599,6 +652,9 @@ impl State {
         let mut reload_xkb = None;
         let mut libinput_config_changed = false;
         let mut output_config_changed = false;
+        let mut window_rules_changed = false;
+        let mut workspaces_changed = false;
+        let mut removed_workspaces = vec![];
         let mut old_config = self.niri.config.borrow_mut();
 
         // Reload the cursor.
@@ -627,6 +683,10 @@ impl State {
 
         if config.input.touchpad != old_config.input.touchpad
             || config.input.mouse != old_config.input.mouse
+            || config.input.trackpoint != old_config.input.trackpoint
+            || config.input.trackball != old_config.input.trackball
+            || config.input.touch != old_config.input.touch
+            || config.input.devices != old_config.input.devices
         {
             libinput_config_changed = true;
         }
@@ -635,15 +695,36 @@ impl State {
             output_config_changed = true;
         }
 
-        if config.binds != old_config.binds {
+        if config.window_rules != old_config.window_rules {
+            window_rules_changed = true;
+        }
+
+        if config.workspaces != old_config.workspaces {
+            workspaces_changed = true;
+            removed_workspaces = old_config
+                .workspaces
+                .iter()
+                .filter(|old| !config.workspaces.iter().any(|ws| ws.name == old.name))
+                .map(|ws| ws.name.clone())
+                .collect();
+        }
+
+        if config.binds != old_config.binds || config.modes != old_config.modes {
             self.niri.hotkey_overlay.on_hotkey_config_updated();
         }
 
+        // The active mode may have been removed or changed its options, so start over.
+        let exit_bind_mode = config.modes != old_config.modes;
+
         *old_config = config;
 
         // Release the borrow.
         drop(old_config);
 
+        if exit_bind_mode {
+            self.niri.exit_bind_mode();
+        }
+
         // Now with a &mut self we can reload the xkb config.
         if let Some(xkb) = reload_xkb {
             let keyboard = self.niri.seat.get_keyboard().unwrap();
@@ -652,50 +733,45 @@ impl State {
             }
         }
 
-        if libinput_config_changed {
+        if workspaces_changed {
+            for name in &removed_workspaces {
+                self.niri.layout.unname_workspace(name);
+            }
+
             let config = self.niri.config.borrow();
-            for mut device in self.niri.devices.iter().cloned() {
-                apply_libinput_settings(&config.input, &mut device);
+            for ws in &config.workspaces {
+                self.niri.layout.ensure_named_workspace(ws);
             }
         }
 
-        if output_config_changed {
-            let mut resized_outputs = vec![];
-            for output in self.niri.global_space.outputs() {
-                let name = output.name();
-                let config = self.niri.config.borrow_mut();
-                let config = config.outputs.iter().find(|o| o.name == name);
-
-                let scale = config.map(|c| c.scale).unwrap_or(1.);
-                let scale = scale.clamp(1., 10.).ceil() as i32;
-
-                let mut transform = config
-                    .map(|c| c.transform.into())
-                    .unwrap_or(Transform::Normal);
-                // FIXME: fix winit damage on other transforms.
-                if name == "winit" {
-                    transform = Transform::Flipped180;
-                }
+        if window_rules_changed {
+            let mut windows = vec![];
+            self.niri
+                .layout
+                .with_windows(|window, _| windows.push(window.clone()));
 
-                if output.current_scale().integer_scale() != scale
-                    || output.current_transform() != transform
-                {
-                    output.change_current_state(
-                        None,
-                        Some(transform),
-                        Some(output::Scale::Integer(scale)),
-                        None,
-                    );
-                    resized_outputs.push(output.clone());
-                }
+            let config = self.niri.config.borrow();
+            for window in &windows {
+                ResolvedWindowRules::recompute(&config.window_rules, window).store(window);
             }
-            for output in resized_outputs {
-                self.niri.output_resized(output);
+            drop(config);
+
+            for window in &windows {
+                self.niri.layout.update_window(window);
             }
+        }
 
-            self.niri.reposition_outputs(None);
+        if libinput_config_changed {
+            let config = self.niri.config.borrow();
+            for mut device in self.niri.devices.iter().cloned() {
+                apply_libinput_settings(&config.input, &mut device);
+            }
+        }
 
-            self.backend.on_output_config_changed(&mut self.niri);
+        if output_config_changed {
+            if let Err(err) = self.reload_output_config() {
+                warn!("error applying output config: {err:?}");
+            }
         }
 
         // Can't really update xdg-decoration settings since we have to hide the globals for CSD
@@ -703,7 +779,157 @@ impl State {
         // global suddenly appearing? Either way, right now it's live-reloaded in a sense that new
         // clients will use the new xdg-decoration setting.
 
+        if let Some(server) = &self.niri.ipc_server {
+            server.send_event(niri_ipc::Event::ConfigReloaded { failed: false });
+        }
+
         self.niri.queue_redraw_all();
+
+        files
+    }
+
+    pub fn reload_output_config(&mut self) -> anyhow::Result<()> {
+        let mut resized_outputs = vec![];
+        for output in self.niri.global_space.outputs() {
+            let name = output.name();
+            let config = self.niri.config.borrow_mut();
+            let config = config.outputs.iter().find(|o| o.name == name);
+
+            let scale = config.map(|c| c.scale).unwrap_or(1.);
+            let scale = closest_representable_scale(scale);
+
+            let mut transform = config
+                .map(|c| c.transform.into())
+                .unwrap_or(Transform::Normal);
+            // FIXME: fix winit damage on other transforms.
+            if name == "winit" {
+                transform = Transform::Flipped180;
+            }
+
+            if output.current_scale().fractional_scale() != scale
+                || output.current_transform() != transform
+            {
+                output.change_current_state(
+                    None,
+                    Some(transform),
+                    Some(output::Scale::Fractional(scale)),
+                    None,
+                );
+                resized_outputs.push(output.clone());
+            }
+        }
+        for output in resized_outputs {
+            self.niri.output_resized(output);
+        }
+
+        self.niri.reposition_outputs(None);
+
+        self.backend.on_output_config_changed(&mut self.niri)
+    }
+
+    /// Applies the runtime output overrides and reloads the outputs if their config changed.
+    pub fn apply_output_overrides(&mut self) -> anyhow::Result<()> {
+        let outputs = self.niri.outputs_with_overrides();
+
+        let mut config = self.niri.config.borrow_mut();
+        if config.outputs == outputs {
+            return Ok(());
+        }
+        config.outputs = outputs;
+        drop(config);
+
+        let result = self.reload_output_config();
+        self.niri.queue_redraw_all();
+        result
+    }
+
+    /// Replaces the runtime output overrides and applies them.
+    ///
+    /// If the backend couldn't apply the new config, the previous overrides are restored.
+    pub fn set_output_overrides(
+        &mut self,
+        overrides: HashMap<String, OutputOverride>,
+    ) -> anyhow::Result<()> {
+        let previous = mem::replace(&mut self.niri.output_overrides, overrides);
+
+        let result = self.apply_output_overrides();
+        if result.is_err() {
+            self.niri.output_overrides = previous;
+            if let Err(err) = self.apply_output_overrides() {
+                warn!("error restoring the previous output config: {err:?}");
+            }
+        }
+        result
+    }
+
+    pub fn apply_ipc_output_action(
+        &mut self,
+        name: &str,
+        action: niri_ipc::OutputAction,
+    ) -> Result<(), String> {
+        let ipc_outputs = self.backend.ipc_outputs();
+        let ipc_outputs = ipc_outputs.borrow();
+        let Some(ipc_output) = ipc_outputs.get(name) else {
+            return Err(format!("output {name:?} is not connected"));
+        };
+
+        let mut override_ = self
+            .niri
+            .output_overrides
+            .get(name)
+            .cloned()
+            .unwrap_or_default();
+
+        match action {
+            niri_ipc::OutputAction::Off => override_.off = Some(true),
+            niri_ipc::OutputAction::On => override_.off = Some(false),
+            niri_ipc::OutputAction::Mode { mode } => {
+                let mode = match mode {
+                    niri_ipc::ModeToSet::Automatic => None,
+                    niri_ipc::ModeToSet::Specific(mode) => {
+                        let exists = ipc_output.modes.iter().any(|m| {
+                            m.width == mode.width
+                                && m.height == mode.height
+                                && mode
+                                    .refresh
+                                    .map_or(true, |r| refresh_rate_matches(m.refresh_rate, r))
+                        });
+                        if !exists {
+                            let refresh = mode.refresh.map(|r| format!("@{r}")).unwrap_or_default();
+                            return Err(format!(
+                                "output {name:?} does not support mode {}x{}{refresh}",
+                                mode.width, mode.height,
+                            ));
+                        }
+
+                        Some(niri_config::Mode::from(mode))
+                    }
+                };
+                override_.mode = Some(mode);
+            }
+            niri_ipc::OutputAction::Scale { scale } => {
+                if !(scale > 0. && scale.is_finite()) {
+                    return Err(format!("invalid scale: {scale}"));
+                }
+                override_.scale = Some(scale);
+            }
+            niri_ipc::OutputAction::Transform { transform } => {
+                override_.transform = Some(transform.into());
+            }
+            niri_ipc::OutputAction::Position { position } => {
+                let position = match position {
+                    niri_ipc::PositionToSet::Automatic => None,
+                    niri_ipc::PositionToSet::Specific(position) => Some(position.into()),
+                };
+                override_.position = Some(position);
+            }
+        }
+        drop(ipc_outputs);
+
+        let mut overrides = self.niri.output_overrides.clone();
+        overrides.insert(name.to_owned(), override_);
+        self.set_output_overrides(overrides)
+            .map_err(|err| format!("error applying output config: {err:?}"))
     }
 
     #[cfg(feature = "xdp-gnome-screencast")]
@@ -804,6 +1030,7 @@ impl Niri {
         stop_signal: LoopSignal,
         display: Display<State>,
         backend: &Backend,
+        create_wayland_socket: bool,
     ) -> Self {
         let _span = tracy_client::span!("Niri::new");
 
@@ -866,6 +1093,9 @@ impl Niri {
         );
         let presentation_state =
             PresentationState::new::<State>(&display_handle, Monotonic::ID as u32);
+        let fractional_scale_manager_state =
+            FractionalScaleManagerState::new::<State>(&display_handle);
+        let viewporter_state = ViewporterState::new::<State>(&display_handle);
         let security_context_state =
             SecurityContextState::new::<State, _>(&display_handle, |client| {
                 !client.get_data::<ClientState>().unwrap().restricted
@@ -885,6 +1115,10 @@ impl Niri {
             ForeignToplevelManagerState::new::<State, _>(&display_handle, |client| {
                 !client.get_data::<ClientState>().unwrap().restricted
             });
+        let output_management_state =
+            OutputManagementManagerState::new::<State, _>(&display_handle, |client| {
+                !client.get_data::<ClientState>().unwrap().restricted
+            });
 
         let mut seat: Seat<State> = seat_state.new_wl_seat(&display_handle, backend.seat_name());
         seat.add_keyboard(
@@ -894,6 +1128,7 @@ impl Niri {
         )
         .unwrap();
         seat.add_pointer();
+        seat.add_touch();
 
         let cursor_shape_manager_state = CursorShapeManagerState::new::<State>(&display_handle);
         let cursor_manager =
@@ -932,30 +1167,27 @@ impl Niri {
             }
         };
 
-        let socket_source = ListeningSocketSource::new_auto().unwrap();
-        let socket_name = socket_source.socket_name().to_os_string();
-        event_loop
-            .insert_source(socket_source, move |client, _, state| {
-                let config = state.niri.config.borrow();
-                let data = Arc::new(ClientState {
-                    compositor_state: Default::default(),
-                    can_view_decoration_globals: config.prefer_no_csd,
-                    restricted: false,
-                });
+        // Tests connect their clients directly, without going through the sockets.
+        let socket_name = create_wayland_socket.then(|| {
+            let socket_source = ListeningSocketSource::new_auto().unwrap();
+            let socket_name = socket_source.socket_name().to_os_string();
+            event_loop
+                .insert_source(socket_source, move |client, _, state| {
+                    state.niri.insert_client(client);
+                })
+                .unwrap();
+            socket_name
+        });
 
-                if let Err(err) = state.niri.display_handle.insert_client(client, data) {
-                    error!("error inserting client: {err}");
+        let ipc_server = socket_name.as_ref().and_then(|socket_name| {
+            match IpcServer::start(&event_loop, &socket_name.to_string_lossy()) {
+                Ok(server) => Some(server),
+                Err(err) => {
+                    warn!("error starting IPC server: {err:?}");
+                    None
                 }
-            })
-            .unwrap();
-
-        let ipc_server = match IpcServer::start(&event_loop, &socket_name.to_string_lossy()) {
-            Ok(server) => Some(server),
-            Err(err) => {
-                warn!("error starting IPC server: {err:?}");
-                None
             }
-        };
+        });
 
         let pipewire = match PipeWire::new(&event_loop) {
             Ok(pipewire) => Some(pipewire),
@@ -976,9 +1208,13 @@ impl Niri {
             })
             .unwrap();
 
+        let config_file_outputs = config_.outputs.clone();
+
         drop(config_);
         Self {
             config,
+            config_file_outputs,
+            output_overrides: HashMap::new(),
 
             event_loop,
             scheduler,
@@ -996,6 +1232,7 @@ impl Niri {
 
             devices: HashSet::new(),
             tablets: HashMap::new(),
+            touch_edge_swipe: None,
 
             compositor_state,
             xdg_shell_state,
@@ -1004,6 +1241,7 @@ impl Niri {
             layer_shell_state,
             session_lock_state,
             foreign_toplevel_state,
+            output_management_state,
             text_input_state,
             input_method_state,
             virtual_keyboard_state,
@@ -1023,8 +1261,15 @@ impl Niri {
             popups: PopupManager::default(),
             popup_grab: None,
             suppressed_keys: HashSet::new(),
+            suppressed_buttons: HashSet::new(),
+            horizontal_wheel_tracker: ScrollTracker::new(120.),
+            vertical_wheel_tracker: ScrollTracker::new(120.),
+            gesture: None,
+            bind_mode: None,
             presentation_state,
             security_context_state,
+            fractional_scale_manager_state,
+            viewporter_state,
 
             seat,
             keyboard_focus: None,
@@ -1075,6 +1320,27 @@ impl Niri {
         Ok(())
     }
 
+    /// Returns the output config from the config file with the runtime overrides applied.
+    pub fn outputs_with_overrides(&self) -> Vec<niri_config::Output> {
+        let mut outputs = self.config_file_outputs.clone();
+
+        for (name, override_) in &self.output_overrides {
+            let idx = match outputs.iter().position(|o| o.name == *name) {
+                Some(idx) => idx,
+                None => {
+                    outputs.push(niri_config::Output {
+                        name: name.clone(),
+                        ..Default::default()
+                    });
+                    outputs.len() - 1
+                }
+            };
+            override_.apply_to(&mut outputs[idx]);
+        }
+
+        outputs
+    }
+
     /// Repositions all outputs, optionally adding a new output.
     pub fn reposition_outputs(&mut self, new_output: Option<&Output>) {
         let _span = tracy_client::span!("Niri::reposition_outputs");
@@ -1202,7 +1468,7 @@ impl Niri {
         let config = self.config.borrow();
         let c = config.outputs.iter().find(|o| o.name == name);
         let scale = c.map(|c| c.scale).unwrap_or(1.);
-        let scale = scale.clamp(1., 10.).ceil() as i32;
+        let scale = closest_representable_scale(scale);
         let mut transform = c.map(|c| c.transform.into()).unwrap_or(Transform::Normal);
         // FIXME: fix winit damage on other transforms.
         if name == "winit" {
@@ -1214,7 +1480,7 @@ impl Niri {
         output.change_current_state(
             None,
             Some(transform),
-            Some(output::Scale::Integer(scale)),
+            Some(output::Scale::Fractional(scale)),
             None,
         );
 
@@ -1338,7 +1604,7 @@ impl Niri {
             let transform = output.current_transform();
             let output_mode = output.current_mode().unwrap();
             let size = transform.transform_size(output_mode.size);
-            let scale = output.current_scale().integer_scale();
+            let scale = output.current_scale().fractional_scale();
             // FIXME: scale changes and transform flips shouldn't matter but they currently do since
             // I haven't quite figured out how to draw the screenshot textures in
             // physical coordinates.
@@ -1374,6 +1640,79 @@ impl Niri {
         self.queue_redraw_all();
     }
 
+    /// Adds a Wayland client connected over the given stream.
+    pub fn insert_client(&mut self, stream: UnixStream) {
+        let data = Arc::new(ClientState {
+            compositor_state: Default::default(),
+            can_view_decoration_globals: self.config.borrow().prefer_no_csd,
+            restricted: false,
+        });
+
+        if let Err(err) = self.display_handle.insert_client(stream, data) {
+            error!("error inserting client: {err}");
+        }
+    }
+
+    /// Whether niri has just started, for the purposes of `at-startup` window rules.
+    pub fn is_at_startup(&self) -> bool {
+        self.start_time.elapsed() < Duration::from_secs(60)
+    }
+
+    /// Returns the workspace that a new window with these rules would open on.
+    pub fn workspace_for_new_window(
+        &self,
+        rules: &ResolvedWindowRules,
+    ) -> Option<&Workspace<Window>> {
+        if let Some((_, ws)) = rules
+            .open_on_workspace
+            .as_deref()
+            .and_then(|name| self.layout.find_workspace_by_name(name))
+        {
+            return Some(ws);
+        }
+
+        let mon = rules
+            .open_on_output
+            .as_deref()
+            .and_then(|name| self.output_by_name.get(name))
+            .and_then(|output| self.layout.monitor_for_output(output));
+
+        match mon {
+            Some(mon) => Some(&mon.workspaces[mon.active_workspace_idx]),
+            None => self.layout.active_workspace(),
+        }
+    }
+
+    /// Finds an open window by its IPC id.
+    pub fn find_window_by_id(&self, id: u64) -> Result<Window, String> {
+        self.layout
+            .find_window_by_id(WindowId::from(id))
+            .ok_or_else(|| format!("window with id {id} does not exist"))
+    }
+
+    /// Resolves a workspace reference into the workspace index and, for named workspaces on an
+    /// output other than the active one, that output.
+    pub fn find_output_and_workspace_index(
+        &self,
+        reference: WorkspaceReference,
+    ) -> Result<(Option<Output>, usize), String> {
+        let (idx, ws) = match reference {
+            WorkspaceReference::Index(index) => {
+                return Ok((None, index.saturating_sub(1) as usize));
+            }
+            WorkspaceReference::Name(name) => self
+                .layout
+                .find_workspace_by_name(&name)
+                .ok_or_else(|| format!("workspace {name:?} does not exist"))?,
+        };
+
+        let output = ws
+            .current_output()
+            .filter(|output| Some(*output) != self.layout.active_output())
+            .cloned();
+        Ok((output, idx))
+    }
+
     pub fn output_under(&self, pos: Point<f64, Logical>) -> Option<(&Output, Point<f64, Logical>)> {
         let output = self.global_space.output_under(pos).next()?;
         let pos_within_output = pos
@@ -1596,6 +1935,14 @@ impl Niri {
             .or_else(|| self.global_space.outputs().next())
     }
 
+    pub fn output_for_touch(&self) -> Option<&Output> {
+        let config = self.config.borrow();
+        let map_to_output = config.input.touch.map_to_output.as_ref();
+        map_to_output
+            .and_then(|name| self.output_by_name.get(name))
+            .or_else(|| self.global_space.outputs().next())
+    }
+
     pub fn output_for_root(&self, root: &WlSurface) -> Option<&Output> {
         // Check the main layout.
         let win_out = self.layout.find_window_and_output(root);
@@ -1679,7 +2026,8 @@ impl Niri {
             .unwrap_or_else(|| self.seat.get_pointer().unwrap().current_location());
         let pointer_pos = pointer_pos - output_pos.to_f64();
 
-        // Get the render cursor to draw.
+        // Get the render cursor to draw. XCursor images only come in integer scales, so fractional
+        // scales use the next one up, downscaled when rendering.
         let cursor_scale = output_scale.integer_scale();
         let render_cursor = self.cursor_manager.get_render_cursor(cursor_scale);
 
@@ -1781,9 +2129,9 @@ impl Niri {
 
                 // FIXME we basically need to pick the largest scale factor across the overlapping
                 // outputs, this is how it's usually done in clients as well.
-                let mut cursor_scale = 1;
+                let mut cursor_scale = output::Scale::Integer(1);
                 let mut cursor_transform = Transform::Normal;
-                let mut dnd_scale = 1;
+                let mut dnd_scale = output::Scale::Integer(1);
                 let mut dnd_transform = Transform::Normal;
                 for output in self.global_space.outputs() {
                     let geo = self.global_space.output_geometry(output).unwrap();
@@ -1791,7 +2139,7 @@ impl Niri {
                     // Compute pointer surface overlap.
                     if let Some(mut overlap) = geo.intersection(bbox) {
                         overlap.loc -= surface_pos;
-                        cursor_scale = cursor_scale.max(output.current_scale().integer_scale());
+                        cursor_scale = max_scale(cursor_scale, output.current_scale());
                         // FIXME: using the largest overlapping or "primary" output transform would
                         // make more sense here.
                         cursor_transform = output.current_transform();
@@ -1804,7 +2152,7 @@ impl Niri {
                     if let Some((surface, bbox)) = dnd {
                         if let Some(mut overlap) = geo.intersection(bbox) {
                             overlap.loc -= surface_pos;
-                            dnd_scale = dnd_scale.max(output.current_scale().integer_scale());
+                            dnd_scale = max_scale(dnd_scale, output.current_scale());
                             // FIXME: using the largest overlapping or "primary" output transform
                             // would make more sense here.
                             dnd_transform = output.current_transform();
@@ -1816,11 +2164,11 @@ impl Niri {
                 }
 
                 with_states(surface, |data| {
-                    send_surface_state(surface, data, cursor_scale, cursor_transform);
+                    send_scale_transform(surface, data, cursor_scale, cursor_transform);
                 });
                 if let Some((surface, _)) = dnd {
                     with_states(surface, |data| {
-                        send_surface_state(surface, data, dnd_scale, dnd_transform);
+                        send_scale_transform(surface, data, dnd_scale, dnd_transform);
                     });
                 }
             }
@@ -1836,13 +2184,14 @@ impl Niri {
                     Default::default()
                 };
 
-                let mut dnd_scale = 1;
+                let mut dnd_scale = output::Scale::Integer(1);
                 let mut dnd_transform = Transform::Normal;
                 for output in self.global_space.outputs() {
                     let geo = self.global_space.output_geometry(output).unwrap();
 
                     // The default cursor is rendered at the right scale for each output, which
-                    // means that it may have a different hotspot for each output.
+                    // means that it may have a different hotspot for each output. XCursor images
+                    // only come in integer scales, fractional ones get the next one up.
                     let output_scale = output.current_scale().integer_scale();
                     let cursor = self
                         .cursor_manager
@@ -1858,7 +2207,7 @@ impl Niri {
 
                     if let Some(mut overlap) = geo.intersection(bbox) {
                         overlap.loc -= surface_pos;
-                        dnd_scale = dnd_scale.max(output.current_scale().integer_scale());
+                        dnd_scale = max_scale(dnd_scale, output.current_scale());
                         // FIXME: using the largest overlapping or "primary" output transform would
                         // make more sense here.
                         dnd_transform = output.current_transform();
@@ -1869,7 +2218,7 @@ impl Niri {
                 }
 
                 with_states(surface, |data| {
-                    send_surface_state(surface, data, dnd_scale, dnd_transform);
+                    send_scale_transform(surface, data, dnd_scale, dnd_transform);
                 });
             }
         }
@@ -1976,6 +2325,13 @@ impl Niri {
             elements.push(element.into());
         }
 
+        // Then the window being moved with the pointer.
+        elements.extend(
+            self.layout
+                .render_interactive_move_for_output(renderer, output)
+                .map(OutputRenderElements::from),
+        );
+
         // Get monitor elements.
         let mon = self.layout.monitor_for_output(output).unwrap();
         let monitor_elements = mon.render_elements(renderer);
@@ -2776,8 +3132,8 @@ impl Niri {
         let output = outputs.into_iter().next().unwrap();
         let geom = self.global_space.output_geometry(&output).unwrap();
 
-        let output_scale = output.current_scale().integer_scale();
-        let geom = geom.to_physical(output_scale);
+        let output_scale = output.current_scale().fractional_scale();
+        let geom = geom.to_f64().to_physical(output_scale).to_i32_round();
 
         let size = geom.size;
         let transform = output.current_transform();
@@ -2788,7 +3144,7 @@ impl Niri {
         let pixels = render_to_vec(
             renderer,
             size,
-            Scale::from(f64::from(output_scale)),
+            Scale::from(output_scale),
             Fourcc::Abgr8888,
             elements,
         )?;
@@ -2828,9 +3184,87 @@ impl Niri {
         !matches!(self.lock_state, LockState::Unlocked)
     }
 
+    /// Enters a bind mode from the config, leaving the active one.
+    pub fn enter_bind_mode(&mut self, name: &str) {
+        let config = self.config.borrow();
+        let Some(mode) = config.find_mode(name) else {
+            warn!("bind mode {name:?} does not exist");
+            return;
+        };
+        let show_hotkey_overlay = mode.show_hotkey_overlay;
+        drop(config);
+
+        self.exit_bind_mode();
+        self.bind_mode = Some(BindModeState {
+            name: name.to_owned(),
+            show_hotkey_overlay,
+            timer: None,
+        });
+        self.restart_bind_mode_timer();
+
+        let mut redraw = self.hotkey_overlay.set_mode(Some(name));
+        if show_hotkey_overlay {
+            redraw |= self.hotkey_overlay.show();
+        }
+        if redraw {
+            self.queue_redraw_all();
+        }
+    }
+
+    /// Leaves the active bind mode, going back to the regular binds.
+    pub fn exit_bind_mode(&mut self) {
+        let Some(mode) = self.bind_mode.take() else {
+            return;
+        };
+
+        if let Some(token) = mode.timer {
+            self.event_loop.remove(token);
+        }
+
+        let mut redraw = self.hotkey_overlay.set_mode(None);
+        if mode.show_hotkey_overlay {
+            redraw |= self.hotkey_overlay.hide();
+        }
+        if redraw {
+            self.queue_redraw_all();
+        }
+    }
+
+    /// Restarts the timeout of the active bind mode, if it has one.
+    pub fn restart_bind_mode_timer(&mut self) {
+        let Some(mode) = &mut self.bind_mode else {
+            return;
+        };
+
+        if let Some(token) = mode.timer.take() {
+            self.event_loop.remove(token);
+        }
+
+        let config = self.config.borrow();
+        let Some(timeout_ms) = config.find_mode(&mode.name).and_then(|m| m.timeout_ms) else {
+            return;
+        };
+
+        let timer = Timer::from_duration(Duration::from_millis(u64::from(timeout_ms)));
+        let token = self
+            .event_loop
+            .insert_source(timer, |_, _, state| {
+                // The timer is dropped on return, so it must not be removed again.
+                if let Some(mode) = &mut state.niri.bind_mode {
+                    mode.timer = None;
+                }
+                state.niri.exit_bind_mode();
+                TimeoutAction::Drop
+            })
+            .unwrap();
+        mode.timer = Some(token);
+    }
+
     pub fn lock(&mut self, confirmation: SessionLocker) {
         info!("locking session");
 
+        // Mode binds would swallow the keys typed into the lock screen.
+        self.exit_bind_mode();
         self.screenshot_ui.close();
         self.cursor_manager
             .set_cursor_image(CursorImageStatus::default_named());
@@ -2927,6 +3361,26 @@ impl Niri {
     }
 }
 
+/// Returns the keyboard layout remembered for a surface with per-window layout tracking.
+///
+/// The default layout is effectively the first layout in the keymap, so surfaces that were never
+/// focused get it.
+pub fn stored_keyboard_layout(surface: &WlSurface) -> KeyboardLayout {
+    with_states(surface, |data| {
+        data.data_map
+            .get::<Cell<KeyboardLayout>>()
+            .map_or_else(KeyboardLayout::default, Cell::get)
+    })
+}
+
+fn store_keyboard_layout(surface: &WlSurface, layout: KeyboardLayout) {
+    with_states(surface, |data| {
+        data.data_map
+            .get_or_insert::<Cell<KeyboardLayout>, _>(Cell::default)
+            .set(layout);
+    });
+}
+
 pub struct ClientState {
     pub compositor_state: CompositorClientState,
     pub can_view_decoration_globals: bool,
@@ -2942,6 +3396,7 @@ impl ClientData for ClientState {
 niri_render_elements! {
     OutputRenderElements => {
         Monitor = MonitorRenderElement<R>,
+        Tile = TileRenderElement<R>,
         Wayland = WaylandSurfaceRenderElement<R>,
         NamedPointer = MemoryRenderBufferRenderElement<R>,
         SolidColor = SolidColorRenderElement,
@@ -2950,3 +3405,12 @@ niri_render_elements! {
         RelocatedMemoryBuffer = RelocateRenderElement<MemoryRenderBufferRenderElement<R>>,
     }
 }
+
+/// Returns the larger of two output scales.
+fn max_scale(a: output::Scale, b: output::Scale) -> output::Scale {
+    if b.fractional_scale() > a.fractional_scale() {
+        b
+    } else {
+        a
+    }
+}
@@ -1 +1,2 @@
 pub mod foreign_toplevel;
+pub mod output_management;
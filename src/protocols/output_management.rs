@@ -0,0 +1,1008 @@
+use std::collections::hash_map::Entry;
+use std::collections::HashMap;
+
+use smithay::reexports::wayland_protocols_wlr;
+use smithay::reexports::wayland_server::backend::ClientId;
+use smithay::reexports::wayland_server::protocol::wl_output;
+use smithay::reexports::wayland_server::{
+    Client, DataInit, Dispatch, DisplayHandle, GlobalDispatch, New, Resource, WEnum,
+};
+use smithay::utils::{Logical, Point, Rectangle, Size, Transform};
+use wayland_protocols_wlr::output_management::v1::server::{
+    zwlr_output_configuration_head_v1, zwlr_output_configuration_v1, zwlr_output_head_v1,
+    zwlr_output_manager_v1, zwlr_output_mode_v1,
+};
+use zwlr_output_configuration_head_v1::ZwlrOutputConfigurationHeadV1;
+use zwlr_output_configuration_v1::ZwlrOutputConfigurationV1;
+use zwlr_output_head_v1::ZwlrOutputHeadV1;
+use zwlr_output_manager_v1::ZwlrOutputManagerV1;
+use zwlr_output_mode_v1::ZwlrOutputModeV1;
+
+use crate::niri::State;
+use crate::utils::refresh_rate_matches;
+
+const VERSION: u32 = 3;
+
+pub struct OutputManagementManagerState {
+    display: DisplayHandle,
+    serial: u32,
+    clients: HashMap<ClientId, ClientData>,
+    heads: HashMap<String, HeadState>,
+}
+
+pub trait OutputManagementHandler {
+    fn output_management_state(&mut self) -> &mut OutputManagementManagerState;
+    /// Applies the configuration, returning an error if the backend couldn't do it.
+    fn apply_output_config(
+        &mut self,
+        config: HashMap<String, HeadConfiguration>,
+    ) -> anyhow::Result<()>;
+}
+
+/// Requested configuration of a single head.
+///
+/// Properties left as `None` were not set by the client and should keep their current value.
+#[derive(Debug, Clone, PartialEq)]
+pub enum HeadConfiguration {
+    Disabled,
+    Enabled {
+        mode: Option<niri_config::Mode>,
+        position: Option<Point<i32, Logical>>,
+        transform: Option<niri_config::Transform>,
+        scale: Option<f64>,
+    },
+}
+
+/// State of an output as advertised to clients.
+#[derive(Debug, Clone, PartialEq)]
+struct HeadState {
+    make: String,
+    model: String,
+    physical_size: Option<(u32, u32)>,
+    modes: Vec<niri_ipc::Mode>,
+    current_mode: Option<usize>,
+    /// Logical state, `None` if the output is disabled.
+    logical: Option<LogicalState>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct LogicalState {
+    position: Point<i32, Logical>,
+    scale: f64,
+    transform: Transform,
+}
+
+struct ClientData {
+    manager: ZwlrOutputManagerV1,
+    heads: HashMap<String, (ZwlrOutputHeadV1, Vec<ZwlrOutputModeV1>)>,
+    configurations: HashMap<ZwlrOutputConfigurationV1, OutputConfigurationState>,
+}
+
+enum OutputConfigurationState {
+    Ongoing {
+        serial: u32,
+        /// Configured heads, `None` for disabled ones.
+        heads: HashMap<String, Option<ConfigurationHead>>,
+        /// Whether the client tried to configure a head that no longer exists.
+        has_stale_heads: bool,
+    },
+    Finished,
+}
+
+struct ConfigurationHead {
+    resource: ZwlrOutputConfigurationHeadV1,
+    mode: Option<niri_config::Mode>,
+    position: Option<Point<i32, Logical>>,
+    transform: Option<niri_config::Transform>,
+    scale: Option<f64>,
+}
+
+pub struct OutputManagementGlobalData {
+    filter: Box<dyn for<'c> Fn(&'c Client) -> bool + Send + Sync>,
+}
+
+impl OutputManagementManagerState {
+    pub fn new<D, F>(display: &DisplayHandle, filter: F) -> Self
+    where
+        D: GlobalDispatch<ZwlrOutputManagerV1, OutputManagementGlobalData>,
+        D: Dispatch<ZwlrOutputManagerV1, ()>,
+        D: 'static,
+        F: for<'c> Fn(&'c Client) -> bool + Send + Sync + 'static,
+    {
+        let global_data = OutputManagementGlobalData {
+            filter: Box::new(filter),
+        };
+        display.create_global::<D, ZwlrOutputManagerV1, _>(VERSION, global_data);
+        Self {
+            display: display.clone(),
+            serial: 0,
+            clients: HashMap::new(),
+            heads: HashMap::new(),
+        }
+    }
+}
+
+pub fn refresh(state: &mut State) {
+    let _span = tracy_client::span!("output_management::refresh");
+
+    let ipc_outputs = state.backend.ipc_outputs();
+    let ipc_outputs = ipc_outputs.borrow();
+
+    let mut new_heads = HashMap::new();
+    for (name, ipc_output) in ipc_outputs.iter() {
+        let output = state
+            .niri
+            .global_space
+            .outputs()
+            .find(|output| output.name() == *name);
+        let logical = output.map(|output| LogicalState {
+            position: state.niri.global_space.output_geometry(output).unwrap().loc,
+            scale: output.current_scale().fractional_scale(),
+            transform: output.current_transform(),
+        });
+
+        let head = HeadState {
+            make: ipc_output.make.clone(),
+            model: ipc_output.model.clone(),
+            physical_size: ipc_output.physical_size,
+            modes: ipc_output.modes.clone(),
+            current_mode: ipc_output.current_mode,
+            logical,
+        };
+        new_heads.insert(name.clone(), head);
+    }
+
+    let protocol_state = &mut state.niri.output_management_state;
+    if protocol_state.heads == new_heads {
+        return;
+    }
+
+    protocol_state.serial = protocol_state.serial.wrapping_add(1);
+    let serial = protocol_state.serial;
+
+    for client_data in protocol_state.clients.values_mut() {
+        let Some(client) = client_data.manager.client() else {
+            continue;
+        };
+
+        // Handle removed heads.
+        client_data.heads.retain(|name, (head, modes)| {
+            if new_heads.contains_key(name) {
+                return true;
+            }
+
+            finish_head(head, modes);
+            false
+        });
+
+        // Handle new and changed heads.
+        for (name, new) in &new_heads {
+            match client_data.heads.entry(name.clone()) {
+                Entry::Occupied(mut entry) => {
+                    let Some(old) = protocol_state.heads.get(name) else {
+                        continue;
+                    };
+                    if old == new {
+                        continue;
+                    }
+
+                    let (head, modes) = entry.get_mut();
+                    if old.make != new.make
+                        || old.model != new.model
+                        || old.physical_size != new.physical_size
+                        || old.modes != new.modes
+                    {
+                        // The immutable head properties changed, re-create the head.
+                        finish_head(head, modes);
+                        let resources = send_new_head::<State>(
+                            &protocol_state.display,
+                            &client,
+                            &client_data.manager,
+                            name,
+                            new,
+                        );
+                        entry.insert(resources);
+                    } else {
+                        send_head_state(head, modes, new);
+                    }
+                }
+                Entry::Vacant(entry) => {
+                    let resources = send_new_head::<State>(
+                        &protocol_state.display,
+                        &client,
+                        &client_data.manager,
+                        name,
+                        new,
+                    );
+                    entry.insert(resources);
+                }
+            }
+        }
+
+        client_data.manager.done(serial);
+    }
+
+    protocol_state.heads = new_heads;
+}
+
+fn finish_head(head: &ZwlrOutputHeadV1, modes: &[ZwlrOutputModeV1]) {
+    for mode in modes {
+        mode.finished();
+    }
+    head.finished();
+}
+
+fn send_new_head<D>(
+    display: &DisplayHandle,
+    client: &Client,
+    manager: &ZwlrOutputManagerV1,
+    name: &str,
+    state: &HeadState,
+) -> (ZwlrOutputHeadV1, Vec<ZwlrOutputModeV1>)
+where
+    D: Dispatch<ZwlrOutputHeadV1, ()>,
+    D: Dispatch<ZwlrOutputModeV1, ()>,
+    D: 'static,
+{
+    let head = client
+        .create_resource::<ZwlrOutputHeadV1, _, D>(display, manager.version(), ())
+        .unwrap();
+    manager.head(&head);
+
+    head.name(name.to_owned());
+    head.description(format!("{} {} ({name})", state.make, state.model));
+    if let Some((width, height)) = state.physical_size {
+        head.physical_size(width as i32, height as i32);
+    }
+
+    let mut modes = Vec::with_capacity(state.modes.len());
+    for mode in &state.modes {
+        let resource = client
+            .create_resource::<ZwlrOutputModeV1, _, D>(display, head.version(), ())
+            .unwrap();
+        head.mode(&resource);
+        resource.size(i32::from(mode.width), i32::from(mode.height));
+        resource.refresh(mode.refresh_rate as i32);
+        modes.push(resource);
+    }
+
+    if head.version() >= zwlr_output_head_v1::EVT_MAKE_SINCE {
+        head.make(state.make.clone());
+    }
+    if head.version() >= zwlr_output_head_v1::EVT_MODEL_SINCE {
+        head.model(state.model.clone());
+    }
+
+    send_head_state(&head, &modes, state);
+
+    (head, modes)
+}
+
+fn send_head_state(head: &ZwlrOutputHeadV1, modes: &[ZwlrOutputModeV1], state: &HeadState) {
+    head.enabled(i32::from(state.logical.is_some()));
+
+    let Some(logical) = state.logical else {
+        return;
+    };
+
+    if let Some(mode) = state.current_mode.and_then(|idx| modes.get(idx)) {
+        if mode.is_alive() {
+            head.current_mode(mode);
+        }
+    }
+    head.position(logical.position.x, logical.position.y);
+    head.transform(to_wl_transform(logical.transform));
+    head.scale(logical.scale);
+}
+
+impl<D> GlobalDispatch<ZwlrOutputManagerV1, OutputManagementGlobalData, D>
+    for OutputManagementManagerState
+where
+    D: GlobalDispatch<ZwlrOutputManagerV1, OutputManagementGlobalData>,
+    D: Dispatch<ZwlrOutputManagerV1, ()>,
+    D: Dispatch<ZwlrOutputHeadV1, ()>,
+    D: Dispatch<ZwlrOutputModeV1, ()>,
+    D: OutputManagementHandler,
+    D: 'static,
+{
+    fn bind(
+        state: &mut D,
+        handle: &DisplayHandle,
+        client: &Client,
+        resource: New<ZwlrOutputManagerV1>,
+        _global_data: &OutputManagementGlobalData,
+        data_init: &mut DataInit<'_, D>,
+    ) {
+        let manager = data_init.init(resource, ());
+
+        let state = state.output_management_state();
+
+        let mut heads = HashMap::new();
+        for (name, head) in &state.heads {
+            let resources = send_new_head::<D>(handle, client, &manager, name, head);
+            heads.insert(name.clone(), resources);
+        }
+        manager.done(state.serial);
+
+        let client_data = ClientData {
+            manager,
+            heads,
+            configurations: HashMap::new(),
+        };
+        state.clients.insert(client.id(), client_data);
+    }
+
+    fn can_view(client: Client, global_data: &OutputManagementGlobalData) -> bool {
+        (global_data.filter)(&client)
+    }
+}
+
+impl<D> Dispatch<ZwlrOutputManagerV1, (), D> for OutputManagementManagerState
+where
+    D: Dispatch<ZwlrOutputManagerV1, ()>,
+    D: Dispatch<ZwlrOutputConfigurationV1, ()>,
+    D: OutputManagementHandler,
+{
+    fn request(
+        state: &mut D,
+        client: &Client,
+        resource: &ZwlrOutputManagerV1,
+        request: <ZwlrOutputManagerV1 as Resource>::Request,
+        _data: &(),
+        _dhandle: &DisplayHandle,
+        data_init: &mut DataInit<'_, D>,
+    ) {
+        let state = state.output_management_state();
+
+        match request {
+            zwlr_output_manager_v1::Request::CreateConfiguration { id, serial } => {
+                let configuration = data_init.init(id, ());
+
+                let Some(client_data) = state.clients.get_mut(&client.id()) else {
+                    configuration.cancelled();
+                    return;
+                };
+
+                // Outdated serials are cancelled upon apply or test.
+                let configuration_state = OutputConfigurationState::Ongoing {
+                    serial,
+                    heads: HashMap::new(),
+                    has_stale_heads: false,
+                };
+                client_data
+                    .configurations
+                    .insert(configuration, configuration_state);
+            }
+            zwlr_output_manager_v1::Request::Stop => {
+                resource.finished();
+                state.clients.remove(&client.id());
+            }
+            _ => unreachable!(),
+        }
+    }
+
+    fn destroyed(state: &mut D, client: ClientId, _resource: &ZwlrOutputManagerV1, _data: &()) {
+        let state = state.output_management_state();
+        state.clients.remove(&client);
+    }
+}
+
+impl<D> Dispatch<ZwlrOutputConfigurationV1, (), D> for OutputManagementManagerState
+where
+    D: Dispatch<ZwlrOutputConfigurationV1, ()>,
+    D: Dispatch<ZwlrOutputConfigurationHeadV1, ()>,
+    D: OutputManagementHandler,
+{
+    fn request(
+        state: &mut D,
+        client: &Client,
+        resource: &ZwlrOutputConfigurationV1,
+        request: <ZwlrOutputConfigurationV1 as Resource>::Request,
+        _data: &(),
+        _dhandle: &DisplayHandle,
+        data_init: &mut DataInit<'_, D>,
+    ) {
+        let protocol_state = state.output_management_state();
+        let current_serial = protocol_state.serial;
+
+        let Some(client_data) = protocol_state.clients.get_mut(&client.id()) else {
+            // The manager is gone, so is the configuration. Still initialize new objects.
+            if let zwlr_output_configuration_v1::Request::EnableHead { id, .. } = request {
+                data_init.init(id, ());
+            }
+            return;
+        };
+
+        let head_name = |head: &ZwlrOutputHeadV1| {
+            client_data
+                .heads
+                .iter()
+                .find(|(_, (resource, _))| resource == head)
+                .map(|(name, _)| name.clone())
+        };
+
+        match request {
+            zwlr_output_configuration_v1::Request::EnableHead { id, head } => {
+                let name = head_name(&head);
+                let config_head = data_init.init(id, ());
+
+                let Some(OutputConfigurationState::Ongoing {
+                    heads,
+                    has_stale_heads,
+                    ..
+                }) = client_data.configurations.get_mut(resource)
+                else {
+                    resource.post_error(
+                        zwlr_output_configuration_v1::Error::AlreadyUsed,
+                        "configuration has already been applied or tested",
+                    );
+                    return;
+                };
+
+                let Some(name) = name else {
+                    *has_stale_heads = true;
+                    return;
+                };
+
+                if heads.contains_key(&name) {
+                    resource.post_error(
+                        zwlr_output_configuration_v1::Error::AlreadyConfiguredHead,
+                        "head has already been configured",
+                    );
+                    return;
+                }
+
+                let config_head = ConfigurationHead {
+                    resource: config_head,
+                    mode: None,
+                    position: None,
+                    transform: None,
+                    scale: None,
+                };
+                heads.insert(name, Some(config_head));
+            }
+            zwlr_output_configuration_v1::Request::DisableHead { head } => {
+                let name = head_name(&head);
+
+                let Some(OutputConfigurationState::Ongoing {
+                    heads,
+                    has_stale_heads,
+                    ..
+                }) = client_data.configurations.get_mut(resource)
+                else {
+                    resource.post_error(
+                        zwlr_output_configuration_v1::Error::AlreadyUsed,
+                        "configuration has already been applied or tested",
+                    );
+                    return;
+                };
+
+                let Some(name) = name else {
+                    *has_stale_heads = true;
+                    return;
+                };
+
+                if heads.contains_key(&name) {
+                    resource.post_error(
+                        zwlr_output_configuration_v1::Error::AlreadyConfiguredHead,
+                        "head has already been configured",
+                    );
+                    return;
+                }
+
+                heads.insert(name, None);
+            }
+            request @ (zwlr_output_configuration_v1::Request::Apply
+            | zwlr_output_configuration_v1::Request::Test) => {
+                let is_apply = matches!(request, zwlr_output_configuration_v1::Request::Apply);
+
+                let Some(configuration) = client_data.configurations.get_mut(resource) else {
+                    return;
+                };
+
+                let OutputConfigurationState::Ongoing {
+                    serial,
+                    heads,
+                    has_stale_heads,
+                } = std::mem::replace(configuration, OutputConfigurationState::Finished)
+                else {
+                    resource.post_error(
+                        zwlr_output_configuration_v1::Error::AlreadyUsed,
+                        "configuration has already been applied or tested",
+                    );
+                    return;
+                };
+
+                if serial != current_serial || has_stale_heads {
+                    resource.cancelled();
+                    return;
+                }
+
+                let mut config = HashMap::new();
+                for (name, head) in heads {
+                    if !protocol_state.heads.contains_key(&name) {
+                        resource.cancelled();
+                        return;
+                    }
+
+                    let head_config = match head {
+                        Some(head) => HeadConfiguration::Enabled {
+                            mode: head.mode,
+                            position: head.position,
+                            transform: head.transform,
+                            scale: head.scale,
+                        },
+                        None => HeadConfiguration::Disabled,
+                    };
+                    config.insert(name, head_config);
+                }
+
+                if let Err(err) = check_config(&protocol_state.heads, &config) {
+                    debug!("rejecting output configuration: {err}");
+                    resource.failed();
+                    return;
+                }
+
+                if is_apply {
+                    if let Err(err) = state.apply_output_config(config) {
+                        warn!("error applying output configuration: {err:?}");
+                        resource.failed();
+                        return;
+                    }
+                }
+
+                resource.succeeded();
+            }
+            zwlr_output_configuration_v1::Request::Destroy => {
+                client_data.configurations.remove(resource);
+            }
+            _ => unreachable!(),
+        }
+    }
+
+    fn destroyed(
+        state: &mut D,
+        client: ClientId,
+        resource: &ZwlrOutputConfigurationV1,
+        _data: &(),
+    ) {
+        let state = state.output_management_state();
+        if let Some(client_data) = state.clients.get_mut(&client) {
+            client_data.configurations.remove(resource);
+        }
+    }
+}
+
+impl<D> Dispatch<ZwlrOutputConfigurationHeadV1, (), D> for OutputManagementManagerState
+where
+    D: Dispatch<ZwlrOutputConfigurationHeadV1, ()>,
+    D: OutputManagementHandler,
+{
+    fn request(
+        state: &mut D,
+        client: &Client,
+        resource: &ZwlrOutputConfigurationHeadV1,
+        request: <ZwlrOutputConfigurationHeadV1 as Resource>::Request,
+        _data: &(),
+        _dhandle: &DisplayHandle,
+        _data_init: &mut DataInit<'_, D>,
+    ) {
+        let state = state.output_management_state();
+
+        let Some(client_data) = state.clients.get_mut(&client.id()) else {
+            return;
+        };
+
+        // Find the configuration head along with the name of the head it configures.
+        let found = client_data
+            .configurations
+            .values_mut()
+            .find_map(|configuration| {
+                let OutputConfigurationState::Ongoing { heads, .. } = configuration else {
+                    return None;
+                };
+                heads.iter_mut().find_map(|(name, head)| {
+                    let head = head.as_mut()?;
+                    (head.resource == *resource).then_some((name.clone(), head))
+                })
+            });
+        let Some((name, config_head)) = found else {
+            // The configuration was already used, ignore the request.
+            return;
+        };
+
+        let already_set = || {
+            resource.post_error(
+                zwlr_output_configuration_head_v1::Error::AlreadySet,
+                "property has already been set",
+            );
+        };
+
+        match request {
+            zwlr_output_configuration_head_v1::Request::SetMode { mode } => {
+                let mode = client_data
+                    .heads
+                    .get(&name)
+                    .and_then(|(_, modes)| modes.iter().position(|m| *m == mode))
+                    .and_then(|idx| state.heads.get(&name)?.modes.get(idx));
+                let Some(mode) = mode else {
+                    resource.post_error(
+                        zwlr_output_configuration_head_v1::Error::InvalidMode,
+                        "mode does not belong to the head",
+                    );
+                    return;
+                };
+
+                if config_head.mode.is_some() {
+                    already_set();
+                    return;
+                }
+
+                config_head.mode = Some(niri_config::Mode {
+                    width: mode.width,
+                    height: mode.height,
+                    refresh: Some(f64::from(mode.refresh_rate) / 1000.),
+                });
+            }
+            zwlr_output_configuration_head_v1::Request::SetCustomMode {
+                width,
+                height,
+                refresh,
+            } => {
+                let (Ok(width), Ok(height), Ok(refresh)) = (
+                    u16::try_from(width),
+                    u16::try_from(height),
+                    u32::try_from(refresh),
+                ) else {
+                    resource.post_error(
+                        zwlr_output_configuration_head_v1::Error::InvalidCustomMode,
+                        "invalid custom mode",
+                    );
+                    return;
+                };
+
+                if config_head.mode.is_some() {
+                    already_set();
+                    return;
+                }
+
+                config_head.mode = Some(niri_config::Mode {
+                    width,
+                    height,
+                    // Zero refresh rate means any.
+                    refresh: (refresh != 0).then(|| f64::from(refresh) / 1000.),
+                });
+            }
+            zwlr_output_configuration_head_v1::Request::SetPosition { x, y } => {
+                if config_head.position.is_some() {
+                    already_set();
+                    return;
+                }
+
+                config_head.position = Some(Point::from((x, y)));
+            }
+            zwlr_output_configuration_head_v1::Request::SetTransform { transform } => {
+                let WEnum::Value(transform) = transform else {
+                    resource.post_error(
+                        zwlr_output_configuration_head_v1::Error::InvalidTransform,
+                        "invalid transform",
+                    );
+                    return;
+                };
+
+                let Some(transform) = from_wl_transform(transform) else {
+                    resource.post_error(
+                        zwlr_output_configuration_head_v1::Error::InvalidTransform,
+                        "invalid transform",
+                    );
+                    return;
+                };
+
+                if config_head.transform.is_some() {
+                    already_set();
+                    return;
+                }
+
+                config_head.transform = Some(transform);
+            }
+            zwlr_output_configuration_head_v1::Request::SetScale { scale } => {
+                if scale <= 0. || !scale.is_finite() {
+                    resource.post_error(
+                        zwlr_output_configuration_head_v1::Error::InvalidScale,
+                        "scale must be positive",
+                    );
+                    return;
+                }
+
+                if config_head.scale.is_some() {
+                    already_set();
+                    return;
+                }
+
+                config_head.scale = Some(scale);
+            }
+            _ => unreachable!(),
+        }
+    }
+}
+
+impl<D> Dispatch<ZwlrOutputHeadV1, (), D> for OutputManagementManagerState
+where
+    D: Dispatch<ZwlrOutputHeadV1, ()>,
+    D: OutputManagementHandler,
+{
+    fn request(
+        _state: &mut D,
+        _client: &Client,
+        _resource: &ZwlrOutputHeadV1,
+        request: <ZwlrOutputHeadV1 as Resource>::Request,
+        _data: &(),
+        _dhandle: &DisplayHandle,
+        _data_init: &mut DataInit<'_, D>,
+    ) {
+        match request {
+            // Keep tracking released heads so that they aren't re-sent as new ones. Events to
+            // destroyed resources are ignored.
+            zwlr_output_head_v1::Request::Release => (),
+            _ => unreachable!(),
+        }
+    }
+}
+
+impl<D> Dispatch<ZwlrOutputModeV1, (), D> for OutputManagementManagerState
+where
+    D: Dispatch<ZwlrOutputModeV1, ()>,
+    D: OutputManagementHandler,
+{
+    fn request(
+        _state: &mut D,
+        _client: &Client,
+        _resource: &ZwlrOutputModeV1,
+        request: <ZwlrOutputModeV1 as Resource>::Request,
+        _data: &(),
+        _dhandle: &DisplayHandle,
+        _data_init: &mut DataInit<'_, D>,
+    ) {
+        match request {
+            // Mode resources stay in the head's list so that indices keep matching.
+            zwlr_output_mode_v1::Request::Release => (),
+            _ => unreachable!(),
+        }
+    }
+}
+
+/// Checks that the configuration can be applied on top of the current state of the heads.
+fn check_config(
+    heads: &HashMap<String, HeadState>,
+    config: &HashMap<String, HeadConfiguration>,
+) -> Result<(), String> {
+    // Logical rectangles of the heads that end up enabled at a known position.
+    let mut rects: Vec<(&str, Rectangle<i32, Logical>)> = Vec::new();
+
+    for (name, head) in heads {
+        let current = head.logical;
+        let (mode, position, transform, scale) = match config.get(name) {
+            Some(HeadConfiguration::Disabled) => continue,
+            None if current.is_none() => continue,
+            None => (None, None, None, None),
+            Some(HeadConfiguration::Enabled {
+                mode,
+                position,
+                transform,
+                scale,
+            }) => (*mode, *position, *transform, *scale),
+        };
+
+        let size = match mode {
+            Some(mode) => {
+                if !has_mode(head, mode) {
+                    return Err(format!("head {name:?} has no mode {mode:?}"));
+                }
+                Size::from((i32::from(mode.width), i32::from(mode.height)))
+            }
+            None => {
+                let Some(mode) = head.current_mode.and_then(|idx| head.modes.get(idx)) else {
+                    return Err(format!("head {name:?} has no current mode"));
+                };
+                Size::from((i32::from(mode.width), i32::from(mode.height)))
+            }
+        };
+
+        let scale = scale.or(current.map(|c| c.scale)).unwrap_or(1.);
+        // Scales are clamped to this range when applied.
+        if !(0.1..=10.).contains(&scale) {
+            return Err(format!("head {name:?} has an unsupported scale {scale}"));
+        }
+
+        let transform = transform
+            .map(Transform::from)
+            .or(current.map(|c| c.transform))
+            .unwrap_or(Transform::Normal);
+        let size = transform
+            .transform_size(size)
+            .to_f64()
+            .to_logical(scale)
+            .to_i32_round();
+        if size.w <= 0 || size.h <= 0 {
+            return Err(format!("head {name:?} would have an empty logical size"));
+        }
+
+        // Heads without a position are placed automatically.
+        let Some(position) = position.or(current.map(|c| c.position)) else {
+            continue;
+        };
+
+        let rect = Rectangle::from_loc_and_size(position, size);
+        if let Some((other, _)) = rects.iter().find(|(_, other)| other.overlaps(rect)) {
+            return Err(format!("heads {name:?} and {other:?} would overlap"));
+        }
+        rects.push((name.as_str(), rect));
+    }
+
+    Ok(())
+}
+
+fn has_mode(head: &HeadState, mode: niri_config::Mode) -> bool {
+    head.modes.iter().any(|m| {
+        m.width == mode.width
+            && m.height == mode.height
+            && mode
+                .refresh
+                .map_or(true, |r| refresh_rate_matches(m.refresh_rate, r))
+    })
+}
+
+fn to_wl_transform(transform: Transform) -> wl_output::Transform {
+    match transform {
+        Transform::Normal => wl_output::Transform::Normal,
+        Transform::_90 => wl_output::Transform::_90,
+        Transform::_180 => wl_output::Transform::_180,
+        Transform::_270 => wl_output::Transform::_270,
+        Transform::Flipped => wl_output::Transform::Flipped,
+        Transform::Flipped90 => wl_output::Transform::Flipped90,
+        Transform::Flipped180 => wl_output::Transform::Flipped180,
+        Transform::Flipped270 => wl_output::Transform::Flipped270,
+    }
+}
+
+fn from_wl_transform(transform: wl_output::Transform) -> Option<niri_config::Transform> {
+    let transform = match transform {
+        wl_output::Transform::Normal => niri_config::Transform::Normal,
+        wl_output::Transform::_90 => niri_config::Transform::_90,
+        wl_output::Transform::_180 => niri_config::Transform::_180,
+        wl_output::Transform::_270 => niri_config::Transform::_270,
+        wl_output::Transform::Flipped => niri_config::Transform::Flipped,
+        wl_output::Transform::Flipped90 => niri_config::Transform::Flipped90,
+        wl_output::Transform::Flipped180 => niri_config::Transform::Flipped180,
+        wl_output::Transform::Flipped270 => niri_config::Transform::Flipped270,
+        _ => return None,
+    };
+    Some(transform)
+}
+
+#[macro_export]
+macro_rules! delegate_output_management {
+    ($(@<$( $lt:tt $( : $clt:tt $(+ $dlt:tt )* )? ),+>)? $ty: ty) => {
+        smithay::reexports::wayland_server::delegate_global_dispatch!($(@< $( $lt $( : $clt $(+ $dlt )* )? ),+ >)? $ty: [
+            smithay::reexports::wayland_protocols_wlr::output_management::v1::server::zwlr_output_manager_v1::ZwlrOutputManagerV1: $crate::protocols::output_management::OutputManagementGlobalData
+        ] => $crate::protocols::output_management::OutputManagementManagerState);
+        smithay::reexports::wayland_server::delegate_dispatch!($(@< $( $lt $( : $clt $(+ $dlt )* )? ),+ >)? $ty: [
+            smithay::reexports::wayland_protocols_wlr::output_management::v1::server::zwlr_output_manager_v1::ZwlrOutputManagerV1: ()
+        ] => $crate::protocols::output_management::OutputManagementManagerState);
+        smithay::reexports::wayland_server::delegate_dispatch!($(@< $( $lt $( : $clt $(+ $dlt )* )? ),+ >)? $ty: [
+            smithay::reexports::wayland_protocols_wlr::output_management::v1::server::zwlr_output_configuration_v1::ZwlrOutputConfigurationV1: ()
+        ] => $crate::protocols::output_management::OutputManagementManagerState);
+        smithay::reexports::wayland_server::delegate_dispatch!($(@< $( $lt $( : $clt $(+ $dlt )* )? ),+ >)? $ty: [
+            smithay::reexports::wayland_protocols_wlr::output_management::v1::server::zwlr_output_configuration_head_v1::ZwlrOutputConfigurationHeadV1: ()
+        ] => $crate::protocols::output_management::OutputManagementManagerState);
+        smithay::reexports::wayland_server::delegate_dispatch!($(@< $( $lt $( : $clt $(+ $dlt )* )? ),+ >)? $ty: [
+            smithay::reexports::wayland_protocols_wlr::output_management::v1::server::zwlr_output_head_v1::ZwlrOutputHeadV1: ()
+        ] => $crate::protocols::output_management::OutputManagementManagerState);
+        smithay::reexports::wayland_server::delegate_dispatch!($(@< $( $lt $( : $clt $(+ $dlt )* )? ),+ >)? $ty: [
+            smithay::reexports::wayland_protocols_wlr::output_management::v1::server::zwlr_output_mode_v1::ZwlrOutputModeV1: ()
+        ] => $crate::protocols::output_management::OutputManagementManagerState);
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use niri_config::Config;
+    use smithay::reexports::calloop::EventLoop;
+    use smithay::reexports::wayland_server::Display;
+
+    use super::*;
+
+    fn headless_state(event_loop: &EventLoop<'static, State>) -> State {
+        // Without a Wayland socket there's no IPC socket either, so this doesn't need a runtime
+        // directory.
+        let display = Display::new().unwrap();
+        State::new(
+            Config::default(),
+            event_loop.handle(),
+            event_loop.get_signal(),
+            display,
+            true,
+            false,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn apply_goes_through_output_config() {
+        let event_loop = EventLoop::try_new().unwrap();
+        let mut state = headless_state(&event_loop);
+
+        let headless = state.backend.headless();
+        headless.add_output(&mut state.niri, 1, (1920, 1080));
+        headless.add_output(&mut state.niri, 2, (1280, 720));
+        refresh(&mut state);
+
+        let heads = &state.niri.output_management_state.heads;
+        assert_eq!(heads.len(), 2);
+        assert_eq!(heads["headless-2"].current_mode, Some(0));
+        let serial = state.niri.output_management_state.serial;
+
+        let config = HashMap::from([(
+            String::from("headless-2"),
+            HeadConfiguration::Enabled {
+                mode: None,
+                position: Some(Point::from((0, -480))),
+                transform: Some(niri_config::Transform::Flipped),
+                scale: Some(1.5),
+            },
+        )]);
+        state.apply_output_config(config).unwrap();
+        refresh(&mut state);
+
+        let output_config = state.niri.config.borrow().outputs[0].clone();
+        assert_eq!(output_config.name, "headless-2");
+        assert_eq!(output_config.scale, 1.5);
+        assert_eq!(
+            output_config.position,
+            Some(niri_config::Position { x: 0, y: -480 })
+        );
+
+        let protocol_state = &state.niri.output_management_state;
+        assert_ne!(protocol_state.serial, serial);
+        let logical = protocol_state.heads["headless-2"].logical.unwrap();
+        assert_eq!(logical.position, Point::from((0, -480)));
+        assert_eq!(logical.scale, 1.5);
+        assert_eq!(logical.transform, Transform::Flipped);
+    }
+
+    #[test]
+    fn modes_are_matched_by_refresh_rate() {
+        let head = HeadState {
+            make: String::new(),
+            model: String::new(),
+            physical_size: None,
+            modes: vec![niri_ipc::Mode {
+                width: 1920,
+                height: 1080,
+                refresh_rate: 59_940,
+            }],
+            current_mode: Some(0),
+            logical: None,
+        };
+
+        let mut mode = niri_config::Mode {
+            width: 1920,
+            height: 1080,
+            refresh: None,
+        };
+        assert!(has_mode(&head, mode));
+
+        mode.refresh = Some(59.94);
+        assert!(has_mode(&head, mode));
+
+        mode.refresh = Some(59.9399);
+        assert!(has_mode(&head, mode));
+
+        mode.refresh = Some(60.);
+        assert!(!has_mode(&head, mode));
+
+        mode.width = 1280;
+        mode.refresh = None;
+        assert!(!has_mode(&head, mode));
+    }
+}
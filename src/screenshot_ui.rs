@@ -41,7 +41,7 @@ pub enum ScreenshotUi {
 
 pub struct OutputData {
     size: Size<i32, Physical>,
-    scale: i32,
+    scale: f64,
     transform: Transform,
     texture: GlesTexture,
     texture_buffer: TextureBuffer<GlesTexture>,
@@ -96,7 +96,7 @@ impl ScreenshotUi {
             }
         };
 
-        let scale = selection.0.current_scale().integer_scale();
+        let scale = logical_pixel_size(&selection.0);
         let selection = (
             selection.0,
             selection.1.loc,
@@ -109,11 +109,12 @@ impl ScreenshotUi {
                 let transform = output.current_transform();
                 let output_mode = output.current_mode().unwrap();
                 let size = transform.transform_size(output_mode.size);
-                let scale = output.current_scale().integer_scale();
+                let scale = output.current_scale().fractional_scale();
+                // The texture is sized explicitly when rendering, so the buffer scale is unused.
                 let texture_buffer = TextureBuffer::from_texture(
                     renderer,
                     texture.clone(),
-                    scale,
+                    1,
                     Transform::Normal,
                     None,
                 );
@@ -162,7 +163,7 @@ impl ScreenshotUi {
             }
         };
 
-        let scale = selection.0.current_scale().integer_scale();
+        let scale = logical_pixel_size(&selection.0);
         let last_selection = Some((
             selection.0.downgrade(),
             rect_from_corner_points(selection.1, selection.2, scale),
@@ -188,7 +189,7 @@ impl ScreenshotUi {
         };
 
         let (selection_output, a, b) = selection;
-        let scale = selection_output.current_scale().integer_scale();
+        let scale = logical_pixel_size(selection_output);
         let mut rect = rect_from_corner_points(*a, *b, scale);
 
         for (output, data) in output_data {
@@ -197,7 +198,7 @@ impl ScreenshotUi {
             let size = data.size;
 
             if output == selection_output {
-                let scale = output.current_scale().integer_scale();
+                let scale = logical_pixel_size(output);
 
                 // Check if the selection is still valid. If not, reset it back to default.
                 if !Rectangle::from_loc_and_size((0, 0), size).contains_rect(rect) {
@@ -268,14 +269,17 @@ impl ScreenshotUi {
             .into()
         }));
 
-        // The screenshot itself goes last.
+        // The screenshot itself goes last. The texture buffer has an integer scale, so size it
+        // explicitly to cover the whole output at fractional scales.
+        let scale = output.current_scale().fractional_scale();
+        let size = output_data.size.to_f64().to_logical(scale).to_i32_round();
         elements.push(
             PrimaryGpuTextureRenderElement(TextureRenderElement::from_texture_buffer(
                 (0., 0.),
                 &output_data.texture_buffer,
                 None,
                 None,
-                None,
+                Some(size),
                 Kind::Unspecified,
             ))
             .into(),
@@ -300,7 +304,7 @@ impl ScreenshotUi {
         };
 
         let data = &output_data[&selection.0];
-        let scale = selection.0.current_scale().integer_scale();
+        let scale = logical_pixel_size(&selection.0);
         let rect = rect_from_corner_points(selection.1, selection.2, scale);
         let buf_rect = rect
             .to_logical(1)
@@ -336,7 +340,7 @@ impl ScreenshotUi {
         }
     }
 
-    pub fn output_size(&self, output: &Output) -> Option<(Size<i32, Physical>, i32, Transform)> {
+    pub fn output_size(&self, output: &Output) -> Option<(Size<i32, Physical>, f64, Transform)> {
         if let Self::Open { output_data, .. } = self {
             let data = output_data.get(output)?;
             Some((data.size, data.scale, data.transform))
@@ -397,14 +401,13 @@ impl ScreenshotUi {
             // Check if the resulting selection is zero-sized, and try to come up with a small
             // default rectangle.
             let (output, a, b) = selection;
-            let scale = output.current_scale().integer_scale();
+            let scale = logical_pixel_size(output);
             let mut rect = rect_from_corner_points(*a, *b, scale);
             if rect.size.is_empty() || rect.size == Size::from((scale, scale)) {
                 let data = &output_data[output];
                 rect = Rectangle::from_loc_and_size((rect.loc.x - 16, rect.loc.y - 16), (32, 32))
                     .intersection(Rectangle::from_loc_and_size((0, 0), data.size))
                     .unwrap_or_default();
-                let scale = output.current_scale().integer_scale();
                 *a = rect.loc;
                 *b = rect.loc + rect.size - Size::from((scale, scale));
             }
@@ -440,6 +443,15 @@ fn action(raw: Keysym, mods: ModifiersState) -> Option<Action> {
     None
 }
 
+/// Returns the size of one logical pixel of the output in physical pixels, rounded.
+///
+/// The screenshot UI works in physical pixels, this keeps the selection snapping and the border
+/// width proportional to the output scale.
+fn logical_pixel_size(output: &Output) -> i32 {
+    let scale = output.current_scale().fractional_scale();
+    max(1, scale.round() as i32)
+}
+
 pub fn rect_from_corner_points(
     a: Point<i32, Physical>,
     b: Point<i32, Physical>,
@@ -0,0 +1,469 @@
+//! Minimal Wayland client for the end-to-end tests.
+
+use std::io;
+use std::os::fd::AsFd;
+use std::os::unix::net::UnixStream;
+
+use wayland_client::backend::WaylandError;
+use wayland_client::protocol::wl_buffer::WlBuffer;
+use wayland_client::protocol::wl_callback::{self, WlCallback};
+use wayland_client::protocol::wl_compositor::WlCompositor;
+use wayland_client::protocol::wl_registry::{self, WlRegistry};
+use wayland_client::protocol::wl_shm::{self, WlShm};
+use wayland_client::protocol::wl_shm_pool::WlShmPool;
+use wayland_client::protocol::wl_surface::WlSurface;
+use wayland_client::{
+    delegate_noop, event_created_child, Connection, Dispatch, EventQueue, QueueHandle,
+};
+use wayland_protocols::xdg::shell::client::xdg_surface::{self, XdgSurface};
+use wayland_protocols::xdg::shell::client::xdg_toplevel::{self, XdgToplevel};
+use wayland_protocols::xdg::shell::client::xdg_wm_base::{self, XdgWmBase};
+use wayland_protocols_wlr::output_management::v1::client::zwlr_output_configuration_head_v1::ZwlrOutputConfigurationHeadV1;
+use wayland_protocols_wlr::output_management::v1::client::zwlr_output_configuration_v1::{
+    self, ZwlrOutputConfigurationV1,
+};
+use wayland_protocols_wlr::output_management::v1::client::zwlr_output_head_v1::{
+    self, ZwlrOutputHeadV1,
+};
+use wayland_protocols_wlr::output_management::v1::client::zwlr_output_manager_v1::{
+    self, ZwlrOutputManagerV1,
+};
+use wayland_protocols_wlr::output_management::v1::client::zwlr_output_mode_v1::ZwlrOutputModeV1;
+
+pub struct Client {
+    connection: Connection,
+    queue: EventQueue<ClientState>,
+    pub state: ClientState,
+}
+
+pub struct ClientState {
+    qh: QueueHandle<ClientState>,
+    compositor: Option<WlCompositor>,
+    shm: Option<WlShm>,
+    xdg_wm_base: Option<XdgWmBase>,
+    output_manager: Option<ZwlrOutputManagerV1>,
+    /// Serial of the last complete set of output heads.
+    output_serial: u32,
+    /// Whether the last sync request got its reply.
+    sync_done: bool,
+    pub windows: Vec<Window>,
+    pub heads: Vec<Head>,
+    /// Results of the sent output configurations, `None` while pending.
+    pub output_configurations: Vec<Option<ConfigurationResult>>,
+}
+
+pub struct Window {
+    pub surface: WlSurface,
+    xdg_surface: XdgSurface,
+    xdg_toplevel: XdgToplevel,
+    /// Toplevel configure waiting for the xdg_surface configure that completes it.
+    pending: Configure,
+    /// Configures received from the compositor, oldest first.
+    pub configures: Vec<Configure>,
+}
+
+/// Output head advertised through wlr-output-management.
+pub struct Head {
+    head: ZwlrOutputHeadV1,
+    pub name: String,
+    pub enabled: bool,
+    pub position: (i32, i32),
+    pub scale: f64,
+}
+
+/// Head configuration to send through wlr-output-management.
+#[derive(Debug, Clone, Copy)]
+pub enum HeadConfig {
+    Disabled,
+    Enabled {
+        position: Option<(i32, i32)>,
+        scale: Option<f64>,
+    },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConfigurationResult {
+    Succeeded,
+    Failed,
+    Cancelled,
+}
+
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct Configure {
+    pub serial: u32,
+    pub size: (i32, i32),
+    pub states: Vec<xdg_toplevel::State>,
+}
+
+impl Client {
+    pub fn new(stream: UnixStream) -> Self {
+        let connection = Connection::from_socket(stream).unwrap();
+        let queue = connection.new_event_queue();
+        let qh = queue.handle();
+        connection.display().get_registry(&qh, ());
+
+        let state = ClientState {
+            qh,
+            compositor: None,
+            shm: None,
+            xdg_wm_base: None,
+            output_manager: None,
+            output_serial: 0,
+            sync_done: false,
+            windows: Vec::new(),
+            heads: Vec::new(),
+            output_configurations: Vec::new(),
+        };
+
+        Self {
+            connection,
+            queue,
+            state,
+        }
+    }
+
+    /// Sends the pending requests and processes whatever events arrived, without blocking.
+    pub fn dispatch(&mut self) {
+        self.connection.flush().unwrap();
+
+        if let Some(guard) = self.connection.prepare_read() {
+            match guard.read() {
+                Ok(_) => (),
+                Err(WaylandError::Io(err)) if err.kind() == io::ErrorKind::WouldBlock => (),
+                Err(err) => panic!("error reading Wayland events: {err:?}"),
+            }
+        }
+
+        self.queue.dispatch_pending(&mut self.state).unwrap();
+    }
+
+    /// Requests a sync callback, see [`Client::sync_done`].
+    pub fn send_sync(&mut self) {
+        self.state.sync_done = false;
+        self.connection.display().sync(&self.state.qh, ());
+    }
+
+    pub fn sync_done(&self) -> bool {
+        self.state.sync_done
+    }
+
+    /// Creates a toplevel window and returns its index.
+    ///
+    /// The window is committed without a buffer, which makes the compositor send the initial
+    /// configure.
+    pub fn create_window(&mut self, app_id: &str) -> usize {
+        let qh = &self.state.qh;
+        let surface = self
+            .state
+            .compositor
+            .as_ref()
+            .unwrap()
+            .create_surface(qh, ());
+        let xdg_surface =
+            self.state
+                .xdg_wm_base
+                .as_ref()
+                .unwrap()
+                .get_xdg_surface(&surface, qh, ());
+        let xdg_toplevel = xdg_surface.get_toplevel(qh, ());
+        xdg_toplevel.set_app_id(app_id.to_owned());
+        surface.commit();
+
+        self.state.windows.push(Window {
+            surface,
+            xdg_surface,
+            xdg_toplevel,
+            pending: Configure::default(),
+            configures: Vec::new(),
+        });
+        self.state.windows.len() - 1
+    }
+
+    /// Acks the latest configure of the window and commits a buffer of the configured size.
+    pub fn ack_and_commit(&mut self, idx: usize) {
+        let window = &self.state.windows[idx];
+        let configure = window.configures.last().unwrap();
+        window.xdg_surface.ack_configure(configure.serial);
+
+        // A zero size means the client picks its own.
+        let (mut w, mut h) = configure.size;
+        if w == 0 {
+            w = 100;
+        }
+        if h == 0 {
+            h = 100;
+        }
+
+        let buffer = self.create_buffer(w, h);
+        let window = &self.state.windows[idx];
+        window.surface.attach(Some(&buffer), 0, 0);
+        window.surface.commit();
+    }
+
+    pub fn head(&self, name: &str) -> &Head {
+        self.state.heads.iter().find(|h| h.name == name).unwrap()
+    }
+
+    /// Sends an output configuration for the latest heads and returns its index in
+    /// `output_configurations`.
+    ///
+    /// The configuration is applied if `apply` is set, and only tested otherwise.
+    pub fn send_output_config(&mut self, heads: &[(&str, HeadConfig)], apply: bool) -> usize {
+        let state = &mut self.state;
+        let qh = &state.qh;
+        let idx = state.output_configurations.len();
+        state.output_configurations.push(None);
+
+        let configuration = state.output_manager.as_ref().unwrap().create_configuration(
+            state.output_serial,
+            qh,
+            idx,
+        );
+
+        for (name, config) in heads {
+            let head = &state.heads.iter().find(|h| h.name == *name).unwrap().head;
+            match *config {
+                HeadConfig::Disabled => configuration.disable_head(head),
+                HeadConfig::Enabled { position, scale } => {
+                    let config_head = configuration.enable_head(head, qh, ());
+                    if let Some((x, y)) = position {
+                        config_head.set_position(x, y);
+                    }
+                    if let Some(scale) = scale {
+                        config_head.set_scale(scale);
+                    }
+                }
+            }
+        }
+
+        if apply {
+            configuration.apply();
+        } else {
+            configuration.test();
+        }
+
+        idx
+    }
+
+    fn create_buffer(&self, w: i32, h: i32) -> WlBuffer {
+        let qh = &self.state.qh;
+        let stride = w * 4;
+        let len = stride * h;
+
+        let file = tempfile::tempfile().unwrap();
+        file.set_len(len as u64).unwrap();
+
+        let pool = self
+            .state
+            .shm
+            .as_ref()
+            .unwrap()
+            .create_pool(file.as_fd(), len, qh, ());
+        let buffer = pool.create_buffer(0, w, h, stride, wl_shm::Format::Argb8888, qh, ());
+        pool.destroy();
+        buffer
+    }
+}
+
+impl ClientState {
+    fn window(&mut self, xdg_surface: &XdgSurface) -> &mut Window {
+        self.windows
+            .iter_mut()
+            .find(|w| w.xdg_surface == *xdg_surface)
+            .unwrap()
+    }
+}
+
+impl Dispatch<WlRegistry, ()> for ClientState {
+    fn event(
+        state: &mut Self,
+        registry: &WlRegistry,
+        event: wl_registry::Event,
+        _data: &(),
+        _conn: &Connection,
+        qh: &QueueHandle<Self>,
+    ) {
+        if let wl_registry::Event::Global {
+            name,
+            interface,
+            version,
+        } = event
+        {
+            match &interface[..] {
+                "wl_compositor" => {
+                    state.compositor = Some(registry.bind(name, version.min(4), qh, ()));
+                }
+                "wl_shm" => state.shm = Some(registry.bind(name, 1, qh, ())),
+                "xdg_wm_base" => {
+                    state.xdg_wm_base = Some(registry.bind(name, version.min(3), qh, ()));
+                }
+                "zwlr_output_manager_v1" => {
+                    state.output_manager = Some(registry.bind(name, version.min(3), qh, ()));
+                }
+                _ => (),
+            }
+        }
+    }
+}
+
+impl Dispatch<WlCallback, ()> for ClientState {
+    fn event(
+        state: &mut Self,
+        _callback: &WlCallback,
+        event: wl_callback::Event,
+        _data: &(),
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+    ) {
+        if let wl_callback::Event::Done { .. } = event {
+            state.sync_done = true;
+        }
+    }
+}
+
+impl Dispatch<XdgWmBase, ()> for ClientState {
+    fn event(
+        _state: &mut Self,
+        xdg_wm_base: &XdgWmBase,
+        event: xdg_wm_base::Event,
+        _data: &(),
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+    ) {
+        if let xdg_wm_base::Event::Ping { serial } = event {
+            xdg_wm_base.pong(serial);
+        }
+    }
+}
+
+impl Dispatch<XdgSurface, ()> for ClientState {
+    fn event(
+        state: &mut Self,
+        xdg_surface: &XdgSurface,
+        event: xdg_surface::Event,
+        _data: &(),
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+    ) {
+        if let xdg_surface::Event::Configure { serial } = event {
+            let window = state.window(xdg_surface);
+            let mut configure = window.pending.clone();
+            configure.serial = serial;
+            window.configures.push(configure);
+        }
+    }
+}
+
+impl Dispatch<XdgToplevel, ()> for ClientState {
+    fn event(
+        state: &mut Self,
+        xdg_toplevel: &XdgToplevel,
+        event: xdg_toplevel::Event,
+        _data: &(),
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+    ) {
+        if let xdg_toplevel::Event::Configure {
+            width,
+            height,
+            states,
+        } = event
+        {
+            let window = state
+                .windows
+                .iter_mut()
+                .find(|w| w.xdg_toplevel == *xdg_toplevel)
+                .unwrap();
+            window.pending.size = (width, height);
+            window.pending.states = states
+                .chunks_exact(4)
+                .map(|x| u32::from_ne_bytes(x.try_into().unwrap()))
+                .filter_map(|x| xdg_toplevel::State::try_from(x).ok())
+                .collect();
+        }
+    }
+}
+
+impl Dispatch<ZwlrOutputManagerV1, ()> for ClientState {
+    fn event(
+        state: &mut Self,
+        _manager: &ZwlrOutputManagerV1,
+        event: zwlr_output_manager_v1::Event,
+        _data: &(),
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+    ) {
+        match event {
+            zwlr_output_manager_v1::Event::Head { head } => state.heads.push(Head {
+                head,
+                name: String::new(),
+                enabled: false,
+                position: (0, 0),
+                scale: 1.,
+            }),
+            zwlr_output_manager_v1::Event::Done { serial } => state.output_serial = serial,
+            _ => (),
+        }
+    }
+
+    event_created_child!(ClientState, ZwlrOutputManagerV1, [
+        zwlr_output_manager_v1::EVT_HEAD_OPCODE => (ZwlrOutputHeadV1, ()),
+    ]);
+}
+
+impl Dispatch<ZwlrOutputHeadV1, ()> for ClientState {
+    fn event(
+        state: &mut Self,
+        head: &ZwlrOutputHeadV1,
+        event: zwlr_output_head_v1::Event,
+        _data: &(),
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+    ) {
+        if let zwlr_output_head_v1::Event::Finished = event {
+            state.heads.retain(|h| h.head != *head);
+            return;
+        }
+
+        let head = state.heads.iter_mut().find(|h| h.head == *head).unwrap();
+        match event {
+            zwlr_output_head_v1::Event::Name { name } => head.name = name,
+            zwlr_output_head_v1::Event::Enabled { enabled } => head.enabled = enabled != 0,
+            zwlr_output_head_v1::Event::Position { x, y } => head.position = (x, y),
+            zwlr_output_head_v1::Event::Scale { scale } => head.scale = scale,
+            _ => (),
+        }
+    }
+
+    event_created_child!(ClientState, ZwlrOutputHeadV1, [
+        zwlr_output_head_v1::EVT_MODE_OPCODE => (ZwlrOutputModeV1, ()),
+    ]);
+}
+
+impl Dispatch<ZwlrOutputConfigurationV1, usize> for ClientState {
+    fn event(
+        state: &mut Self,
+        configuration: &ZwlrOutputConfigurationV1,
+        event: zwlr_output_configuration_v1::Event,
+        idx: &usize,
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+    ) {
+        let result = match event {
+            zwlr_output_configuration_v1::Event::Succeeded => ConfigurationResult::Succeeded,
+            zwlr_output_configuration_v1::Event::Failed => ConfigurationResult::Failed,
+            zwlr_output_configuration_v1::Event::Cancelled => ConfigurationResult::Cancelled,
+            _ => return,
+        };
+        state.output_configurations[*idx] = Some(result);
+        configuration.destroy();
+    }
+}
+
+delegate_noop!(ClientState: WlCompositor);
+delegate_noop!(ClientState: WlShmPool);
+delegate_noop!(ClientState: ignore WlShm);
+delegate_noop!(ClientState: ignore WlSurface);
+delegate_noop!(ClientState: ignore WlBuffer);
+delegate_noop!(ClientState: ZwlrOutputConfigurationHeadV1);
+delegate_noop!(ClientState: ignore ZwlrOutputModeV1);
@@ -0,0 +1,133 @@
+//! Headless compositor with clients connected to it, all running on the test thread.
+
+use std::io::{self, Read, Write};
+use std::os::unix::net::UnixStream;
+use std::time::Duration;
+
+use niri_config::Config;
+use niri_ipc::{Reply, Request};
+use smithay::reexports::calloop::EventLoop;
+use smithay::reexports::wayland_server::Display;
+
+use super::client::Client;
+use crate::ipc::server::on_new_ipc_client;
+use crate::niri::State;
+
+/// Iterations to wait for a reply before deciding that it isn't coming.
+const MAX_DISPATCHES: usize = 100;
+
+pub struct Fixture {
+    event_loop: EventLoop<'static, State>,
+    pub state: State,
+    clients: Vec<Client>,
+}
+
+impl Fixture {
+    pub fn new() -> Self {
+        Self::with_config(Config::default())
+    }
+
+    pub fn with_config(config: Config) -> Self {
+        let event_loop = EventLoop::try_new().unwrap();
+        let display = Display::new().unwrap();
+
+        // No sockets: clients are connected directly, so tests don't need a runtime directory
+        // and can run in parallel.
+        let state = State::new(
+            config,
+            event_loop.handle(),
+            event_loop.get_signal(),
+            display,
+            true,
+            false,
+        )
+        .unwrap();
+
+        Self {
+            event_loop,
+            state,
+            clients: Vec::new(),
+        }
+    }
+
+    /// Adds a virtual output named `headless-{n}`.
+    pub fn add_output(&mut self, n: u8, size: (u16, u16)) {
+        let state = &mut self.state;
+        state
+            .backend
+            .headless()
+            .add_output(&mut state.niri, n, size);
+        self.dispatch();
+    }
+
+    /// Connects a new Wayland client and returns its index.
+    pub fn add_client(&mut self) -> usize {
+        let (server, client) = UnixStream::pair().unwrap();
+        self.state.niri.insert_client(server);
+        self.clients.push(Client::new(client));
+
+        let idx = self.clients.len() - 1;
+        self.roundtrip(idx);
+        idx
+    }
+
+    pub fn client(&mut self, idx: usize) -> &mut Client {
+        &mut self.clients[idx]
+    }
+
+    /// Runs one iteration of the compositor and of every client.
+    pub fn dispatch(&mut self) {
+        self.event_loop
+            .dispatch(Duration::ZERO, &mut self.state)
+            .unwrap();
+        self.state.refresh_and_flush_clients();
+
+        for client in &mut self.clients {
+            client.dispatch();
+        }
+    }
+
+    /// Dispatches until the compositor has processed everything the client sent so far and the
+    /// client has processed the replies.
+    pub fn roundtrip(&mut self, idx: usize) {
+        self.clients[idx].send_sync();
+
+        for _ in 0..MAX_DISPATCHES {
+            self.dispatch();
+            if self.clients[idx].sync_done() {
+                return;
+            }
+        }
+
+        panic!("client {idx} roundtrip timed out");
+    }
+
+    /// Sends an IPC request and waits for the reply.
+    pub fn ipc(&mut self, request: Request) -> Reply {
+        let (server, mut client) = UnixStream::pair().unwrap();
+        on_new_ipc_client(&mut self.state, server);
+
+        let mut buf = serde_json::to_vec(&request).unwrap();
+        buf.push(b'\n');
+        client.write_all(&buf).unwrap();
+        client.set_nonblocking(true).unwrap();
+
+        let mut reply = Vec::new();
+        for _ in 0..MAX_DISPATCHES {
+            self.dispatch();
+
+            let mut chunk = [0; 4096];
+            match client.read(&mut chunk) {
+                Ok(len) => reply.extend_from_slice(&chunk[..len]),
+                Err(err) if err.kind() == io::ErrorKind::WouldBlock => (),
+                Err(err) => panic!("error reading IPC reply: {err:?}"),
+            }
+
+            if reply.ends_with(b"\n") {
+                return serde_json::from_slice(&reply).unwrap();
+            }
+        }
+
+        panic!("IPC request timed out: {request:?}");
+    }
+}
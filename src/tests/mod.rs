@@ -0,0 +1,129 @@
+//! End-to-end tests running the compositor headless with real Wayland and IPC clients.
+
+use niri_ipc::{Action, LayoutSwitchTarget, Request, Response, SizeChange, WorkspaceReferenceArg};
+
+use self::fixture::Fixture;
+
+mod client;
+mod fixture;
+mod output_management;
+
+/// Opens a window in the given client and maps it, returning its index in the client.
+fn open_window(f: &mut Fixture, client: usize, app_id: &str) -> usize {
+    let window = f.client(client).create_window(app_id);
+    f.roundtrip(client);
+    f.client(client).ack_and_commit(window);
+    f.roundtrip(client);
+    window
+}
+
+#[test]
+fn window_opens_and_follows_actions() {
+    let mut f = Fixture::new();
+    f.add_output(1, (1280, 720));
+    let client = f.add_client();
+
+    let window = open_window(&mut f, client, "test-app");
+    let mut count = 0;
+    f.state.niri.layout.with_windows(|_, _| count += 1);
+    assert_eq!(count, 1);
+
+    let Ok(Response::Windows(windows)) = f.ipc(Request::Windows) else {
+        panic!("unexpected reply");
+    };
+    assert_eq!(windows.len(), 1);
+    assert_eq!(windows[0].app_id.as_deref(), Some("test-app"));
+    assert_eq!(windows[0].output.as_deref(), Some("headless-1"));
+
+    let reply = f.ipc(Request::Action(Action::MaximizeColumn { id: None }));
+    assert!(matches!(reply, Ok(Response::Handled)));
+    f.roundtrip(client);
+
+    // Full width and height of the output minus the default gaps.
+    let configure = f.client(client).state.windows[window]
+        .configures
+        .last()
+        .unwrap()
+        .clone();
+    assert_eq!(configure.size, (1280 - 16 * 2, 720 - 16 * 2));
+
+    let Ok(Response::Workspaces(workspaces)) = f.ipc(Request::Workspaces) else {
+        panic!("unexpected reply");
+    };
+    // The workspace with the window and the empty one below it.
+    assert_eq!(workspaces.len(), 2);
+}
+
+#[test]
+fn failed_actions_reply_with_error() {
+    let mut f = Fixture::new();
+    f.add_output(1, (1280, 720));
+
+    let reply = f.ipc(Request::Action(Action::FocusWorkspace {
+        reference: WorkspaceReferenceArg::Name(String::from("missing")),
+    }));
+    let Err(err) = reply else {
+        panic!("unexpected reply");
+    };
+    assert_eq!(err, "workspace \"missing\" does not exist");
+
+    let reply = f.ipc(Request::Action(Action::CloseWindow { id: Some(1000) }));
+    let Err(err) = reply else {
+        panic!("unexpected reply");
+    };
+    assert_eq!(err, "window with id 1000 does not exist");
+
+    let reply = f.ipc(Request::Action(Action::SwitchLayout {
+        layout: LayoutSwitchTarget::Index(5),
+    }));
+    let Err(err) = reply else {
+        panic!("unexpected reply");
+    };
+    assert_eq!(err, "keyboard layout 5 does not exist");
+
+    let reply = f.ipc(Request::Action(Action::SwitchLayout {
+        layout: LayoutSwitchTarget::Name(String::from("Missing")),
+    }));
+    let Err(err) = reply else {
+        panic!("unexpected reply");
+    };
+    assert_eq!(err, "keyboard layout \"Missing\" does not exist");
+}
+
+#[test]
+fn column_width_by_id_keeps_focus() {
+    let mut f = Fixture::new();
+    f.add_output(1, (1280, 720));
+    let client = f.add_client();
+
+    let first = open_window(&mut f, client, "first");
+    open_window(&mut f, client, "second");
+
+    let Ok(Response::Windows(windows)) = f.ipc(Request::Windows) else {
+        panic!("unexpected reply");
+    };
+    let id = windows
+        .iter()
+        .find(|w| w.app_id.as_deref() == Some("first"))
+        .unwrap()
+        .id;
+
+    let reply = f.ipc(Request::Action(Action::SetColumnWidth {
+        id: Some(id),
+        change: SizeChange::SetFixed(500),
+    }));
+    assert!(matches!(reply, Ok(Response::Handled)));
+    f.roundtrip(client);
+
+    let configure = f.client(client).state.windows[first]
+        .configures
+        .last()
+        .unwrap()
+        .clone();
+    assert_eq!(configure.size.0, 500);
+
+    let Ok(Response::FocusedWindow(Some(focused))) = f.ipc(Request::FocusedWindow) else {
+        panic!("unexpected reply");
+    };
+    assert_eq!(focused.app_id.as_deref(), Some("second"));
+}
@@ -0,0 +1,127 @@
+use super::client::{ConfigurationResult, HeadConfig};
+use super::fixture::Fixture;
+
+fn fixture_with_two_outputs() -> (Fixture, usize) {
+    let mut f = Fixture::new();
+    f.add_output(1, (1280, 720));
+    f.add_output(2, (1280, 720));
+    let client = f.add_client();
+    // Bind the output manager and receive the heads.
+    f.roundtrip(client);
+    f.roundtrip(client);
+    (f, client)
+}
+
+#[test]
+fn heads_are_advertised() {
+    let (mut f, client) = fixture_with_two_outputs();
+
+    let head = f.client(client).head("headless-1");
+    assert!(head.enabled);
+    assert_eq!(head.position, (0, 0));
+    assert_eq!(head.scale, 1.);
+
+    let head = f.client(client).head("headless-2");
+    assert!(head.enabled);
+    assert_eq!(head.position, (1280, 0));
+}
+
+#[test]
+fn apply_changes_outputs() {
+    let (mut f, client) = fixture_with_two_outputs();
+
+    let config = [
+        (
+            "headless-1",
+            HeadConfig::Enabled {
+                position: None,
+                scale: None,
+            },
+        ),
+        (
+            "headless-2",
+            HeadConfig::Enabled {
+                position: Some((0, 720)),
+                scale: Some(2.),
+            },
+        ),
+    ];
+    let idx = f.client(client).send_output_config(&config, true);
+    f.roundtrip(client);
+    f.roundtrip(client);
+
+    let c = f.client(client);
+    assert_eq!(
+        c.state.output_configurations[idx],
+        Some(ConfigurationResult::Succeeded)
+    );
+    let head = c.head("headless-2");
+    assert_eq!(head.position, (0, 720));
+    assert_eq!(head.scale, 2.);
+}
+
+#[test]
+fn test_rejects_invalid_configs() {
+    let (mut f, client) = fixture_with_two_outputs();
+
+    let overlapping = [
+        (
+            "headless-1",
+            HeadConfig::Enabled {
+                position: None,
+                scale: None,
+            },
+        ),
+        (
+            "headless-2",
+            HeadConfig::Enabled {
+                position: Some((640, 0)),
+                scale: None,
+            },
+        ),
+    ];
+    let bad_scale = [(
+        "headless-1",
+        HeadConfig::Enabled {
+            position: None,
+            scale: Some(20.),
+        },
+    )];
+
+    let c = f.client(client);
+    let overlapping = c.send_output_config(&overlapping, false);
+    let bad_scale = c.send_output_config(&bad_scale, false);
+    f.roundtrip(client);
+
+    let results = &f.client(client).state.output_configurations;
+    assert_eq!(results[overlapping], Some(ConfigurationResult::Failed));
+    assert_eq!(results[bad_scale], Some(ConfigurationResult::Failed));
+}
+
+#[test]
+fn apply_fails_when_backend_cannot_disable() {
+    let (mut f, client) = fixture_with_two_outputs();
+
+    let config = [
+        (
+            "headless-1",
+            HeadConfig::Enabled {
+                position: None,
+                scale: None,
+            },
+        ),
+        ("headless-2", HeadConfig::Disabled),
+    ];
+    let idx = f.client(client).send_output_config(&config, true);
+    f.roundtrip(client);
+    f.roundtrip(client);
+
+    let c = f.client(client);
+    assert_eq!(
+        c.state.output_configurations[idx],
+        Some(ConfigurationResult::Failed)
+    );
+    assert!(c.head("headless-2").enabled);
+    // The failed configuration doesn't stick around to be applied later.
+    assert!(f.state.niri.config.borrow().outputs.is_empty());
+}
@@ -13,9 +13,12 @@ use anyhow::{ensure, Context};
 use directories::UserDirs;
 use git_version::git_version;
 use niri_config::Config;
-use smithay::output::Output;
+use smithay::output::{self, Output};
 use smithay::reexports::rustix::time::{clock_gettime, ClockId};
-use smithay::utils::{Logical, Point, Rectangle, Size};
+use smithay::reexports::wayland_server::protocol::wl_surface::WlSurface;
+use smithay::utils::{Logical, Point, Rectangle, Size, Transform};
+use smithay::wayland::compositor::{send_surface_state, SurfaceData};
+use smithay::wayland::fractional_scale::with_fractional_scale;
 
 pub fn clone2<T: Clone, U: Clone>(t: (&T, &U)) -> (T, U) {
     (t.0.clone(), t.1.clone())
@@ -39,13 +42,48 @@ pub fn center(rect: Rectangle<i32, Logical>) -> Point<i32, Logical> {
 }
 
 pub fn output_size(output: &Output) -> Size<i32, Logical> {
-    let output_scale = output.current_scale().integer_scale();
+    let output_scale = output.current_scale().fractional_scale();
     let output_transform = output.current_transform();
     let output_mode = output.current_mode().unwrap();
 
     output_transform
         .transform_size(output_mode.size)
+        .to_f64()
         .to_logical(output_scale)
+        .to_i32_round()
+}
+
+/// Returns the closest scale that clients can represent with wp-fractional-scale-v1.
+pub fn closest_representable_scale(scale: f64) -> f64 {
+    // The protocol sends the scale as a numerator over 120.
+    const FRACTIONAL_SCALE_DENOM: f64 = 120.;
+
+    let scale = scale.clamp(0.1, 10.);
+    (scale * FRACTIONAL_SCALE_DENOM).round() / FRACTIONAL_SCALE_DENOM
+}
+
+/// Returns whether a mode refresh rate in millihertz matches a configured one in hertz.
+///
+/// Configured refresh rates are usually written with two decimals, like 59.94, so they're allowed
+/// to be off by the rounding.
+pub fn refresh_rate_matches(millihertz: u32, hertz: f64) -> bool {
+    (f64::from(millihertz) - hertz * 1000.).abs() <= 5.
+}
+
+/// Sends the output scale and transform to a surface.
+///
+/// Clients that bind wp-fractional-scale-v1 get the exact fractional scale, others get the integer
+/// buffer scale rounded up.
+pub fn send_scale_transform(
+    surface: &WlSurface,
+    data: &SurfaceData,
+    scale: output::Scale,
+    transform: Transform,
+) {
+    send_surface_state(surface, data, scale.integer_scale(), transform);
+    with_fractional_scale(data, |fractional| {
+        fractional.set_preferred_scale(scale.fractional_scale());
+    });
 }
 
 pub fn make_screenshot_path(config: &Config) -> anyhow::Result<Option<PathBuf>> {
@@ -1,74 +1,418 @@
 //! File modification watcher.
 
-use std::path::PathBuf;
-use std::sync::atomic::{AtomicBool, Ordering};
-use std::sync::Arc;
-use std::thread;
-use std::time::Duration;
+use std::collections::HashSet;
+use std::io;
+use std::os::fd::OwnedFd;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
 
-use smithay::reexports::calloop::channel::SyncSender;
+use smithay::reexports::calloop::generic::Generic;
+use smithay::reexports::calloop::{
+    self, EventSource, Interest, Mode, Poll, PostAction, Readiness, Token, TokenFactory,
+};
+use smithay::reexports::rustix::fs::inotify::{
+    inotify_add_watch, inotify_init, inotify_remove_watch, CreateFlags, WatchFlags,
+};
+use smithay::reexports::rustix::io::{read, Errno};
 
+/// Event source that fires when any of the watched files changes.
+///
+/// Besides the files themselves, this watches their parent directories, so that it notices
+/// editors saving by renaming a new file over the old one, and symlinks being swapped out. When a
+/// parent directory doesn't exist yet, its nearest existing ancestor is watched instead, and the
+/// watch moves down as the missing directories get created.
+///
+/// The callback receives the list of watched files and can replace it, for example when the set
+/// of included config files has changed.
 pub struct Watcher {
-    should_stop: Arc<AtomicBool>,
+    inotify: Generic<OwnedFd>,
+    paths: Vec<PathBuf>,
+    watches: HashSet<i32>,
+    last_props: Vec<Option<(SystemTime, PathBuf)>>,
 }
 
-impl Drop for Watcher {
-    fn drop(&mut self) {
-        self.should_stop.store(true, Ordering::SeqCst);
+impl Watcher {
+    pub fn new(paths: Vec<PathBuf>) -> io::Result<Self> {
+        let inotify = inotify_init(CreateFlags::CLOEXEC | CreateFlags::NONBLOCK)?;
+
+        let mut watcher = Self {
+            inotify: Generic::new(inotify, Interest::READ, Mode::Level),
+            paths,
+            watches: HashSet::new(),
+            last_props: Vec::new(),
+        };
+        watcher.update_watches();
+        watcher.last_props = props(&watcher.paths);
+
+        Ok(watcher)
+    }
+
+    /// Makes the inotify watches match the current files on disk.
+    ///
+    /// Renames and symlink swaps replace the watched inodes, so this needs to run after every
+    /// event.
+    fn update_watches(&mut self) {
+        let fd = self.inotify.get_ref();
+
+        let file_flags = WatchFlags::MODIFY
+            | WatchFlags::CLOSE_WRITE
+            | WatchFlags::ATTRIB
+            | WatchFlags::MOVE_SELF
+            | WatchFlags::DELETE_SELF;
+        let dir_flags = WatchFlags::ONLYDIR
+            | WatchFlags::CREATE
+            | WatchFlags::DELETE
+            | WatchFlags::MOVED_FROM
+            | WatchFlags::MOVED_TO
+            | WatchFlags::MOVE_SELF
+            | WatchFlags::DELETE_SELF;
+
+        let mut watches = HashSet::new();
+        for path in &self.paths {
+            // The file itself, following symlinks, for in-place writes.
+            if let Ok(wd) = inotify_add_watch(fd, path.as_path(), file_flags) {
+                watches.insert(wd);
+            }
+
+            // The nearest existing directory, for renames, symlink swaps and the missing
+            // directories on the way to the file getting created.
+            let mut dirs = path.ancestors().skip(1).map(|dir| {
+                if dir.as_os_str().is_empty() {
+                    Path::new(".")
+                } else {
+                    dir
+                }
+            });
+            if let Some(wd) = dirs.find_map(|dir| inotify_add_watch(fd, dir, dir_flags).ok()) {
+                watches.insert(wd);
+            }
+
+            // The directory of the symlink target, for writes that replace the target.
+            let canonical_parent = path
+                .canonicalize()
+                .ok()
+                .and_then(|canon| canon.parent().map(Path::to_owned));
+            if let Some(dir) = canonical_parent {
+                if let Ok(wd) = inotify_add_watch(fd, &dir, dir_flags) {
+                    watches.insert(wd);
+                }
+            }
+        }
+
+        for wd in self.watches.difference(&watches) {
+            // Watches on deleted inodes are already gone, so ignore errors.
+            let _ = inotify_remove_watch(fd, *wd);
+        }
+
+        self.watches = watches;
     }
 }
 
-impl Watcher {
-    pub fn new(path: PathBuf, changed: SyncSender<()>) -> Self {
-        let should_stop = Arc::new(AtomicBool::new(false));
-
-        {
-            let should_stop = should_stop.clone();
-            thread::Builder::new()
-                .name(format!("Filesystem Watcher for {}", path.to_string_lossy()))
-                .spawn(move || {
-                    // this "should" be as simple as mtime, but it does not quite work in practice;
-                    // it doesn't work if the config is a symlink, and its target changes but the
-                    // new target and old target have identical mtimes.
-                    //
-                    // in practice, this does not occur on any systems other than nix.
-                    // because, on nix practically everything is a symlink to /nix/store
-                    // and due to reproducibility, /nix/store keeps no mtime (= 1970-01-01)
-                    // so, symlink targets change frequently when mtime doesn't.
-                    let mut last_props = path
-                        .canonicalize()
-                        .and_then(|canon| Ok((canon.metadata()?.modified()?, canon)))
-                        .ok();
-
-                    loop {
-                        thread::sleep(Duration::from_millis(500));
-
-                        if should_stop.load(Ordering::SeqCst) {
-                            break;
-                        }
-
-                        if let Ok(new_props) = path
-                            .canonicalize()
-                            .and_then(|canon| Ok((canon.metadata()?.modified()?, canon)))
-                        {
-                            if last_props.as_ref() != Some(&new_props) {
-                                trace!("file changed: {}", path.to_string_lossy());
-
-                                if let Err(err) = changed.send(()) {
-                                    warn!("error sending change notification: {err:?}");
-                                    break;
-                                }
-
-                                last_props = Some(new_props);
-                            }
-                        }
-                    }
-
-                    debug!("exiting watcher thread for {}", path.to_string_lossy());
+impl EventSource for Watcher {
+    type Event = ();
+    type Metadata = Vec<PathBuf>;
+    type Ret = ();
+    type Error = io::Error;
+
+    fn process_events<F>(
+        &mut self,
+        readiness: Readiness,
+        token: Token,
+        mut callback: F,
+    ) -> Result<PostAction, Self::Error>
+    where
+        F: FnMut(Self::Event, &mut Self::Metadata) -> Self::Ret,
+    {
+        let mut woken_up = false;
+        self.inotify.process_events(readiness, token, |_, fd| {
+            // We re-check all files on any event, so the events themselves don't matter.
+            let mut buf = [0; 4096];
+            loop {
+                match read(&**fd, &mut buf) {
+                    Ok(0) | Err(Errno::AGAIN) => break,
+                    Ok(_) | Err(Errno::INTR) => (),
+                    Err(err) => return Err(err.into()),
+                }
+            }
+
+            woken_up = true;
+            Ok(PostAction::Continue)
+        })?;
+
+        if !woken_up {
+            return Ok(PostAction::Continue);
+        }
+
+        self.update_watches();
+
+        // this "should" be as simple as mtime, but it does not quite work in practice;
+        // it doesn't work if the config is a symlink, and its target changes but the
+        // new target and old target have identical mtimes.
+        //
+        // in practice, this does not occur on any systems other than nix.
+        // because, on nix practically everything is a symlink to /nix/store
+        // and due to reproducibility, /nix/store keeps no mtime (= 1970-01-01)
+        // so, symlink targets change frequently when mtime doesn't.
+        let new_props = props(&self.paths);
+        let mut changed = false;
+        for ((path, last), new) in self.paths.iter().zip(&mut self.last_props).zip(new_props) {
+            // Files that are temporarily missing, e.g. in the middle of a save, don't count.
+            if new.is_some() && *last != new {
+                trace!("file changed: {}", path.to_string_lossy());
+                changed = true;
+                *last = new;
+            }
+        }
+
+        if changed {
+            let old_paths = self.paths.clone();
+            callback((), &mut self.paths);
+
+            if self.paths != old_paths {
+                self.update_watches();
+                self.last_props = props(&self.paths);
+            }
+        }
+
+        Ok(PostAction::Continue)
+    }
+
+    fn register(
+        &mut self,
+        poll: &mut Poll,
+        token_factory: &mut TokenFactory,
+    ) -> calloop::Result<()> {
+        self.inotify.register(poll, token_factory)
+    }
+
+    fn reregister(
+        &mut self,
+        poll: &mut Poll,
+        token_factory: &mut TokenFactory,
+    ) -> calloop::Result<()> {
+        self.inotify.reregister(poll, token_factory)
+    }
+
+    fn unregister(&mut self, poll: &mut Poll) -> calloop::Result<()> {
+        self.inotify.unregister(poll)
+    }
+}
+
+fn props(paths: &[PathBuf]) -> Vec<Option<(SystemTime, PathBuf)>> {
+    paths
+        .iter()
+        .map(|path| {
+            path.canonicalize()
+                .and_then(|canon| Ok((canon.metadata()?.modified()?, canon)))
+                .ok()
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs::{self, File};
+    use std::os::unix::fs::symlink;
+    use std::time::Duration;
+
+    use smithay::reexports::calloop::EventLoop;
+    use tempfile::TempDir;
+
+    use super::*;
+
+    fn set_mtime(path: &Path, secs: u64) {
+        let mtime = SystemTime::UNIX_EPOCH + Duration::from_secs(secs);
+        File::options()
+            .write(true)
+            .open(path)
+            .unwrap()
+            .set_modified(mtime)
+            .unwrap();
+    }
+
+    struct Fixture {
+        dir: TempDir,
+        event_loop: EventLoop<'static, usize>,
+        changes: usize,
+    }
+
+    impl Fixture {
+        fn new(files: &[&str]) -> Self {
+            let dir = tempfile::tempdir().unwrap();
+            let paths = files.iter().map(|file| dir.path().join(file)).collect();
+
+            let event_loop = EventLoop::try_new().unwrap();
+            event_loop
+                .handle()
+                .insert_source(Watcher::new(paths).unwrap(), |(), _, changes| {
+                    *changes += 1;
                 })
                 .unwrap();
+
+            Self {
+                dir,
+                event_loop,
+                changes: 0,
+            }
+        }
+
+        fn path(&self, file: &str) -> PathBuf {
+            self.dir.path().join(file)
         }
 
-        Self { should_stop }
+        /// Dispatches pending events and returns the number of change notifications.
+        fn changes(&mut self) -> usize {
+            self.event_loop
+                .dispatch(Duration::from_millis(50), &mut self.changes)
+                .unwrap();
+            std::mem::take(&mut self.changes)
+        }
+    }
+
+    #[test]
+    fn in_place_write() {
+        let mut f = Fixture::new(&["config.kdl"]);
+        fs::write(f.path("config.kdl"), "a").unwrap();
+        set_mtime(&f.path("config.kdl"), 1);
+        assert_eq!(f.changes(), 1);
+
+        fs::write(f.path("config.kdl"), "b").unwrap();
+        set_mtime(&f.path("config.kdl"), 2);
+        assert_eq!(f.changes(), 1);
+
+        assert_eq!(f.changes(), 0);
+    }
+
+    #[test]
+    fn atomic_rename() {
+        let mut f = Fixture::new(&["config.kdl"]);
+        fs::write(f.path("config.kdl"), "a").unwrap();
+        set_mtime(&f.path("config.kdl"), 1);
+        assert_eq!(f.changes(), 1);
+
+        // Save like editors do: write a new file and rename it over the old one.
+        for (i, contents) in ["b", "c"].into_iter().enumerate() {
+            fs::write(f.path("config.kdl.tmp"), contents).unwrap();
+            set_mtime(&f.path("config.kdl.tmp"), 2 + i as u64);
+            fs::rename(f.path("config.kdl.tmp"), f.path("config.kdl")).unwrap();
+            assert_eq!(f.changes(), 1);
+        }
+    }
+
+    #[test]
+    fn symlink_swap_with_same_mtime() {
+        let mut f = Fixture::new(&["config.kdl"]);
+        fs::create_dir(f.path("a")).unwrap();
+        fs::create_dir(f.path("b")).unwrap();
+        fs::write(f.path("a/config.kdl"), "a").unwrap();
+        fs::write(f.path("b/config.kdl"), "b").unwrap();
+        set_mtime(&f.path("a/config.kdl"), 0);
+        set_mtime(&f.path("b/config.kdl"), 0);
+        symlink(f.path("a/config.kdl"), f.path("config.kdl")).unwrap();
+        assert_eq!(f.changes(), 1);
+
+        // Swap the symlink atomically, like home-manager does.
+        symlink(f.path("b/config.kdl"), f.path("config.kdl.tmp")).unwrap();
+        fs::rename(f.path("config.kdl.tmp"), f.path("config.kdl")).unwrap();
+        assert_eq!(f.changes(), 1);
+
+        // Writes to the new target are noticed, writes to the old one aren't.
+        fs::write(f.path("a/config.kdl"), "aa").unwrap();
+        set_mtime(&f.path("a/config.kdl"), 1);
+        assert_eq!(f.changes(), 0);
+
+        fs::write(f.path("b/config.kdl"), "bb").unwrap();
+        set_mtime(&f.path("b/config.kdl"), 1);
+        assert_eq!(f.changes(), 1);
+    }
+
+    #[test]
+    fn unrelated_files_are_ignored() {
+        let mut f = Fixture::new(&["config.kdl"]);
+        fs::write(f.path("config.kdl"), "a").unwrap();
+        assert_eq!(f.changes(), 1);
+
+        fs::write(f.path("other.kdl"), "a").unwrap();
+        fs::rename(f.path("other.kdl"), f.path("other2.kdl")).unwrap();
+        fs::remove_file(f.path("other2.kdl")).unwrap();
+        assert_eq!(f.changes(), 0);
+
+        // A temporarily missing file is not a change by itself.
+        fs::remove_file(f.path("config.kdl")).unwrap();
+        assert_eq!(f.changes(), 0);
+    }
+
+    #[test]
+    fn missing_directories() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("niri/conf.d/config.kdl");
+
+        let watcher = Watcher::new(vec![path.clone()]).unwrap();
+        // Only the temporary directory itself, not its ancestors.
+        assert_eq!(watcher.watches.len(), 1);
+
+        let mut event_loop = EventLoop::<usize>::try_new().unwrap();
+        event_loop
+            .handle()
+            .insert_source(watcher, |(), _, changes| *changes += 1)
+            .unwrap();
+
+        let mut changes = 0;
+        let mut dispatch = |changes: &mut usize| {
+            event_loop
+                .dispatch(Duration::from_millis(50), changes)
+                .unwrap();
+            std::mem::take(changes)
+        };
+
+        // The watch moves down one directory at a time.
+        fs::create_dir(dir.path().join("niri")).unwrap();
+        assert_eq!(dispatch(&mut changes), 0);
+        fs::create_dir(dir.path().join("niri/conf.d")).unwrap();
+        assert_eq!(dispatch(&mut changes), 0);
+
+        fs::write(&path, "a").unwrap();
+        assert_eq!(dispatch(&mut changes), 1);
+    }
+
+    #[test]
+    fn callback_can_replace_paths() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = dir.path().join("config.kdl");
+        let include = dir.path().join("include.kdl");
+        fs::write(&config, "a").unwrap();
+        fs::write(&include, "a").unwrap();
+        set_mtime(&config, 0);
+        set_mtime(&include, 0);
+
+        let mut event_loop = EventLoop::<usize>::try_new().unwrap();
+        let new_paths = vec![config.clone(), include.clone()];
+        event_loop
+            .handle()
+            .insert_source(
+                Watcher::new(vec![config.clone()]).unwrap(),
+                move |(), paths, changes| {
+                    *paths = new_paths.clone();
+                    *changes += 1;
+                },
+            )
+            .unwrap();
+
+        let mut changes = 0;
+        let mut dispatch = |changes: &mut usize| {
+            event_loop
+                .dispatch(Duration::from_millis(50), changes)
+                .unwrap();
+            std::mem::take(changes)
+        };
+
+        set_mtime(&include, 1);
+        assert_eq!(dispatch(&mut changes), 0);
+
+        set_mtime(&config, 1);
+        assert_eq!(dispatch(&mut changes), 1);
+
+        set_mtime(&include, 2);
+        assert_eq!(dispatch(&mut changes), 1);
     }
 }
@@ -0,0 +1,316 @@
+use std::cell::RefCell;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use niri_config::{BorderRule, Match, WindowRule};
+use smithay::desktop::Window;
+use smithay::wayland::compositor::with_states;
+use smithay::wayland::shell::xdg::{ToplevelSurface, XdgToplevelSurfaceData};
+
+use crate::layout::workspace::ColumnWidth;
+
+/// Unique id of a window.
+///
+/// Ids are never reused while niri is running, so they stay valid for as long as the window
+/// exists.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct WindowId(u64);
+
+impl WindowId {
+    /// Returns the id of the window, assigning a new one if it doesn't have one yet.
+    pub fn of(window: &Window) -> Self {
+        static NEXT_ID: AtomicU64 = AtomicU64::new(1);
+
+        *window
+            .user_data()
+            .get_or_insert(|| Self(NEXT_ID.fetch_add(1, Ordering::Relaxed)))
+    }
+
+    pub fn get(self) -> u64 {
+        self.0
+    }
+}
+
+impl From<u64> for WindowId {
+    fn from(value: u64) -> Self {
+        Self(value)
+    }
+}
+
+/// Rules fully resolved for a window.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct ResolvedWindowRules {
+    /// Default width for this window.
+    ///
+    /// - `None`: unset (global default should be used).
+    /// - `Some(None)`: set to empty (window picks its own width).
+    /// - `Some(Some(width))`: set to a particular width.
+    pub default_width: Option<Option<ColumnWidth>>,
+
+    /// Output to open this window on.
+    pub open_on_output: Option<String>,
+
+    /// Workspace to open this window on.
+    pub open_on_workspace: Option<String>,
+
+    /// Whether the window should open full-width.
+    pub open_maximized: Option<bool>,
+
+    /// Whether the window should open fullscreen.
+    pub open_fullscreen: Option<bool>,
+
+    /// Extra bound on the minimum window width.
+    pub min_width: Option<u16>,
+    /// Extra bound on the minimum window height.
+    pub min_height: Option<u16>,
+    /// Extra bound on the maximum window width.
+    pub max_width: Option<u16>,
+    /// Extra bound on the maximum window height.
+    pub max_height: Option<u16>,
+
+    /// Focus ring overrides.
+    pub focus_ring: BorderRule,
+    /// Window border overrides.
+    pub border: BorderRule,
+
+    /// Whether the window opened during the startup period.
+    ///
+    /// Kept so that `at-startup` matchers resolve the same way when the rules are recomputed
+    /// later, for example on config reload.
+    pub is_at_startup: bool,
+}
+
+impl ResolvedWindowRules {
+    pub fn compute(rules: &[WindowRule], toplevel: &ToplevelSurface, is_at_startup: bool) -> Self {
+        let _span = tracy_client::span!("ResolvedWindowRules::compute");
+
+        with_states(toplevel.wl_surface(), |states| {
+            let role = states
+                .data_map
+                .get::<XdgToplevelSurfaceData>()
+                .unwrap()
+                .lock()
+                .unwrap();
+
+            Self::compute_for(
+                rules,
+                role.app_id.as_deref(),
+                role.title.as_deref(),
+                is_at_startup,
+            )
+        })
+    }
+
+    /// Recomputes the rules for a window that has already opened, e.g. after a config reload.
+    ///
+    /// The window keeps the startup state it opened with.
+    pub fn recompute(rules: &[WindowRule], window: &Window) -> Self {
+        let is_at_startup = Self::with(window, |rules| rules.is_at_startup);
+        Self::compute(rules, window.toplevel(), is_at_startup)
+    }
+
+    fn compute_for(
+        rules: &[WindowRule],
+        app_id: Option<&str>,
+        title: Option<&str>,
+        is_at_startup: bool,
+    ) -> Self {
+        let mut resolved = ResolvedWindowRules {
+            is_at_startup,
+            ..Default::default()
+        };
+
+        let matches = |m: &Match| window_matches(app_id, title, m, is_at_startup);
+
+        for rule in rules {
+            if !(rule.matches.is_empty() || rule.matches.iter().any(matches)) {
+                continue;
+            }
+
+            if rule.excludes.iter().any(matches) {
+                continue;
+            }
+
+            // Later rules take precedence over earlier ones.
+            if let Some(x) = rule.default_column_width.as_ref() {
+                resolved.default_width = Some(x.0.first().copied().map(ColumnWidth::from));
+            }
+
+            if let Some(x) = rule.open_on_output.as_deref() {
+                resolved.open_on_output = Some(x.to_owned());
+            }
+
+            if let Some(x) = rule.open_on_workspace.as_deref() {
+                resolved.open_on_workspace = Some(x.to_owned());
+            }
+
+            if let Some(x) = rule.open_maximized {
+                resolved.open_maximized = Some(x);
+            }
+
+            if let Some(x) = rule.open_fullscreen {
+                resolved.open_fullscreen = Some(x);
+            }
+
+            if let Some(x) = rule.min_width {
+                resolved.min_width = Some(x);
+            }
+            if let Some(x) = rule.min_height {
+                resolved.min_height = Some(x);
+            }
+            if let Some(x) = rule.max_width {
+                resolved.max_width = Some(x);
+            }
+            if let Some(x) = rule.max_height {
+                resolved.max_height = Some(x);
+            }
+
+            resolved.focus_ring.merge_with(&rule.focus_ring);
+            resolved.border.merge_with(&rule.border);
+        }
+
+        resolved
+    }
+
+    /// Returns the rules stored on the window.
+    pub fn of(window: &Window) -> Self {
+        Self::with(window, Clone::clone)
+    }
+
+    /// Calls `f` with the rules stored on the window without cloning them.
+    pub fn with<T>(window: &Window, f: impl FnOnce(&ResolvedWindowRules) -> T) -> T {
+        match window.user_data().get::<RefCell<ResolvedWindowRules>>() {
+            Some(rules) => f(&rules.borrow()),
+            None => f(&ResolvedWindowRules::default()),
+        }
+    }
+
+    /// Stores the rules on the window.
+    pub fn store(self, window: &Window) {
+        let data = window.user_data();
+        data.insert_if_missing(|| RefCell::new(ResolvedWindowRules::default()));
+        *data
+            .get::<RefCell<ResolvedWindowRules>>()
+            .unwrap()
+            .borrow_mut() = self;
+    }
+}
+
+fn window_matches(
+    app_id: Option<&str>,
+    title: Option<&str>,
+    m: &Match,
+    is_at_startup: bool,
+) -> bool {
+    if let Some(at_startup) = m.at_startup {
+        if at_startup != is_at_startup {
+            return false;
+        }
+    }
+
+    if let Some(app_id_re) = &m.app_id {
+        let Some(app_id) = app_id else {
+            return false;
+        };
+        if !app_id_re.0.is_match(app_id) {
+            return false;
+        }
+    }
+
+    if let Some(title_re) = &m.title {
+        let Some(title) = title else {
+            return false;
+        };
+        if !title_re.0.is_match(title) {
+            return false;
+        }
+    }
+
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use niri_config::Config;
+
+    use super::*;
+
+    fn rules(text: &str) -> Vec<WindowRule> {
+        Config::parse("test.kdl", text).unwrap().window_rules
+    }
+
+    #[test]
+    fn match_and_exclude() {
+        let rules = rules(
+            r#"
+            window-rule {
+                match app-id="^firefox$"
+                match title="Terminal"
+                exclude title="Private"
+
+                min-width 100
+            }
+            "#,
+        );
+
+        let resolve = |app_id, title| {
+            ResolvedWindowRules::compute_for(&rules, app_id, title, false).min_width
+        };
+
+        assert_eq!(resolve(Some("firefox"), Some("Page")), Some(100));
+        assert_eq!(resolve(None, Some("My Terminal")), Some(100));
+        assert_eq!(resolve(Some("firefox-beta"), None), None);
+        assert_eq!(resolve(Some("firefox"), Some("Private Browsing")), None);
+        assert_eq!(resolve(None, None), None);
+    }
+
+    #[test]
+    fn later_rules_take_precedence() {
+        let rules = rules(
+            r#"
+            window-rule {
+                min-width 100
+                max-width 500
+                border { off; }
+            }
+
+            window-rule {
+                match app-id="kitty"
+
+                min-width 200
+                border { on; width 2; }
+            }
+            "#,
+        );
+
+        let resolved = ResolvedWindowRules::compute_for(&rules, Some("kitty"), None, false);
+        assert_eq!(resolved.min_width, Some(200));
+        assert_eq!(resolved.max_width, Some(500));
+        assert!(resolved.border.on);
+        assert!(!resolved.border.off);
+        assert_eq!(resolved.border.width, Some(2));
+
+        let resolved = ResolvedWindowRules::compute_for(&rules, Some("foot"), None, false);
+        assert_eq!(resolved.min_width, Some(100));
+        assert!(resolved.border.off);
+    }
+
+    #[test]
+    fn at_startup() {
+        let rules = rules(
+            r#"
+            window-rule {
+                match at-startup=true
+
+                open-on-workspace "chat"
+            }
+            "#,
+        );
+
+        let resolved = ResolvedWindowRules::compute_for(&rules, None, None, true);
+        assert_eq!(resolved.open_on_workspace.as_deref(), Some("chat"));
+        assert!(resolved.is_at_startup);
+
+        let resolved = ResolvedWindowRules::compute_for(&rules, None, None, false);
+        assert_eq!(resolved.open_on_workspace, None);
+    }
+}